@@ -31,9 +31,7 @@ pub struct XClipClipboard {
 impl XClipClipboard {
     pub fn new() -> Self {
         let command = Command::new("xclip").arg("-h").output();
-        let is_xclip_available = command
-            .map(|output| output.status.success())
-            .unwrap_or(false);
+        let is_xclip_available = command.is_ok_and(|output| output.status.success());
 
         Self { is_xclip_available }
     }
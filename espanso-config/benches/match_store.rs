@@ -0,0 +1,108 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{fmt::Write, path::Path};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use espanso_config::matches::store::{self, DefaultMatchStore, MatchStore};
+use tempdir::TempDir;
+
+const MATCH_FILES: usize = 20;
+const MATCHES_PER_FILE: usize = 500;
+const APP_PROFILES: usize = 10;
+
+// Generate a corpus of 10k matches split into several files, all importing a
+// shared group of global variables, and return the paths queried by each app profile
+fn generate_corpus(dir: &Path) -> Vec<Vec<String>> {
+    let common_file = dir.join("_common.yml");
+    std::fs::write(
+        &common_file,
+        "global_vars:\n  - name: shared\n    type: echo\n    params:\n      echo: shared\n",
+    )
+    .unwrap();
+
+    let mut files = Vec::new();
+    for file_index in 0..MATCH_FILES {
+        let mut content = String::from("imports:\n  - \"_common.yml\"\n\nmatches:\n");
+        for match_index in 0..MATCHES_PER_FILE {
+            writeln!(
+                content,
+                "  - trigger: \":m{file_index}_{match_index}\"\n    replace: \"{{{{shared}}}} {match_index}\""
+            )
+            .unwrap();
+        }
+
+        let file = dir.join(format!("file{file_index}.yml"));
+        std::fs::write(&file, content).unwrap();
+        files.push(file.to_string_lossy().to_string());
+    }
+
+    // Each app profile excludes a different file, as a `config/*.yml` would do
+    (0..APP_PROFILES)
+        .map(|profile| {
+            files
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != profile)
+                .map(|(_, file)| file.clone())
+                .collect()
+        })
+        .collect()
+}
+
+fn bench_query(c: &mut Criterion) {
+    let dir = TempDir::new("espanso-bench").unwrap();
+    let profiles = generate_corpus(dir.path());
+
+    let mut all_paths: Vec<String> = profiles.concat();
+    all_paths.sort();
+    all_paths.dedup();
+    let (match_store, errors) = store::load(&all_paths);
+    assert!(errors.is_empty());
+    assert_eq!(
+        match_store.query(&all_paths).matches.len(),
+        MATCH_FILES * MATCHES_PER_FILE
+    );
+
+    // The baseline is the plain store, which traverses the groups on every query
+    let (uncached_store, _) = DefaultMatchStore::load(&all_paths);
+
+    let mut group = c.benchmark_group("match_store_query");
+
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            for paths in &profiles {
+                criterion::black_box(uncached_store.query(paths));
+            }
+        });
+    });
+
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            for paths in &profiles {
+                criterion::black_box(match_store.query(paths));
+            }
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_query);
+criterion_main!(benches);
@@ -28,7 +28,7 @@ mod parse;
 mod path;
 mod resolve;
 pub(crate) mod store;
-mod util;
+pub(crate) mod util;
 
 #[cfg(test)]
 use mockall::{automock, predicate::*};
@@ -136,10 +136,22 @@ pub trait Config: Send + Sync {
     // Hotkey used to trigger the Search UI
     fn search_shortcut(&self) -> Option<String>;
 
+    // Hotkey used to revert the last expansion, independently of the
+    // backspace-based undo.
+    fn undo_shortcut(&self) -> Option<String>;
+
     // When enabled, espanso automatically "reverts" an expansion if the user
     // presses the Backspace key afterwards.
     fn undo_backspace(&self) -> bool;
 
+    // Key used to jump to the next tab stop of an expansion with `tab_stops`
+    // enabled, or None if jumping is disabled.
+    fn tab_stop_key(&self) -> Option<String>;
+
+    // How the cursor is moved to the `$|$` hint (or the first tab stop) after
+    // an expansion. Can be overridden by each match.
+    fn cursor_hint_strategy(&self) -> CursorHintStrategy;
+
     // If false, disable all notifications
     fn show_notifications(&self) -> bool;
 
@@ -236,6 +248,9 @@ pub trait Config: Send + Sync {
         backspace_limit: {}
         search_trigger: {:?}
         search_shortcut: {:?}
+        undo_shortcut: {:?}
+        tab_stop_key: {:?}
+        cursor_hint_strategy: {:?}
         keyboard_layout: {:?}
 
         show_icon: {:?}
@@ -275,6 +290,9 @@ pub trait Config: Send + Sync {
           self.backspace_limit(),
           self.search_trigger(),
           self.search_shortcut(),
+          self.undo_shortcut(),
+          self.tab_stop_key(),
+          self.cursor_hint_strategy(),
           self.keyboard_layout(),
 
           self.show_icon(),
@@ -307,13 +325,29 @@ pub struct AppProperties<'a> {
     pub exec: Option<&'a str>,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Backend {
     Inject,
     Clipboard,
     Auto,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CursorHintStrategy {
+    Chars,
+    Lines,
+}
+
+impl CursorHintStrategy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "chars" => Some(Self::Chars),
+            "lines" => Some(Self::Lines),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum ToggleKey {
     Ctrl,
@@ -330,7 +364,7 @@ pub enum ToggleKey {
     LeftMeta,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct RMLVOConfig {
     pub rules: Option<String>,
     pub model: Option<String>,
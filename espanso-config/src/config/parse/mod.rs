@@ -40,7 +40,10 @@ pub struct ParsedConfig {
     pub apply_patch: Option<bool>,
     pub search_trigger: Option<String>,
     pub search_shortcut: Option<String>,
+    pub undo_shortcut: Option<String>,
     pub undo_backspace: Option<bool>,
+    pub tab_stop_key: Option<String>,
+    pub cursor_hint_strategy: Option<String>,
     pub show_notifications: Option<bool>,
     pub show_icon: Option<bool>,
     pub secure_input_notification: Option<bool>,
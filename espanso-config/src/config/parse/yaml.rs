@@ -100,9 +100,18 @@ pub struct YAMLConfig {
     #[serde(default)]
     pub search_shortcut: Option<String>,
 
+    #[serde(default)]
+    pub undo_shortcut: Option<String>,
+
     #[serde(default)]
     pub undo_backspace: Option<bool>,
 
+    #[serde(default)]
+    pub tab_stop_key: Option<String>,
+
+    #[serde(default)]
+    pub cursor_hint_strategy: Option<String>,
+
     #[serde(default)]
     pub show_notifications: Option<bool>,
 
@@ -226,7 +235,10 @@ impl TryFrom<YAMLConfig> for ParsedConfig {
             }),
             search_trigger: yaml_config.search_trigger,
             search_shortcut: yaml_config.search_shortcut,
+            undo_shortcut: yaml_config.undo_shortcut,
             undo_backspace: yaml_config.undo_backspace,
+            tab_stop_key: yaml_config.tab_stop_key,
+            cursor_hint_strategy: yaml_config.cursor_hint_strategy,
 
             show_icon: yaml_config.show_icon,
             show_notifications: yaml_config.show_notifications,
@@ -302,7 +314,10 @@ mod tests {
       options: test_options
     search_trigger: "search"
     search_shortcut: "CTRL+SPACE"
+    undo_shortcut: "CTRL+ALT+Z"
     undo_backspace: false
+    tab_stop_key: "F2"
+    cursor_hint_strategy: lines
     show_icon: false
     show_notifications: false
     secure_input_notification: false
@@ -363,7 +378,10 @@ mod tests {
                 keyboard_layout: Some(keyboard_layout),
                 search_trigger: Some("search".to_owned()),
                 search_shortcut: Some("CTRL+SPACE".to_owned()),
+                undo_shortcut: Some("CTRL+ALT+Z".to_owned()),
                 undo_backspace: Some(false),
+                tab_stop_key: Some("F2".to_owned()),
+                cursor_hint_strategy: Some("lines".to_owned()),
                 show_icon: Some(false),
                 show_notifications: Some(false),
                 secure_input_notification: Some(false),
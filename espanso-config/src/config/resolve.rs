@@ -26,9 +26,9 @@ use super::{
     parse::ParsedConfig,
     path::calculate_paths,
     util::os_matches,
-    AppProperties, Backend, Config, RMLVOConfig, ToggleKey,
+    AppProperties, Backend, Config, CursorHintStrategy, RMLVOConfig, ToggleKey,
 };
-use crate::{counter::next_id, merge};
+use crate::{counter::next_id, error::LocatedError, merge, util::locate_yaml_key};
 use anyhow::Result;
 use log::error;
 use regex::Regex;
@@ -36,7 +36,14 @@ use std::path::PathBuf;
 use std::{collections::HashSet, path::Path};
 use thiserror::Error;
 
-const STANDARD_INCLUDES: &[&str] = &["../match/**/[!_]*.yml", "../match/**/[!_]*.yaml"];
+// TOML and JSON match files need an explicit suffix, so that data files
+// shipped inside packages (or kept in the match folder) are not loaded as matches
+const STANDARD_INCLUDES: &[&str] = &[
+    "../match/**/[!_]*.yml",
+    "../match/**/[!_]*.yaml",
+    "../match/**/[!_]*.match.toml",
+    "../match/**/[!_]*.match.json",
+];
 
 #[derive(Debug, Clone, Default)]
 pub struct ResolvedConfig {
@@ -290,6 +297,32 @@ impl Config for ResolvedConfig {
         }
     }
 
+    fn undo_shortcut(&self) -> Option<String> {
+        match self.parsed.undo_shortcut.as_deref() {
+            Some("OFF" | "off") => None,
+            Some(x) => Some(x.to_string()),
+            None => None,
+        }
+    }
+
+    fn tab_stop_key(&self) -> Option<String> {
+        match self.parsed.tab_stop_key.as_deref() {
+            Some("OFF" | "off") => None,
+            Some(x) => Some(x.to_string()),
+            None => Some("TAB".to_string()),
+        }
+    }
+
+    fn cursor_hint_strategy(&self) -> CursorHintStrategy {
+        match self.parsed.cursor_hint_strategy.as_deref() {
+            None => CursorHintStrategy::Chars,
+            Some(value) => CursorHintStrategy::parse(value).unwrap_or_else(|| {
+                error!("invalid cursor_hint_strategy specified {value:?}, falling back to chars");
+                CursorHintStrategy::Chars
+            }),
+        }
+    }
+
     fn undo_backspace(&self) -> bool {
         self.parsed.undo_backspace.unwrap_or(false)
     }
@@ -383,23 +416,11 @@ impl ResolvedConfig {
             .into_iter()
             .collect();
 
-        let filter_title = if let Some(filter_title) = config.filter_title.as_deref() {
-            Some(Regex::new(filter_title)?)
-        } else {
-            None
-        };
-
-        let filter_class = if let Some(filter_class) = config.filter_class.as_deref() {
-            Some(Regex::new(filter_class)?)
-        } else {
-            None
-        };
-
-        let filter_exec = if let Some(filter_exec) = config.filter_exec.as_deref() {
-            Some(Regex::new(filter_exec)?)
-        } else {
-            None
-        };
+        let filter_title =
+            Self::compile_filter(path, "filter_title", config.filter_title.as_deref())?;
+        let filter_class =
+            Self::compile_filter(path, "filter_class", config.filter_class.as_deref())?;
+        let filter_exec = Self::compile_filter(path, "filter_exec", config.filter_exec.as_deref())?;
 
         Ok(Self {
             parsed: config,
@@ -412,6 +433,26 @@ impl ResolvedConfig {
         })
     }
 
+    fn compile_filter(path: &Path, key: &str, filter: Option<&str>) -> Result<Option<Regex>> {
+        let Some(filter) = filter else {
+            return Ok(None);
+        };
+
+        Regex::new(filter).map(Some).map_err(|err| {
+            let error = anyhow::Error::new(err).context(format!("invalid '{key}' regex"));
+
+            // The filter might have been inherited from the parent config,
+            // in which case it cannot be located in this file
+            let location = std::fs::read_to_string(path)
+                .ok()
+                .and_then(|content| locate_yaml_key(&content, key));
+            match location {
+                Some(location) => LocatedError::new(location, error).into(),
+                None => error,
+            }
+        })
+    }
+
     pub fn add_match_path(&mut self, path: String) {
         self.match_paths.push(path);
     }
@@ -445,7 +486,10 @@ impl ResolvedConfig {
             keyboard_layout,
             search_trigger,
             search_shortcut,
+            undo_shortcut,
             undo_backspace,
+            tab_stop_key,
+            cursor_hint_strategy,
             show_icon,
             show_notifications,
             secure_input_notification,
@@ -538,6 +582,7 @@ pub enum ResolveError {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::{locate_error, SourceLocation};
     use crate::util::tests::use_test_directory;
     use std::fs::create_dir_all;
 
@@ -547,7 +592,9 @@ mod tests {
             ResolvedConfig::aggregate_includes(&ParsedConfig::default()),
             [
                 "../match/**/[!_]*.yml".to_string(),
-                "../match/**/[!_]*.yaml".to_string()
+                "../match/**/[!_]*.yaml".to_string(),
+                "../match/**/[!_]*.match.toml".to_string(),
+                "../match/**/[!_]*.match.json".to_string()
             ]
             .iter()
             .cloned()
@@ -576,6 +623,8 @@ mod tests {
             [
                 "../match/**/[!_]*.yml".to_string(),
                 "../match/**/[!_]*.yaml".to_string(),
+                "../match/**/[!_]*.match.toml".to_string(),
+                "../match/**/[!_]*.match.json".to_string(),
                 "custom/*.yml".to_string()
             ]
             .iter()
@@ -594,6 +643,8 @@ mod tests {
             [
                 "../match/**/[!_]*.yml".to_string(),
                 "../match/**/[!_]*.yaml".to_string(),
+                "../match/**/[!_]*.match.toml".to_string(),
+                "../match/**/[!_]*.match.json".to_string(),
                 "custom/*.yml".to_string()
             ]
             .iter()
@@ -613,6 +664,8 @@ mod tests {
             [
                 "../match/**/[!_]*.yml".to_string(),
                 "../match/**/[!_]*.yaml".to_string(),
+                "../match/**/[!_]*.match.toml".to_string(),
+                "../match/**/[!_]*.match.json".to_string(),
                 "custom/*.yml".to_string(),
                 "sub/*.yml".to_string()
             ]
@@ -746,6 +799,39 @@ mod tests {
         });
     }
 
+    #[test]
+    fn match_paths_only_include_suffixed_toml_and_json_files() {
+        use_test_directory(|_, match_dir, config_dir| {
+            let packages_dir = match_dir.join("packages").join("sample");
+            create_dir_all(&packages_dir).unwrap();
+
+            let toml_file = match_dir.join("team.match.toml");
+            std::fs::write(&toml_file, "test").unwrap();
+            let json_file = match_dir.join("team.match.json");
+            std::fs::write(&json_file, "test").unwrap();
+            std::fs::write(match_dir.join("data.json"), "test").unwrap();
+            std::fs::write(match_dir.join("settings.toml"), "test").unwrap();
+            std::fs::write(packages_dir.join("_manifest.json"), "test").unwrap();
+            std::fs::write(packages_dir.join("data.json"), "test").unwrap();
+
+            let config_file = config_dir.join("default.yml");
+            std::fs::write(&config_file, "").unwrap();
+
+            let config = ResolvedConfig::load(&config_file, None).unwrap();
+
+            let mut expected = vec![
+                toml_file.to_string_lossy().to_string(),
+                json_file.to_string_lossy().to_string(),
+            ];
+            expected.sort();
+
+            let mut result = config.match_paths().to_vec();
+            result.sort();
+
+            assert_eq!(result, expected.as_slice());
+        });
+    }
+
     #[test]
     fn match_paths_generated_correctly_with_child_config() {
         use_test_directory(|_, match_dir, config_dir| {
@@ -1012,4 +1098,27 @@ mod tests {
             },
         ));
     }
+
+    #[test]
+    fn invalid_filter_is_located() {
+        use_test_directory(|_, _, config_dir| {
+            let config_file = config_dir.join("default.yml");
+            std::fs::write(&config_file, "backend: Clipboard\nfilter_class: \"(\"\n").unwrap();
+
+            let error = ResolvedConfig::load(&config_file, None).unwrap_err();
+            assert_eq!(locate_error(&error), Some(SourceLocation::new(2, 1)));
+            assert!(format!("{error:#}").starts_with("invalid 'filter_class' regex"));
+        });
+    }
+
+    #[test]
+    fn invalid_syntax_is_located() {
+        use_test_directory(|_, _, config_dir| {
+            let config_file = config_dir.join("default.yml");
+            std::fs::write(&config_file, "backend: Clipboard\nenable: [\n").unwrap();
+
+            let error = ResolvedConfig::load(&config_file, None).unwrap_err();
+            assert!(locate_error(&error).is_some());
+        });
+    }
 }
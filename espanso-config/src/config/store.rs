@@ -155,7 +155,9 @@ impl DefaultConfigStore {
             Self {
                 default: Arc::new(default),
                 customs: Vec::new(),
-                extra_matches: vec![path.to_string_lossy().to_string()].into_iter().collect(),
+                extra_matches: vec![path.to_string_lossy().to_string()]
+                    .into_iter()
+                    .collect(),
             },
             non_fatal_errors,
         ))
@@ -185,6 +187,7 @@ mod tests {
         let store = DefaultConfigStore {
             default: Arc::new(default),
             customs: vec![Arc::new(custom1), Arc::new(custom2)],
+            extra_matches: HashSet::new(),
         };
 
         assert_eq!(store.default().label(), "default");
@@ -209,6 +212,7 @@ mod tests {
         let store = DefaultConfigStore {
             default: Arc::new(default),
             customs: vec![Arc::new(custom1), Arc::new(custom2)],
+            extra_matches: HashSet::new(),
         };
 
         assert_eq!(store.default().label(), "default");
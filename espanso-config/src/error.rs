@@ -18,6 +18,7 @@
  */
 
 use anyhow::Error;
+use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
@@ -46,12 +47,14 @@ impl NonFatalErrorSet {
 pub struct ErrorRecord {
     pub level: ErrorLevel,
     pub error: Error,
+    pub location: Option<SourceLocation>,
 }
 
 impl ErrorRecord {
     pub fn error(error: Error) -> Self {
         Self {
             level: ErrorLevel::Error,
+            location: locate_error(&error),
             error,
         }
     }
@@ -59,9 +62,18 @@ impl ErrorRecord {
     pub fn warn(error: Error) -> Self {
         Self {
             level: ErrorLevel::Warning,
+            location: locate_error(&error),
             error,
         }
     }
+
+    // A location extracted from the error itself (for example, from the parser)
+    // is more precise than the one provided by the caller, so it takes precedence
+    #[must_use]
+    pub fn with_location(mut self, location: Option<SourceLocation>) -> Self {
+        self.location = self.location.or(location);
+        self
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -69,3 +81,139 @@ pub enum ErrorLevel {
     Error,
     Warning,
 }
+
+/// A position inside a configuration or match file. Both line and column are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourceLocation {
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Attaches a source location to an error, without altering its message or cause chain.
+#[derive(Debug)]
+pub struct LocatedError {
+    pub location: SourceLocation,
+    error: Error,
+}
+
+impl LocatedError {
+    pub fn new(location: SourceLocation, error: Error) -> Self {
+        Self { location, error }
+    }
+}
+
+impl Display for LocatedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for LocatedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Searches the cause chain of the given error for a location inside the source file,
+/// either attached explicitly through a `LocatedError` or reported by one of the parsers.
+pub fn locate_error(error: &Error) -> Option<SourceLocation> {
+    error.chain().find_map(|cause| {
+        if let Some(located) = cause.downcast_ref::<LocatedError>() {
+            Some(located.location)
+        } else if let Some(yaml_error) = cause.downcast_ref::<serde_norway::Error>() {
+            yaml_error
+                .location()
+                .map(|location| SourceLocation::new(location.line(), location.column()))
+        } else if let Some(json_error) = cause.downcast_ref::<serde_json::Error>() {
+            (json_error.line() > 0)
+                .then(|| SourceLocation::new(json_error.line(), json_error.column()))
+        } else if let Some(toml_error) = cause.downcast_ref::<toml::de::Error>() {
+            // TOML reports 0-based positions
+            toml_error
+                .line_col()
+                .map(|(line, column)| SourceLocation::new(line + 1, column + 1))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn locate_error_yaml() {
+        let error: Error = serde_norway::from_str::<Vec<String>>("- a\n- [b")
+            .unwrap_err()
+            .into();
+        let error = error.context("failed to parse");
+
+        assert_eq!(locate_error(&error).map(|l| l.line), Some(2));
+    }
+
+    #[test]
+    fn locate_error_json() {
+        let error: Error = serde_json::from_str::<Vec<String>>("[\n  \"a\",\n  3\n]")
+            .unwrap_err()
+            .into();
+
+        assert_eq!(locate_error(&error), Some(SourceLocation::new(3, 3)));
+    }
+
+    #[test]
+    fn locate_error_toml() {
+        let error: Error = toml::from_str::<toml::Value>("a = 1\nb = \n")
+            .unwrap_err()
+            .into();
+
+        assert_eq!(locate_error(&error).map(|l| l.line), Some(2));
+    }
+
+    #[test]
+    fn located_error_is_transparent() {
+        let error: Error = LocatedError::new(
+            SourceLocation::new(4, 2),
+            anyhow!("inner cause").context("outer message"),
+        )
+        .into();
+        let error = error.context("while loading");
+
+        assert_eq!(locate_error(&error), Some(SourceLocation::new(4, 2)));
+        assert_eq!(
+            format!("{error:#}"),
+            "while loading: outer message: inner cause"
+        );
+    }
+
+    #[test]
+    fn locate_error_without_location() {
+        assert_eq!(locate_error(&anyhow!("plain error")), None);
+    }
+
+    #[test]
+    fn with_location_prefers_error_location() {
+        let record = ErrorRecord::error(
+            LocatedError::new(SourceLocation::new(1, 1), anyhow!("located")).into(),
+        )
+        .with_location(Some(SourceLocation::new(5, 3)));
+        assert_eq!(record.location, Some(SourceLocation::new(1, 1)));
+
+        let record = ErrorRecord::warn(anyhow!("not located"))
+            .with_location(Some(SourceLocation::new(5, 3)));
+        assert_eq!(record.location, Some(SourceLocation::new(5, 3)));
+    }
+}
@@ -36,6 +36,25 @@ type LoadableConfig = (
 );
 
 pub fn load(base_path: &Path) -> Result<LoadableConfig> {
+    load_with_match_store(base_path, |paths| {
+        let (match_store, non_fatal_errors) = matches::store::load(paths);
+        (Box::new(match_store), non_fatal_errors)
+    })
+}
+
+/// Load the configuration again, re-using the match groups of the previous
+/// match store whose files haven't changed since they were loaded.
+pub fn reload(
+    base_path: &Path,
+    previous_match_store: Box<dyn MatchStore>,
+) -> Result<LoadableConfig> {
+    load_with_match_store(base_path, |paths| previous_match_store.reload(paths))
+}
+
+fn load_with_match_store(
+    base_path: &Path,
+    load_match_store: impl FnOnce(&[String]) -> (Box<dyn MatchStore>, Vec<error::NonFatalErrorSet>),
+) -> Result<LoadableConfig> {
     if base_path.is_file() {
         let (config_store, non_fatal_config_errors) = config::load_from_single_file(base_path)?;
         let mut root_paths = config_store.get_all_match_paths();
@@ -44,19 +63,13 @@ pub fn load(base_path: &Path) -> Result<LoadableConfig> {
         root_paths.insert(base_path.to_string_lossy().to_string());
 
         let (match_store, non_fatal_match_errors) =
-            matches::store::load(&root_paths.into_iter().collect::<Vec<String>>());
-            
-        println!("DEBUG: Matches loaded. Count: unknown (need to check store)");
+            load_match_store(&root_paths.into_iter().collect::<Vec<String>>());
 
         let mut non_fatal_errors = Vec::new();
         non_fatal_errors.extend(non_fatal_config_errors);
         non_fatal_errors.extend(non_fatal_match_errors);
 
-        return Ok((
-            Box::new(config_store),
-            Box::new(match_store),
-            non_fatal_errors,
-        ));
+        return Ok((Box::new(config_store), match_store, non_fatal_errors));
     }
 
     let config_dir = base_path.join("config");
@@ -68,17 +81,13 @@ pub fn load(base_path: &Path) -> Result<LoadableConfig> {
     let root_paths = config_store.get_all_match_paths();
 
     let (match_store, non_fatal_match_errors) =
-        matches::store::load(&root_paths.into_iter().collect::<Vec<String>>());
+        load_match_store(&root_paths.into_iter().collect::<Vec<String>>());
 
     let mut non_fatal_errors = Vec::new();
     non_fatal_errors.extend(non_fatal_config_errors);
     non_fatal_errors.extend(non_fatal_match_errors);
 
-    Ok((
-        Box::new(config_store),
-        Box::new(match_store),
-        non_fatal_errors,
-    ))
+    Ok((Box::new(config_store), match_store, non_fatal_errors))
 }
 
 #[derive(Error, Debug)]
@@ -188,6 +197,50 @@ mod tests {
         });
     }
 
+    #[test]
+    fn reload_picks_up_new_match_files() {
+        use_test_directory(|base, match_dir, config_dir| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(
+                base_file,
+                r#"
+      matches:
+        - trigger: "hello"
+          replace: "world"
+      "#,
+            )
+            .unwrap();
+
+            let config_file = config_dir.join("default.yml");
+            std::fs::write(config_file, "").unwrap();
+
+            let (_, match_store, errors) = load(base).unwrap();
+            assert_eq!(errors.len(), 0);
+
+            let another_file = match_dir.join("another.yml");
+            std::fs::write(
+                another_file,
+                r#"
+      matches:
+        - trigger: "hello2"
+          replace: "world2"
+      "#,
+            )
+            .unwrap();
+
+            let (config_store, match_store, errors) = reload(base, match_store).unwrap();
+            assert_eq!(errors.len(), 0);
+            assert_eq!(config_store.default().match_paths().len(), 2);
+            assert_eq!(
+                match_store
+                    .query(config_store.default().match_paths())
+                    .matches
+                    .len(),
+                2
+            );
+        });
+    }
+
     #[test]
     fn load_non_fatal_errors() {
         use_test_directory(|base, match_dir, config_dir| {
@@ -0,0 +1,657 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Format-preserving edits of YAML match files.
+//!
+//! Instead of serializing the whole match group again, which would drop
+//! comments and reorder keys, these functions only rewrite the lines
+//! belonging to the match being edited and leave the rest of the file untouched.
+
+use anyhow::Result;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::group::loader::yaml::{
+    parse::{YAMLMatch, YAMLMatchGroup},
+    try_convert_into_match,
+};
+use crate::util::{locate_yaml_key, yaml_content_indent};
+
+const MATCHES_KEY: &str = "matches";
+const DEFAULT_ITEM_INDENT: usize = 2;
+
+/// The fields of a match that can be set when adding or updating it.
+/// When updating a match, `None` fields are left unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct MatchFields {
+    pub trigger: Option<String>,
+    pub replace: Option<String>,
+    pub label: Option<String>,
+    pub word: Option<bool>,
+}
+
+/// Append a new match with the given trigger to the `matches` of the YAML file.
+pub fn add_match(yaml: &str, trigger: &str, fields: &MatchFields) -> Result<String> {
+    let mut document = Document::new(yaml);
+    let items = document.locate_items()?;
+
+    if items.iter().any(|item| item.has_trigger(trigger)) {
+        return Err(MatchEditError::DuplicateTrigger(trigger.to_string()).into());
+    }
+    let Some(replace) = &fields.replace else {
+        return Err(MatchEditError::MissingReplace.into());
+    };
+
+    let (insert_at, indent) = if let Some(last) = items.last() {
+        (last.end, last.indent)
+    } else {
+        document.prepare_empty_matches()?
+    };
+
+    let content_indent = indent + 2;
+    let mut lines = render_field(
+        "trigger",
+        trigger,
+        &format!("{}- ", " ".repeat(indent)),
+        content_indent,
+    )?;
+    let field_prefix = " ".repeat(content_indent);
+    lines.extend(render_field(
+        "replace",
+        replace,
+        &field_prefix,
+        content_indent,
+    )?);
+    if let Some(label) = &fields.label {
+        lines.extend(render_field("label", label, &field_prefix, content_indent)?);
+    }
+    if let Some(word) = &fields.word {
+        lines.extend(render_field("word", word, &field_prefix, content_indent)?);
+    }
+    document.lines.splice(insert_at..insert_at, lines);
+
+    let yaml = document.render();
+    validate(&yaml, trigger)?;
+    Ok(yaml)
+}
+
+/// Update the fields of the match with the given trigger, keeping the
+/// ones that are not specified.
+pub fn update_match(yaml: &str, trigger: &str, fields: &MatchFields) -> Result<String> {
+    let mut document = Document::new(yaml);
+    let items = document.locate_items()?;
+    let Some(item) = items.iter().find(|item| item.has_trigger(trigger)) else {
+        return Err(MatchEditError::NotFound(trigger.to_string()).into());
+    };
+
+    let mut final_trigger = trigger;
+    if let Some(new_trigger) = fields.trigger.as_deref().filter(|new| *new != trigger) {
+        if !item.has_single_trigger {
+            return Err(MatchEditError::MultipleTriggers(trigger.to_string()).into());
+        }
+        if items.iter().any(|item| item.has_trigger(new_trigger)) {
+            return Err(MatchEditError::DuplicateTrigger(new_trigger.to_string()).into());
+        }
+        final_trigger = new_trigger;
+    }
+
+    let mut span = (item.start, item.end);
+    if final_trigger != trigger {
+        document.set_field(&mut span, "trigger", &final_trigger)?;
+    }
+    if let Some(replace) = &fields.replace {
+        document.set_field(&mut span, "replace", replace)?;
+    }
+    if let Some(label) = &fields.label {
+        document.set_field(&mut span, "label", label)?;
+    }
+    if let Some(word) = &fields.word {
+        document.set_field(&mut span, "word", word)?;
+    }
+
+    let yaml = document.render();
+    validate(&yaml, final_trigger)?;
+    Ok(yaml)
+}
+
+/// Remove the match with the given trigger from the YAML file.
+pub fn remove_match(yaml: &str, trigger: &str) -> Result<String> {
+    let mut document = Document::new(yaml);
+    let items = document.locate_items()?;
+    let Some(item) = items.iter().find(|item| item.has_trigger(trigger)) else {
+        return Err(MatchEditError::NotFound(trigger.to_string()).into());
+    };
+
+    document.lines.drain(item.start..item.end);
+
+    let yaml = document.render();
+    YAMLMatchGroup::parse_from_str(&yaml)?;
+    Ok(yaml)
+}
+
+// Make sure the edited file is still valid, and that the edited match
+// didn't end up sharing its trigger with another one
+fn validate(yaml: &str, trigger: &str) -> Result<()> {
+    let group = YAMLMatchGroup::parse_from_str(yaml)?;
+    let mut candidates = group
+        .matches
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| triggers_of(m).any(|t| t == trigger));
+
+    let Some(edited) = candidates.next() else {
+        return Err(MatchEditError::NotFound(trigger.to_string()).into());
+    };
+    if candidates.next().is_some() {
+        return Err(MatchEditError::DuplicateTrigger(trigger.to_string()).into());
+    }
+
+    try_convert_into_match(edited, false)?;
+    Ok(())
+}
+
+fn triggers_of(yaml_match: &YAMLMatch) -> impl Iterator<Item = &String> {
+    yaml_match
+        .trigger
+        .iter()
+        .chain(yaml_match.triggers.iter().flatten())
+}
+
+// Render a `key: value` pair, where continuation lines (for example, those of
+// block scalars) are indented with the given indentation
+fn render_field(
+    key: &str,
+    value: &(impl Serialize + ?Sized),
+    prefix: &str,
+    indent: usize,
+) -> Result<Vec<String>> {
+    let rendered = serde_norway::to_string(value)?;
+    let rendered = rendered.strip_suffix('\n').unwrap_or(&rendered);
+    let mut rendered_lines = rendered.split('\n');
+
+    let mut lines = vec![format!(
+        "{prefix}{key}: {}",
+        rendered_lines.next().unwrap_or_default()
+    )];
+    lines.extend(rendered_lines.map(|line| {
+        if line.is_empty() {
+            String::new()
+        } else {
+            format!("{}{line}", " ".repeat(indent))
+        }
+    }));
+    Ok(lines)
+}
+
+fn is_content(line: &str) -> bool {
+    yaml_content_indent(line).is_some()
+}
+
+struct Document {
+    lines: Vec<String>,
+    line_ending: &'static str,
+    trailing_newline: bool,
+    bom: bool,
+}
+
+struct MatchItem {
+    // Range of lines of the item, excluding trailing blank lines and comments
+    start: usize,
+    end: usize,
+    indent: usize,
+    triggers: Vec<String>,
+    has_single_trigger: bool,
+}
+
+impl MatchItem {
+    fn has_trigger(&self, trigger: &str) -> bool {
+        self.triggers.iter().any(|t| t == trigger)
+    }
+}
+
+impl Document {
+    fn new(yaml: &str) -> Self {
+        let content = yaml.trim_start_matches('\u{FEFF}');
+        Self {
+            lines: content.lines().map(String::from).collect(),
+            line_ending: if content.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            },
+            trailing_newline: content.is_empty() || content.ends_with('\n'),
+            bom: content.len() != yaml.len(),
+        }
+    }
+
+    fn content(&self) -> String {
+        let mut content = self.lines.join(self.line_ending);
+        if self.trailing_newline && !self.lines.is_empty() {
+            content.push_str(self.line_ending);
+        }
+        content
+    }
+
+    fn render(&self) -> String {
+        if self.bom {
+            format!("\u{FEFF}{}", self.content())
+        } else {
+            self.content()
+        }
+    }
+
+    fn locate_items(&self) -> Result<Vec<MatchItem>> {
+        let group = YAMLMatchGroup::parse_from_str(&self.content())?;
+        let matches = group.matches.unwrap_or_default();
+        if matches.len() != group.locations.matches.len() {
+            return Err(MatchEditError::UnsupportedLayout.into());
+        }
+
+        let starts: Vec<(usize, usize)> = group
+            .locations
+            .matches
+            .iter()
+            .map(|location| (location.line - 1, location.column - 1))
+            .collect();
+
+        Ok(matches
+            .iter()
+            .enumerate()
+            .map(|(index, yaml_match)| {
+                let (start, indent) = starts[index];
+                let end = starts
+                    .get(index + 1)
+                    .map_or_else(|| self.block_end(start, indent), |(next, _)| *next);
+                MatchItem {
+                    start,
+                    end: self.trim_end(start, end),
+                    indent,
+                    triggers: triggers_of(yaml_match).cloned().collect(),
+                    has_single_trigger: yaml_match.trigger.is_some()
+                        && yaml_match.triggers.is_none(),
+                }
+            })
+            .collect())
+    }
+
+    // Returns the index of the first line after `start` that is not
+    // nested deeper than the given indentation
+    fn block_end(&self, start: usize, indent: usize) -> usize {
+        (start + 1..self.lines.len())
+            .find(|index| yaml_content_indent(&self.lines[*index]).is_some_and(|i| i <= indent))
+            .unwrap_or(self.lines.len())
+    }
+
+    fn trim_end(&self, start: usize, mut end: usize) -> usize {
+        while end > start + 1 && !is_content(&self.lines[end - 1]) {
+            end -= 1;
+        }
+        end
+    }
+
+    // Make sure the file has an empty `matches` key where items can be appended,
+    // returning the line at which they should be inserted and their indentation
+    fn prepare_empty_matches(&mut self) -> Result<(usize, usize)> {
+        let Some(location) = locate_yaml_key(&self.content(), MATCHES_KEY) else {
+            self.lines.push(format!("{MATCHES_KEY}:"));
+            return Ok((self.lines.len(), DEFAULT_ITEM_INDENT));
+        };
+
+        let index = location.line - 1;
+        let key_indent = location.column - 1;
+        let line = &self.lines[index];
+        let (_, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.split(" #").next().unwrap_or_default().trim();
+
+        match value {
+            "" => {}
+            "[]" | "null" | "~" => {
+                self.lines[index] = format!("{}{MATCHES_KEY}:", " ".repeat(key_indent));
+            }
+            _ => return Err(MatchEditError::UnsupportedLayout.into()),
+        }
+
+        Ok((index + 1, key_indent + DEFAULT_ITEM_INDENT))
+    }
+
+    // Replace the value of the given key of the item spanning the given lines,
+    // or insert it at the end of the item if missing. The span is updated to
+    // reflect the change in the number of lines.
+    fn set_field(
+        &mut self,
+        span: &mut (usize, usize),
+        key: &str,
+        value: &(impl Serialize + ?Sized),
+    ) -> Result<()> {
+        let (start, end) = *span;
+        let content_indent = self.item_content_indent(start, end);
+
+        let field = (start..end).find(|index| {
+            let line = &self.lines[*index];
+            let is_at_indent = *index == start || yaml_content_indent(line) == Some(content_indent);
+            is_at_indent
+                && line
+                    .get(content_indent..)
+                    .and_then(|text| text.strip_prefix(key))
+                    .and_then(|rest| rest.strip_prefix(':'))
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+        });
+
+        let (field_start, field_end, prefix) = if let Some(field_start) = field {
+            let field_end = (field_start + 1..end)
+                .find(|index| {
+                    yaml_content_indent(&self.lines[*index]).is_some_and(|i| i <= content_indent)
+                })
+                .unwrap_or(end);
+            (
+                field_start,
+                self.trim_end(field_start, field_end),
+                self.lines[field_start][..content_indent].to_string(),
+            )
+        } else {
+            (end, end, " ".repeat(content_indent))
+        };
+
+        let lines = render_field(key, value, &prefix, content_indent)?;
+        let added = lines.len();
+        self.lines.splice(field_start..field_end, lines);
+        span.1 = end + added - (field_end - field_start);
+        Ok(())
+    }
+
+    // Returns the indentation of the keys of the item starting at the given line
+    fn item_content_indent(&self, start: usize, end: usize) -> usize {
+        let line = &self.lines[start];
+        let indent = yaml_content_indent(line).unwrap_or_default();
+        let rest = &line[indent + 1..];
+        let inline_content = rest.trim_start_matches(' ');
+
+        if inline_content.trim().is_empty() || inline_content.starts_with('#') {
+            (start + 1..end)
+                .find_map(|index| yaml_content_indent(&self.lines[index]))
+                .unwrap_or(indent + 2)
+        } else {
+            indent + 1 + rest.len() - inline_content.len()
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MatchEditError {
+    #[error("no match with trigger `{0}` found")]
+    NotFound(String),
+
+    #[error("a match with trigger `{0}` already exists")]
+    DuplicateTrigger(String),
+
+    #[error("the trigger of match `{0}` can't be changed because it defines multiple triggers")]
+    MultipleTriggers(String),
+
+    #[error("a replacement is required to add a match")]
+    MissingReplace,
+
+    #[error("the matches of this file must be written as a block sequence to be edited")]
+    UnsupportedLayout,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"# espanso match file
+
+matches:
+  # Simple text replacement
+  - trigger: ":espanso"
+    replace: "Hi there!"
+
+  - trigger: ":sig"
+    replace: |
+      Best regards,
+      Jon
+    label: Signature
+
+  # Dates
+  - triggers: [":date", ":today"]
+    replace: "{{mydate}}"
+    vars:
+      - name: mydate
+        type: date
+        params:
+          format: "%m/%d/%Y"
+
+# trailing comment
+"#;
+
+    fn fields(replace: &str) -> MatchFields {
+        MatchFields {
+            replace: Some(replace.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn add_appends_after_last_match() {
+        let result = add_match(BASE, ":x", &fields("hello")).unwrap();
+        let expected = BASE.replace(
+            "          format: \"%m/%d/%Y\"\n",
+            "          format: \"%m/%d/%Y\"\n  - trigger: :x\n    replace: hello\n",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn add_renders_multiline_replace_as_block_scalar() {
+        let result = add_match(
+            "matches:\n  - trigger: a\n    replace: b\n",
+            ":x",
+            &MatchFields {
+                replace: Some("first\n\nsecond\n".to_string()),
+                label: Some("a: label".to_string()),
+                word: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+      result,
+      "matches:\n  - trigger: a\n    replace: b\n  - trigger: :x\n    replace: |\n      first\n\n      second\n    label: 'a: label'\n    word: true\n"
+    );
+
+        let group = YAMLMatchGroup::parse_from_str(&result).unwrap();
+        let added = &group.matches.unwrap()[1];
+        assert_eq!(added.replace.as_deref(), Some("first\n\nsecond\n"));
+        assert_eq!(added.label.as_deref(), Some("a: label"));
+    }
+
+    #[test]
+    fn add_creates_missing_matches_key() {
+        assert_eq!(
+            add_match("# empty\n", ":x", &fields("y")).unwrap(),
+            "# empty\nmatches:\n  - trigger: :x\n    replace: y\n"
+        );
+        assert_eq!(
+            add_match("", ":x", &fields("y")).unwrap(),
+            "matches:\n  - trigger: :x\n    replace: y\n"
+        );
+    }
+
+    #[test]
+    fn add_replaces_empty_flow_sequence() {
+        assert_eq!(
+            add_match(
+                "matches: [] # none yet\nglobal_vars: []\n",
+                ":x",
+                &fields("y")
+            )
+            .unwrap(),
+            "matches:\n  - trigger: :x\n    replace: y\nglobal_vars: []\n"
+        );
+    }
+
+    #[test]
+    fn add_preserves_line_endings() {
+        assert_eq!(
+            add_match(
+                "matches:\r\n- trigger: a\r\n  replace: b",
+                ":x",
+                &fields("y")
+            )
+            .unwrap(),
+            "matches:\r\n- trigger: a\r\n  replace: b\r\n- trigger: :x\r\n  replace: y"
+        );
+    }
+
+    #[test]
+    fn add_rejects_duplicate_trigger() {
+        let err = add_match(BASE, ":today", &fields("y")).unwrap_err();
+        assert!(matches!(
+          err.downcast_ref::<MatchEditError>(),
+          Some(MatchEditError::DuplicateTrigger(trigger)) if trigger == ":today"
+        ));
+    }
+
+    #[test]
+    fn add_requires_replace() {
+        let err = add_match(BASE, ":x", &MatchFields::default()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MatchEditError>(),
+            Some(MatchEditError::MissingReplace)
+        ));
+    }
+
+    #[test]
+    fn add_rejects_flow_style_matches() {
+        let err =
+            add_match("matches: [{trigger: a, replace: b}]\n", ":x", &fields("y")).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MatchEditError>(),
+            Some(MatchEditError::UnsupportedLayout)
+        ));
+    }
+
+    #[test]
+    fn update_replaces_block_scalar() {
+        let result = update_match(BASE, ":sig", &fields("Cheers")).unwrap();
+        let expected = BASE.replace(
+            "    replace: |\n      Best regards,\n      Jon\n",
+            "    replace: Cheers\n",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn update_inserts_missing_fields() {
+        let result = update_match(
+            BASE,
+            ":espanso",
+            &MatchFields {
+                label: Some("Greeting".to_string()),
+                word: Some(false),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let expected = BASE.replace(
+            "    replace: \"Hi there!\"\n",
+            "    replace: \"Hi there!\"\n    label: Greeting\n    word: false\n",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn update_renames_trigger() {
+        let result = update_match(
+            "matches:\n  -   trigger: a # first\n      replace: b\n",
+            "a",
+            &MatchFields {
+                trigger: Some(":a".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(result, "matches:\n  -   trigger: :a\n      replace: b\n");
+    }
+
+    #[test]
+    fn update_rejects_duplicate_trigger() {
+        let err = update_match(
+            BASE,
+            ":sig",
+            &MatchFields {
+                trigger: Some(":espanso".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+          err.downcast_ref::<MatchEditError>(),
+          Some(MatchEditError::DuplicateTrigger(trigger)) if trigger == ":espanso"
+        ));
+    }
+
+    #[test]
+    fn update_rejects_renaming_multiple_triggers() {
+        let err = update_match(
+            BASE,
+            ":date",
+            &MatchFields {
+                trigger: Some(":d".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MatchEditError>(),
+            Some(MatchEditError::MultipleTriggers(_))
+        ));
+    }
+
+    #[test]
+    fn update_missing_match_fails() {
+        let err = update_match(BASE, ":missing", &fields("y")).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MatchEditError>(),
+            Some(MatchEditError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn remove_keeps_surrounding_comments() {
+        let result = remove_match(BASE, ":sig").unwrap();
+        let expected = BASE.replace(
+      "  - trigger: \":sig\"\n    replace: |\n      Best regards,\n      Jon\n    label: Signature\n",
+      "",
+    );
+        assert_eq!(result, expected);
+
+        let result = remove_match(BASE, ":today").unwrap();
+        assert!(result.ends_with("  # Dates\n\n# trailing comment\n"));
+        assert!(!result.contains("mydate"));
+    }
+
+    #[test]
+    fn remove_missing_match_fails() {
+        let err = remove_match(BASE, ":missing").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MatchEditError>(),
+            Some(MatchEditError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,315 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::{
+    error::{NonFatalErrorSet, SourceLocation},
+    matches::group::MatchGroup,
+};
+
+use super::{
+    yaml::{
+        convert_match_group,
+        parse::{YAMLMatchGroup, YAMLMatchGroupLocations},
+    },
+    Importer,
+};
+
+pub struct JSONImporter {}
+
+impl JSONImporter {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Importer for JSONImporter {
+    fn is_supported(&self, extension: &str) -> bool {
+        extension == "json"
+    }
+
+    fn load_group(&self, path: &Path) -> Result<(MatchGroup, Option<NonFatalErrorSet>)> {
+        let content = std::fs::read_to_string(path)?;
+        let group = parse_json_match_group(&content).context("failed to parse JSON match group")?;
+
+        convert_match_group(path, group)
+    }
+}
+
+fn parse_json_match_group(json: &str) -> Result<YAMLMatchGroup> {
+    // Remove UTF-8 BOM if present
+    let json = json.trim_start_matches('\u{FEFF}');
+
+    // Treat empty files as empty groups, consistently with the YAML importer
+    if json.trim().is_empty() {
+        return Ok(YAMLMatchGroup::default());
+    }
+
+    let mut group: YAMLMatchGroup = serde_json::from_str(json)?;
+    group.locations = locate_json_items(json).validate(&group);
+    Ok(group)
+}
+
+enum JSONContainer {
+    Object { key: Option<String> },
+    Array { index: usize },
+}
+
+impl JSONContainer {
+    fn key(&self) -> Option<&str> {
+        match self {
+            JSONContainer::Object { key } => key.as_deref(),
+            JSONContainer::Array { .. } => None,
+        }
+    }
+}
+
+// Locate the items of the arrays declared in a match group, by scanning the
+// document for the position at which each array element starts.
+// The document is expected to be valid, as it has already been parsed.
+fn locate_json_items(json: &str) -> YAMLMatchGroupLocations {
+    let mut locations = YAMLMatchGroupLocations::default();
+
+    let mut stack: Vec<JSONContainer> = Vec::new();
+    let mut expecting_value = false;
+    let mut chars = json.chars();
+    let (mut line, mut column) = (1, 0);
+
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+            column = 0;
+            continue;
+        }
+        column += 1;
+
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let starts_value = expecting_value && !matches!(c, ',' | ':' | ']' | '}');
+        if starts_value {
+            expecting_value = false;
+            if matches!(stack.last(), Some(JSONContainer::Array { .. })) {
+                locate_json_array_item(
+                    &stack[..stack.len() - 1],
+                    SourceLocation::new(line, column),
+                    &mut locations,
+                );
+            }
+        }
+
+        match c {
+            '{' => stack.push(JSONContainer::Object { key: None }),
+            '[' => {
+                stack.push(JSONContainer::Array { index: 0 });
+                expecting_value = true;
+            }
+            '}' | ']' => {
+                stack.pop();
+                expecting_value = false;
+            }
+            ':' => expecting_value = true,
+            ',' => {
+                if let Some(JSONContainer::Array { index }) = stack.last_mut() {
+                    *index += 1;
+                    expecting_value = true;
+                }
+            }
+            '"' => {
+                let mut string = String::new();
+                while let Some(c) = chars.next() {
+                    column += 1;
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                column += 1;
+                                string.push(escaped);
+                            }
+                        }
+                        c => string.push(c),
+                    }
+                }
+
+                // Strings that don't start a value are object keys
+                if !starts_value {
+                    if let Some(JSONContainer::Object { key }) = stack.last_mut() {
+                        *key = Some(string);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    locations
+}
+
+// Record the location of an array element, given the containers enclosing the array
+fn locate_json_array_item(
+    parents: &[JSONContainer],
+    location: SourceLocation,
+    locations: &mut YAMLMatchGroupLocations,
+) {
+    match parents {
+        [root] => match root.key() {
+            Some("imports") => locations.imports.push(location),
+            Some("global_vars") => locations.global_vars.push(location),
+            Some("matches") => {
+                locations.matches.push(location);
+                locations.match_vars.push(Vec::new());
+            }
+            _ => {}
+        },
+        [root, JSONContainer::Array { index: match_index }, m]
+            if root.key() == Some("matches") && m.key() == Some("vars") =>
+        {
+            if let Some(vars) = locations.match_vars.get_mut(*match_index) {
+                vars.push(location);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        matches::{MatchCause, MatchEffect},
+        util::tests::use_test_directory,
+    };
+
+    #[test]
+    fn empty_json_is_empty_group() {
+        let group = parse_json_match_group("  \n").unwrap();
+        assert!(group.matches.is_none());
+    }
+
+    #[test]
+    fn invalid_json_is_rejected() {
+        assert!(parse_json_match_group("{ \"matches\": [ }").is_err());
+    }
+
+    #[test]
+    fn json_group_maps_correctly() {
+        use_test_directory(|_, match_dir, _| {
+            let file = match_dir.join("base.json");
+            std::fs::write(
+                &file,
+                r#"{
+  "global_vars": [
+    { "name": "name", "type": "echo", "params": { "echo": "John" } }
+  ],
+  "matches": [
+    { "trigger": ":hi", "replace": "Hello {{name}}\n\"quoted\"" },
+    { "regex": ":re(\\d)", "replace": "regex", "uppercase_style": "capitalize" }
+  ]
+}"#,
+            )
+            .unwrap();
+
+            let (group, non_fatal_error_set) = JSONImporter::new().load_group(&file).unwrap();
+
+            assert_eq!(group.global_vars.len(), 1);
+            assert_eq!(group.global_vars[0].name, "name");
+            assert_eq!(group.matches.len(), 2);
+            assert_eq!(
+                group.matches[0].effect,
+                MatchEffect::Text(crate::matches::TextEffect {
+                    replace: "Hello {{name}}\n\"quoted\"".to_string(),
+                    ..Default::default()
+                })
+            );
+            assert_eq!(
+                group.matches[1].cause,
+                MatchCause::Regex(crate::matches::RegexCause {
+                    regex: ":re(\\d)".to_string()
+                })
+            );
+
+            // Same warnings as the YAML importer
+            assert_eq!(non_fatal_error_set.unwrap().errors.len(), 1);
+        });
+    }
+
+    #[test]
+    fn json_errors_are_located_at_the_invalid_variable() {
+        use_test_directory(|_, match_dir, _| {
+            let file = match_dir.join("base.json");
+            std::fs::write(
+                &file,
+                r#"{
+  "imports": [],
+  "matches": [
+    { "trigger": ":a", "replace": "a", "vars": [] },
+    {
+      "trigger": ":b",
+      "replace": "{{first}} {{second}}",
+      "vars": [
+        { "name": "first", "type": "echo", "params": { "echo": "[\"quoted\"]" } },
+        { "name": "second", "type": "echo", "params": { "echo": 18446744073709551615 } }
+      ]
+    }
+  ]
+}"#,
+            )
+            .unwrap();
+
+            let (group, non_fatal_error_set) = JSONImporter::new().load_group(&file).unwrap();
+            assert_eq!(group.matches.len(), 1);
+
+            let errors = non_fatal_error_set.unwrap().errors;
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].location, Some(SourceLocation::new(10, 9)));
+        });
+    }
+
+    #[test]
+    fn locate_json_items_works_correctly() {
+        let json = r#"{"global_vars": [1, {"a": [2]}],
+ "matches": [{"vars": [{}, []]}, "x",
+   {"other": [3], "vars": [4]}]}"#;
+        let locations = locate_json_items(json);
+        assert_eq!(
+            locations.global_vars,
+            vec![SourceLocation::new(1, 18), SourceLocation::new(1, 21)]
+        );
+        assert_eq!(
+            locations.matches,
+            vec![
+                SourceLocation::new(2, 14),
+                SourceLocation::new(2, 34),
+                SourceLocation::new(3, 4),
+            ]
+        );
+        assert_eq!(
+            locations.match_vars,
+            vec![
+                vec![SourceLocation::new(2, 24), SourceLocation::new(2, 28)],
+                vec![],
+                vec![SourceLocation::new(3, 28)],
+            ]
+        );
+    }
+}
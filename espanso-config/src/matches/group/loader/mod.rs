@@ -23,10 +23,12 @@ use thiserror::Error;
 
 use crate::error::NonFatalErrorSet;
 
-use self::yaml::YAMLImporter;
+use self::{json::JSONImporter, toml::TOMLImporter, yaml::YAMLImporter};
 
 use super::MatchGroup;
 
+mod json;
+mod toml;
 pub mod yaml;
 
 trait Importer {
@@ -34,8 +36,13 @@ trait Importer {
     fn load_group(&self, path: &Path) -> Result<(MatchGroup, Option<NonFatalErrorSet>)>;
 }
 
-static IMPORTERS: LazyLock<Vec<Box<dyn Importer + Sync + Send>>> =
-    LazyLock::new(|| vec![Box::new(YAMLImporter::new())]);
+static IMPORTERS: LazyLock<Vec<Box<dyn Importer + Sync + Send>>> = LazyLock::new(|| {
+    vec![
+        Box::new(YAMLImporter::new()),
+        Box::new(TOMLImporter::new()),
+        Box::new(JSONImporter::new()),
+    ]
+});
 
 pub fn load_match_group(path: &Path) -> Result<(MatchGroup, Option<NonFatalErrorSet>)> {
     if let Some(extension) = path.extension() {
@@ -175,4 +182,52 @@ mod tests {
             assert_eq!(load_match_group(&file).unwrap().0.matches.len(), 1);
         });
     }
+
+    #[test]
+    fn load_group_toml_format() {
+        use_test_directory(|_, match_dir, _| {
+            let file = match_dir.join("base.toml");
+            std::fs::write(
+                &file,
+                r#"
+      [[matches]]
+      trigger = "hello"
+      replace = "world"
+      "#,
+            )
+            .unwrap();
+
+            assert_eq!(load_match_group(&file).unwrap().0.matches.len(), 1);
+        });
+    }
+
+    #[test]
+    fn load_group_json_format() {
+        use_test_directory(|_, match_dir, _| {
+            let file = match_dir.join("base.JSON");
+            std::fs::write(
+                &file,
+                r#"{ "matches": [{ "trigger": "hello", "replace": "world" }] }"#,
+            )
+            .unwrap();
+
+            assert_eq!(load_match_group(&file).unwrap().0.matches.len(), 1);
+        });
+    }
+
+    #[test]
+    fn load_group_json_parsing_error() {
+        use_test_directory(|_, match_dir, _| {
+            let file = match_dir.join("base.json");
+            std::fs::write(&file, "test").unwrap();
+
+            assert!(matches!(
+                load_match_group(&file)
+                    .unwrap_err()
+                    .downcast::<LoadError>()
+                    .unwrap(),
+                LoadError::ParsingError(_)
+            ));
+        });
+    }
 }
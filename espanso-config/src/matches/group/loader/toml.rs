@@ -0,0 +1,229 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::{
+    error::{NonFatalErrorSet, SourceLocation},
+    matches::group::MatchGroup,
+};
+
+use super::{
+    yaml::{
+        convert_match_group,
+        parse::{YAMLMatchGroup, YAMLMatchGroupLocations},
+    },
+    Importer,
+};
+
+pub struct TOMLImporter {}
+
+impl TOMLImporter {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Importer for TOMLImporter {
+    fn is_supported(&self, extension: &str) -> bool {
+        extension == "toml"
+    }
+
+    fn load_group(&self, path: &Path) -> Result<(MatchGroup, Option<NonFatalErrorSet>)> {
+        let content = std::fs::read_to_string(path)?;
+        let group = parse_toml_match_group(&content).context("failed to parse TOML match group")?;
+
+        convert_match_group(path, group)
+    }
+}
+
+fn parse_toml_match_group(toml: &str) -> Result<YAMLMatchGroup> {
+    // Remove UTF-8 BOM if present
+    let toml = toml.trim_start_matches('\u{FEFF}');
+
+    let mut group: YAMLMatchGroup = ::toml::from_str(toml)?;
+    group.locations = locate_toml_items(toml).validate(&group);
+    Ok(group)
+}
+
+// Locate the items declared as arrays of tables (`[[matches]]`, `[[matches.vars]]`, ...).
+// Items declared with inline arrays are not detected, in which case the validation
+// discards the locations of the whole array.
+fn locate_toml_items(toml: &str) -> YAMLMatchGroupLocations {
+    let mut locations = YAMLMatchGroupLocations::default();
+
+    for (index, line) in toml.lines().enumerate() {
+        let trimmed_line = line.trim_start();
+        let Some(header) = trimmed_line
+            .strip_prefix("[[")
+            .and_then(|rest| rest.split_once("]]"))
+            .map(|(header, _)| header.trim())
+        else {
+            continue;
+        };
+
+        let location = SourceLocation::new(index + 1, line.len() - trimmed_line.len() + 1);
+        match header {
+            "global_vars" => locations.global_vars.push(location),
+            "matches" => {
+                locations.matches.push(location);
+                locations.match_vars.push(Vec::new());
+            }
+            "matches.vars" => {
+                if let Some(vars) = locations.match_vars.last_mut() {
+                    vars.push(location);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    locations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        matches::{MatchCause, MatchEffect, TextEffect, TriggerCause, Value},
+        util::tests::use_test_directory,
+    };
+
+    #[test]
+    fn empty_toml_is_empty_group() {
+        let group = parse_toml_match_group("# only a comment\n").unwrap();
+        assert!(group.matches.is_none());
+    }
+
+    #[test]
+    fn invalid_toml_is_rejected() {
+        assert!(parse_toml_match_group("[[matches]\ntrigger = ").is_err());
+    }
+
+    #[test]
+    fn toml_group_maps_correctly() {
+        use_test_directory(|_, match_dir, _| {
+            let file = match_dir.join("base.toml");
+            std::fs::write(
+                &file,
+                r#"
+[[matches]]
+trigger = ":hi"
+replace = """
+Hello {{name}}
+  indented"""
+word = true
+
+[[matches.vars]]
+name = "name"
+type = "shell"
+params = { cmd = "echo John", trim = true }
+
+[[matches]]
+trigger = ":up"
+replace = "up"
+uppercase_style = "capitalize"
+"#,
+            )
+            .unwrap();
+
+            let (group, non_fatal_error_set) = TOMLImporter::new().load_group(&file).unwrap();
+
+            assert_eq!(group.matches.len(), 2);
+            assert_eq!(
+                group.matches[0].cause,
+                MatchCause::Trigger(TriggerCause {
+                    triggers: vec![":hi".to_string()],
+                    left_word: true,
+                    right_word: true,
+                    ..Default::default()
+                })
+            );
+
+            let MatchEffect::Text(TextEffect { replace, vars, .. }) = &group.matches[0].effect
+            else {
+                panic!("expected a text effect");
+            };
+            assert_eq!(replace, "Hello {{name}}\n  indented");
+            assert_eq!(vars.len(), 1);
+            assert_eq!(vars[0].var_type, "shell");
+            assert_eq!(
+                vars[0].params.get("cmd"),
+                Some(&Value::String("echo John".to_string()))
+            );
+            assert_eq!(vars[0].params.get("trim"), Some(&Value::Bool(true)));
+
+            // Same warnings as the YAML importer
+            assert_eq!(non_fatal_error_set.unwrap().errors.len(), 1);
+        });
+    }
+
+    #[test]
+    fn toml_errors_are_located_at_the_invalid_match() {
+        use_test_directory(|_, match_dir, _| {
+            let file = match_dir.join("base.toml");
+            std::fs::write(
+                &file,
+                r#"
+[[matches]]
+trigger = ":a"
+replace = "a"
+
+[[matches]]
+trigger = ":b"
+replace = "{{first}}"
+filter_title = "("
+
+  [[matches.vars]]
+  name = "first"
+  type = "echo"
+  params = { echo = "first" }
+"#,
+            )
+            .unwrap();
+
+            let (group, non_fatal_error_set) = TOMLImporter::new().load_group(&file).unwrap();
+            assert_eq!(group.matches.len(), 1);
+
+            let errors = non_fatal_error_set.unwrap().errors;
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].location, Some(SourceLocation::new(6, 1)));
+        });
+    }
+
+    #[test]
+    fn locate_toml_items_works_correctly() {
+        let toml = "[[matches]]\ntrigger = \":a\"\n\n[[matches]]\n  [[matches.vars]]\n  name = \"a\"\n  [[ matches.vars ]]\n\n[[global_vars]]\n";
+        let locations = locate_toml_items(toml);
+        assert_eq!(
+            locations.matches,
+            vec![SourceLocation::new(1, 1), SourceLocation::new(4, 1)]
+        );
+        assert_eq!(
+            locations.match_vars,
+            vec![
+                vec![],
+                vec![SourceLocation::new(5, 3), SourceLocation::new(7, 3)]
+            ]
+        );
+        assert_eq!(locations.global_vars, vec![SourceLocation::new(9, 1)]);
+    }
+}
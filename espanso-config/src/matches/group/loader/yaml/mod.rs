@@ -17,15 +17,19 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::LazyLock;
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::LazyLock,
+};
 
 use crate::{
+    config::CursorHintStrategy,
     counter::next_id,
-    error::{ErrorRecord, NonFatalErrorSet},
+    error::{ErrorRecord, NonFatalErrorSet, SourceLocation},
     matches::{
         group::{path::resolve_imports, MatchGroup},
-        ImageEffect, Match, Params, RegexCause, TextFormat, TextInjectMode, UpperCasingStyle,
-        Value, Variable,
+        AppFilter, HotkeyCause, ImageEffect, MacroEffect, MacroStep, Match, Params, RegexCause,
+        TextFormat, TextInjectMode, UpperCasingStyle, Value, Variable,
     },
 };
 use anyhow::{anyhow, bail, Context, Result};
@@ -33,7 +37,7 @@ use parse::YAMLMatchGroup;
 use regex::{Captures, Regex};
 
 use self::{
-    parse::{YAMLMatch, YAMLVariable},
+    parse::{YAMLMacroStep, YAMLMatch, YAMLVariable},
     util::convert_params,
 };
 use crate::matches::{MatchCause, MatchEffect, TextEffect, TriggerCause};
@@ -43,6 +47,9 @@ use super::Importer;
 pub mod parse;
 mod util;
 
+// Longer waits would block the injection of the following expansions for too long
+const MAX_MACRO_WAIT_MS: u64 = 10_000;
+
 static VAR_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new("\\{\\{\\s*(\\w+)(\\.\\w+)?\\s*\\}\\}").unwrap());
 static FORM_CONTROL_REGEX: LazyLock<Regex> =
@@ -71,55 +78,131 @@ impl Importer for YAMLImporter {
         let yaml_group =
             YAMLMatchGroup::parse_from_file(path).context("failed to parse YAML match group")?;
 
-        let mut non_fatal_errors = Vec::new();
+        convert_match_group(path, yaml_group)
+    }
+}
 
-        let mut global_vars = Vec::new();
-        for yaml_global_var in yaml_group.global_vars.clone().unwrap_or_default() {
-            match try_convert_into_variable(yaml_global_var, false) {
-                Ok((var, warnings)) => {
-                    global_vars.push(var);
-                    non_fatal_errors.extend(warnings.into_iter().map(ErrorRecord::warn));
-                }
-                Err(err) => {
-                    non_fatal_errors.push(ErrorRecord::error(err));
-                }
+/// Convert a parsed match group into a `MatchGroup`, collecting the non-fatal errors.
+/// Shared by all the importers that map onto the `YAMLMatchGroup` schema.
+pub fn convert_match_group(
+    path: &Path,
+    yaml_group: YAMLMatchGroup,
+) -> Result<(MatchGroup, Option<NonFatalErrorSet>)> {
+    let mut non_fatal_errors = Vec::new();
+
+    let locations = &yaml_group.locations;
+
+    let mut global_vars = Vec::new();
+    for (index, yaml_global_var) in yaml_group
+        .global_vars
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+    {
+        let location = locations.global_vars.get(index).copied();
+        match try_convert_into_variable(yaml_global_var, false) {
+            Ok((var, warnings)) => {
+                global_vars.push(var);
+                non_fatal_errors.extend(
+                    warnings
+                        .into_iter()
+                        .map(|warning| ErrorRecord::warn(warning).with_location(location)),
+                );
+            }
+            Err(err) => {
+                non_fatal_errors.push(ErrorRecord::error(err).with_location(location));
             }
         }
+    }
 
-        let mut matches = Vec::new();
-        for yaml_match in yaml_group.matches.clone().unwrap_or_default() {
-            match try_convert_into_match(yaml_match, false) {
-                Ok((m, warnings)) => {
-                    matches.push(m);
-                    non_fatal_errors.extend(warnings.into_iter().map(ErrorRecord::warn));
-                }
-                Err(err) => {
-                    non_fatal_errors.push(ErrorRecord::error(err));
-                }
+    let mut matches = Vec::new();
+    for (index, yaml_match) in yaml_group
+        .matches
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+    {
+        let location = locations.matches.get(index).copied();
+        let var_locations = locations
+            .match_vars
+            .get(index)
+            .map_or(&[][..], Vec::as_slice);
+        match try_convert_into_match(yaml_match.clone(), false) {
+            Ok((m, warnings)) => {
+                matches.push(m);
+                non_fatal_errors.extend(
+                    warnings
+                        .into_iter()
+                        .map(|warning| ErrorRecord::warn(warning).with_location(location)),
+                );
+            }
+            Err(err) => {
+                let location = locate_invalid_variable(&yaml_match, var_locations).or(location);
+                non_fatal_errors.push(ErrorRecord::error(err).with_location(location));
             }
         }
+    }
 
-        // Resolve imports
-        let (resolved_imports, import_errors) =
-            resolve_imports(path, &yaml_group.imports.unwrap_or_default())
-                .context("failed to resolve YAML match group imports")?;
-        non_fatal_errors.extend(import_errors);
-
-        let non_fatal_error_set = if non_fatal_errors.is_empty() {
-            None
-        } else {
-            Some(NonFatalErrorSet::new(path, non_fatal_errors))
-        };
+    // Resolve imports one by one, so that errors can be traced back to their declaration
+    let mut resolved_imports = Vec::new();
+    for (index, import) in yaml_group
+        .imports
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+    {
+        let location = locations.imports.get(index).copied();
+        let (imports, import_errors) =
+            resolve_imports(path, &[import]).context("failed to resolve match group imports")?;
+        resolved_imports.extend(imports);
+        non_fatal_errors.extend(
+            import_errors
+                .into_iter()
+                .map(|error| error.with_location(location)),
+        );
+    }
 
-        Ok((
-            MatchGroup {
-                imports: resolved_imports,
-                global_vars,
-                matches,
-            },
-            non_fatal_error_set,
-        ))
+    if let Some(base_dir) = path.parent() {
+        resolve_file_variable_paths(&mut global_vars, base_dir);
+        for m in &mut matches {
+            if let MatchEffect::Text(effect) = &mut m.effect {
+                resolve_file_variable_paths(&mut effect.vars, base_dir);
+            }
+        }
     }
+
+    let non_fatal_error_set = if non_fatal_errors.is_empty() {
+        None
+    } else {
+        Some(NonFatalErrorSet::new(path, non_fatal_errors))
+    };
+
+    Ok((
+        MatchGroup {
+            imports: resolved_imports,
+            global_vars,
+            matches,
+        },
+        non_fatal_error_set,
+    ))
+}
+
+// Returns the location of the first variable of the given match that can't be converted,
+// so that errors are reported next to the offending variable rather than the whole match
+fn locate_invalid_variable(
+    yaml_match: &YAMLMatch,
+    var_locations: &[SourceLocation],
+) -> Option<SourceLocation> {
+    yaml_match
+        .vars
+        .iter()
+        .flatten()
+        .zip(var_locations)
+        .find(|(yaml_var, _)| try_convert_into_variable((*yaml_var).clone(), false).is_err())
+        .map(|(_, location)| *location)
 }
 
 pub fn try_convert_into_match(
@@ -134,6 +217,16 @@ pub fn try_convert_into_match(
         ));
     }
 
+    if yaml_match.hotkey.is_some()
+        && (yaml_match.trigger.is_some()
+            || yaml_match.triggers.is_some()
+            || yaml_match.regex.is_some())
+    {
+        warnings.push(anyhow!(
+            "the 'hotkey' option cannot be combined with 'trigger', 'triggers' or 'regex', ignoring the hotkey"
+        ));
+    }
+
     let triggers = if let Some(trigger) = yaml_match.trigger {
         Some(vec![trigger])
     } else {
@@ -194,6 +287,8 @@ pub fn try_convert_into_match(
         })
     } else if let Some(regex) = yaml_match.regex {
         MatchCause::Regex(RegexCause { regex })
+    } else if let Some(hotkey) = yaml_match.hotkey {
+        MatchCause::Hotkey(HotkeyCause { hotkey })
     } else {
         MatchCause::None
     };
@@ -210,6 +305,20 @@ pub fn try_convert_into_match(
         None
     };
 
+    let cursor_hint_strategy = match yaml_match.cursor_hint_strategy.as_deref() {
+        Some(strategy) => {
+            let parsed = CursorHintStrategy::parse(strategy);
+            if parsed.is_none() {
+                warnings.push(anyhow!(
+                    "unrecognized cursor_hint_strategy: {:?}, falling back to the default",
+                    strategy
+                ));
+            }
+            parsed
+        }
+        None => None,
+    };
+
     let effect = if yaml_match.replace.is_some()
         || yaml_match.markdown.is_some()
         || yaml_match.html.is_some()
@@ -238,6 +347,9 @@ pub fn try_convert_into_match(
             vars,
             format,
             force_mode,
+            sequential_vars: yaml_match.sequential_vars.unwrap_or(false),
+            tab_stops: yaml_match.tab_stops.unwrap_or(false),
+            cursor_hint_strategy,
         })
     } else if let Some(form_layout) = yaml_match.form {
         // Replace all the form fields with actual variables
@@ -297,9 +409,22 @@ pub fn try_convert_into_match(
             vars,
             format: TextFormat::Plain,
             force_mode,
+            tab_stops: yaml_match.tab_stops.unwrap_or(false),
+            cursor_hint_strategy,
+            ..Default::default()
         })
     } else if let Some(image_path) = yaml_match.image_path {
         MatchEffect::Image(ImageEffect { path: image_path })
+    } else if let Some(yaml_steps) = yaml_match.macro_steps {
+        let steps = yaml_steps
+            .into_iter()
+            .enumerate()
+            .map(|(index, step)| {
+                try_convert_into_macro_step(step)
+                    .with_context(|| format!("invalid macro step #{}", index + 1))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        MatchEffect::Macro(MacroEffect { steps })
     } else {
         MatchEffect::None
     };
@@ -311,6 +436,13 @@ pub fn try_convert_into_match(
     );
     }
 
+    let filter = AppFilter {
+        title: parse_filter_regex("filter_title", yaml_match.filter_title.as_deref())?,
+        class: parse_filter_regex("filter_class", yaml_match.filter_class.as_deref())?,
+        exec: parse_filter_regex("filter_exec", yaml_match.filter_exec.as_deref())?,
+        os: yaml_match.filter_os,
+    };
+
     Ok((
         Match {
             cause,
@@ -318,11 +450,86 @@ pub fn try_convert_into_match(
             label: yaml_match.label,
             id: next_id(),
             search_terms: yaml_match.search_terms.unwrap_or_default(),
+            filter,
         },
         warnings,
     ))
 }
 
+fn parse_filter_regex(name: &str, filter: Option<&str>) -> Result<Option<Regex>> {
+    filter
+        .map(|filter| Regex::new(filter).with_context(|| format!("invalid '{name}' regex")))
+        .transpose()
+}
+
+// Relative paths of `file` variables are resolved against the directory of the
+// match file declaring them. Paths starting with a placeholder (like %CONFIG%)
+// or a variable are resolved at render time. Existing files are canonicalized,
+// so that paths like `../email.md` compare equal to the ones of the file watcher.
+fn resolve_file_variable_paths(vars: &mut [Variable], base_dir: &Path) {
+    for var in vars.iter_mut().filter(|var| var.var_type == "file") {
+        if let Some(Value::String(path)) = var.params.get_mut("path") {
+            if !path.starts_with('%') && !path.starts_with("{{") && Path::new(path).is_relative() {
+                let full_path = base_dir.join(&*path);
+                let full_path = dunce::canonicalize(&full_path)
+                    .unwrap_or_else(|_| remove_dot_components(&full_path));
+                *path = full_path.to_string_lossy().to_string();
+            }
+        }
+    }
+}
+
+fn remove_dot_components(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn try_convert_into_macro_step(yaml_step: YAMLMacroStep) -> Result<MacroStep> {
+    let mut steps = Vec::new();
+    if let Some(text) = yaml_step.text {
+        steps.push(MacroStep::Text(text));
+    }
+    if let Some(keys) = yaml_step.keys {
+        if keys.trim().is_empty() {
+            bail!("the 'keys' field must contain at least one key, for example: {{Tab}}");
+        }
+        steps.push(MacroStep::Keys(keys));
+    }
+    if let Some(wait) = yaml_step.wait {
+        if wait > MAX_MACRO_WAIT_MS {
+            bail!(
+                "the 'wait' step can't exceed {} milliseconds, but got {}",
+                MAX_MACRO_WAIT_MS,
+                wait
+            );
+        }
+        steps.push(MacroStep::Wait(wait));
+    }
+    if let Some(paste) = yaml_step.paste {
+        steps.push(MacroStep::Paste(paste));
+    }
+
+    if steps.len() != 1 {
+        bail!("each macro step must specify exactly one of 'text', 'keys', 'wait' or 'paste'");
+    }
+
+    Ok(steps.remove(0))
+}
+
 pub fn try_convert_into_variable(
     yaml_var: YAMLVariable,
     use_compatibility_mode: bool,
@@ -344,6 +551,7 @@ pub fn try_convert_into_variable(
 mod tests {
     use super::*;
     use crate::{
+        error::{locate_error, ErrorLevel, SourceLocation},
         matches::{Match, Params, Value},
         util::tests::use_test_directory,
     };
@@ -422,6 +630,154 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hotkey_maps_correctly() {
+        assert_eq!(
+            create_match(
+                r#"
+        hotkey: "CTRL+ALT+D"
+        replace: "world"
+        "#
+            )
+            .unwrap(),
+            Match {
+                cause: MatchCause::Hotkey(HotkeyCause {
+                    hotkey: "CTRL+ALT+D".to_string(),
+                }),
+                effect: MatchEffect::Text(TextEffect {
+                    replace: "world".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn hotkey_with_trigger_is_ignored_with_warning() {
+        let (m, warnings) = create_match_with_warnings(
+            r#"
+        trigger: "Hello"
+        hotkey: "CTRL+ALT+D"
+        replace: "world"
+        "#,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            m.cause,
+            MatchCause::Trigger(TriggerCause {
+                triggers: vec!["Hello".to_string()],
+                ..Default::default()
+            })
+        );
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn filters_map_correctly() {
+        let m = create_match(
+            r#"
+        trigger: "Hello"
+        replace: "world"
+        filter_class: "thunderbird"
+        filter_title: "Write:"
+        filter_exec: "bin"
+        filter_os: "linux"
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(m.filter.class.unwrap().as_str(), "thunderbird");
+        assert_eq!(m.filter.title.unwrap().as_str(), "Write:");
+        assert_eq!(m.filter.exec.unwrap().as_str(), "bin");
+        assert_eq!(m.filter.os.as_deref(), Some("linux"));
+    }
+
+    #[test]
+    fn invalid_filter_regex_is_rejected() {
+        assert!(create_match(
+            r#"
+        trigger: "Hello"
+        replace: "world"
+        filter_class: "("
+        "#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn macro_maps_correctly() {
+        assert_eq!(
+            create_match(
+                r#"
+        trigger: ":form"
+        macro:
+          - text: "John"
+          - keys: "{Tab}"
+          - wait: 200
+          - paste: "Doe"
+          - keys: "{Ctrl+A}{Enter}"
+        "#
+            )
+            .unwrap(),
+            Match {
+                cause: MatchCause::Trigger(TriggerCause {
+                    triggers: vec![":form".to_string()],
+                    ..Default::default()
+                }),
+                effect: MatchEffect::Macro(MacroEffect {
+                    steps: vec![
+                        MacroStep::Text("John".to_string()),
+                        MacroStep::Keys("{Tab}".to_string()),
+                        MacroStep::Wait(200),
+                        MacroStep::Paste("Doe".to_string()),
+                        MacroStep::Keys("{Ctrl+A}{Enter}".to_string()),
+                    ],
+                }),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn macro_with_invalid_steps_is_rejected() {
+        assert!(create_match(
+            r#"
+        trigger: ":form"
+        macro:
+          - text: "John"
+            keys: "{Tab}"
+        "#,
+        )
+        .is_err());
+        assert!(create_match(
+            r#"
+        trigger: ":form"
+        macro:
+          - {}
+        "#,
+        )
+        .is_err());
+        assert!(create_match(
+            r#"
+        trigger: ":form"
+        macro:
+          - keys: " "
+        "#,
+        )
+        .is_err());
+        assert!(create_match(
+            r#"
+        trigger: ":form"
+        macro:
+          - wait: 10001
+        "#,
+        )
+        .is_err());
+    }
+
     #[test]
     fn word_maps_correctly() {
         assert_eq!(
@@ -811,6 +1167,93 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sequential_vars_maps_correctly() {
+        let vars = vec![Variable {
+            name: "var1".to_string(),
+            var_type: "test".to_string(),
+            ..Default::default()
+        }];
+        assert_eq!(
+            create_match(
+                r#"
+        trigger: "Hello"
+        replace: "world"
+        sequential_vars: true
+        vars:
+          - name: var1
+            type: test
+        "#
+            )
+            .unwrap(),
+            Match {
+                cause: MatchCause::Trigger(TriggerCause {
+                    triggers: vec!["Hello".to_string()],
+                    ..Default::default()
+                }),
+                effect: MatchEffect::Text(TextEffect {
+                    replace: "world".to_string(),
+                    vars,
+                    sequential_vars: true,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn tab_stops_maps_correctly() {
+        assert_eq!(
+            create_match(
+                r#"
+        trigger: "Hello"
+        replace: "Dear $1,\n\n$0"
+        tab_stops: true
+        "#
+            )
+            .unwrap(),
+            Match {
+                cause: MatchCause::Trigger(TriggerCause {
+                    triggers: vec!["Hello".to_string()],
+                    ..Default::default()
+                }),
+                effect: MatchEffect::Text(TextEffect {
+                    replace: "Dear $1,\n\n$0".to_string(),
+                    tab_stops: true,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn cursor_hint_strategy_maps_correctly() {
+        assert_eq!(
+            create_match(
+                r#"
+        trigger: "Hello"
+        replace: "a$|$\nb"
+        cursor_hint_strategy: lines
+        "#
+            )
+            .unwrap(),
+            Match {
+                cause: MatchCause::Trigger(TriggerCause {
+                    triggers: vec!["Hello".to_string()],
+                    ..Default::default()
+                }),
+                effect: MatchEffect::Text(TextEffect {
+                    replace: "a$|$\nb".to_string(),
+                    cursor_hint_strategy: Some(CursorHintStrategy::Lines),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        );
+    }
+
     #[test]
     fn vars_no_params_maps_correctly() {
         let vars = vec![Variable {
@@ -918,6 +1361,65 @@ mod tests {
         });
     }
 
+    #[test]
+    fn importer_resolves_file_variable_paths() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(
+                &base_file,
+                r#"
+      global_vars:
+        - name: "global"
+          type: "file"
+          params:
+            path: "templates/signature.txt"
+
+      matches:
+        - trigger: "hello"
+          replace: "{{relative}} {{config}} {{injected}}"
+          vars:
+            - name: "relative"
+              type: "file"
+              params:
+                path: "../email.md"
+            - name: "config"
+              type: "file"
+              params:
+                path: "%CONFIG%/email.md"
+            - name: "injected"
+              type: "file"
+              params:
+                path: "{{global}}"
+      "#,
+            )
+            .unwrap();
+
+            let importer = YAMLImporter::new();
+            let (group, _) = importer.load_group(&base_file).unwrap();
+
+            let path_of =
+                |var: &Variable| var.params.get("path").unwrap().as_string().unwrap().clone();
+            assert_eq!(
+                path_of(&group.global_vars[0]),
+                match_dir.join("templates/signature.txt").to_string_lossy()
+            );
+
+            let MatchEffect::Text(effect) = &group.matches[0].effect else {
+                panic!("expected text effect");
+            };
+            assert_eq!(
+                path_of(&effect.vars[0]),
+                match_dir
+                    .parent()
+                    .unwrap()
+                    .join("email.md")
+                    .to_string_lossy()
+            );
+            assert_eq!(path_of(&effect.vars[1]), "%CONFIG%/email.md");
+            assert_eq!(path_of(&effect.vars[2]), "{{global}}");
+        });
+    }
+
     #[test]
     fn importer_invalid_syntax() {
         use_test_directory(|_, match_dir, _| {
@@ -936,4 +1438,103 @@ mod tests {
             assert!(importer.load_group(&base_file).is_err());
         });
     }
+
+    #[test]
+    fn importer_reports_error_locations() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(
+                &base_file,
+                r#"
+imports:
+  - "missing.yml"
+
+matches:
+  - trigger: "hello"
+    replace: "world"
+
+  - trigger: "invalid"
+    replace: "regex"
+    filter_title: "("
+
+  - trigger: "case"
+    replace: "style"
+    uppercase_style: "capitalize"
+"#,
+            )
+            .unwrap();
+
+            let importer = YAMLImporter::new();
+            let (group, non_fatal_error_set) = importer.load_group(&base_file).unwrap();
+            assert_eq!(group.matches.len(), 2);
+
+            let locations: Vec<(ErrorLevel, Option<SourceLocation>)> = non_fatal_error_set
+                .unwrap()
+                .errors
+                .into_iter()
+                .map(|record| (record.level, record.location))
+                .collect();
+            assert_eq!(
+                locations,
+                vec![
+                    (ErrorLevel::Error, Some(SourceLocation::new(9, 3))),
+                    (ErrorLevel::Warning, Some(SourceLocation::new(13, 3))),
+                    (ErrorLevel::Error, Some(SourceLocation::new(3, 3))),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn importer_reports_invalid_variable_locations() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(
+                &base_file,
+                r#"
+matches:
+  - trigger: "first"
+    replace: "{{a}}"
+    vars:
+      - name: a
+        type: echo
+        params:
+          echo: "a"
+
+  - trigger: "second"
+    replace: "{{b}} {{c}}"
+    vars:
+      - name: b
+        type: echo
+        params:
+          echo: "b"
+      - name: c
+        type: echo
+        params:
+          1: "invalid key"
+"#,
+            )
+            .unwrap();
+
+            let (group, non_fatal_error_set) = YAMLImporter::new().load_group(&base_file).unwrap();
+            assert_eq!(group.matches.len(), 1);
+
+            let errors = non_fatal_error_set.unwrap().errors;
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].level, ErrorLevel::Error);
+            assert_eq!(errors[0].location, Some(SourceLocation::new(18, 7)));
+        });
+    }
+
+    #[test]
+    fn importer_invalid_syntax_has_location() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(&base_file, "matches:\n  - trigger: \"a\"\n    replace: [\n").unwrap();
+
+            let importer = YAMLImporter::new();
+            let error = importer.load_group(&base_file).unwrap_err();
+            assert!(locate_error(&error).is_some());
+        });
+    }
 }
@@ -23,9 +23,10 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_norway::Mapping;
 
-use crate::util::is_yaml_empty;
+use crate::error::SourceLocation;
+use crate::util::{is_yaml_empty, locate_yaml_nested_sequence_items, locate_yaml_sequence_items};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct YAMLMatchGroup {
     #[serde(default)]
     pub imports: Option<Vec<String>>,
@@ -35,6 +36,70 @@ pub struct YAMLMatchGroup {
 
     #[serde(default)]
     pub matches: Option<Vec<YAMLMatch>>,
+
+    #[serde(skip)]
+    pub locations: YAMLMatchGroupLocations,
+}
+
+/// Locations of the items declared in a match group file, used to
+/// point the user to the right place when reporting errors.
+/// Each vector is either empty or has exactly one entry per item.
+#[derive(Debug, Clone, Default)]
+pub struct YAMLMatchGroupLocations {
+    pub imports: Vec<SourceLocation>,
+    pub global_vars: Vec<SourceLocation>,
+    pub matches: Vec<SourceLocation>,
+    /// Locations of the `vars` items of each match, indexed as `matches`
+    pub match_vars: Vec<Vec<SourceLocation>>,
+}
+
+impl YAMLMatchGroupLocations {
+    fn locate(yaml: &str, group: &YAMLMatchGroup) -> Self {
+        let matches = locate_yaml_sequence_items(yaml, "matches");
+        let match_vars = matches
+            .iter()
+            .map(|item| locate_yaml_nested_sequence_items(yaml, *item, "vars"))
+            .collect();
+
+        Self {
+            imports: locate_yaml_sequence_items(yaml, "imports"),
+            global_vars: locate_yaml_sequence_items(yaml, "global_vars"),
+            matches,
+            match_vars,
+        }
+        .validate(group)
+    }
+
+    /// Discard the locations that don't correspond one-to-one with the parsed items,
+    /// as the scanners used to find them don't support every syntax of the source formats.
+    #[must_use]
+    pub fn validate(self, group: &YAMLMatchGroup) -> Self {
+        fn check<T>(locations: Vec<SourceLocation>, items: Option<&Vec<T>>) -> Vec<SourceLocation> {
+            if Some(locations.len()) == items.map(Vec::len) {
+                locations
+            } else {
+                Vec::new()
+            }
+        }
+
+        let matches = check(self.matches, group.matches.as_ref());
+        let match_vars = if matches.is_empty() || self.match_vars.len() != matches.len() {
+            Vec::new()
+        } else {
+            self.match_vars
+                .into_iter()
+                .zip(group.matches.iter().flatten())
+                .map(|(locations, m)| check(locations, m.vars.as_ref()))
+                .collect()
+        };
+
+        Self {
+            imports: check(self.imports, group.imports.as_ref()),
+            global_vars: check(self.global_vars, group.global_vars.as_ref()),
+            matches,
+            match_vars,
+        }
+    }
 }
 
 impl YAMLMatchGroup {
@@ -49,7 +114,9 @@ impl YAMLMatchGroup {
             )?);
         }
 
-        Ok(serde_norway::from_str(yaml)?)
+        let mut group: Self = serde_norway::from_str(yaml)?;
+        group.locations = YAMLMatchGroupLocations::locate(yaml, &group);
+        Ok(group)
     }
 
     pub fn parse_from_file(path: &Path) -> Result<Self> {
@@ -72,12 +139,18 @@ pub struct YAMLMatch {
     #[serde(default)]
     pub regex: Option<String>,
 
+    #[serde(default)]
+    pub hotkey: Option<String>,
+
     #[serde(default)]
     pub replace: Option<String>,
 
     #[serde(default)]
     pub image_path: Option<String>,
 
+    #[serde(default, rename = "macro")]
+    pub macro_steps: Option<Vec<YAMLMacroStep>>,
+
     #[serde(default)]
     pub form: Option<String>,
 
@@ -87,6 +160,15 @@ pub struct YAMLMatch {
     #[serde(default)]
     pub vars: Option<Vec<YAMLVariable>>,
 
+    #[serde(default)]
+    pub sequential_vars: Option<bool>,
+
+    #[serde(default)]
+    pub tab_stops: Option<bool>,
+
+    #[serde(default)]
+    pub cursor_hint_strategy: Option<String>,
+
     #[serde(default)]
     pub word: Option<bool>,
 
@@ -119,6 +201,33 @@ pub struct YAMLMatch {
 
     #[serde(default)]
     pub search_terms: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub filter_title: Option<String>,
+
+    #[serde(default)]
+    pub filter_class: Option<String>,
+
+    #[serde(default)]
+    pub filter_exec: Option<String>,
+
+    #[serde(default)]
+    pub filter_os: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct YAMLMacroStep {
+    #[serde(default)]
+    pub text: Option<String>,
+
+    #[serde(default)]
+    pub keys: Option<String>,
+
+    #[serde(default)]
+    pub wait: Option<u64>,
+
+    #[serde(default)]
+    pub paste: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
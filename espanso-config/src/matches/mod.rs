@@ -19,10 +19,14 @@
 
 use enum_as_inner::EnumAsInner;
 use ordered_float::OrderedFloat;
+use regex::Regex;
 use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
 
+use crate::config::{util::os_matches, AppProperties, CursorHintStrategy};
 use crate::counter::StructId;
 
+pub mod edit;
 pub(crate) mod group;
 pub mod store;
 
@@ -36,6 +40,8 @@ pub struct Match {
     // Metadata
     pub label: Option<String>,
     pub search_terms: Vec<String>,
+
+    pub filter: AppFilter,
 }
 
 impl Default for Match {
@@ -46,6 +52,7 @@ impl Default for Match {
             label: None,
             id: 0,
             search_terms: vec![],
+            filter: AppFilter::default(),
         }
     }
 }
@@ -58,6 +65,8 @@ impl Match {
             &text_effect.replace
         } else if let MatchEffect::Image(_) = &self.effect {
             "Image content"
+        } else if let MatchEffect::Macro(_) = &self.effect {
+            "Macro"
         } else {
             "No description available for this match"
         }
@@ -76,6 +85,65 @@ impl Match {
     }
 }
 
+// Filters
+
+/// Restricts a match to the applications that satisfy all the specified filters.
+/// An empty filter (the default) accepts every application.
+#[derive(Debug, Clone, Default)]
+pub struct AppFilter {
+    pub title: Option<Regex>,
+    pub class: Option<Regex>,
+    pub exec: Option<Regex>,
+    pub os: Option<String>,
+}
+
+impl AppFilter {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.class.is_none() && self.exec.is_none() && self.os.is_none()
+    }
+
+    pub fn is_match(&self, app: &AppProperties) -> bool {
+        let is_os_match = self.os.as_deref().is_none_or(os_matches);
+        let is_title_match = is_property_match(self.title.as_ref(), app.title);
+        let is_class_match = is_property_match(self.class.as_ref(), app.class);
+        let is_exec_match = is_property_match(self.exec.as_ref(), app.exec);
+
+        is_os_match && is_title_match && is_class_match && is_exec_match
+    }
+
+    fn patterns(&self) -> [Option<&str>; 4] {
+        [
+            self.title.as_ref().map(Regex::as_str),
+            self.class.as_ref().map(Regex::as_str),
+            self.exec.as_ref().map(Regex::as_str),
+            self.os.as_deref(),
+        ]
+    }
+}
+
+fn is_property_match(regex: Option<&Regex>, property: Option<&str>) -> bool {
+    match (regex, property) {
+        (Some(regex), Some(property)) => regex.is_match(property),
+        (Some(_), None) => false,
+        (None, _) => true,
+    }
+}
+
+// Regex does not implement equality and hashing, so we compare the source patterns instead
+impl PartialEq for AppFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns() == other.patterns()
+    }
+}
+
+impl Eq for AppFilter {}
+
+impl Hash for AppFilter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.patterns().hash(state);
+    }
+}
+
 // Causes
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq, EnumAsInner)]
@@ -83,7 +151,7 @@ pub enum MatchCause {
     None,
     Trigger(TriggerCause),
     Regex(RegexCause),
-    // TODO: shortcut
+    Hotkey(HotkeyCause),
 }
 
 impl MatchCause {
@@ -91,25 +159,27 @@ impl MatchCause {
         match &self {
             Self::Trigger(trigger_cause) => trigger_cause.triggers.first().map(String::as_str),
             Self::Regex(trigger_cause) => Some(trigger_cause.regex.as_str()),
+            Self::Hotkey(hotkey_cause) => Some(hotkey_cause.hotkey.as_str()),
             Self::None => None,
         }
-        // TODO: insert rendering for hotkey/shortcut
     }
 
     pub fn long_description(&self) -> String {
         match &self {
             Self::Trigger(trigger_cause) => format!("triggers: {:?}", trigger_cause.triggers),
             Self::Regex(trigger_cause) => format!("regex: {:?}", trigger_cause.regex),
+            Self::Hotkey(hotkey_cause) => format!("hotkey: {:?}", hotkey_cause.hotkey),
             Self::None => "No description available".to_owned(),
         }
-        // TODO: insert rendering for hotkey/shortcut
     }
 
     pub fn search_terms(&self) -> Vec<&str> {
-        if let Self::Trigger(trigger_cause) = &self {
-            trigger_cause.triggers.iter().map(String::as_str).collect()
-        } else {
-            vec![]
+        match &self {
+            Self::Trigger(trigger_cause) => {
+                trigger_cause.triggers.iter().map(String::as_str).collect()
+            }
+            Self::Hotkey(hotkey_cause) => vec![hotkey_cause.hotkey.as_str()],
+            Self::Regex(_) | Self::None => vec![],
         }
     }
 }
@@ -149,6 +219,11 @@ pub struct RegexCause {
     pub regex: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct HotkeyCause {
+    pub hotkey: String,
+}
+
 // Effects
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, EnumAsInner)]
@@ -156,6 +231,7 @@ pub enum MatchEffect {
     None,
     Text(TextEffect),
     Image(ImageEffect),
+    Macro(MacroEffect),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -164,6 +240,9 @@ pub struct TextEffect {
     pub vars: Vec<Variable>,
     pub format: TextFormat,
     pub force_mode: Option<TextInjectMode>,
+    pub sequential_vars: bool,
+    pub tab_stops: bool,
+    pub cursor_hint_strategy: Option<CursorHintStrategy>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -186,6 +265,9 @@ impl Default for TextEffect {
             vars: Vec::new(),
             format: TextFormat::Plain,
             force_mode: None,
+            sequential_vars: false,
+            tab_stops: false,
+            cursor_hint_strategy: None,
         }
     }
 }
@@ -195,6 +277,24 @@ pub struct ImageEffect {
     pub path: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct MacroEffect {
+    pub steps: Vec<MacroStep>,
+}
+
+/// A single action of a macro, executed in order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MacroStep {
+    // Type the given text
+    Text(String),
+    // Press a sequence of key combinations, such as "{Ctrl+A}{Delete}"
+    Keys(String),
+    // Wait for the given number of milliseconds
+    Wait(u64),
+    // Paste the given text through the clipboard
+    Paste(String),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Variable {
     pub id: StructId,
@@ -253,6 +353,12 @@ mod tests {
         }
     }
 
+    fn hotkey_cause() -> HotkeyCause {
+        HotkeyCause {
+            hotkey: "CTRL+ALT+D".to_string(),
+        }
+    }
+
     #[test]
     fn match_cause_trigger_description() {
         let trigger = trigger_cause();
@@ -266,6 +372,65 @@ mod tests {
         assert_eq!(MatchCause::Regex(regex).description(), Some(":greet\\d"));
     }
 
+    #[test]
+    fn match_cause_hotkey_description() {
+        let hotkey = hotkey_cause();
+        assert_eq!(MatchCause::Hotkey(hotkey).description(), Some("CTRL+ALT+D"));
+    }
+
+    fn app<'a>(
+        title: Option<&'a str>,
+        class: Option<&'a str>,
+        exec: Option<&'a str>,
+    ) -> AppProperties<'a> {
+        AppProperties { title, class, exec }
+    }
+
+    #[test]
+    fn app_filter_empty_matches_everything() {
+        let filter = AppFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.is_match(&app(None, None, None)));
+        assert!(filter.is_match(&app(Some("Inbox"), Some("thunderbird"), None)));
+    }
+
+    #[test]
+    fn app_filter_requires_all_filters() {
+        let filter = AppFilter {
+            class: Some(Regex::new("thunderbird").unwrap()),
+            title: Some(Regex::new("Write:").unwrap()),
+            ..Default::default()
+        };
+        assert!(!filter.is_empty());
+        assert!(filter.is_match(&app(Some("Write: hello"), Some("thunderbird"), None)));
+        assert!(!filter.is_match(&app(Some("Inbox"), Some("thunderbird"), None)));
+        assert!(!filter.is_match(&app(Some("Write: hello"), Some("kitty"), None)));
+        assert!(!filter.is_match(&app(Some("Write: hello"), None, None)));
+    }
+
+    #[test]
+    fn app_filter_os() {
+        let filter = AppFilter {
+            os: Some("invalid".to_string()),
+            ..Default::default()
+        };
+        assert!(!filter.is_match(&app(None, None, None)));
+    }
+
+    #[test]
+    fn app_filter_equality_uses_patterns() {
+        let first = AppFilter {
+            exec: Some(Regex::new("bash").unwrap()),
+            ..Default::default()
+        };
+        let second = AppFilter {
+            exec: Some(Regex::new("bash").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(first, second);
+        assert_ne!(first, AppFilter::default());
+    }
+
     #[test]
     fn match_cause_trigger_long_description() {
         let trigger = trigger_cause();
@@ -285,4 +450,23 @@ mod tests {
             r#"regex: ":greet\\d""#
         );
     }
+
+    #[test]
+    fn match_cause_hotkey_search_terms() {
+        let hotkey = hotkey_cause();
+        assert_eq!(
+            MatchCause::Hotkey(hotkey).search_terms(),
+            vec!["CTRL+ALT+D"]
+        );
+    }
+
+    #[test]
+    fn match_cause_hotkey_long_description() {
+        let hotkey = hotkey_cause();
+
+        assert_eq!(
+            MatchCause::Hotkey(hotkey).long_description(),
+            r#"hotkey: "CTRL+ALT+D""#
+        );
+    }
 }
@@ -0,0 +1,176 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{cell::RefCell, collections::HashMap};
+
+use super::{
+    default::{DefaultMatchStore, GroupPositions},
+    MatchSet, MatchStore,
+};
+use crate::error::NonFatalErrorSet;
+
+/// A `MatchStore` that memoizes the result of each `paths` query, so that
+/// repeated queries (for example, once for every keystroke when multiple app
+/// profiles are defined) don't traverse the match groups and their imports again.
+/// Match groups can't change without a reload, which starts from an empty cache.
+///
+/// As a `MatchSet` borrows the groups owned by the store, the cache keeps its
+/// positions instead: these are contiguous ranges of the groups' matches, so
+/// serving a cached query only copies the references of a few slices.
+pub struct CachingMatchStore {
+    store: DefaultMatchStore,
+    cache: RefCell<HashMap<Vec<String>, Vec<GroupPositions>>>,
+}
+
+impl CachingMatchStore {
+    pub fn new(store: DefaultMatchStore) -> Self {
+        Self {
+            store,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn load(paths: &[String]) -> (Self, Vec<NonFatalErrorSet>) {
+        let (store, non_fatal_error_sets) = DefaultMatchStore::load(paths);
+        (Self::new(store), non_fatal_error_sets)
+    }
+}
+
+impl MatchStore for CachingMatchStore {
+    fn query(&'_ self, paths: &[String]) -> MatchSet<'_> {
+        if let Some(positions) = self.cache.borrow().get(paths) {
+            return self.store.resolve(positions);
+        }
+
+        let positions = self.store.locate(paths);
+        let match_set = self.store.resolve(&positions);
+        self.cache.borrow_mut().insert(paths.to_vec(), positions);
+        match_set
+    }
+
+    fn loaded_paths(&self) -> Vec<String> {
+        self.store.loaded_paths()
+    }
+
+    fn reload(self: Box<Self>, paths: &[String]) -> (Box<dyn MatchStore>, Vec<NonFatalErrorSet>) {
+        let (store, non_fatal_error_sets) = self.store.reload_groups(paths);
+        (Box::new(Self::new(store)), non_fatal_error_sets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::use_test_directory;
+
+    fn cached_queries(match_store: &CachingMatchStore) -> usize {
+        match_store.cache.borrow().len()
+    }
+
+    #[test]
+    fn caching_store_returns_same_results_as_default() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(
+                &base_file,
+                r#"
+      imports:
+        - "_sub.yml"
+
+      global_vars:
+        - name: var1
+          type: test
+
+      matches:
+        - trigger: "hello"
+          replace: "world"
+      "#,
+            )
+            .unwrap();
+
+            let sub_file = match_dir.join("_sub.yml");
+            std::fs::write(
+                &sub_file,
+                r#"
+      imports:
+        - "base.yml"
+
+      matches:
+        - trigger: "foo"
+          replace: "bar"
+      "#,
+            )
+            .unwrap();
+
+            let paths = vec![base_file.to_string_lossy().to_string()];
+            let (match_store, _) = CachingMatchStore::load(&paths);
+            let uncached = match_store.store.query(&paths);
+
+            assert_eq!(match_store.query(&paths), uncached);
+            assert_eq!(cached_queries(&match_store), 1);
+
+            // The second query is served by the cache
+            assert_eq!(match_store.query(&paths), uncached);
+            assert_eq!(cached_queries(&match_store), 1);
+
+            let sub_paths = vec![sub_file.to_string_lossy().to_string()];
+            assert_eq!(
+                match_store.query(&sub_paths),
+                match_store.store.query(&sub_paths)
+            );
+            assert_eq!(cached_queries(&match_store), 2);
+        });
+    }
+
+    #[test]
+    fn caching_store_reload_starts_from_empty_cache() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(
+                &base_file,
+                r#"
+      matches:
+        - trigger: "hello"
+          replace: "world"
+      "#,
+            )
+            .unwrap();
+
+            let paths = vec![base_file.to_string_lossy().to_string()];
+            let (match_store, _) = CachingMatchStore::load(&paths);
+            assert_eq!(match_store.query(&paths).matches.len(), 1);
+
+            std::fs::write(
+                &base_file,
+                r#"
+      matches:
+        - trigger: "hello"
+          replace: "world"
+        - trigger: "foo"
+          replace: "bar"
+      "#,
+            )
+            .unwrap();
+
+            let (match_store, non_fatal_error_sets) = Box::new(match_store).reload(&paths);
+            assert_eq!(non_fatal_error_sets.len(), 0);
+            assert_eq!(match_store.query(&paths).matches.len(), 2);
+        });
+    }
+}
@@ -26,76 +26,147 @@ use crate::{
 use anyhow::Context;
 use std::{
     collections::{HashMap, HashSet},
-    path::PathBuf,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 pub struct DefaultMatchStore {
     pub groups: HashMap<String, MatchGroup>,
+
+    // Fingerprints of the files whose groups can be re-used by a reload
+    fingerprints: HashMap<String, FileFingerprint>,
 }
 
 impl DefaultMatchStore {
     pub fn load(paths: &[String]) -> (Self, Vec<NonFatalErrorSet>) {
-        let mut groups = HashMap::new();
-        let mut non_fatal_error_sets = Vec::new();
+        Self::load_reusing(paths, HashMap::new())
+    }
 
-        // Because match groups can imports other match groups,
-        // we have to load them recursively starting from the
-        // top-level ones.
-        load_match_groups_recursively(&mut groups, paths, &mut non_fatal_error_sets);
+    /// Load the groups needed by the given paths, re-using the current
+    /// ones whose files haven't changed since they were loaded.
+    pub fn reload_groups(mut self, paths: &[String]) -> (Self, Vec<NonFatalErrorSet>) {
+        let mut reusable_groups = HashMap::new();
+        for (path, group) in self.groups.drain() {
+            if let Some(fingerprint) = self.fingerprints.remove(&path) {
+                if FileFingerprint::of(Path::new(&path)).as_ref() == Some(&fingerprint) {
+                    reusable_groups.insert(path, (group, fingerprint));
+                }
+            }
+        }
 
-        (Self { groups }, non_fatal_error_sets)
+        Self::load_reusing(paths, reusable_groups)
     }
-}
 
-impl MatchStore for DefaultMatchStore {
-    fn query(&'_ self, paths: &[String]) -> MatchSet<'_> {
-        let mut matches: Vec<&Match> = Vec::new();
-        let mut global_vars: Vec<&Variable> = Vec::new();
+    /// Find the position of the matches and global variables that
+    /// are included by the given paths, following their imports.
+    pub fn locate(&self, paths: &[String]) -> Vec<GroupPositions> {
+        let mut positions = Vec::new();
         let mut visited_paths = HashSet::new();
         let mut visited_matches = HashSet::new();
         let mut visited_global_vars = HashSet::new();
 
-        query_matches_for_paths(
+        locate_matches_for_paths(
             &self.groups,
             &mut visited_paths,
             &mut visited_matches,
             &mut visited_global_vars,
-            &mut matches,
-            &mut global_vars,
+            &mut positions,
             paths,
         );
 
+        positions
+    }
+
+    /// Build the `MatchSet` corresponding to the positions previously
+    /// returned by `locate`, without traversing the imports again.
+    pub fn resolve(&self, positions: &[GroupPositions]) -> MatchSet<'_> {
+        let mut matches: Vec<&Match> =
+            Vec::with_capacity(positions.iter().map(|p| p.match_count).sum());
+        let mut global_vars: Vec<&Variable> =
+            Vec::with_capacity(positions.iter().map(|p| p.global_var_count).sum());
+
+        for group_positions in positions {
+            if let Some(group) = self.groups.get(&group_positions.path) {
+                for range in &group_positions.matches {
+                    matches.extend(group.matches.get(range.clone()).unwrap_or_default());
+                }
+                for range in &group_positions.global_vars {
+                    global_vars.extend(group.global_vars.get(range.clone()).unwrap_or_default());
+                }
+            }
+        }
+
         MatchSet {
             matches,
             global_vars,
         }
     }
 
-    fn loaded_paths(&self) -> Vec<String> {
-        self.groups.keys().cloned().collect()
+    fn load_reusing(
+        paths: &[String],
+        mut reusable_groups: HashMap<String, (MatchGroup, FileFingerprint)>,
+    ) -> (Self, Vec<NonFatalErrorSet>) {
+        let mut store = Self {
+            groups: HashMap::new(),
+            fingerprints: HashMap::new(),
+        };
+        let mut non_fatal_error_sets = Vec::new();
+
+        // Because match groups can imports other match groups,
+        // we have to load them recursively starting from the
+        // top-level ones.
+        store.load_match_groups_recursively(paths, &mut reusable_groups, &mut non_fatal_error_sets);
+
+        (store, non_fatal_error_sets)
     }
-}
 
-fn load_match_groups_recursively(
-    groups: &mut HashMap<String, MatchGroup>,
-    paths: &[String],
-    non_fatal_error_sets: &mut Vec<NonFatalErrorSet>,
-) {
-    for path in paths {
-        if !groups.contains_key(path) {
+    fn load_match_groups_recursively(
+        &mut self,
+        paths: &[String],
+        reusable_groups: &mut HashMap<String, (MatchGroup, FileFingerprint)>,
+        non_fatal_error_sets: &mut Vec<NonFatalErrorSet>,
+    ) {
+        for path in paths {
+            if self.groups.contains_key(path) {
+                continue;
+            }
+
+            if let Some((group, fingerprint)) = reusable_groups.remove(path) {
+                let imports = group.imports.clone();
+                self.groups.insert(path.clone(), group);
+                self.fingerprints.insert(path.clone(), fingerprint);
+
+                self.load_match_groups_recursively(&imports, reusable_groups, non_fatal_error_sets);
+                continue;
+            }
+
             let group_path = PathBuf::from(path);
+
+            // The fingerprint is taken before reading the file, so that changes
+            // happening while loading are picked up by the next reload
+            let fingerprint = FileFingerprint::of(&group_path);
+
             match MatchGroup::load(&group_path)
                 .with_context(|| format!("unable to load match group {}", group_path.display()))
             {
                 Ok((group, non_fatal_error_set)) => {
                     let imports = group.imports.clone();
-                    groups.insert(path.clone(), group);
+                    self.groups.insert(path.clone(), group);
 
+                    // Groups with errors are always loaded again, as they might depend on
+                    // files that didn't exist at the time (for example, a missing import)
                     if let Some(non_fatal_error_set) = non_fatal_error_set {
                         non_fatal_error_sets.push(non_fatal_error_set);
+                    } else if let Some(fingerprint) = fingerprint {
+                        self.fingerprints.insert(path.clone(), fingerprint);
                     }
 
-                    load_match_groups_recursively(groups, &imports, non_fatal_error_sets);
+                    self.load_match_groups_recursively(
+                        &imports,
+                        reusable_groups,
+                        non_fatal_error_sets,
+                    );
                 }
                 Err(err) => {
                     non_fatal_error_sets.push(NonFatalErrorSet::single_error(&group_path, err));
@@ -105,13 +176,77 @@ fn load_match_groups_recursively(
     }
 }
 
-fn query_matches_for_paths<'a>(
-    groups: &'a HashMap<String, MatchGroup>,
+// Used to detect whether a match file changed since it was loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileFingerprint {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl FileFingerprint {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+}
+
+impl MatchStore for DefaultMatchStore {
+    fn query(&'_ self, paths: &[String]) -> MatchSet<'_> {
+        self.resolve(&self.locate(paths))
+    }
+
+    fn loaded_paths(&self) -> Vec<String> {
+        self.groups.keys().cloned().collect()
+    }
+
+    fn reload(self: Box<Self>, paths: &[String]) -> (Box<dyn MatchStore>, Vec<NonFatalErrorSet>) {
+        let (store, non_fatal_error_sets) = self.reload_groups(paths);
+        (Box::new(store), non_fatal_error_sets)
+    }
+}
+
+/// The matches and global variables of a group included by a query, stored
+/// as ranges of indexes so that they can be kept without borrowing the group.
+/// As most groups are included as a whole, building the corresponding
+/// `MatchSet` usually amounts to copying the references of a single slice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupPositions {
+    path: String,
+    matches: Vec<Range<usize>>,
+    match_count: usize,
+    global_vars: Vec<Range<usize>>,
+    global_var_count: usize,
+}
+
+impl GroupPositions {
+    fn push_match(&mut self, index: usize) {
+        push_index(&mut self.matches, index);
+        self.match_count += 1;
+    }
+
+    fn push_global_var(&mut self, index: usize) {
+        push_index(&mut self.global_vars, index);
+        self.global_var_count += 1;
+    }
+}
+
+// Add the index to the last range when contiguous, or start a new one
+fn push_index(ranges: &mut Vec<Range<usize>>, index: usize) {
+    match ranges.last_mut() {
+        Some(range) if range.end == index => range.end += 1,
+        _ => ranges.push(index..index + 1),
+    }
+}
+
+fn locate_matches_for_paths(
+    groups: &HashMap<String, MatchGroup>,
     visited_paths: &mut HashSet<String>,
     visited_matches: &mut HashSet<StructId>,
     visited_global_vars: &mut HashSet<StructId>,
-    matches: &mut Vec<&'a Match>,
-    global_vars: &mut Vec<&'a Variable>,
+    positions: &mut Vec<GroupPositions>,
     paths: &[String],
 ) {
     for path in paths {
@@ -119,29 +254,38 @@ fn query_matches_for_paths<'a>(
             visited_paths.insert(path.clone());
 
             if let Some(group) = groups.get(path) {
-                query_matches_for_paths(
+                locate_matches_for_paths(
                     groups,
                     visited_paths,
                     visited_matches,
                     visited_global_vars,
-                    matches,
-                    global_vars,
+                    positions,
                     &group.imports,
                 );
 
-                for m in &group.matches {
+                let mut group_positions = GroupPositions {
+                    path: path.clone(),
+                    matches: Vec::new(),
+                    match_count: 0,
+                    global_vars: Vec::new(),
+                    global_var_count: 0,
+                };
+
+                for (index, m) in group.matches.iter().enumerate() {
                     if !visited_matches.contains(&m.id) {
-                        matches.push(m);
+                        group_positions.push_match(index);
                         visited_matches.insert(m.id);
                     }
                 }
 
-                for var in &group.global_vars {
+                for (index, var) in group.global_vars.iter().enumerate() {
                     if !visited_global_vars.contains(&var.id) {
-                        global_vars.push(var);
+                        group_positions.push_global_var(index);
                         visited_global_vars.insert(var.id);
                     }
                 }
+
+                positions.push(group_positions);
             }
         }
     }
@@ -734,5 +878,183 @@ mod tests {
         });
     }
 
+    #[test]
+    fn push_index_merges_contiguous_indexes() {
+        let mut ranges = Vec::new();
+        for index in [0, 1, 2, 4, 5, 7] {
+            push_index(&mut ranges, index);
+        }
+        assert_eq!(ranges, vec![0..3, 4..6, 7..8]);
+    }
+
+    #[test]
+    fn match_set_retain_for_app_drops_filtered_matches() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(
+                &base_file,
+                r#"
+      matches:
+        - trigger: ":sig"
+          replace: "mail signature"
+          filter_class: "thunderbird"
+        - trigger: ":sig"
+          replace: "terminal signature"
+          filter_class: "kitty"
+        - trigger: "foo"
+          replace: "bar"
+      "#,
+            )
+            .unwrap();
+
+            let (match_store, non_fatal_error_sets) =
+                DefaultMatchStore::load(&[base_file.to_string_lossy().to_string()]);
+            assert_eq!(non_fatal_error_sets.len(), 0);
+
+            let mut match_set = match_store.query(&[base_file.to_string_lossy().to_string()]);
+            assert_eq!(match_set.matches.len(), 3);
+
+            match_set.retain_for_app(&crate::config::AppProperties {
+                title: None,
+                class: Some("Mail.thunderbird"),
+                exec: None,
+            });
+
+            assert_eq!(
+                match_set
+                    .matches
+                    .into_iter()
+                    .map(Match::description)
+                    .collect::<Vec<&str>>(),
+                vec!["mail signature", "bar"]
+            );
+        });
+    }
+
+    fn match_ids(store: &dyn MatchStore, path: &Path) -> Vec<i32> {
+        store
+            .query(&[path.to_string_lossy().to_string()])
+            .matches
+            .into_iter()
+            .map(|m| m.id)
+            .collect()
+    }
+
+    #[test]
+    fn match_store_reload_only_loads_changed_files() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(
+                &base_file,
+                r#"
+      matches:
+        - trigger: "hello"
+          replace: "world"
+      "#,
+            )
+            .unwrap();
+
+            let other_file = match_dir.join("other.yml");
+            std::fs::write(
+                &other_file,
+                r#"
+      matches:
+        - trigger: "foo"
+          replace: "bar"
+      "#,
+            )
+            .unwrap();
+
+            let paths = vec![
+                base_file.to_string_lossy().to_string(),
+                other_file.to_string_lossy().to_string(),
+            ];
+            let (match_store, non_fatal_error_sets) = DefaultMatchStore::load(&paths);
+            assert_eq!(non_fatal_error_sets.len(), 0);
+            let base_ids = match_ids(&match_store, &base_file);
+            let other_ids = match_ids(&match_store, &other_file);
+
+            std::fs::write(
+                &other_file,
+                r#"
+      matches:
+        - trigger: "foo"
+          replace: "changed"
+        - trigger: "new"
+          replace: "match"
+      "#,
+            )
+            .unwrap();
+
+            let (match_store, non_fatal_error_sets) = Box::new(match_store).reload(&paths);
+            assert_eq!(non_fatal_error_sets.len(), 0);
+
+            // Unchanged groups are re-used, so their matches keep the same ids
+            assert_eq!(match_ids(&*match_store, &base_file), base_ids);
+
+            let reloaded_other_ids = match_ids(&*match_store, &other_file);
+            assert_eq!(reloaded_other_ids.len(), 2);
+            assert!(!reloaded_other_ids.contains(&other_ids[0]));
+        });
+    }
+
+    #[test]
+    fn match_store_reload_drops_unused_groups() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(&base_file, "matches: []").unwrap();
+            let other_file = match_dir.join("other.yml");
+            std::fs::write(&other_file, "matches: []").unwrap();
+
+            let (match_store, _) = DefaultMatchStore::load(&[
+                base_file.to_string_lossy().to_string(),
+                other_file.to_string_lossy().to_string(),
+            ]);
+            assert_eq!(match_store.loaded_paths().len(), 2);
+
+            let (match_store, _) =
+                Box::new(match_store).reload(&[base_file.to_string_lossy().to_string()]);
+            assert_eq!(
+                match_store.loaded_paths(),
+                vec![base_file.to_string_lossy().to_string()]
+            );
+        });
+    }
+
+    #[test]
+    fn match_store_reload_retries_groups_with_errors() {
+        use_test_directory(|_, match_dir, _| {
+            let base_file = match_dir.join("base.yml");
+            std::fs::write(
+                &base_file,
+                r#"
+      imports:
+        - "sub.yml"
+      "#,
+            )
+            .unwrap();
+            let paths = vec![base_file.to_string_lossy().to_string()];
+
+            let (match_store, non_fatal_error_sets) = DefaultMatchStore::load(&paths);
+            assert_eq!(non_fatal_error_sets.len(), 1);
+
+            // Creating the missing import doesn't change the importing file,
+            // but the group is loaded again because it had errors
+            std::fs::write(
+                match_dir.join("sub.yml"),
+                r#"
+      matches:
+        - trigger: "hello"
+          replace: "world"
+      "#,
+            )
+            .unwrap();
+
+            let (match_store, non_fatal_error_sets) = Box::new(match_store).reload(&paths);
+            assert_eq!(non_fatal_error_sets.len(), 0);
+            assert_eq!(match_ids(&*match_store, &base_file).len(), 1);
+        });
+    }
+
     // TODO: add fatal and non-fatal error cases
 }
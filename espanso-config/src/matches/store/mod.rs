@@ -17,15 +17,24 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use crate::error::NonFatalErrorSet;
+use crate::{config::AppProperties, error::NonFatalErrorSet};
 
 use super::{Match, Variable};
 
+mod cache;
 mod default;
 
+pub use cache::CachingMatchStore;
+pub use default::DefaultMatchStore;
+
 pub trait MatchStore: Send {
     fn query(&'_ self, paths: &[String]) -> MatchSet<'_>;
     fn loaded_paths(&self) -> Vec<String>;
+
+    /// Load the match groups needed by the given paths, re-using the ones
+    /// already loaded by this store whose files haven't changed since then.
+    /// Errors are only reported for the groups that have been loaded again.
+    fn reload(self: Box<Self>, paths: &[String]) -> (Box<dyn MatchStore>, Vec<NonFatalErrorSet>);
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -34,8 +43,13 @@ pub struct MatchSet<'a> {
     pub global_vars: Vec<&'a Variable>,
 }
 
-pub fn load(paths: &[String]) -> (impl MatchStore, Vec<NonFatalErrorSet>) {
-    // TODO: here we can replace the DefaultMatchStore with a caching wrapper
-    // that returns the same response for the given "paths" query
-    default::DefaultMatchStore::load(paths)
+impl MatchSet<'_> {
+    /// Drop the matches whose application filters don't match the given app.
+    pub fn retain_for_app(&mut self, app: &AppProperties) {
+        self.matches.retain(|m| m.filter.is_match(app));
+    }
+}
+
+pub fn load(paths: &[String]) -> (CachingMatchStore, Vec<NonFatalErrorSet>) {
+    CachingMatchStore::load(paths)
 }
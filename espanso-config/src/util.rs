@@ -17,6 +17,8 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::error::SourceLocation;
+
 /// Check if the given string represents an empty YAML.
 /// In other words, it checks if the document is only composed
 /// of spaces and/or comments
@@ -31,6 +33,142 @@ pub fn is_yaml_empty(yaml: &str) -> bool {
     true
 }
 
+/// Find the location of the given top-level key inside a YAML document.
+/// This is a line-based scan, so it only recognizes block mappings, which
+/// is what configuration and match files are composed of.
+pub fn locate_yaml_key(yaml: &str, key: &str) -> Option<SourceLocation> {
+    find_yaml_key_line(yaml, key).map(|(index, indent)| SourceLocation::new(index + 1, indent + 1))
+}
+
+/// Find the location of each item of the block sequence stored under
+/// the given top-level key of a YAML document.
+/// Items written using the flow syntax (`[a, b]`) are not detected, so callers
+/// should check that the number of locations matches the number of parsed items.
+pub fn locate_yaml_sequence_items(yaml: &str, key: &str) -> Vec<SourceLocation> {
+    find_yaml_key_line(yaml, key)
+        .map(|(key_index, key_indent)| {
+            locate_yaml_sequence_items_after(yaml, key_index, key_indent)
+        })
+        .unwrap_or_default()
+}
+
+/// Find the location of each item of the block sequence stored under the given
+/// key of a mapping which is itself a sequence item, such as the `vars` of a match.
+/// The `item` location must point to the dash introducing the mapping, as the
+/// ones returned by `locate_yaml_sequence_items`.
+pub fn locate_yaml_nested_sequence_items(
+    yaml: &str,
+    item: SourceLocation,
+    key: &str,
+) -> Vec<SourceLocation> {
+    find_yaml_item_key_line(yaml, item, key)
+        .map(|(key_index, key_indent)| {
+            locate_yaml_sequence_items_after(yaml, key_index, key_indent)
+        })
+        .unwrap_or_default()
+}
+
+fn locate_yaml_sequence_items_after(
+    yaml: &str,
+    key_index: usize,
+    key_indent: usize,
+) -> Vec<SourceLocation> {
+    let mut locations = Vec::new();
+    let mut item_indent = None;
+    for (index, line) in yaml.lines().enumerate().skip(key_index + 1) {
+        let Some(indent) = yaml_content_indent(line) else {
+            continue;
+        };
+        let trimmed_line = &line[indent..];
+        let is_item = trimmed_line == "-" || trimmed_line.starts_with("- ");
+        let item_indent = *item_indent.get_or_insert(indent);
+
+        if indent < item_indent || indent < key_indent || (indent == item_indent && !is_item) {
+            break;
+        }
+
+        if indent == item_indent {
+            locations.push(SourceLocation::new(index + 1, indent + 1));
+        }
+    }
+
+    locations
+}
+
+// Returns the index and indentation of the line declaring the given top-level key.
+// Top-level keys share the indentation of the first line with content.
+fn find_yaml_key_line(yaml: &str, key: &str) -> Option<(usize, usize)> {
+    let base_indent = yaml.lines().find_map(yaml_content_indent)?;
+
+    yaml.lines().enumerate().find_map(|(index, line)| {
+        if yaml_content_indent(line) != Some(base_indent) {
+            return None;
+        }
+
+        is_yaml_key_declaration(&line[base_indent..], key).then_some((index, base_indent))
+    })
+}
+
+// Returns the index and indentation of the line declaring the given key inside
+// the mapping introduced by the sequence item at the given location
+fn find_yaml_item_key_line(yaml: &str, item: SourceLocation, key: &str) -> Option<(usize, usize)> {
+    let item_index = item.line.checked_sub(1)?;
+    let item_indent = item.column.checked_sub(1)?;
+    let mut lines = yaml.lines().enumerate().skip(item_index);
+
+    // The first key can be declared on the same line as the dash
+    let (_, item_line) = lines.next()?;
+    let content = item_line
+        .get(item_indent..)?
+        .strip_prefix('-')?
+        .trim_start_matches(' ');
+    let mut mapping_indent = None;
+    if !content.trim().is_empty() && !content.starts_with('#') {
+        let indent = item_line.len() - content.len();
+        if is_yaml_key_declaration(content, key) {
+            return Some((item_index, indent));
+        }
+        mapping_indent = Some(indent);
+    }
+
+    for (index, line) in lines {
+        let Some(indent) = yaml_content_indent(line) else {
+            continue;
+        };
+        if indent <= item_indent {
+            break;
+        }
+
+        let mapping_indent = *mapping_indent.get_or_insert(indent);
+        if indent == mapping_indent && is_yaml_key_declaration(&line[indent..], key) {
+            return Some((index, indent));
+        }
+    }
+
+    None
+}
+
+// Checks if the given line, stripped of its indentation, declares the given key
+fn is_yaml_key_declaration(trimmed_line: &str, key: &str) -> bool {
+    [key.to_string(), format!("\"{key}\""), format!("'{key}'")]
+        .iter()
+        .any(|candidate| {
+            trimmed_line
+                .strip_prefix(candidate.as_str())
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        })
+}
+
+// Returns the indentation of the given line, or None if it has no content
+pub(crate) fn yaml_content_indent(line: &str) -> Option<usize> {
+    let trimmed_line = line.trim_start_matches(' ');
+    if trimmed_line.trim().is_empty() || trimmed_line.starts_with('#') {
+        None
+    } else {
+        Some(line.len() - trimmed_line.len())
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -71,4 +209,110 @@ pub mod tests {
     fn is_yaml_empty_document_with_content() {
         assert!(!is_yaml_empty("\nfield: true\n"));
     }
+
+    #[test]
+    fn locate_yaml_key_works_correctly() {
+        let yaml = "# comment\nbackend: Clipboard\n  nested: true\n\"quoted\" : 1\n";
+        assert_eq!(
+            locate_yaml_key(yaml, "backend"),
+            Some(SourceLocation::new(2, 1))
+        );
+        assert_eq!(
+            locate_yaml_key(yaml, "quoted"),
+            Some(SourceLocation::new(4, 1))
+        );
+        assert_eq!(locate_yaml_key(yaml, "nested"), None);
+        assert_eq!(
+            locate_yaml_key("\n    backend: Clipboard\n", "backend"),
+            Some(SourceLocation::new(2, 5))
+        );
+        assert_eq!(locate_yaml_key(yaml, "missing"), None);
+    }
+
+    #[test]
+    fn locate_yaml_sequence_items_works_correctly() {
+        let yaml = r#"
+global_vars:
+  - name: a
+    type: echo
+
+matches:
+  # comment
+  - trigger: ":a"
+    replace: |
+      - not an item
+  -
+    trigger: ":b"
+    replace: "b"
+  - trigger: ":c"
+    replace: "c"
+other: true
+"#;
+        assert_eq!(
+            locate_yaml_sequence_items(yaml, "matches"),
+            vec![
+                SourceLocation::new(8, 3),
+                SourceLocation::new(11, 3),
+                SourceLocation::new(14, 3),
+            ]
+        );
+        assert_eq!(
+            locate_yaml_sequence_items(yaml, "global_vars"),
+            vec![SourceLocation::new(3, 3)]
+        );
+    }
+
+    #[test]
+    fn locate_yaml_sequence_items_without_indentation() {
+        let yaml = "matches:\n- trigger: a\n  replace: b\n- trigger: c\n  replace: d\n";
+        assert_eq!(
+            locate_yaml_sequence_items(yaml, "matches"),
+            vec![SourceLocation::new(2, 1), SourceLocation::new(4, 1)]
+        );
+    }
+
+    #[test]
+    fn locate_yaml_sequence_items_flow_or_missing() {
+        assert!(locate_yaml_sequence_items("matches: [{trigger: a}]\n", "matches").is_empty());
+        assert!(locate_yaml_sequence_items("imports:\n  - a.yml\n", "matches").is_empty());
+    }
+
+    #[test]
+    fn locate_yaml_nested_sequence_items_works_correctly() {
+        let yaml = r#"
+matches:
+  - trigger: ":a"
+    vars:
+      - name: one
+        type: echo
+      - name: two
+        type: echo
+    replace: "a"
+  -
+    vars:
+    - name: three
+      type: echo
+    trigger: ":b"
+  - vars:
+      - name: four
+    trigger: ":c"
+  - trigger: ":d"
+    replace: "d"
+"#;
+        let items = locate_yaml_sequence_items(yaml, "matches");
+        assert_eq!(items.len(), 4);
+        assert_eq!(
+            locate_yaml_nested_sequence_items(yaml, items[0], "vars"),
+            vec![SourceLocation::new(5, 7), SourceLocation::new(7, 7)]
+        );
+        assert_eq!(
+            locate_yaml_nested_sequence_items(yaml, items[1], "vars"),
+            vec![SourceLocation::new(12, 5)]
+        );
+        assert_eq!(
+            locate_yaml_nested_sequence_items(yaml, items[2], "vars"),
+            vec![SourceLocation::new(16, 7)]
+        );
+        assert!(locate_yaml_nested_sequence_items(yaml, items[3], "vars").is_empty());
+    }
 }
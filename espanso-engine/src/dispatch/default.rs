@@ -20,7 +20,9 @@
 use super::{
     ContextMenuHandler, Event, IconHandler, ImageInjector, SecureInputManager, TextUIHandler,
 };
-use super::{Dispatcher, Executor, HtmlInjector, KeyInjector, ModeProvider, TextInjector};
+use super::{
+    Dispatcher, Executor, HtmlInjector, KeyInjector, MacroKeyInjector, ModeProvider, TextInjector,
+};
 
 pub struct DefaultDispatcher<'a> {
     executors: Vec<Box<dyn Executor + 'a>>,
@@ -33,6 +35,7 @@ impl<'a> DefaultDispatcher<'a> {
         clipboard_injector: &'a dyn TextInjector,
         mode_provider: &'a dyn ModeProvider,
         key_injector: &'a dyn KeyInjector,
+        macro_key_injector: &'a dyn MacroKeyInjector,
         html_injector: &'a dyn HtmlInjector,
         image_injector: &'a dyn ImageInjector,
         context_menu_handler: &'a dyn ContextMenuHandler,
@@ -50,6 +53,11 @@ impl<'a> DefaultDispatcher<'a> {
                 Box::new(super::executor::key_inject::KeyInjectExecutor::new(
                     key_injector,
                 )),
+                Box::new(super::executor::macro_inject::MacroInjectExecutor::new(
+                    event_injector,
+                    clipboard_injector,
+                    macro_key_injector,
+                )),
                 Box::new(super::executor::html_inject::HtmlInjectExecutor::new(
                     html_injector,
                 )),
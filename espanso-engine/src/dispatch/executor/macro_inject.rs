@@ -0,0 +1,197 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use super::text_inject::TextInjector;
+use crate::{
+    dispatch::Executor,
+    event::{effect::MacroStep, Event, EventType},
+};
+use anyhow::Result;
+use log::error;
+
+pub trait MacroKeyInjector {
+    fn inject_keys(&self, keys: &str) -> Result<()>;
+}
+
+pub struct MacroInjectExecutor<'a> {
+    event_injector: &'a dyn TextInjector,
+    clipboard_injector: &'a dyn TextInjector,
+    key_injector: &'a dyn MacroKeyInjector,
+}
+
+impl<'a> MacroInjectExecutor<'a> {
+    pub fn new(
+        event_injector: &'a dyn TextInjector,
+        clipboard_injector: &'a dyn TextInjector,
+        key_injector: &'a dyn MacroKeyInjector,
+    ) -> Self {
+        Self {
+            event_injector,
+            clipboard_injector,
+            key_injector,
+        }
+    }
+
+    fn execute_step(&self, step: &MacroStep) -> Result<()> {
+        match step {
+            MacroStep::Text(text) => self.event_injector.inject_text(text),
+            MacroStep::Keys(keys) => self.key_injector.inject_keys(keys),
+            MacroStep::Wait(millis) => {
+                std::thread::sleep(Duration::from_millis(*millis));
+                Ok(())
+            }
+            MacroStep::Paste(text) => self.clipboard_injector.inject_text(text),
+        }
+    }
+}
+
+impl Executor for MacroInjectExecutor<'_> {
+    fn execute(&self, event: &Event) -> bool {
+        if let EventType::MacroInject(inject_event) = &event.etype {
+            for step in &inject_event.steps {
+                // The following steps usually depend on the previous ones (for example,
+                // moving to the next field of a form), so we stop at the first error
+                if let Err(error) = self.execute_step(step) {
+                    error!("macro injection stopped at step {step:?}: {error:?}");
+                    break;
+                }
+            }
+            return true;
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, time::Instant};
+
+    use anyhow::bail;
+
+    use super::*;
+    use crate::event::effect::MacroInjectRequest;
+
+    // Records the injected steps of all the mock injectors, in order
+    #[derive(Default)]
+    struct Log {
+        steps: RefCell<Vec<String>>,
+    }
+
+    struct MockInjector<'a> {
+        kind: &'static str,
+        log: &'a Log,
+    }
+
+    impl MockInjector<'_> {
+        fn inject(&self, value: &str) -> Result<()> {
+            if value == "fail" {
+                bail!("injection failed");
+            }
+            self.log
+                .steps
+                .borrow_mut()
+                .push(format!("{}:{value}", self.kind));
+            Ok(())
+        }
+    }
+
+    impl TextInjector for MockInjector<'_> {
+        fn name(&self) -> &'static str {
+            self.kind
+        }
+
+        fn inject_text(&self, text: &str) -> Result<()> {
+            self.inject(text)
+        }
+    }
+
+    impl MacroKeyInjector for MockInjector<'_> {
+        fn inject_keys(&self, keys: &str) -> Result<()> {
+            self.inject(keys)
+        }
+    }
+
+    fn execute(steps: Vec<MacroStep>) -> (bool, Vec<String>) {
+        let log = Log::default();
+        let event_injector = MockInjector {
+            kind: "text",
+            log: &log,
+        };
+        let clipboard_injector = MockInjector {
+            kind: "paste",
+            log: &log,
+        };
+        let key_injector = MockInjector {
+            kind: "keys",
+            log: &log,
+        };
+        let executor =
+            MacroInjectExecutor::new(&event_injector, &clipboard_injector, &key_injector);
+
+        let handled = executor.execute(&Event::caused_by(
+            1,
+            EventType::MacroInject(MacroInjectRequest { steps }),
+        ));
+        let steps = log.steps.take();
+        (handled, steps)
+    }
+
+    #[test]
+    fn steps_are_dispatched_in_order() {
+        let start = Instant::now();
+        let (handled, steps) = execute(vec![
+            MacroStep::Text("John".to_string()),
+            MacroStep::Keys("{Tab}".to_string()),
+            MacroStep::Wait(50),
+            MacroStep::Paste("Doe".to_string()),
+        ]);
+
+        assert!(handled);
+        assert_eq!(steps, vec!["text:John", "keys:{Tab}", "paste:Doe"]);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn execution_stops_at_the_first_error() {
+        let (handled, steps) = execute(vec![
+            MacroStep::Text("John".to_string()),
+            MacroStep::Keys("fail".to_string()),
+            MacroStep::Paste("Doe".to_string()),
+        ]);
+
+        assert!(handled);
+        assert_eq!(steps, vec!["text:John"]);
+    }
+
+    #[test]
+    fn other_events_are_ignored() {
+        let log = Log::default();
+        let injector = MockInjector {
+            kind: "text",
+            log: &log,
+        };
+        let executor = MacroInjectExecutor::new(&injector, &injector, &injector);
+
+        assert!(!executor.execute(&Event::caused_by(1, EventType::NOOP)));
+        assert!(log.steps.borrow().is_empty());
+    }
+}
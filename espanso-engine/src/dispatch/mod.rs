@@ -36,6 +36,7 @@ pub use executor::html_inject::HtmlInjector;
 pub use executor::icon_update::IconHandler;
 pub use executor::image_inject::ImageInjector;
 pub use executor::key_inject::KeyInjector;
+pub use executor::macro_inject::MacroKeyInjector;
 pub use executor::secure_input::SecureInputManager;
 pub use executor::text_inject::{Mode, ModeProvider, TextInjector};
 pub use executor::text_ui::{TextUIExecutor, TextUIHandler};
@@ -46,6 +47,7 @@ pub fn default<'a>(
     clipboard_injector: &'a dyn TextInjector,
     mode_provider: &'a dyn ModeProvider,
     key_injector: &'a dyn KeyInjector,
+    macro_key_injector: &'a dyn MacroKeyInjector,
     html_injector: &'a dyn HtmlInjector,
     image_injector: &'a dyn ImageInjector,
     context_menu_handler: &'a dyn ContextMenuHandler,
@@ -58,6 +60,7 @@ pub fn default<'a>(
         clipboard_injector,
         mode_provider,
         key_injector,
+        macro_key_injector,
         html_injector,
         image_injector,
         context_menu_handler,
@@ -28,6 +28,24 @@ pub struct TriggerCompensationEvent {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CursorHintCompensationEvent {
     pub cursor_hint_back_count: usize,
+    pub cursor_hint_line_moves: Option<CursorHintLineMoves>,
+}
+
+// Alternative to moving back `cursor_hint_back_count` times, used when the hint
+// is on a previous line: move up, then to the end (or start) of the line and
+// finally left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorHintLineMoves {
+    pub up_count: usize,
+    pub to_line_start: bool,
+    pub left_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabStopJumpEvent {
+    pub backspace_count: usize,
+    pub left_count: usize,
+    pub right_count: usize,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -61,3 +79,18 @@ pub struct KeySequenceInjectRequest {
 pub struct ImageInjectRequest {
     pub image_path: String,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroInjectRequest {
+    pub steps: Vec<MacroStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroStep {
+    Text(String),
+    // Key combinations, written as "{Ctrl+A}{Tab}"
+    Keys(String),
+    // Milliseconds to wait
+    Wait(u64),
+    Paste(String),
+}
@@ -19,7 +19,9 @@
 
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MatchExecRequestEvent {
     pub trigger: Option<String>,
     pub args: HashMap<String, String>,
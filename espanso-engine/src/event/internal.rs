@@ -19,6 +19,8 @@
 
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MatchesDetectedEvent {
     pub matches: Vec<DetectedMatch>,
@@ -68,6 +70,13 @@ pub struct ImageRequestedEvent {
     pub trigger: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroRequestedEvent {
+    pub match_id: i32,
+    pub steps: Vec<super::effect::MacroStep>,
+    pub trigger: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ImageResolvedEvent {
     pub image_path: String,
@@ -94,7 +103,7 @@ pub struct DiscardBetweenEvent {
     pub end_id: u32,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SecureInputEnabledEvent {
     pub app_name: String,
     pub app_path: String,
@@ -104,5 +113,15 @@ pub struct SecureInputEnabledEvent {
 pub struct UndoEvent {
     pub match_id: i32,
     pub trigger: String,
-    pub replace: String,
+
+    // Number of chars of the expansion that have to be deleted
+    pub delete_count: usize,
+
+    // Number of right-arrow presses needed to move from the cursor hint back to
+    // the end of the expansion before deleting it
+    pub forward_count: usize,
+
+    // Number of chars typed after the expansion, which are skipped with the
+    // left arrow before deleting it and with the right arrow after restoring the trigger
+    pub backward_count: usize,
 }
@@ -70,12 +70,14 @@ pub enum EventType {
 
     RenderingRequested(internal::RenderingRequestedEvent),
     ImageRequested(internal::ImageRequestedEvent),
+    MacroRequested(internal::MacroRequestedEvent),
     Rendered(internal::RenderedEvent),
     ImageResolved(internal::ImageResolvedEvent),
     MatchInjected,
     DiscardPrevious(internal::DiscardPreviousEvent),
     DiscardBetween(internal::DiscardBetweenEvent),
     Undo(internal::UndoEvent),
+    UndoRequest,
     RenderingError,
 
     Disabled,
@@ -89,12 +91,14 @@ pub enum EventType {
     // Effects
     TriggerCompensation(effect::TriggerCompensationEvent),
     CursorHintCompensation(effect::CursorHintCompensationEvent),
+    TabStopJump(effect::TabStopJumpEvent),
 
     KeySequenceInject(effect::KeySequenceInjectRequest),
     TextInject(effect::TextInjectRequest),
     MarkdownInject(effect::MarkdownInjectRequest),
     HtmlInject(effect::HtmlInjectRequest),
     ImageInject(effect::ImageInjectRequest),
+    MacroInject(effect::MacroInjectRequest),
 
     // UI
     ShowContextMenu(ui::ShowContextMenuEvent),
@@ -114,4 +118,8 @@ pub enum ExitMode {
     Exit,
     ExitAllProcesses,
     RestartWorker,
+
+    // Stop the engine so that it can be restarted with the updated
+    // configuration, without terminating the worker process
+    ReloadConfig,
 }
@@ -30,6 +30,7 @@ pub mod dispatch;
 pub mod event;
 pub mod funnel;
 pub mod process;
+pub mod record;
 
 pub struct Engine<'a> {
     funnel: &'a dyn Funnel,
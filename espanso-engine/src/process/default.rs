@@ -36,10 +36,11 @@ use super::{
         render::RenderMiddleware,
         stats::StatsMiddleware,
     },
-    AltCodeSynthEnabledProvider, DisableOptions, EnabledStatusProvider, MatchFilter,
-    MatchInfoProvider, MatchProvider, MatchResolver, MatchSelector, Matcher,
+    AltCodeSynthEnabledProvider, CursorHintStrategyProvider, DisableOptions, EnabledStatusProvider,
+    MatchFilter, MatchInfoProvider, MatchProvider, MatchResolver, MatchSelector, Matcher,
     MatcherMiddlewareConfigProvider, Middleware, ModifierStateProvider, Multiplexer,
-    NotificationManager, PathProvider, Processor, Renderer, UndoEnabledProvider,
+    NotificationManager, PathProvider, Processor, ProcessorState, Renderer, TabStopKeyProvider,
+    TabStopsEnabledProvider, UndoEnabledProvider,
 };
 use crate::{
     event::{Event, EventType},
@@ -80,12 +81,16 @@ impl<'a> DefaultProcessor<'a> {
         match_resolver: &'a dyn MatchResolver,
         notification_manager: &'a dyn NotificationManager,
         alt_code_synth_enabled_provider: &'a dyn AltCodeSynthEnabledProvider,
+        cursor_hint_strategy_provider: &'a dyn CursorHintStrategyProvider,
+        tab_stops_enabled_provider: &'a dyn TabStopsEnabledProvider,
+        tab_stop_key_provider: &'a dyn TabStopKeyProvider,
+        state: &'a ProcessorState,
     ) -> Self {
         Self {
             event_queue: VecDeque::new(),
             middleware: vec![
                 Box::new(EventsDiscardMiddleware::new()),
-                Box::new(DisableMiddleware::new(disable_options)),
+                Box::new(DisableMiddleware::new(disable_options, state)),
                 Box::new(IconStatusMiddleware::new()),
                 Box::new(AltCodeSynthesizerMiddleware::new(
                     alt_code_synth_enabled_provider,
@@ -94,6 +99,7 @@ impl<'a> DefaultProcessor<'a> {
                     matchers,
                     matcher_options_provider,
                     modifier_state_provider,
+                    state,
                 )),
                 Box::new(MatchExecRequestMiddleware::new(match_resolver)),
                 Box::new(SuppressMiddleware::new(enabled_status_provider)),
@@ -110,9 +116,13 @@ impl<'a> DefaultProcessor<'a> {
                 Box::new(StatsMiddleware::new()),
                 Box::new(RenderMiddleware::new(renderer)),
                 Box::new(ImageResolverMiddleware::new(path_provider)),
-                Box::new(CursorHintMiddleware::new()),
+                Box::new(CursorHintMiddleware::new(
+                    cursor_hint_strategy_provider,
+                    tab_stops_enabled_provider,
+                    tab_stop_key_provider,
+                )),
                 Box::new(ExitMiddleware::new()),
-                Box::new(UndoMiddleware::new(undo_enabled_provider)),
+                Box::new(UndoMiddleware::new(undo_enabled_provider, state)),
                 Box::new(ActionMiddleware::new(
                     match_info_provider,
                     event_sequence_provider,
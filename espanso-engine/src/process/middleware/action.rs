@@ -20,8 +20,8 @@
 use super::super::Middleware;
 use crate::event::{
     effect::{
-        HtmlInjectRequest, ImageInjectRequest, KeySequenceInjectRequest, MarkdownInjectRequest,
-        TextInjectMode, TextInjectRequest,
+        HtmlInjectRequest, ImageInjectRequest, KeySequenceInjectRequest, MacroInjectRequest,
+        MarkdownInjectRequest, TextInjectMode, TextInjectRequest,
     },
     input::Key,
     internal::{DiscardPreviousEvent, TextFormat},
@@ -60,7 +60,7 @@ impl Middleware for ActionMiddleware<'_> {
 
     fn next(&self, event: Event, dispatch: &mut dyn FnMut(Event)) -> Event {
         match &event.etype {
-            EventType::Rendered(_) | EventType::ImageResolved(_) => {
+            EventType::Rendered(_) | EventType::ImageResolved(_) | EventType::MacroRequested(_) => {
                 dispatch(Event::caused_by(event.source_id, EventType::MatchInjected));
                 dispatch(Event::caused_by(
                     event.source_id,
@@ -95,6 +95,12 @@ impl Middleware for ActionMiddleware<'_> {
                             image_path: m_event.image_path.clone(),
                         }),
                     ),
+                    EventType::MacroRequested(m_event) => Event::caused_by(
+                        event.source_id,
+                        EventType::MacroInject(MacroInjectRequest {
+                            steps: m_event.steps.clone(),
+                        }),
+                    ),
                     _ => unreachable!(),
                 }
             }
@@ -106,13 +112,43 @@ impl Middleware for ActionMiddleware<'_> {
                     }),
                 ));
 
+                let keys = if let Some(line_moves) = &m_event.cursor_hint_line_moves {
+                    let line_key = if line_moves.to_line_start {
+                        Key::Home
+                    } else {
+                        Key::End
+                    };
+                    std::iter::repeat_n(Key::ArrowUp, line_moves.up_count)
+                        .chain(std::iter::once(line_key))
+                        .chain(std::iter::repeat_n(Key::ArrowLeft, line_moves.left_count))
+                        .collect()
+                } else {
+                    (0..m_event.cursor_hint_back_count)
+                        .map(|_| Key::ArrowLeft)
+                        .collect()
+                };
+
                 Event::caused_by(
                     event.source_id,
-                    EventType::KeySequenceInject(KeySequenceInjectRequest {
-                        keys: (0..m_event.cursor_hint_back_count)
-                            .map(|_| Key::ArrowLeft)
-                            .collect(),
+                    EventType::KeySequenceInject(KeySequenceInjectRequest { keys }),
+                )
+            }
+            EventType::TabStopJump(m_event) => {
+                dispatch(Event::caused_by(
+                    event.source_id,
+                    EventType::DiscardPrevious(DiscardPreviousEvent {
+                        minimum_source_id: self.event_sequence_provider.get_next_id(),
                     }),
+                ));
+
+                let keys = std::iter::repeat_n(Key::Backspace, m_event.backspace_count)
+                    .chain(std::iter::repeat_n(Key::ArrowLeft, m_event.left_count))
+                    .chain(std::iter::repeat_n(Key::ArrowRight, m_event.right_count))
+                    .collect();
+
+                Event::caused_by(
+                    event.source_id,
+                    EventType::KeySequenceInject(KeySequenceInjectRequest { keys }),
                 )
             }
             EventType::TriggerCompensation(m_event) => {
@@ -131,10 +167,6 @@ impl Middleware for ActionMiddleware<'_> {
                 )
             }
             EventType::Undo(m_event) => {
-                // We subtract one, because the backspace that triggered the undo feature
-                // already removed the last char
-                let backspace_count = m_event.replace.chars().count() - 1;
-
                 dispatch(Event::caused_by(
                     event.source_id,
                     EventType::TextInject(TextInjectRequest {
@@ -143,11 +175,26 @@ impl Middleware for ActionMiddleware<'_> {
                     }),
                 ));
 
+                // Move back over the text typed after the expansion, once the
+                // trigger has been restored
+                if m_event.backward_count > 0 {
+                    dispatch(Event::caused_by(
+                        event.source_id,
+                        EventType::KeySequenceInject(KeySequenceInjectRequest {
+                            keys: std::iter::repeat_n(Key::ArrowRight, m_event.backward_count)
+                                .collect(),
+                        }),
+                    ));
+                }
+
+                let keys = std::iter::repeat_n(Key::ArrowRight, m_event.forward_count)
+                    .chain(std::iter::repeat_n(Key::ArrowLeft, m_event.backward_count))
+                    .chain(std::iter::repeat_n(Key::Backspace, m_event.delete_count))
+                    .collect();
+
                 Event::caused_by(
                     event.source_id,
-                    EventType::KeySequenceInject(KeySequenceInjectRequest {
-                        keys: (0..backspace_count).map(|_| Key::Backspace).collect(),
-                    }),
+                    EventType::KeySequenceInject(KeySequenceInjectRequest { keys }),
                 )
             }
             _ => event,
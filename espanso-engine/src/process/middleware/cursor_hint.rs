@@ -17,34 +17,102 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{cell::RefCell, collections::VecDeque};
+
 use super::super::Middleware;
 use crate::event::{
-    effect::CursorHintCompensationEvent, internal::RenderedEvent, Event, EventType,
+    effect::{CursorHintCompensationEvent, CursorHintLineMoves, TabStopJumpEvent},
+    input::{Key, KeyboardEvent, Status},
+    internal::{RenderedEvent, TextFormat},
+    Event, EventType,
 };
 use unicode_segmentation::UnicodeSegmentation;
 
-pub struct CursorHintMiddleware {}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorHintStrategy {
+    // Move back one grapheme at a time with the left arrow
+    Chars,
+    // Reach the line of the hint with the up arrow, then move from
+    // the end (or start) of that line
+    Lines,
+}
+
+pub trait CursorHintStrategyProvider {
+    fn get_cursor_hint_strategy(&self, match_id: i32) -> CursorHintStrategy;
+}
+
+pub trait TabStopsEnabledProvider {
+    fn are_tab_stops_enabled(&self, match_id: i32) -> bool;
+}
+
+pub trait TabStopKeyProvider {
+    fn get_tab_stop_key(&self) -> Option<Key>;
+}
 
-impl CursorHintMiddleware {
-    pub fn new() -> Self {
-        Self {}
+pub struct CursorHintMiddleware<'a> {
+    strategy_provider: &'a dyn CursorHintStrategyProvider,
+    tab_stops_enabled_provider: &'a dyn TabStopsEnabledProvider,
+    tab_stop_key_provider: &'a dyn TabStopKeyProvider,
+    session: RefCell<Option<TabStopSession>>,
+}
+
+impl<'a> CursorHintMiddleware<'a> {
+    pub fn new(
+        strategy_provider: &'a dyn CursorHintStrategyProvider,
+        tab_stops_enabled_provider: &'a dyn TabStopsEnabledProvider,
+        tab_stop_key_provider: &'a dyn TabStopKeyProvider,
+    ) -> Self {
+        Self {
+            strategy_provider,
+            tab_stops_enabled_provider,
+            tab_stop_key_provider,
+            session: RefCell::new(None),
+        }
     }
 }
 
-impl Middleware for CursorHintMiddleware {
+impl Middleware for CursorHintMiddleware<'_> {
     fn name(&self) -> &'static str {
         "cursor_hint"
     }
 
     fn next(&self, event: Event, dispatch: &mut dyn FnMut(Event)) -> Event {
+        let mut session = self.session.borrow_mut();
+
         if let EventType::Rendered(m_event) = event.etype {
-            let (body, cursor_hint_back_count) = process_cursor_hint(m_event.body);
+            // A new expansion always replaces the tab stops of the previous one
+            *session = None;
+
+            let (body, cursor_hint_back_count) = if m_event.format == TextFormat::Plain
+                && self
+                    .tab_stops_enabled_provider
+                    .are_tab_stops_enabled(m_event.match_id)
+            {
+                let (body, stops) = process_tab_stops(&m_event.body);
+                let total_size = body.graphemes(true).count();
+                let cursor_hint_back_count = stops.first().map(|first| total_size - first);
+                if stops.len() > 1 {
+                    *session = Some(TabStopSession::new(stops));
+                }
+                (body, cursor_hint_back_count)
+            } else {
+                process_cursor_hint(m_event.body)
+            };
 
             if let Some(cursor_hint_back_count) = cursor_hint_back_count {
+                let cursor_hint_line_moves = match self
+                    .strategy_provider
+                    .get_cursor_hint_strategy(m_event.match_id)
+                {
+                    CursorHintStrategy::Chars => None,
+                    CursorHintStrategy::Lines => compute_line_moves(&body, cursor_hint_back_count),
+                };
+
                 dispatch(Event::caused_by(
                     event.source_id,
                     EventType::CursorHintCompensation(CursorHintCompensationEvent {
                         cursor_hint_back_count,
+                        cursor_hint_line_moves,
                     }),
                 ));
             }
@@ -56,6 +124,34 @@ impl Middleware for CursorHintMiddleware {
             );
         }
 
+        if let EventType::Keyboard(m_event) = &event.etype {
+            if m_event.status == Status::Pressed {
+                if let Some(current) = &mut *session {
+                    let tab_stop_key = self.tab_stop_key_provider.get_tab_stop_key();
+                    if tab_stop_key.as_ref() == Some(&m_event.key) {
+                        // The application already received the key, so if it produced
+                        // a char (as Tab does), we have to remove it before moving
+                        let jump = current.jump(m_event.value.is_some());
+                        if current.is_finished() {
+                            *session = None;
+                        }
+                        if let Some(jump) = jump {
+                            dispatch(Event::caused_by(
+                                event.source_id,
+                                EventType::TabStopJump(jump),
+                            ));
+                        }
+                    } else if !current.track_key(m_event) {
+                        *session = None;
+                    }
+                }
+            }
+        } else if let EventType::Mouse(_) = &event.etype {
+            // Similarly to the undo feature, a mouse event could move the
+            // cursor (or change application), so the stops are no longer reliable
+            *session = None;
+        }
+
         event
     }
 }
@@ -78,3 +174,437 @@ fn process_cursor_hint(body: String) -> (String, Option<usize>) {
         (body, None)
     }
 }
+
+// Compute the moves needed to reach the position that is `back_count` graphemes
+// before the end of the body by navigating lines. Moving relative to the end
+// of the target line makes the result independent of any indentation added
+// by the application. Returns None if the position is on the last line.
+fn compute_line_moves(body: &str, back_count: usize) -> Option<CursorHintLineMoves> {
+    let graphemes: Vec<&str> = body.graphemes(true).collect();
+    let position = graphemes.len().checked_sub(back_count)?;
+
+    let after = &graphemes[position..];
+    let up_count = after
+        .iter()
+        .filter(|grapheme| is_line_break(grapheme))
+        .count();
+    if up_count == 0 {
+        return None;
+    }
+
+    let line_start = graphemes[..position]
+        .iter()
+        .rposition(|grapheme| is_line_break(grapheme))
+        .map_or(0, |index| index + 1);
+    let is_indented = graphemes.get(line_start).is_some_and(|grapheme| {
+        !is_line_break(grapheme) && grapheme.chars().all(char::is_whitespace)
+    });
+
+    // "Smart" Home keys jump to the first non-whitespace char, so we
+    // can only rely on Home for lines without indentation
+    if position == line_start && !is_indented {
+        return Some(CursorHintLineMoves {
+            up_count,
+            to_line_start: true,
+            left_count: 0,
+        });
+    }
+
+    Some(CursorHintLineMoves {
+        up_count,
+        to_line_start: false,
+        left_count: after
+            .iter()
+            .position(|grapheme| is_line_break(grapheme))
+            .unwrap_or_default(),
+    })
+}
+
+fn is_line_break(grapheme: &str) -> bool {
+    grapheme == "\n" || grapheme == "\r\n"
+}
+
+// Remove the `$1`, `${1}`, ..., `$0` tab stops from the body, returning the
+// position (in graphemes) of each stop in jump order. As for editor snippets,
+// `$0` is the final cursor position and `$$` can be used for a literal dollar.
+// The legacy `$|$` hint is treated as a `$0` stop.
+fn process_tab_stops(body: &str) -> (String, Vec<usize>) {
+    let mut output = String::with_capacity(body.len());
+    let mut stops: Vec<(u32, usize)> = Vec::new();
+    let mut add_stop = |number: u32, byte_index: usize| {
+        // Only the first occurrence of each stop is considered
+        if !stops.iter().any(|(existing, _)| *existing == number) {
+            stops.push((number, byte_index));
+        }
+    };
+
+    let mut rest = body;
+    while let Some(index) = rest.find('$') {
+        output.push_str(&rest[..index]);
+        let tail = &rest[index..];
+
+        if let Some(after) = tail.strip_prefix("$|$") {
+            add_stop(0, output.len());
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("$$") {
+            output.push('$');
+            rest = after;
+        } else if let Some((number, after)) = parse_tab_stop(&tail[1..]) {
+            add_stop(number, output.len());
+            rest = after;
+        } else {
+            output.push('$');
+            rest = &tail[1..];
+        }
+    }
+    output.push_str(rest);
+
+    // The final stop ($0) always comes last
+    stops.sort_by_key(|(number, _)| (*number == 0, *number));
+
+    let positions = stops
+        .into_iter()
+        .map(|(_, byte_index)| output[..byte_index].graphemes(true).count())
+        .collect();
+    (output, positions)
+}
+
+fn parse_tab_stop(text: &str) -> Option<(u32, &str)> {
+    if let Some(braced) = text.strip_prefix('{') {
+        let end = braced.find('}')?;
+        let digits = &braced[..end];
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some((digits.parse().ok()?, &braced[end + 1..]))
+    } else {
+        let end = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        if end == 0 {
+            return None;
+        }
+        Some((text[..end].parse().ok()?, &text[end..]))
+    }
+}
+
+// Keeps track of the cursor position relative to the injected text, so that
+// the remaining stops can still be reached after the user typed at a stop.
+struct TabStopSession {
+    cursor: usize,
+    pending: VecDeque<usize>,
+}
+
+impl TabStopSession {
+    fn new(stops: Vec<usize>) -> Self {
+        let mut pending = VecDeque::from(stops);
+        let cursor = pending.pop_front().unwrap_or_default();
+        Self { cursor, pending }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn jump(&mut self, remove_inserted_char: bool) -> Option<TabStopJumpEvent> {
+        let target = self.pending.pop_front()?;
+        let jump = TabStopJumpEvent {
+            backspace_count: usize::from(remove_inserted_char),
+            left_count: self.cursor.saturating_sub(target),
+            right_count: target.saturating_sub(self.cursor),
+        };
+        self.cursor = target;
+        Some(jump)
+    }
+
+    // Update the positions based on the given key press, returning false
+    // if the key moved the cursor in a way we cannot follow.
+    fn track_key(&mut self, event: &KeyboardEvent) -> bool {
+        match (&event.key, &event.value) {
+            (
+                Key::Alt | Key::CapsLock | Key::Control | Key::Meta | Key::NumLock | Key::Shift,
+                _,
+            ) => true,
+            (Key::Backspace, _) => self.delete(),
+            (_, Some(value)) => {
+                self.insert(value.graphemes(true).count());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn insert(&mut self, count: usize) {
+        for stop in &mut self.pending {
+            if *stop >= self.cursor {
+                *stop += count;
+            }
+        }
+        self.cursor += count;
+    }
+
+    fn delete(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+
+        self.cursor -= 1;
+        for stop in &mut self.pending {
+            if *stop > self.cursor {
+                *stop -= 1;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_tab_stops_orders_stops_with_final_last() {
+        let (body, stops) = process_tab_stops("Hi $2, from $1.$0");
+        assert_eq!(body, "Hi , from .");
+        assert_eq!(stops, vec![10, 3, 11]);
+    }
+
+    #[test]
+    fn process_tab_stops_handles_braces_escapes_and_duplicates() {
+        let (body, stops) = process_tab_stops("${1}px costs $$5 $1 $ $x $|$");
+        assert_eq!(body, "px costs $5  $ $x ");
+        assert_eq!(stops, vec![0, 18]);
+    }
+
+    #[test]
+    fn process_tab_stops_counts_graphemes_across_lines() {
+        let (body, stops) = process_tab_stops("fn $1() {\r\n    $2\n}$0");
+        assert_eq!(body, "fn () {\r\n    \n}");
+        assert_eq!(stops, vec![3, 12, 14]);
+    }
+
+    fn key(key: Key, value: Option<&str>) -> KeyboardEvent {
+        KeyboardEvent {
+            key,
+            value: value.map(String::from),
+            status: Status::Pressed,
+            variant: None,
+        }
+    }
+
+    #[test]
+    fn session_follows_typed_text() {
+        let mut session = TabStopSession::new(vec![3, 10, 1]);
+
+        assert!(session.track_key(&key(Key::Shift, None)));
+        assert!(session.track_key(&key(Key::Other(65), Some("A"))));
+        assert!(session.track_key(&key(Key::Other(66), Some("b"))));
+        assert!(session.track_key(&key(Key::Backspace, None)));
+
+        assert_eq!(
+            session.jump(true),
+            Some(TabStopJumpEvent {
+                backspace_count: 1,
+                left_count: 0,
+                right_count: 7,
+            })
+        );
+        assert_eq!(
+            session.jump(false),
+            Some(TabStopJumpEvent {
+                backspace_count: 0,
+                left_count: 10,
+                right_count: 0,
+            })
+        );
+        assert!(session.is_finished());
+        assert!(!session.track_key(&key(Key::ArrowUp, None)));
+    }
+
+    #[test]
+    fn line_moves_on_last_line_are_not_needed() {
+        assert_eq!(compute_line_moves("one\ntwo", 1), None);
+        assert_eq!(compute_line_moves("single line", 4), None);
+        assert_eq!(compute_line_moves("", 0), None);
+    }
+
+    #[test]
+    fn line_moves_count_from_the_end_of_the_line() {
+        // Hint in "hello\nw|orld\nlast"
+        assert_eq!(
+            compute_line_moves("hello\nworld\nlast", 9),
+            Some(CursorHintLineMoves {
+                up_count: 1,
+                to_line_start: false,
+                left_count: 4,
+            })
+        );
+        assert_eq!(
+            compute_line_moves("fn main() {\n    \n}", 2),
+            Some(CursorHintLineMoves {
+                up_count: 1,
+                to_line_start: false,
+                left_count: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn line_moves_handle_crlf_and_trailing_newlines() {
+        assert_eq!(
+            compute_line_moves("first\r\nsecond\r\n", 9),
+            Some(CursorHintLineMoves {
+                up_count: 2,
+                to_line_start: false,
+                left_count: 1,
+            })
+        );
+        assert_eq!(
+            compute_line_moves("text\n", 1),
+            Some(CursorHintLineMoves {
+                up_count: 1,
+                to_line_start: false,
+                left_count: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn line_moves_use_home_only_for_unindented_lines() {
+        assert_eq!(
+            compute_line_moves("Dear\n\nBest regards\nJohn", 18),
+            Some(CursorHintLineMoves {
+                up_count: 2,
+                to_line_start: true,
+                left_count: 0,
+            })
+        );
+        assert_eq!(
+            compute_line_moves("Dear\n\nBest regards\nJohn", 17),
+            Some(CursorHintLineMoves {
+                up_count: 1,
+                to_line_start: true,
+                left_count: 0,
+            })
+        );
+        assert_eq!(
+            compute_line_moves("if x {\n    body\n}", 10),
+            Some(CursorHintLineMoves {
+                up_count: 1,
+                to_line_start: false,
+                left_count: 8,
+            })
+        );
+    }
+
+    struct MockProvider {}
+
+    impl CursorHintStrategyProvider for MockProvider {
+        fn get_cursor_hint_strategy(&self, _: i32) -> CursorHintStrategy {
+            CursorHintStrategy::Chars
+        }
+    }
+
+    impl TabStopsEnabledProvider for MockProvider {
+        fn are_tab_stops_enabled(&self, match_id: i32) -> bool {
+            match_id == 1
+        }
+    }
+
+    impl TabStopKeyProvider for MockProvider {
+        fn get_tab_stop_key(&self) -> Option<Key> {
+            Some(Key::Tab)
+        }
+    }
+
+    fn rendered(match_id: i32, body: &str) -> Event {
+        Event::caused_by(
+            1,
+            EventType::Rendered(RenderedEvent {
+                match_id,
+                body: body.to_string(),
+                format: TextFormat::Plain,
+            }),
+        )
+    }
+
+    fn run(middleware: &CursorHintMiddleware, event: Event) -> (Event, Vec<Event>) {
+        let mut dispatched = Vec::new();
+        let event = middleware.next(event, &mut |event| dispatched.push(event));
+        (event, dispatched)
+    }
+
+    fn keyboard(event: KeyboardEvent) -> Event {
+        Event::caused_by(2, EventType::Keyboard(event))
+    }
+
+    #[test]
+    fn middleware_jumps_between_stops() {
+        let provider = MockProvider {};
+        let middleware = CursorHintMiddleware::new(&provider, &provider, &provider);
+
+        let (event, dispatched) = run(&middleware, rendered(1, "a$1b$2c"));
+        assert!(matches!(
+            event.etype,
+            EventType::Rendered(RenderedEvent { ref body, .. }) if body == "abc"
+        ));
+        assert!(matches!(
+            dispatched[0].etype,
+            EventType::CursorHintCompensation(CursorHintCompensationEvent {
+                cursor_hint_back_count: 2,
+                cursor_hint_line_moves: None,
+            })
+        ));
+
+        let (_, dispatched) = run(&middleware, keyboard(key(Key::Tab, Some("\t"))));
+        assert!(matches!(
+            dispatched[0].etype,
+            EventType::TabStopJump(TabStopJumpEvent {
+                backspace_count: 1,
+                left_count: 0,
+                right_count: 1,
+            })
+        ));
+
+        // All the stops have been visited, so Tab behaves normally again
+        let (_, dispatched) = run(&middleware, keyboard(key(Key::Tab, Some("\t"))));
+        assert!(dispatched.is_empty());
+    }
+
+    #[test]
+    fn middleware_keeps_legacy_behavior_when_disabled() {
+        let provider = MockProvider {};
+        let middleware = CursorHintMiddleware::new(&provider, &provider, &provider);
+
+        let (event, dispatched) = run(&middleware, rendered(2, "$1 and $|$ $2"));
+        assert!(matches!(
+            event.etype,
+            EventType::Rendered(RenderedEvent { ref body, .. }) if body == "$1 and  $2"
+        ));
+        assert_eq!(dispatched.len(), 1);
+
+        let (_, dispatched) = run(&middleware, keyboard(key(Key::Tab, Some("\t"))));
+        assert!(dispatched.is_empty());
+    }
+
+    #[test]
+    fn mouse_events_end_the_session() {
+        let provider = MockProvider {};
+        let middleware = CursorHintMiddleware::new(&provider, &provider, &provider);
+
+        run(&middleware, rendered(1, "$1 $2"));
+        run(
+            &middleware,
+            Event::caused_by(
+                2,
+                EventType::Mouse(crate::event::input::MouseEvent {
+                    button: crate::event::input::MouseButton::Left,
+                    status: Status::Pressed,
+                }),
+            ),
+        );
+
+        let (_, dispatched) = run(&middleware, keyboard(key(Key::Tab, Some("\t"))));
+        assert!(dispatched.is_empty());
+    }
+}
@@ -76,9 +76,11 @@ fn is_injection_event(event_type: &EventType) -> bool {
         event_type,
         EventType::TriggerCompensation(_)
             | EventType::CursorHintCompensation(_)
+            | EventType::TabStopJump(_)
             | EventType::KeySequenceInject(_)
             | EventType::TextInject(_)
             | EventType::ImageInject(_)
+            | EventType::MacroInject(_)
             | EventType::HtmlInject(_)
             | EventType::MarkdownInject(_)
     )
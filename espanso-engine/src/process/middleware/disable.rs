@@ -24,7 +24,7 @@ use std::{
 
 use log::info;
 
-use super::super::Middleware;
+use super::super::{Middleware, ProcessorState};
 use crate::event::{
     input::{Key, KeyboardEvent, Status, Variant},
     Event, EventType,
@@ -37,23 +37,23 @@ pub struct DisableOptions {
     // TODO: toggle shortcut?
 }
 
-pub struct DisableMiddleware {
-    enabled: RefCell<bool>,
+pub struct DisableMiddleware<'a> {
+    enabled: &'a RefCell<bool>,
     last_toggle_press: RefCell<Option<Instant>>,
     options: DisableOptions,
 }
 
-impl DisableMiddleware {
-    pub fn new(options: DisableOptions) -> Self {
+impl<'a> DisableMiddleware<'a> {
+    pub fn new(options: DisableOptions, state: &'a ProcessorState) -> Self {
         Self {
-            enabled: RefCell::new(true),
+            enabled: &state.enabled,
             last_toggle_press: RefCell::new(None),
             options,
         }
     }
 }
 
-impl Middleware for DisableMiddleware {
+impl Middleware for DisableMiddleware<'_> {
     fn name(&self) -> &'static str {
         "disable"
     }
@@ -63,26 +63,24 @@ impl Middleware for DisableMiddleware {
         let mut enabled = self.enabled.borrow_mut();
 
         match &event.etype {
-            EventType::Keyboard(m_event) => {
-                if m_event.status == Status::Released {
-                    let mut last_toggle_press = self.last_toggle_press.borrow_mut();
-                    if is_toggle_key(m_event, &self.options) {
-                        if let Some(previous_press) = *last_toggle_press {
-                            if previous_press.elapsed() < self.options.toggle_key_maximum_window {
-                                *enabled = !*enabled;
-                                *last_toggle_press = None;
-                                has_status_changed = true;
-                            } else {
-                                *last_toggle_press = Some(Instant::now());
-                            }
+            EventType::Keyboard(m_event) if m_event.status == Status::Released => {
+                let mut last_toggle_press = self.last_toggle_press.borrow_mut();
+                if is_toggle_key(m_event, &self.options) {
+                    if let Some(previous_press) = *last_toggle_press {
+                        if previous_press.elapsed() < self.options.toggle_key_maximum_window {
+                            *enabled = !*enabled;
+                            *last_toggle_press = None;
+                            has_status_changed = true;
                         } else {
                             *last_toggle_press = Some(Instant::now());
                         }
                     } else {
-                        // If another key is pressed (not the toggle key), we should reset the window
-                        // For more information, see: https://github.com/espanso/espanso/issues/815
-                        *last_toggle_press = None;
+                        *last_toggle_press = Some(Instant::now());
                     }
+                } else {
+                    // If another key is pressed (not the toggle key), we should reset the window
+                    // For more information, see: https://github.com/espanso/espanso/issues/815
+                    *last_toggle_press = None;
                 }
             }
             EventType::EnableRequest => {
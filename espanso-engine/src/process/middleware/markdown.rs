@@ -38,18 +38,10 @@ impl Middleware for MarkdownMiddleware {
 
     fn next(&self, event: Event, _: &mut dyn FnMut(Event)) -> Event {
         if let EventType::MarkdownInject(m_event) = &event.etype {
-            // Render the markdown into HTML using pulldown-cmark
-            let parser = Parser::new(&m_event.markdown);
-            let mut html_output = String::new();
-            html::push_html(&mut html_output, parser);
-
-            let html = html_output.trim();
-            let html = remove_paragraph_tag_if_single_occurrence(html);
-
             return Event::caused_by(
                 event.source_id,
                 EventType::HtmlInject(HtmlInjectRequest {
-                    html: html.to_owned(),
+                    html: render_markdown(&m_event.markdown),
                 }),
             );
         }
@@ -58,6 +50,16 @@ impl Middleware for MarkdownMiddleware {
     }
 }
 
+// Render the markdown into HTML using pulldown-cmark
+pub(crate) fn render_markdown(markdown: &str) -> String {
+    let parser = Parser::new(markdown);
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, parser);
+
+    let html = html_output.trim();
+    remove_paragraph_tag_if_single_occurrence(html).to_owned()
+}
+
 // If the match is composed of a single paragraph, we remove the tag to avoid
 // a forced "newline" on some editors. In other words, we assume that if the snippet
 // is composed of a single paragraph, then it should be inlined.
@@ -23,7 +23,7 @@ use std::{
     collections::{HashMap, VecDeque},
 };
 
-use super::super::Middleware;
+use super::super::{Middleware, ProcessorState};
 use crate::event::{
     input::{Key, Status},
     internal::{DetectedMatch, MatchesDetectedEvent},
@@ -38,7 +38,7 @@ pub trait Matcher<'a, State> {
     ) -> (State, Vec<MatchResult>);
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum MatcherEvent {
     Key { key: Key, chars: Option<String> },
     VirtualSeparator,
@@ -73,6 +73,9 @@ pub struct MatcherMiddleware<'a, State> {
 
     matcher_states: RefCell<VecDeque<Vec<State>>>,
 
+    // The events that produced each of the matcher states
+    matcher_events: &'a RefCell<VecDeque<MatcherEvent>>,
+
     max_history_size: usize,
 
     modifier_status_provider: &'a dyn ModifierStateProvider,
@@ -83,18 +86,50 @@ impl<'a, State> MatcherMiddleware<'a, State> {
         matchers: &'a [&'a dyn Matcher<'a, State>],
         options_provider: &'a dyn MatcherMiddlewareConfigProvider,
         modifier_status_provider: &'a dyn ModifierStateProvider,
+        state: &'a ProcessorState,
     ) -> Self {
         let max_history_size = options_provider.max_history_size();
 
+        // Rebuild the state left by a previous processor on top of the current matchers,
+        // ignoring the matches detected along the way as they were already handled
+        let mut matcher_events = state.matcher_events.borrow_mut();
+        while matcher_events.len() > max_history_size {
+            matcher_events.pop_front();
+        }
+        let mut matcher_states: VecDeque<Vec<State>> = VecDeque::new();
+        for event in matcher_events.iter() {
+            let (states, _) = process_matchers(matchers, matcher_states.back(), event);
+            matcher_states.push_back(states);
+        }
+
         Self {
             matchers,
-            matcher_states: RefCell::new(VecDeque::new()),
+            matcher_states: RefCell::new(matcher_states),
+            matcher_events: &state.matcher_events,
             max_history_size,
             modifier_status_provider,
         }
     }
 }
 
+fn process_matchers<'a, State>(
+    matchers: &'a [&'a dyn Matcher<'a, State>],
+    prev_states: Option<&Vec<State>>,
+    event: &MatcherEvent,
+) -> (Vec<State>, Vec<MatchResult>) {
+    let mut all_results = Vec::new();
+    let mut new_states = Vec::new();
+    for (i, matcher) in matchers.iter().enumerate() {
+        let prev_state = prev_states.and_then(|states| states.get(i));
+
+        let (state, results) = matcher.process(prev_state, event);
+        all_results.extend(results);
+
+        new_states.push(state);
+    }
+    (new_states, all_results)
+}
+
 impl<State> Middleware for MatcherMiddleware<'_, State> {
     fn name(&self) -> &'static str {
         "matcher"
@@ -103,6 +138,7 @@ impl<State> Middleware for MatcherMiddleware<'_, State> {
     fn next(&self, event: Event, _: &mut dyn FnMut(Event)) -> Event {
         if is_event_of_interest(&event.etype) {
             let mut matcher_states = self.matcher_states.borrow_mut();
+            let mut matcher_events = self.matcher_events.borrow_mut();
             let prev_states = if matcher_states.is_empty() {
                 None
             } else {
@@ -114,6 +150,7 @@ impl<State> Middleware for MatcherMiddleware<'_, State> {
                 if keyboard_event.key == Key::Backspace {
                     trace!("popping the last matcher state");
                     matcher_states.pop_back();
+                    matcher_events.pop_back();
                     return event;
                 }
 
@@ -134,25 +171,19 @@ impl<State> Middleware for MatcherMiddleware<'_, State> {
             if is_invalidating_event(&event.etype) {
                 trace!("invalidating event detected, clearing matching state");
                 matcher_states.clear();
+                matcher_events.clear();
                 return event;
             }
 
-            let mut all_results = Vec::new();
-
             if let Some(matcher_event) = convert_to_matcher_event(&event.etype) {
-                let mut new_states = Vec::new();
-                for (i, matcher) in self.matchers.iter().enumerate() {
-                    let prev_state = prev_states.and_then(|states| states.get(i));
-
-                    let (state, results) = matcher.process(prev_state, &matcher_event);
-                    all_results.extend(results);
-
-                    new_states.push(state);
-                }
+                let (new_states, all_results) =
+                    process_matchers(self.matchers, prev_states, &matcher_event);
 
                 matcher_states.push_back(new_states);
+                matcher_events.push_back(matcher_event);
                 if matcher_states.len() > self.max_history_size {
                     matcher_states.pop_front();
+                    matcher_events.pop_front();
                 }
 
                 if !all_results.is_empty() {
@@ -182,30 +213,23 @@ impl<State> Middleware for MatcherMiddleware<'_, State> {
 
 fn is_event_of_interest(event_type: &EventType) -> bool {
     match event_type {
-        EventType::Keyboard(keyboard_event) => {
-            if keyboard_event.status == Status::Pressed {
-                // Skip linux Keyboard (XKB) Extension function and modifier keys
-                // In hex, they have the byte 3 = 0xfe
-                // See list in "keysymdef.h" file
-                if cfg!(target_os = "linux") {
-                    if let (Key::Other(raw_code), None) =
-                        (&keyboard_event.key, &keyboard_event.value)
-                    {
-                        if (65025..=65276).contains(raw_code) {
-                            return false;
-                        }
+        EventType::Keyboard(keyboard_event) if keyboard_event.status == Status::Pressed => {
+            // Skip linux Keyboard (XKB) Extension function and modifier keys
+            // In hex, they have the byte 3 = 0xfe
+            // See list in "keysymdef.h" file
+            if cfg!(target_os = "linux") {
+                if let (Key::Other(raw_code), None) = (&keyboard_event.key, &keyboard_event.value) {
+                    if (65025..=65276).contains(raw_code) {
+                        return false;
                     }
                 }
-
-                // Skip modifier keys
-                !matches!(
-                    keyboard_event.key,
-                    Key::Alt | Key::Shift | Key::CapsLock | Key::Meta | Key::NumLock | Key::Control
-                )
-            } else {
-                // Skip non-press events
-                false
             }
+
+            // Skip modifier keys
+            !matches!(
+                keyboard_event.key,
+                Key::Alt | Key::Shift | Key::CapsLock | Key::Meta | Key::NumLock | Key::Control
+            )
         }
         EventType::Mouse(mouse_event) => mouse_event.status == Status::Pressed,
         EventType::MatchInjected => true,
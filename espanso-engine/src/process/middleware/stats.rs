@@ -101,6 +101,20 @@ impl Middleware for StatsMiddleware {
                     );
                 }
             }
+            EventType::MacroRequested(m_event) => {
+                if let Some(trigger) = &m_event.trigger {
+                    let mut pend = self.pending.borrow_mut();
+                    if pend.len() > 4096 {
+                        pend.clear();
+                    }
+                    pend.insert(
+                        event.source_id,
+                        PendingEntry {
+                            trigger: trigger.clone(),
+                        },
+                    );
+                }
+            }
             EventType::DiscardPrevious(e) => {
                 let mut pend = self.pending.borrow_mut();
                 let min_id = e.minimum_source_id;
@@ -17,29 +17,39 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::cell::RefCell;
+use std::{cell::RefCell, collections::VecDeque};
 
-use super::super::Middleware;
+use super::super::{Middleware, ProcessorState};
+use super::markdown::render_markdown;
 use crate::event::{
-    input::{Key, Status},
-    internal::{TextFormat, UndoEvent},
+    input::{Key, KeyboardEvent, Status},
+    internal::{RenderedEvent, TextFormat, UndoEvent},
     Event, EventType,
 };
 
+// Number of expansions that can be reverted, starting from the most recent one
+const MAX_HISTORY_SIZE: usize = 10;
+
 pub trait UndoEnabledProvider {
     fn is_undo_enabled(&self) -> bool;
+
+    // Hotkey reverting the last expansion, such as "ALT+SHIFT+U"
+    fn undo_shortcut(&self) -> Option<String>;
 }
 
 pub struct UndoMiddleware<'a> {
     undo_enabled_provider: &'a dyn UndoEnabledProvider,
-    record: RefCell<Option<InjectionRecord>>,
+    history: &'a RefCell<UndoHistory>,
 }
 
 impl<'a> UndoMiddleware<'a> {
-    pub fn new(undo_enabled_provider: &'a dyn UndoEnabledProvider) -> Self {
+    pub fn new(
+        undo_enabled_provider: &'a dyn UndoEnabledProvider,
+        state: &'a ProcessorState,
+    ) -> Self {
         Self {
             undo_enabled_provider,
-            record: RefCell::new(None),
+            history: &state.undo_history,
         }
     }
 }
@@ -50,63 +60,707 @@ impl Middleware for UndoMiddleware<'_> {
     }
 
     fn next(&self, event: Event, _: &mut dyn FnMut(Event)) -> Event {
-        let mut record = self.record.borrow_mut();
-
-        if let EventType::TriggerCompensation(m_event) = &event.etype {
-            *record = Some(InjectionRecord {
-                id: Some(event.source_id),
-                trigger: Some(m_event.trigger.clone()),
-                ..Default::default()
-            });
-        } else if let EventType::Rendered(m_event) = &event.etype {
-            if m_event.format == TextFormat::Plain {
-                if let Some(record) = &mut *record {
-                    if record.id == Some(event.source_id) {
-                        record.injected_text = Some(m_event.body.clone());
-                        record.match_id = Some(m_event.match_id);
-                    }
+        let mut history = self.history.borrow_mut();
+
+        match &event.etype {
+            EventType::TriggerCompensation(m_event) => {
+                let mut restored = m_event.trigger.as_str();
+                if let Some(left_separator) = &m_event.left_separator {
+                    restored = restored.strip_prefix(left_separator).unwrap_or(restored);
                 }
+                history.begin_injection(event.source_id, restored);
+            }
+            EventType::Rendered(m_event) => {
+                history.complete_injection(event.source_id, m_event);
             }
-        } else if let EventType::Keyboard(m_event) = &event.etype {
-            if m_event.status == Status::Pressed {
-                if m_event.key == Key::Backspace {
-                    if let Some(record) = (*record).take() {
-                        if let (Some(trigger), Some(injected_text), Some(match_id)) =
-                            (record.trigger, record.injected_text, record.match_id)
-                        {
-                            if self.undo_enabled_provider.is_undo_enabled() {
-                                return Event::caused_by(
-                                    event.source_id,
-                                    EventType::Undo(UndoEvent {
-                                        match_id,
-                                        trigger,
-                                        replace: injected_text,
-                                    }),
-                                );
-                            }
-                        }
+            EventType::CursorHintCompensation(m_event) => {
+                // Moving by lines depends on the content surrounding the expansion,
+                // so we can't reliably go back to its end
+                if m_event.cursor_hint_line_moves.is_some() {
+                    history.clear();
+                } else {
+                    history.cursor_back_count = m_event.cursor_hint_back_count;
+                }
+            }
+            EventType::Keyboard(m_event) => {
+                if m_event.key == Key::Backspace
+                    && m_event.status == Status::Pressed
+                    && history.is_fresh()
+                    && self.undo_enabled_provider.is_undo_enabled()
+                {
+                    // The backspace that triggered the undo feature already
+                    // removed the last char of the expansion
+                    if let Some(undo) = history.undo(1) {
+                        return Event::caused_by(event.source_id, EventType::Undo(undo));
                     }
                 }
-                *record = None;
+
+                history.track_key(m_event, &|| self.undo_enabled_provider.undo_shortcut());
+            }
+            EventType::UndoRequest => {
+                if let Some(undo) = history.undo(0) {
+                    return Event::caused_by(event.source_id, EventType::Undo(undo));
+                }
+            }
+            EventType::Mouse(_)
+            | EventType::TabStopJump(_)
+            | EventType::ImageResolved(_)
+            | EventType::MacroRequested(_) => {
+                // Explanation:
+                // * Any mouse event invalidates the history, as it could
+                //   represent a change in application or cursor position
+                // * Tab stop jumps move the cursor around the expansion, and it
+                //   would be pretty complex to determine which delete operations
+                //   should be performed. This might change in the future.
+                // * Images and macros inject content whose length can't be tracked
+                history.clear();
             }
-        } else if let EventType::Mouse(_) | EventType::CursorHintCompensation(_) = &event.etype {
-            // Explanation:
-            // * Any mouse event invalidates the undo feature, as it could
-            //   represent a change in application
-            // * Cursor hints invalidate the undo feature, as it would be pretty
-            //   complex to determine which delete operations should be performed.
-            //   This might change in the future.
-            *record = None;
+            _ => {}
         }
 
         event
     }
 }
 
+// Model of the text surrounding the cursor, which is composed of the
+// recent expansions, each followed by the chars typed after it
 #[derive(Default)]
+pub(crate) struct UndoHistory {
+    records: VecDeque<InjectionRecord>,
+
+    // Number of chars between the cursor and the end of the last expansion,
+    // when the cursor has been moved inside it by a cursor hint
+    cursor_back_count: usize,
+
+    control_pressed: bool,
+    alt_pressed: bool,
+    meta_pressed: bool,
+    shift_pressed: bool,
+}
+
 struct InjectionRecord {
-    id: Option<u32>,
+    id: u32,
     match_id: Option<i32>,
-    trigger: Option<String>,
-    injected_text: Option<String>,
+    trigger: String,
+
+    // Number of chars injected by the expansion that are still present
+    injected_count: usize,
+
+    // Number of chars typed after the expansion, before the next one
+    typed_count: usize,
+
+    // Rich text is pasted, so it can only be reverted with an explicit undo request
+    rich_text: bool,
+
+    // True if no key has been pressed since the expansion
+    fresh: bool,
+}
+
+impl UndoHistory {
+    fn clear(&mut self) {
+        self.records.clear();
+        self.cursor_back_count = 0;
+    }
+
+    // The backspace-based undo is only available right after a plain text
+    // expansion, as long as the cursor is still inside of it
+    fn is_fresh(&self) -> bool {
+        self.records.back().is_some_and(|record| {
+            record.fresh && !record.rich_text && self.cursor_back_count < record.injected_count
+        })
+    }
+
+    fn begin_injection(&mut self, id: u32, trigger: &str) {
+        // If the last record has never been rendered, something we can't
+        // track (such as an image) might have been injected in its place
+        if self.cursor_back_count > 0
+            || self
+                .records
+                .back()
+                .is_some_and(|record| record.match_id.is_none())
+        {
+            self.clear();
+        }
+
+        for _ in 0..trigger.chars().count() {
+            self.delete();
+        }
+
+        if self.records.len() == MAX_HISTORY_SIZE {
+            self.records.pop_front();
+        }
+
+        self.records.push_back(InjectionRecord {
+            id,
+            match_id: None,
+            trigger: trigger.to_string(),
+            injected_count: 0,
+            typed_count: 0,
+            rich_text: false,
+            fresh: false,
+        });
+    }
+
+    fn complete_injection(&mut self, id: u32, rendered: &RenderedEvent) {
+        let count = match rendered.format {
+            TextFormat::Plain => Some(rendered.body.chars().count()),
+            TextFormat::Markdown => count_html_visible_chars(&render_markdown(&rendered.body)),
+            TextFormat::Html => count_html_visible_chars(&rendered.body),
+        };
+
+        // Without an exact count, reverting this expansion (or the previous
+        // ones) could delete text that doesn't belong to it
+        let Some(count) = count else {
+            self.clear();
+            return;
+        };
+
+        match self.records.back_mut() {
+            Some(record) if record.id == id => {
+                record.match_id = Some(rendered.match_id);
+                record.injected_count = count;
+                record.rich_text = rendered.format != TextFormat::Plain;
+                record.fresh = true;
+            }
+            // Expansions without a trigger (for example, those started with a hotkey)
+            // can't be reverted, but we still need to account for their content
+            _ => self.insert(count),
+        }
+    }
+
+    fn track_key(&mut self, event: &KeyboardEvent, undo_shortcut: &dyn Fn() -> Option<String>) {
+        let is_pressed = event.status == Status::Pressed;
+        match event.key {
+            Key::Control => self.control_pressed = is_pressed,
+            Key::Alt => self.alt_pressed = is_pressed,
+            Key::Meta => self.meta_pressed = is_pressed,
+            Key::Shift => self.shift_pressed = is_pressed,
+            _ => {}
+        }
+
+        if !is_pressed {
+            return;
+        }
+
+        for record in &mut self.records {
+            record.fresh = false;
+        }
+
+        match (&event.key, &event.value) {
+            (
+                Key::Alt | Key::CapsLock | Key::Control | Key::Meta | Key::NumLock | Key::Shift,
+                _,
+            ) => {}
+            // Shortcuts (such as pasting) change the text in ways we can't track,
+            // except for the undo hotkey itself
+            _ if self.control_pressed || self.alt_pressed || self.meta_pressed => {
+                if !undo_shortcut().is_some_and(|shortcut| self.is_shortcut(event, &shortcut)) {
+                    self.clear();
+                }
+            }
+            (Key::Backspace, _) => self.delete(),
+            (_, Some(value)) => self.insert(value.chars().count()),
+            // Any other key (such as the arrows) moves the cursor somewhere
+            // we can't track
+            _ => self.clear(),
+        }
+    }
+
+    // Check if the pressed chord matches the given hotkey. The key codes depend on the
+    // platform, so the main key is only compared when it produces an alphanumeric char.
+    fn is_shortcut(&self, event: &KeyboardEvent, shortcut: &str) -> bool {
+        let mut modifiers = (false, false, false, false);
+        let mut main_key = None;
+        for token in shortcut.split('+').map(|token| token.trim().to_uppercase()) {
+            match token.as_str() {
+                "CTRL" => modifiers.0 = true,
+                "ALT" | "OPTION" => modifiers.1 = true,
+                "META" | "CMD" => modifiers.2 = true,
+                "SHIFT" => modifiers.3 = true,
+                _ => main_key = Some(token),
+            }
+        }
+
+        let pressed = (
+            self.control_pressed,
+            self.alt_pressed,
+            self.meta_pressed,
+            self.shift_pressed,
+        );
+        if modifiers != pressed {
+            return false;
+        }
+
+        match (main_key, &event.value) {
+            (Some(main_key), Some(value))
+                if value.chars().count() == 1
+                    && value.chars().all(|c| c.is_ascii_alphanumeric()) =>
+            {
+                value.to_uppercase() == main_key
+            }
+            (main_key, _) => main_key.is_some(),
+        }
+    }
+
+    fn insert(&mut self, count: usize) {
+        if let Some(record) = self.records.back_mut() {
+            if self.cursor_back_count > 0 {
+                // The cursor is inside the expansion
+                record.injected_count += count;
+            } else {
+                record.typed_count += count;
+            }
+        }
+    }
+
+    fn delete(&mut self) {
+        while let Some(record) = self.records.back_mut() {
+            if self.cursor_back_count > 0 {
+                if record.injected_count > self.cursor_back_count {
+                    record.injected_count -= 1;
+                } else {
+                    self.clear();
+                }
+                return;
+            }
+
+            if record.typed_count > 0 {
+                record.typed_count -= 1;
+                return;
+            }
+
+            if record.match_id.is_some() && record.injected_count > 0 {
+                record.injected_count -= 1;
+                if record.injected_count == 0 {
+                    self.records.pop_back();
+                }
+                return;
+            }
+
+            if record.match_id.is_none() {
+                // Pending expansions might be followed by untracked content
+                self.clear();
+                return;
+            }
+
+            self.records.pop_back();
+        }
+    }
+
+    fn undo(&mut self, already_deleted: usize) -> Option<UndoEvent> {
+        let record = self.records.pop_back()?;
+        let Some(match_id) = record.match_id else {
+            self.clear();
+            return None;
+        };
+
+        let undo = UndoEvent {
+            match_id,
+            trigger: record.trigger,
+            delete_count: record.injected_count.saturating_sub(already_deleted),
+            forward_count: self.cursor_back_count,
+            backward_count: record.typed_count,
+        };
+
+        // After the undo, the restored trigger and the typed chars follow
+        // the previous expansion
+        self.cursor_back_count = 0;
+        if let Some(previous) = self.records.back_mut() {
+            previous.typed_count += undo.trigger.chars().count() + undo.backward_count;
+        }
+
+        Some(undo)
+    }
+}
+
+// Count the chars an HTML snippet produces once pasted as rich text. Editors differ
+// on block elements, line breaks and whitespace, so the count is only returned
+// when the snippet is made of inline formatting and single spaces.
+fn count_html_visible_chars(html: &str) -> Option<usize> {
+    let mut count = 0;
+    let mut pending_space = false;
+    let mut chars = html.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '<' {
+            let tag: String = chars.by_ref().take_while(|c| *c != '>').collect();
+            let name = tag
+                .trim_start_matches('/')
+                .chars()
+                .take_while(char::is_ascii_alphanumeric)
+                .collect::<String>()
+                .to_ascii_lowercase();
+
+            if !is_inline_element(&name) {
+                return None;
+            }
+            continue;
+        }
+
+        if c.is_whitespace() {
+            // Leading, repeated and special whitespace might be collapsed
+            if c != ' ' || count == 0 || pending_space {
+                return None;
+            }
+            pending_space = true;
+            continue;
+        }
+
+        // Entities are rendered as a single char
+        if c == '&' {
+            let rest: String = chars.clone().take(10).collect();
+            if let Some(end) = rest.find(';') {
+                let entity = &rest[..end];
+                if !entity.is_empty()
+                    && entity
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '#')
+                {
+                    chars.nth(end);
+                }
+            }
+        }
+
+        if pending_space {
+            count += 1;
+        }
+        count += 1;
+        pending_space = false;
+    }
+
+    // Trailing whitespace might be collapsed as well
+    if pending_space {
+        return None;
+    }
+
+    Some(count)
+}
+
+fn is_inline_element(name: &str) -> bool {
+    matches!(
+        name,
+        "a" | "b"
+            | "strong"
+            | "i"
+            | "em"
+            | "u"
+            | "s"
+            | "del"
+            | "ins"
+            | "mark"
+            | "code"
+            | "span"
+            | "sub"
+            | "sup"
+            | "small"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::effect::{CursorHintCompensationEvent, TriggerCompensationEvent};
+
+    struct MockProvider {}
+
+    impl UndoEnabledProvider for MockProvider {
+        fn is_undo_enabled(&self) -> bool {
+            true
+        }
+
+        fn undo_shortcut(&self) -> Option<String> {
+            Some("ALT+SHIFT+U".to_string())
+        }
+    }
+
+    fn expand(middleware: &UndoMiddleware, id: u32, trigger: &str, body: &str, format: TextFormat) {
+        middleware.next(
+            Event::caused_by(
+                id,
+                EventType::TriggerCompensation(TriggerCompensationEvent {
+                    trigger: trigger.to_string(),
+                    left_separator: None,
+                }),
+            ),
+            &mut |_| {},
+        );
+        middleware.next(
+            Event::caused_by(
+                id,
+                EventType::Rendered(RenderedEvent {
+                    match_id: i32::try_from(id).unwrap(),
+                    body: body.to_string(),
+                    format,
+                }),
+            ),
+            &mut |_| {},
+        );
+    }
+
+    fn press(middleware: &UndoMiddleware, key: Key, value: Option<&str>) -> Event {
+        middleware.next(
+            Event::caused_by(
+                100,
+                EventType::Keyboard(KeyboardEvent {
+                    key,
+                    value: value.map(str::to_string),
+                    status: Status::Pressed,
+                    variant: None,
+                }),
+            ),
+            &mut |_| {},
+        )
+    }
+
+    fn type_text(middleware: &UndoMiddleware, text: &str) {
+        for c in text.chars() {
+            press(middleware, Key::Other(0), Some(&c.to_string()));
+        }
+    }
+
+    fn request_undo(middleware: &UndoMiddleware) -> Option<UndoEvent> {
+        match middleware
+            .next(Event::caused_by(100, EventType::UndoRequest), &mut |_| {})
+            .etype
+        {
+            EventType::Undo(undo) => Some(undo),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn backspace_reverts_last_expansion() {
+        let provider = MockProvider {};
+        let state = ProcessorState::new();
+        let middleware = UndoMiddleware::new(&provider, &state);
+
+        expand(&middleware, 1, ":hi", "hello", TextFormat::Plain);
+
+        assert!(matches!(
+            press(&middleware, Key::Backspace, None).etype,
+            EventType::Undo(undo) if undo == UndoEvent {
+                match_id: 1,
+                trigger: ":hi".to_string(),
+                delete_count: 4,
+                forward_count: 0,
+                backward_count: 0,
+            }
+        ));
+
+        // Only the first backspace reverts the expansion
+        type_text(&middleware, "x");
+        assert!(matches!(
+            press(&middleware, Key::Backspace, None).etype,
+            EventType::Keyboard(_)
+        ));
+    }
+
+    #[test]
+    fn undo_requests_revert_expansions_in_reverse_order() {
+        let provider = MockProvider {};
+        let state = ProcessorState::new();
+        let middleware = UndoMiddleware::new(&provider, &state);
+
+        expand(&middleware, 1, ":a", "first", TextFormat::Plain);
+        type_text(&middleware, " :b");
+        expand(&middleware, 2, ":b", "second", TextFormat::Plain);
+        type_text(&middleware, "!");
+
+        assert_eq!(
+            request_undo(&middleware),
+            Some(UndoEvent {
+                match_id: 2,
+                trigger: ":b".to_string(),
+                delete_count: 6,
+                forward_count: 0,
+                backward_count: 1,
+            })
+        );
+        assert_eq!(
+            request_undo(&middleware),
+            Some(UndoEvent {
+                match_id: 1,
+                trigger: ":a".to_string(),
+                delete_count: 5,
+                forward_count: 0,
+                backward_count: 4,
+            })
+        );
+        assert_eq!(request_undo(&middleware), None);
+    }
+
+    #[test]
+    fn undo_tracks_cursor_hint_compensation() {
+        let provider = MockProvider {};
+        let state = ProcessorState::new();
+        let middleware = UndoMiddleware::new(&provider, &state);
+
+        expand(&middleware, 1, ":div", "<div></div>", TextFormat::Plain);
+        middleware.next(
+            Event::caused_by(
+                1,
+                EventType::CursorHintCompensation(CursorHintCompensationEvent {
+                    cursor_hint_back_count: 6,
+                    cursor_hint_line_moves: None,
+                }),
+            ),
+            &mut |_| {},
+        );
+        type_text(&middleware, "ab");
+        press(&middleware, Key::Backspace, None);
+
+        assert_eq!(
+            request_undo(&middleware),
+            Some(UndoEvent {
+                match_id: 1,
+                trigger: ":div".to_string(),
+                delete_count: 12,
+                forward_count: 6,
+                backward_count: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn untracked_cursor_movements_clear_history() {
+        let provider = MockProvider {};
+        let state = ProcessorState::new();
+        let middleware = UndoMiddleware::new(&provider, &state);
+
+        expand(&middleware, 1, ":a", "first", TextFormat::Plain);
+        press(&middleware, Key::ArrowLeft, None);
+        assert_eq!(request_undo(&middleware), None);
+
+        expand(&middleware, 2, ":b", "second", TextFormat::Plain);
+        middleware.next(
+            Event::caused_by(
+                3,
+                EventType::Mouse(crate::event::input::MouseEvent {
+                    button: crate::event::input::MouseButton::Left,
+                    status: Status::Pressed,
+                }),
+            ),
+            &mut |_| {},
+        );
+        assert_eq!(request_undo(&middleware), None);
+    }
+
+    fn press_chord(middleware: &UndoMiddleware, modifiers: &[Key], key: Key, value: Option<&str>) {
+        for modifier in modifiers {
+            press(middleware, modifier.clone(), None);
+        }
+        press(middleware, key, value);
+        for modifier in modifiers {
+            middleware.next(
+                Event::caused_by(
+                    100,
+                    EventType::Keyboard(KeyboardEvent {
+                        key: modifier.clone(),
+                        value: None,
+                        status: Status::Released,
+                        variant: None,
+                    }),
+                ),
+                &mut |_| {},
+            );
+        }
+    }
+
+    #[test]
+    fn shortcuts_clear_history() {
+        let provider = MockProvider {};
+        let state = ProcessorState::new();
+        let middleware = UndoMiddleware::new(&provider, &state);
+
+        expand(&middleware, 1, ":hi", "hello", TextFormat::Plain);
+        press_chord(&middleware, &[Key::Control], Key::Other(0), Some("v"));
+        assert_eq!(request_undo(&middleware), None);
+
+        // A different main key with the same modifiers isn't the undo hotkey
+        expand(&middleware, 2, ":hi", "hello", TextFormat::Plain);
+        press_chord(
+            &middleware,
+            &[Key::Alt, Key::Shift],
+            Key::Other(0),
+            Some("X"),
+        );
+        assert_eq!(request_undo(&middleware), None);
+    }
+
+    #[test]
+    fn undo_shortcut_keeps_history() {
+        let provider = MockProvider {};
+        let state = ProcessorState::new();
+        let middleware = UndoMiddleware::new(&provider, &state);
+
+        expand(&middleware, 1, ":hi", "hello", TextFormat::Plain);
+        press_chord(
+            &middleware,
+            &[Key::Alt, Key::Shift],
+            Key::Other(0),
+            Some("U"),
+        );
+        assert_eq!(request_undo(&middleware).map(|undo| undo.match_id), Some(1));
+
+        // Some platforms don't report the value of the chord
+        expand(&middleware, 2, ":hi", "hello", TextFormat::Plain);
+        press_chord(&middleware, &[Key::Shift, Key::Alt], Key::Other(22), None);
+        assert_eq!(request_undo(&middleware).map(|undo| undo.match_id), Some(2));
+    }
+
+    #[test]
+    fn rich_text_expansions_are_reverted() {
+        let provider = MockProvider {};
+        let state = ProcessorState::new();
+        let middleware = UndoMiddleware::new(&provider, &state);
+
+        expand(&middleware, 1, ":md", "**bold** text", TextFormat::Markdown);
+        type_text(&middleware, ":html");
+        expand(&middleware, 2, ":html", "<b>a&amp;b</b>", TextFormat::Html);
+
+        // Pasted content can only be reverted with an explicit request
+        assert!(matches!(
+            press(&middleware, Key::Backspace, None).etype,
+            EventType::Keyboard(_)
+        ));
+        assert_eq!(request_undo(&middleware).unwrap().delete_count, 2);
+        assert_eq!(request_undo(&middleware).unwrap().delete_count, 9);
+    }
+
+    #[test]
+    fn rich_text_expansions_without_exact_length_are_not_reverted() {
+        let provider = MockProvider {};
+        let state = ProcessorState::new();
+        let middleware = UndoMiddleware::new(&provider, &state);
+
+        expand(&middleware, 1, ":a", "first", TextFormat::Plain);
+        type_text(&middleware, " :p");
+        // Depending on the editor, the paragraphs are pasted as "a\nb" or "a\n\nb",
+        // so deleting the estimated length could remove the text before them
+        expand(&middleware, 2, ":p", "<p>a</p><p>b</p>", TextFormat::Html);
+
+        assert!(matches!(
+            press(&middleware, Key::Backspace, None).etype,
+            EventType::Keyboard(_)
+        ));
+        assert_eq!(request_undo(&middleware), None);
+    }
+
+    #[test]
+    fn count_html_visible_chars_handles_formatting_and_entities() {
+        assert_eq!(count_html_visible_chars("hello"), Some(5));
+        assert_eq!(count_html_visible_chars("<i>x y</i> &lt;3"), Some(6));
+        assert_eq!(count_html_visible_chars("<a href=\"x\">R&D</a>"), Some(3));
+    }
+
+    #[test]
+    fn count_html_visible_chars_rejects_editor_dependent_content() {
+        assert_eq!(count_html_visible_chars("<p>a</p>\n<p>b</p>"), None);
+        assert_eq!(count_html_visible_chars("a<br>b"), None);
+        assert_eq!(count_html_visible_chars("<ul><li>a</li></ul>"), None);
+        assert_eq!(count_html_visible_chars("<i>x  y</i>"), None);
+        assert_eq!(count_html_visible_chars("x\ny"), None);
+        assert_eq!(count_html_visible_chars(" x"), None);
+        assert_eq!(count_html_visible_chars("x "), None);
+    }
 }
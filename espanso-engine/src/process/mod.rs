@@ -21,6 +21,7 @@ use super::Event;
 
 mod default;
 mod middleware;
+mod state;
 
 pub trait Middleware {
     fn name(&self) -> &'static str;
@@ -35,14 +36,17 @@ pub trait Processor {
 
 pub use middleware::action::{EventSequenceProvider, MatchInfoProvider};
 pub use middleware::alt_code_synthesizer::AltCodeSynthEnabledProvider;
+pub use middleware::cursor_hint::{
+    CursorHintStrategy, CursorHintStrategyProvider, TabStopKeyProvider, TabStopsEnabledProvider,
+};
 pub use middleware::delay_modifiers::ModifierStatusProvider;
 pub use middleware::disable::DisableOptions;
 pub use middleware::image_resolve::PathProvider;
 pub use middleware::match_exec::MatchResolver;
 pub use middleware::match_select::{MatchFilter, MatchSelector};
 pub use middleware::matcher::{
-    MatchResult, Matcher, MatcherEvent, MatcherMiddlewareConfigProvider, ModifierState,
-    ModifierStateProvider,
+    MatchResult, Matcher, MatcherEvent, MatcherMiddleware, MatcherMiddlewareConfigProvider,
+    ModifierState, ModifierStateProvider,
 };
 pub use middleware::multiplex::Multiplexer;
 pub use middleware::notification::NotificationManager;
@@ -52,6 +56,7 @@ pub use middleware::search::MatchProvider;
 pub use middleware::stats::{set_global_recorder, StatsRecord, StatsRecorder};
 pub use middleware::suppress::EnabledStatusProvider;
 pub use middleware::undo::UndoEnabledProvider;
+pub use state::ProcessorState;
 
 #[allow(clippy::too_many_arguments)]
 pub fn default<'a, MatcherState>(
@@ -74,6 +79,10 @@ pub fn default<'a, MatcherState>(
     match_resolver: &'a dyn MatchResolver,
     notification_manager: &'a dyn NotificationManager,
     alt_code_synth_enabled_provider: &'a dyn AltCodeSynthEnabledProvider,
+    cursor_hint_strategy_provider: &'a dyn CursorHintStrategyProvider,
+    tab_stops_enabled_provider: &'a dyn TabStopsEnabledProvider,
+    tab_stop_key_provider: &'a dyn TabStopKeyProvider,
+    state: &'a ProcessorState,
 ) -> impl Processor + 'a {
     default::DefaultProcessor::new(
         matchers,
@@ -95,5 +104,9 @@ pub fn default<'a, MatcherState>(
         match_resolver,
         notification_manager,
         alt_code_synth_enabled_provider,
+        cursor_hint_strategy_provider,
+        tab_stops_enabled_provider,
+        tab_stop_key_provider,
+        state,
     )
 }
@@ -0,0 +1,205 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{cell::RefCell, collections::VecDeque};
+
+use super::{middleware::undo::UndoHistory, MatcherEvent};
+
+/// The state accumulated by the middleware while processing events.
+///
+/// It's owned by the caller rather than by the processor, so that it survives
+/// when the processor is rebuilt on top of new match and config providers,
+/// as it happens when the configuration is reloaded.
+pub struct ProcessorState {
+    pub(crate) enabled: RefCell<bool>,
+    pub(crate) undo_history: RefCell<UndoHistory>,
+
+    // The events fed to the matchers since the last invalidation. The matchers
+    // state refers to the matches it was built from, so it's rebuilt by
+    // replaying these events when a new processor is created.
+    pub(crate) matcher_events: RefCell<VecDeque<MatcherEvent>>,
+}
+
+impl ProcessorState {
+    pub fn new() -> Self {
+        Self {
+            enabled: RefCell::new(true),
+            undo_history: RefCell::new(UndoHistory::default()),
+            matcher_events: RefCell::new(VecDeque::new()),
+        }
+    }
+}
+
+impl Default for ProcessorState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        event::{
+            input::{Key, KeyboardEvent, Status},
+            Event, EventType,
+        },
+        process::{
+            middleware::disable::DisableMiddleware, DisableOptions, MatchResult, Matcher,
+            MatcherMiddleware, MatcherMiddlewareConfigProvider, Middleware, ModifierState,
+            ModifierStateProvider,
+        },
+    };
+
+    struct TriggerMatcher {
+        trigger: &'static str,
+    }
+
+    impl<'a> Matcher<'a, String> for TriggerMatcher {
+        fn process(
+            &'a self,
+            prev_state: Option<&String>,
+            event: &MatcherEvent,
+        ) -> (String, Vec<MatchResult>) {
+            let mut buffer = prev_state.cloned().unwrap_or_default();
+            match event {
+                MatcherEvent::Key {
+                    chars: Some(chars), ..
+                } => buffer.push_str(chars),
+                _ => buffer.clear(),
+            }
+
+            let results = if buffer.ends_with(self.trigger) {
+                vec![MatchResult {
+                    id: 1,
+                    trigger: self.trigger.to_string(),
+                    left_separator: None,
+                    right_separator: None,
+                    args: HashMap::new(),
+                }]
+            } else {
+                Vec::new()
+            };
+            (buffer, results)
+        }
+    }
+
+    struct MockProvider {}
+
+    impl MatcherMiddlewareConfigProvider for MockProvider {
+        fn max_history_size(&self) -> usize {
+            10
+        }
+    }
+
+    impl ModifierStateProvider for MockProvider {
+        fn get_modifier_state(&self) -> ModifierState {
+            ModifierState {
+                is_ctrl_down: false,
+                is_alt_down: false,
+                is_meta_down: false,
+            }
+        }
+    }
+
+    fn key_event(key: Key, value: Option<&str>, status: Status) -> Event {
+        Event::caused_by(
+            0,
+            EventType::Keyboard(KeyboardEvent {
+                key,
+                value: value.map(str::to_string),
+                status,
+                variant: None,
+            }),
+        )
+    }
+
+    fn type_text(middleware: &dyn Middleware, text: &str) -> Vec<EventType> {
+        text.chars()
+            .map(|c| {
+                middleware
+                    .next(
+                        key_event(Key::Other(0), Some(&c.to_string()), Status::Pressed),
+                        &mut |_| {},
+                    )
+                    .etype
+            })
+            .collect()
+    }
+
+    fn disable_options() -> DisableOptions {
+        DisableOptions {
+            toggle_key: None,
+            toggle_key_variant: None,
+            toggle_key_maximum_window: std::time::Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn matcher_state_survives_new_matchers() {
+        let state = ProcessorState::new();
+        let provider = MockProvider {};
+
+        let previous_matcher = TriggerMatcher { trigger: ":hello" };
+        let previous_matchers: Vec<&dyn Matcher<String>> = vec![&previous_matcher];
+        {
+            let middleware =
+                MatcherMiddleware::new(&previous_matchers, &provider, &provider, &state);
+            type_text(&middleware, ":hx");
+            middleware.next(
+                key_event(Key::Backspace, None, Status::Pressed),
+                &mut |_| {},
+            );
+        }
+
+        // The new matchers pick up the text typed before the reload
+        let current_matcher = TriggerMatcher { trigger: ":hi" };
+        let current_matchers: Vec<&dyn Matcher<String>> = vec![&current_matcher];
+        let middleware = MatcherMiddleware::new(&current_matchers, &provider, &provider, &state);
+        let events = type_text(&middleware, "i");
+        assert!(matches!(
+            events.as_slice(),
+            [EventType::MatchesDetected(detected)] if detected.matches[0].trigger.as_deref() == Some(":hi")
+        ));
+    }
+
+    #[test]
+    fn disabled_state_survives_new_processor() {
+        let state = ProcessorState::new();
+
+        {
+            let middleware = DisableMiddleware::new(disable_options(), &state);
+            middleware.next(Event::caused_by(0, EventType::DisableRequest), &mut |_| {});
+        }
+
+        let middleware = DisableMiddleware::new(disable_options(), &state);
+        assert!(matches!(
+            type_text(&middleware, "a").as_slice(),
+            [EventType::NOOP]
+        ));
+
+        middleware.next(Event::caused_by(0, EventType::EnableRequest), &mut |_| {});
+        assert!(matches!(
+            type_text(&middleware, "a").as_slice(),
+            [EventType::Keyboard(_)]
+        ));
+    }
+}
@@ -0,0 +1,463 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Recording and replay of the events entering the processor, used to
+//! reproduce issues in the middleware chain outside of the worker.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    io::{BufRead, Write},
+};
+
+use anyhow::{Context, Result};
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    event::{
+        external::MatchExecRequestEvent,
+        input::{ContextMenuClickedEvent, HotKeyEvent, Key, KeyboardEvent, MouseEvent},
+        internal::SecureInputEnabledEvent,
+        Event, EventType, SourceId,
+    },
+    process::{EventSequenceProvider, Processor},
+};
+
+// The subset of events produced by the worker sources that can be recorded.
+// Heartbeats and exit requests are skipped, as they don't affect the matching.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RecordedEventType {
+    Keyboard(KeyboardEvent),
+    Mouse(MouseEvent),
+    HotKey(HotKeyEvent),
+    TrayIconClicked,
+    ContextMenuClicked(ContextMenuClickedEvent),
+    MatchExecRequest(MatchExecRequestEvent),
+    DisableRequest,
+    EnableRequest,
+    ToggleRequest,
+    UndoRequest,
+    ShowSearchBar,
+    ShowConfigFolder,
+    SecureInputEnabled(SecureInputEnabledEvent),
+    SecureInputDisabled,
+}
+
+impl RecordedEventType {
+    pub fn from_event_type(etype: &EventType) -> Option<Self> {
+        Some(match etype {
+            EventType::Keyboard(event) => Self::Keyboard(event.clone()),
+            EventType::Mouse(event) => Self::Mouse(event.clone()),
+            EventType::HotKey(event) => Self::HotKey(event.clone()),
+            EventType::TrayIconClicked => Self::TrayIconClicked,
+            EventType::ContextMenuClicked(event) => Self::ContextMenuClicked(event.clone()),
+            EventType::MatchExecRequest(event) => Self::MatchExecRequest(event.clone()),
+            EventType::DisableRequest => Self::DisableRequest,
+            EventType::EnableRequest => Self::EnableRequest,
+            EventType::ToggleRequest => Self::ToggleRequest,
+            EventType::UndoRequest => Self::UndoRequest,
+            EventType::ShowSearchBar => Self::ShowSearchBar,
+            EventType::ShowConfigFolder => Self::ShowConfigFolder,
+            EventType::SecureInputEnabled(event) => Self::SecureInputEnabled(event.clone()),
+            EventType::SecureInputDisabled => Self::SecureInputDisabled,
+            _ => return None,
+        })
+    }
+
+    pub fn into_event_type(self) -> EventType {
+        match self {
+            Self::Keyboard(event) => EventType::Keyboard(event),
+            Self::Mouse(event) => EventType::Mouse(event),
+            Self::HotKey(event) => EventType::HotKey(event),
+            Self::TrayIconClicked => EventType::TrayIconClicked,
+            Self::ContextMenuClicked(event) => EventType::ContextMenuClicked(event),
+            Self::MatchExecRequest(event) => EventType::MatchExecRequest(event),
+            Self::DisableRequest => EventType::DisableRequest,
+            Self::EnableRequest => EventType::EnableRequest,
+            Self::ToggleRequest => EventType::ToggleRequest,
+            Self::UndoRequest => EventType::UndoRequest,
+            Self::ShowSearchBar => EventType::ShowSearchBar,
+            Self::ShowConfigFolder => EventType::ShowConfigFolder,
+            Self::SecureInputEnabled(event) => EventType::SecureInputEnabled(event),
+            Self::SecureInputDisabled => EventType::SecureInputDisabled,
+        }
+    }
+
+    // Hide the typed characters, while preserving whitespace and punctuation
+    // so that the word boundaries are still visible in the recording
+    fn redact(&mut self) {
+        if let Self::Keyboard(event) = self {
+            if let Key::Other(_) = event.key {
+                event.key = Key::Other(0);
+            }
+
+            if let Some(value) = &mut event.value {
+                *value = value
+                    .chars()
+                    .map(|c| {
+                        if c.is_alphabetic() {
+                            'x'
+                        } else if c.is_numeric() {
+                            '0'
+                        } else {
+                            c
+                        }
+                    })
+                    .collect();
+            }
+        }
+    }
+}
+
+// A single line of a recording
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub source_id: SourceId,
+    pub event: RecordedEventType,
+
+    // The ids generated by the event sequence provider while the event was
+    // processed, which are needed to discard the same events on replay
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sequence_ids: Vec<SourceId>,
+}
+
+// Writes the recorded events to the given writer, one JSON object per line.
+// The recorder must also be used as the processor's event sequence provider,
+// so that the generated ids can be recorded as well.
+pub struct EventRecorder<'a> {
+    writer: RefCell<Box<dyn Write + 'a>>,
+    redact: bool,
+    event_sequence_provider: &'a dyn EventSequenceProvider,
+    sequence_ids: RefCell<Vec<SourceId>>,
+}
+
+impl<'a> EventRecorder<'a> {
+    pub fn new(
+        writer: Box<dyn Write + 'a>,
+        redact: bool,
+        event_sequence_provider: &'a dyn EventSequenceProvider,
+    ) -> Self {
+        Self {
+            writer: RefCell::new(writer),
+            redact,
+            event_sequence_provider,
+            sequence_ids: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn write(&self, source_id: SourceId, mut event: RecordedEventType) {
+        if self.redact {
+            event.redact();
+        }
+
+        let recorded = RecordedEvent {
+            source_id,
+            event,
+            sequence_ids: self.sequence_ids.take(),
+        };
+
+        let result = serde_json::to_string(&recorded)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| Ok(writeln!(self.writer.borrow_mut(), "{line}")?));
+        if let Err(err) = result {
+            error!("unable to record event: {err:?}");
+        }
+    }
+}
+
+impl EventSequenceProvider for EventRecorder<'_> {
+    fn get_next_id(&self) -> SourceId {
+        let id = self.event_sequence_provider.get_next_id();
+        self.sequence_ids.borrow_mut().push(id);
+        id
+    }
+}
+
+// Records the events before passing them to the wrapped processor
+pub struct RecordingProcessor<'a, 'b> {
+    processor: &'a mut dyn Processor,
+    recorder: &'a EventRecorder<'b>,
+}
+
+impl<'a, 'b> RecordingProcessor<'a, 'b> {
+    pub fn new(processor: &'a mut dyn Processor, recorder: &'a EventRecorder<'b>) -> Self {
+        Self {
+            processor,
+            recorder,
+        }
+    }
+}
+
+impl Processor for RecordingProcessor<'_, '_> {
+    fn process(&mut self, event: Event) -> Vec<Event> {
+        let source_id = event.source_id;
+        let recorded = RecordedEventType::from_event_type(&event.etype);
+        self.recorder.sequence_ids.borrow_mut().clear();
+
+        let processed = self.processor.process(event);
+
+        if let Some(recorded) = recorded {
+            self.recorder.write(source_id, recorded);
+        } else {
+            debug!("skipping the recording of event with id {source_id}");
+        }
+
+        processed
+    }
+}
+
+pub fn read_recording(reader: impl BufRead) -> Result<Vec<RecordedEvent>> {
+    let mut events = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event = serde_json::from_str(&line)
+            .with_context(|| format!("invalid recorded event at line {}", index + 1))?;
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+// Returns the ids generated while recording, so that the replayed events
+// are discarded in the same way as the original ones
+#[derive(Default)]
+pub struct ReplaySequenceProvider {
+    ids: RefCell<VecDeque<SourceId>>,
+    next_fallback_id: Cell<SourceId>,
+}
+
+impl ReplaySequenceProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn load(&self, recorded: &RecordedEvent) {
+        *self.ids.borrow_mut() = recorded.sequence_ids.iter().copied().collect();
+        self.next_fallback_id.set(recorded.source_id + 1);
+    }
+}
+
+impl EventSequenceProvider for ReplaySequenceProvider {
+    fn get_next_id(&self) -> SourceId {
+        if let Some(id) = self.ids.borrow_mut().pop_front() {
+            return id;
+        }
+
+        let id = self.next_fallback_id.get();
+        self.next_fallback_id.set(id + 1);
+        id
+    }
+}
+
+// Feed the recorded events to the processor (which should use the given
+// sequence provider) and return the effect events it produced, stopping
+// at the first exit event
+pub fn replay(
+    processor: &mut dyn Processor,
+    sequence_provider: &ReplaySequenceProvider,
+    recording: Vec<RecordedEvent>,
+) -> Vec<Event> {
+    let mut effects = Vec::new();
+
+    for recorded in recording {
+        sequence_provider.load(&recorded);
+
+        let event = Event::caused_by(recorded.source_id, recorded.event.into_event_type());
+        for processed in processor.process(event) {
+            if let EventType::Exit(_) = processed.etype {
+                return effects;
+            }
+
+            if is_effect(&processed.etype) {
+                effects.push(processed);
+            }
+        }
+    }
+
+    effects
+}
+
+fn is_effect(etype: &EventType) -> bool {
+    matches!(
+        etype,
+        EventType::TriggerCompensation(_)
+            | EventType::CursorHintCompensation(_)
+            | EventType::TabStopJump(_)
+            | EventType::KeySequenceInject(_)
+            | EventType::TextInject(_)
+            | EventType::MarkdownInject(_)
+            | EventType::HtmlInject(_)
+            | EventType::ImageInject(_)
+            | EventType::MacroInject(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{effect::TextInjectRequest, input::Status, ExitMode};
+
+    struct Counter(Cell<SourceId>);
+
+    impl EventSequenceProvider for Counter {
+        fn get_next_id(&self) -> SourceId {
+            let id = self.0.get();
+            self.0.set(id + 1);
+            id
+        }
+    }
+
+    // Injects the next sequence id for each key press, and exits on mouse clicks
+    struct MockProcessor<'a> {
+        sequence_provider: &'a dyn EventSequenceProvider,
+    }
+
+    impl Processor for MockProcessor<'_> {
+        fn process(&mut self, event: Event) -> Vec<Event> {
+            let etype = match event.etype {
+                EventType::Keyboard(_) => EventType::TextInject(TextInjectRequest {
+                    text: self.sequence_provider.get_next_id().to_string(),
+                    force_mode: None,
+                }),
+                EventType::Mouse(_) => EventType::Exit(ExitMode::Exit),
+                _ => EventType::NOOP,
+            };
+            vec![Event::caused_by(event.source_id, etype)]
+        }
+    }
+
+    fn keyboard(value: &str) -> EventType {
+        EventType::Keyboard(KeyboardEvent {
+            key: Key::Other(65),
+            value: Some(value.to_string()),
+            status: Status::Pressed,
+            variant: None,
+        })
+    }
+
+    fn record(events: Vec<Event>, redact: bool) -> String {
+        let counter = Counter(Cell::new(100));
+        let mut buffer = Vec::new();
+        {
+            let recorder = EventRecorder::new(Box::new(&mut buffer), redact, &counter);
+            let mut processor = MockProcessor {
+                sequence_provider: &recorder,
+            };
+            let mut recording_processor = RecordingProcessor::new(&mut processor, &recorder);
+            for event in events {
+                recording_processor.process(event);
+            }
+        }
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn recording_is_replayed_with_the_same_sequence_ids() {
+        let recording = record(
+            vec![
+                Event::caused_by(1, keyboard("a")),
+                Event::caused_by(2, EventType::Heartbeat),
+                Event::caused_by(3, EventType::ToggleRequest),
+                Event::caused_by(4, keyboard("b")),
+            ],
+            false,
+        );
+        let recording = read_recording(recording.as_bytes()).unwrap();
+        assert_eq!(
+            recording,
+            vec![
+                RecordedEvent {
+                    source_id: 1,
+                    event: RecordedEventType::from_event_type(&keyboard("a")).unwrap(),
+                    sequence_ids: vec![100],
+                },
+                RecordedEvent {
+                    source_id: 3,
+                    event: RecordedEventType::ToggleRequest,
+                    sequence_ids: vec![],
+                },
+                RecordedEvent {
+                    source_id: 4,
+                    event: RecordedEventType::from_event_type(&keyboard("b")).unwrap(),
+                    sequence_ids: vec![101],
+                },
+            ]
+        );
+
+        let sequence_provider = ReplaySequenceProvider::new();
+        let mut processor = MockProcessor {
+            sequence_provider: &sequence_provider,
+        };
+        let effects: Vec<String> = replay(&mut processor, &sequence_provider, recording)
+            .into_iter()
+            .filter_map(|event| match event.etype {
+                EventType::TextInject(request) => Some(request.text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(effects, vec!["100", "101"]);
+    }
+
+    #[test]
+    fn redaction_hides_typed_characters() {
+        let recording = record(vec![Event::caused_by(1, keyboard("Ab1 ;"))], true);
+        let recording = read_recording(recording.as_bytes()).unwrap();
+        assert_eq!(
+            recording[0].event,
+            RecordedEventType::Keyboard(KeyboardEvent {
+                key: Key::Other(0),
+                value: Some("xx0 ;".to_string()),
+                status: Status::Pressed,
+                variant: None,
+            })
+        );
+    }
+
+    #[test]
+    fn replay_stops_at_exit() {
+        let recording = read_recording(
+            r#"{"source_id":1,"event":{"type":"Mouse","button":"Left","status":"Pressed"}}
+
+{"source_id":2,"event":{"type":"Keyboard","key":"Space","value":" ","status":"Pressed","variant":null}}"#
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(recording.len(), 2);
+
+        let sequence_provider = ReplaySequenceProvider::new();
+        let mut processor = MockProcessor {
+            sequence_provider: &sequence_provider,
+        };
+        assert!(replay(&mut processor, &sequence_provider, recording).is_empty());
+    }
+
+    #[test]
+    fn read_recording_reports_invalid_lines() {
+        let err = read_recording("{\"source_id\":1,\"event\":{\"type\":\"Unknown\"}}".as_bytes())
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("line 1"));
+    }
+}
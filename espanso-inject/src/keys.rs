@@ -21,6 +21,7 @@ use std::fmt::Display;
 use std::sync::LazyLock;
 
 use regex::Regex;
+use thiserror::Error;
 
 static RAW_PARSER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^RAW\((\d+)\)$").unwrap());
 
@@ -340,6 +341,97 @@ impl Key {
     }
 }
 
+/// A group of keys pressed together, such as `{Ctrl+A}`, optionally repeated
+/// as in `{Tab 3}`.
+#[derive(Debug, Clone)]
+pub struct KeyCombination {
+    pub keys: Vec<Key>,
+    pub repeat: usize,
+}
+
+/// Parse a sequence of key combinations, each one wrapped in braces,
+/// for example: `{Ctrl+A}{Delete}{Tab 2}{Enter}`.
+/// Key names are case-insensitive and whitespace between combinations is ignored.
+pub fn parse_key_sequence(sequence: &str) -> Result<Vec<KeyCombination>, KeySequenceError> {
+    let mut combinations = Vec::new();
+
+    let mut rest = sequence.trim_start();
+    while !rest.is_empty() {
+        let Some(group) = rest.strip_prefix('{') else {
+            let text = rest.split('{').next().unwrap_or(rest).trim_end();
+            return Err(KeySequenceError::UnexpectedText(text.to_string()));
+        };
+        let Some(end) = group.find('}') else {
+            return Err(KeySequenceError::Unclosed(rest.to_string()));
+        };
+
+        combinations.push(parse_key_combination(&group[..end])?);
+        rest = group[end + 1..].trim_start();
+    }
+
+    if combinations.is_empty() {
+        return Err(KeySequenceError::Empty);
+    }
+
+    Ok(combinations)
+}
+
+fn parse_key_combination(group: &str) -> Result<KeyCombination, KeySequenceError> {
+    let group = group.trim();
+    let (names, repeat) = match group.rsplit_once(char::is_whitespace) {
+        Some((names, count)) if count.chars().all(|c| c.is_ascii_digit()) => {
+            let repeat = count
+                .parse::<usize>()
+                .ok()
+                .filter(|repeat| *repeat > 0)
+                .ok_or_else(|| KeySequenceError::Repeat(count.to_string()))?;
+            (names.trim_end(), repeat)
+        }
+        _ => (group, 1),
+    };
+
+    if names.is_empty() {
+        return Err(KeySequenceError::Empty);
+    }
+
+    let keys = names
+        .split('+')
+        .map(|name| {
+            let normalized = name.trim().to_uppercase();
+            let normalized = match normalized.as_str() {
+                "CONTROL" => "CTRL",
+                "ESCAPE" => "ESC",
+                "RETURN" => "ENTER",
+                "DEL" => "DELETE",
+                "PGUP" => "PAGEUP",
+                "PGDN" => "PAGEDOWN",
+                other => other,
+            };
+            Key::parse(normalized).ok_or_else(|| KeySequenceError::UnknownKey(name.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(KeyCombination { keys, repeat })
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum KeySequenceError {
+    #[error("no keys specified")]
+    Empty,
+
+    #[error("unexpected text {0:?}, keys must be wrapped in braces, for example: {{Tab}}")]
+    UnexpectedText(String),
+
+    #[error("missing closing brace in {0:?}")]
+    Unclosed(String),
+
+    #[error("unknown key {0:?}")]
+    UnknownKey(String),
+
+    #[error("invalid repeat count {0:?}")]
+    Repeat(String),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,4 +447,56 @@ mod tests {
         assert!(Key::parse("INVALID").is_none());
         assert!(Key::parse("RAW(a)").is_none());
     }
+
+    fn keys_to_string(combination: &KeyCombination) -> String {
+        combination
+            .keys
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+
+    #[test]
+    fn parse_key_sequence_works_correctly() {
+        let sequence = parse_key_sequence("{Ctrl+a} {delete}{Tab 3}{shift + Return}").unwrap();
+        let parsed: Vec<_> = sequence
+            .iter()
+            .map(|combination| (keys_to_string(combination), combination.repeat))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                ("CTRL+A".to_string(), 1),
+                ("DELETE".to_string(), 1),
+                ("TAB".to_string(), 3),
+                ("SHIFT+ENTER".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_key_sequence_invalid() {
+        assert_eq!(parse_key_sequence("").unwrap_err(), KeySequenceError::Empty);
+        assert_eq!(
+            parse_key_sequence("{}").unwrap_err(),
+            KeySequenceError::Empty
+        );
+        assert_eq!(
+            parse_key_sequence("{Tab} hello {Enter}").unwrap_err(),
+            KeySequenceError::UnexpectedText("hello".to_string())
+        );
+        assert_eq!(
+            parse_key_sequence("{Ctrl+A").unwrap_err(),
+            KeySequenceError::Unclosed("{Ctrl+A".to_string())
+        );
+        assert_eq!(
+            parse_key_sequence("{Ctrl+Foo}").unwrap_err(),
+            KeySequenceError::UnknownKey("Foo".to_string())
+        );
+        assert_eq!(
+            parse_key_sequence("{Tab 0}").unwrap_err(),
+            KeySequenceError::Repeat("0".to_string())
+        );
+    }
 }
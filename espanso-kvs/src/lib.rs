@@ -28,13 +28,30 @@ mod persistent;
 pub trait KVS: Send + Sync + Clone {
     fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>>;
     fn set<T: Serialize>(&self, key: &str, value: T) -> Result<()>;
+
+    // Atomically replace the value of the given key with the one returned by
+    // the callback, which receives the current value (if any). Namespaced
+    // stores are never cached, so the update is also atomic across processes,
+    // where sets and deletes of the same key wait for it to complete
+    fn update<T, F>(&self, key: &str, update: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Option<T>) -> Result<T>;
+
     fn delete(&self, key: &str) -> Result<()>;
+    fn keys(&self) -> Result<Vec<String>>;
 }
 
 pub fn get_persistent(base_dir: &Path) -> Result<impl KVS> {
     persistent::PersistentJsonKVS::new(base_dir)
 }
 
+// Get a persistent store whose values are kept separate from the default one.
+// Values are never cached, so changes made by other processes are always visible.
+pub fn get_persistent_namespace(base_dir: &Path, namespace: &str) -> Result<impl KVS> {
+    persistent::PersistentJsonKVS::new_namespace(base_dir, namespace)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,6 +108,71 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_update() {
+        use_test_directory(|base_dir| {
+            let kvs = get_persistent(base_dir).unwrap();
+
+            let increment = |value: Option<i64>| Ok(value.unwrap_or(0) + 1);
+            assert_eq!(kvs.update("counter", increment).unwrap(), 1);
+            assert_eq!(kvs.update("counter", increment).unwrap(), 2);
+            assert_eq!(kvs.get::<i64>("counter").unwrap().unwrap(), 2);
+
+            assert!(kvs
+                .update::<i64, _>("counter", |_| Err(anyhow::anyhow!("failed")))
+                .is_err());
+            assert_eq!(kvs.get::<i64>("counter").unwrap().unwrap(), 2);
+        });
+    }
+
+    #[test]
+    fn test_keys() {
+        use_test_directory(|base_dir| {
+            let kvs = get_persistent(base_dir).unwrap();
+            assert!(kvs.keys().unwrap().is_empty());
+
+            kvs.set("b_key", 1).unwrap();
+            kvs.set("a_key", 2).unwrap();
+            std::fs::write(base_dir.join("kvs").join("invalid key"), "1").unwrap();
+
+            assert_eq!(kvs.keys().unwrap(), vec!["a_key", "b_key"]);
+        });
+    }
+
+    #[test]
+    fn test_namespace_is_separate() {
+        use_test_directory(|base_dir| {
+            let kvs = get_persistent(base_dir).unwrap();
+            let namespace = get_persistent_namespace(base_dir, "state").unwrap();
+
+            kvs.set("my_key", "default".to_string()).unwrap();
+            namespace.set("my_key", "namespace".to_string()).unwrap();
+
+            assert_eq!(kvs.get::<String>("my_key").unwrap().unwrap(), "default");
+            assert_eq!(
+                namespace.get::<String>("my_key").unwrap().unwrap(),
+                "namespace"
+            );
+            assert_eq!(kvs.keys().unwrap(), vec!["my_key"]);
+
+            assert!(get_persistent_namespace(base_dir, "invalid/namespace").is_err());
+        });
+    }
+
+    #[test]
+    fn test_namespace_sees_external_changes() {
+        use_test_directory(|base_dir| {
+            let first = get_persistent_namespace(base_dir, "state").unwrap();
+            let second = get_persistent_namespace(base_dir, "state").unwrap();
+
+            first.set("counter", 1).unwrap();
+            assert_eq!(second.get::<i64>("counter").unwrap().unwrap(), 1);
+
+            second.set("counter", 5).unwrap();
+            assert_eq!(first.get::<i64>("counter").unwrap().unwrap(), 5);
+        });
+    }
+
     #[test]
     fn test_invalid_key_name() {
         use_test_directory(|base_dir| {
@@ -21,6 +21,7 @@ use anyhow::Result;
 use serde_json::Value;
 use std::{
     collections::HashMap,
+    fs::OpenOptions,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
@@ -34,11 +35,28 @@ const DEFAULT_KVS_DIR_NAME: &str = "kvs";
 pub struct PersistentJsonKVS {
     kvs_dir: PathBuf,
     store: Arc<Mutex<HashMap<String, Value>>>,
+
+    // Namespaced stores can be modified by other processes (for example, by the CLI),
+    // so values are always read from disk instead of the in-memory cache
+    cached: bool,
 }
 
 impl PersistentJsonKVS {
     pub fn new(base_dir: &Path) -> Result<Self> {
         let kvs_dir = base_dir.join(DEFAULT_KVS_DIR_NAME);
+        Self::with_dir(kvs_dir, true)
+    }
+
+    pub fn new_namespace(base_dir: &Path, namespace: &str) -> Result<Self> {
+        if !is_valid_key_name(namespace) {
+            return Err(PersistentJsonKVSError::InvalidNamespace(namespace.to_string()).into());
+        }
+
+        let kvs_dir = base_dir.join(DEFAULT_KVS_DIR_NAME).join(namespace);
+        Self::with_dir(kvs_dir, false)
+    }
+
+    fn with_dir(kvs_dir: PathBuf, cached: bool) -> Result<Self> {
         if !kvs_dir.is_dir() {
             std::fs::create_dir_all(&kvs_dir)?;
         }
@@ -46,21 +64,15 @@ impl PersistentJsonKVS {
         Ok(Self {
             kvs_dir,
             store: Arc::new(Mutex::new(HashMap::new())),
+            cached,
         })
     }
-}
 
-impl KVS for PersistentJsonKVS {
-    fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
-        if !is_valid_key_name(key) {
-            return Err(PersistentJsonKVSError::InvalidKey(key.to_string()).into());
-        }
-
-        let mut lock = self.store.lock().expect("unable to obtain KVS read lock");
-
-        if let Some(cached_value) = lock.get(key) {
-            let converted_value = serde_json::from_value(cached_value.clone())?;
-            return Ok(Some(converted_value));
+    fn read(&self, store: &mut HashMap<String, Value>, key: &str) -> Result<Option<Value>> {
+        if self.cached {
+            if let Some(cached_value) = store.get(key) {
+                return Ok(Some(cached_value.clone()));
+            }
         }
 
         // Not found in the cache, read from the file
@@ -68,32 +80,102 @@ impl KVS for PersistentJsonKVS {
         if target_file.is_file() {
             let content = std::fs::read_to_string(&target_file)?;
             let deserialized_value: Value = serde_json::from_str(&content)?;
-            let converted_value = serde_json::from_value(deserialized_value.clone())?;
 
-            lock.insert(key.to_string(), deserialized_value);
+            if self.cached {
+                store.insert(key.to_string(), deserialized_value.clone());
+            }
 
-            return Ok(Some(converted_value));
+            return Ok(Some(deserialized_value));
         }
 
         Ok(None)
     }
 
-    fn set<T: serde::Serialize>(&self, key: &str, value: T) -> Result<()> {
+    fn write(&self, store: &mut HashMap<String, Value>, key: &str, value: Value) -> Result<()> {
+        let serialized_string = serde_json::to_string(&value)?;
+
+        // Write to a temporary file first and then rename it, so that a crash
+        // in the middle of the write never leaves a truncated value behind.
+        // The leading dot guarantees that the name can't clash with a valid key.
+        let target_file = self.kvs_dir.join(key);
+        let temp_file = self.kvs_dir.join(format!(".{key}.tmp"));
+        std::fs::write(&temp_file, serialized_string)?;
+        if let Err(err) = std::fs::rename(&temp_file, &target_file) {
+            let _ = std::fs::remove_file(&temp_file);
+            return Err(err.into());
+        }
+
+        if self.cached {
+            store.insert(key.to_string(), value);
+        }
+
+        Ok(())
+    }
+
+    // Run the given operation on the key while holding the in-process lock.
+    // Other processes (such as the CLI) don't share it, so the key file is locked as well
+    fn with_key_lock<R>(
+        &self,
+        key: &str,
+        operation: impl FnOnce(&mut HashMap<String, Value>) -> Result<R>,
+    ) -> Result<R> {
+        let mut lock = self.store.lock().expect("unable to obtain KVS write lock");
+        let lock_file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.kvs_dir.join(format!(".{key}.lock")))?;
+        lock_file.lock()?;
+
+        operation(&mut lock)
+    }
+}
+
+impl KVS for PersistentJsonKVS {
+    fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
         if !is_valid_key_name(key) {
             return Err(PersistentJsonKVSError::InvalidKey(key.to_string()).into());
         }
 
-        let mut lock = self.store.lock().expect("unable to obtain KVS write lock");
+        let mut lock = self.store.lock().expect("unable to obtain KVS read lock");
+
+        match self.read(&mut lock, key)? {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn set<T: serde::Serialize>(&self, key: &str, value: T) -> Result<()> {
+        if !is_valid_key_name(key) {
+            return Err(PersistentJsonKVSError::InvalidKey(key.to_string()).into());
+        }
 
         let serialized_value = serde_json::to_value(value)?;
-        let serialized_string = serde_json::to_string(&serialized_value)?;
+        self.with_key_lock(key, |store| self.write(store, key, serialized_value))
+    }
 
-        lock.insert(key.to_string(), serialized_value);
+    fn update<T, F>(&self, key: &str, update: F) -> Result<T>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+        F: FnOnce(Option<T>) -> Result<T>,
+    {
+        if !is_valid_key_name(key) {
+            return Err(PersistentJsonKVSError::InvalidKey(key.to_string()).into());
+        }
 
-        let target_file = self.kvs_dir.join(key);
-        std::fs::write(target_file, serialized_string)?;
+        // The lock is held for the whole read-modify-write cycle
+        self.with_key_lock(key, |store| {
+            let current = match self.read(store, key)? {
+                Some(value) => Some(serde_json::from_value(value)?),
+                None => None,
+            };
+            let updated = update(current)?;
 
-        Ok(())
+            let serialized_value = serde_json::to_value(&updated)?;
+            self.write(store, key, serialized_value)?;
+
+            Ok(updated)
+        })
     }
 
     fn delete(&self, key: &str) -> Result<()> {
@@ -101,17 +183,37 @@ impl KVS for PersistentJsonKVS {
             return Err(PersistentJsonKVSError::InvalidKey(key.to_string()).into());
         }
 
-        self.store
-            .lock()
-            .expect("unable to obtain KVS delete lock")
-            .remove(key);
+        self.with_key_lock(key, |store| {
+            store.remove(key);
 
-        let target_file = self.kvs_dir.join(key);
-        if target_file.is_file() {
-            std::fs::remove_file(target_file)?;
+            let target_file = self.kvs_dir.join(key);
+            if target_file.is_file() {
+                std::fs::remove_file(target_file)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        let _lock = self.store.lock().expect("unable to obtain KVS read lock");
+
+        let mut keys = Vec::new();
+        for entry in std::fs::read_dir(&self.kvs_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            if let Some(name) = entry.file_name().to_str() {
+                if is_valid_key_name(name) {
+                    keys.push(name.to_string());
+                }
+            }
         }
+        keys.sort();
 
-        Ok(())
+        Ok(keys)
     }
 }
 
@@ -131,11 +233,15 @@ fn is_valid_key_name(key: &str) -> bool {
 pub enum PersistentJsonKVSError {
     #[error("The provided key `{0}` is is invalid. Keys must only be composed of ascii letters, numbers and underscores.")]
     InvalidKey(String),
+
+    #[error("The provided namespace `{0}` is invalid. Namespaces must only be composed of ascii letters, numbers and underscores.")]
+    InvalidNamespace(String),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempdir::TempDir;
 
     #[test]
     fn test_valid_key_names() {
@@ -151,4 +257,61 @@ mod tests {
         assert!(!is_valid_key_name("with/special"));
         assert!(!is_valid_key_name("with\\special"));
     }
+
+    #[test]
+    fn update_is_atomic_across_instances() {
+        let dir = TempDir::new("espanso-kvs").unwrap();
+
+        // Separate instances don't share the in-process lock, like different processes
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let kvs = PersistentJsonKVS::new_namespace(dir.path(), "state").unwrap();
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        kvs.update("counter", |value: Option<i64>| Ok(value.unwrap_or(0) + 1))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let kvs = PersistentJsonKVS::new_namespace(dir.path(), "state").unwrap();
+        assert_eq!(kvs.get::<i64>("counter").unwrap(), Some(100));
+        assert_eq!(kvs.keys().unwrap(), vec!["counter".to_string()]);
+    }
+
+    #[test]
+    fn set_waits_for_updates_of_other_instances() {
+        let dir = TempDir::new("espanso-kvs").unwrap();
+        let kvs = PersistentJsonKVS::new_namespace(dir.path(), "state").unwrap();
+        kvs.set("counter", 1).unwrap();
+
+        let (started_sender, started_receiver) = std::sync::mpsc::channel();
+        let updater = {
+            let kvs = PersistentJsonKVS::new_namespace(dir.path(), "state").unwrap();
+            std::thread::spawn(move || {
+                kvs.update("counter", |value: Option<i64>| {
+                    started_sender.send(()).unwrap();
+                    std::thread::sleep(std::time::Duration::from_millis(200));
+                    Ok(value.unwrap_or(0) + 1)
+                })
+                .unwrap();
+            })
+        };
+
+        // The value is set after the update completes, instead of being
+        // overwritten by a value computed from the previous one
+        started_receiver.recv().unwrap();
+        kvs.set("counter", 10).unwrap();
+        updater.join().unwrap();
+
+        assert_eq!(kvs.get::<i64>("counter").unwrap(), Some(10));
+
+        kvs.delete("counter").unwrap();
+        assert_eq!(kvs.get::<i64>("counter").unwrap(), None);
+        assert!(kvs.keys().unwrap().is_empty());
+    }
 }
@@ -151,6 +151,10 @@ const int ERROR_METADATA_LEVEL_WARNING = 2;
 typedef struct ErrorMetadata {
     const int level;
     const char *message;
+
+    // 1-based position of the error, zero if unknown
+    const int line;
+    const int column;
 } ErrorMetadata;
 
 typedef struct ErrorSetMetadata {
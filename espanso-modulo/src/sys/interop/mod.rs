@@ -185,6 +185,10 @@ pub const ERROR_METADATA_LEVEL_WARNING: c_int = 2;
 pub struct ErrorMetadata {
     pub level: c_int,
     pub message: *const c_char,
+
+    // 1-based position of the error, zero if unknown
+    pub line: c_int,
+    pub column: c_int,
 }
 
 #[repr(C)]
@@ -90,6 +90,8 @@ mod interop {
     pub struct OwnedErrorMetadata {
         level: c_int,
         message: CString,
+        line: c_int,
+        column: c_int,
     }
 
     impl OwnedErrorMetadata {
@@ -97,6 +99,8 @@ mod interop {
             ErrorMetadata {
                 level: self.level,
                 message: self.message.as_ptr(),
+                line: self.line,
+                column: self.column,
             }
         }
     }
@@ -112,6 +116,13 @@ mod interop {
                     crate::troubleshooting::ErrorLevel::Warning => ERROR_METADATA_LEVEL_WARNING,
                 },
                 message,
+                // Zero means that the location is unknown
+                line: item.location.as_ref().map_or(0, |location| {
+                    c_int::try_from(location.line).unwrap_or_default()
+                }),
+                column: item.location.as_ref().map_or(0, |location| {
+                    c_int::try_from(location.column).unwrap_or_default()
+                }),
             }
         }
     }
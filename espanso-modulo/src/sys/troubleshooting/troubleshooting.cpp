@@ -87,8 +87,15 @@ class ErrorSetPanel : public wxPanel {
                 ERROR_METADATA_LEVEL_WARNING) {
                 level = wxT("WARNING");
             }
+            wxString location = wxEmptyString;
+            if (error_set_metadata->errors[i].line > 0) {
+                location = wxString::Format(
+                    wxT("(line %d, column %d) "),
+                    error_set_metadata->errors[i].line,
+                    error_set_metadata->errors[i].column);
+            }
             wxString error_text = wxString::Format(
-                wxT("[%s] %s\n"), level,
+                wxT("[%s] %s%s\n"), level, location,
                 wxString::FromUTF8(error_set_metadata->errors[i].message));
             errors_text.Append(error_text);
         }
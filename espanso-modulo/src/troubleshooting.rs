@@ -37,6 +37,13 @@ pub struct ErrorSet {
 pub struct ErrorRecord {
     pub level: ErrorLevel,
     pub message: String,
+    pub location: Option<ErrorLocation>,
+}
+
+/// Position of the error inside the file, both line and column are 1-based.
+pub struct ErrorLocation {
+    pub line: usize,
+    pub column: usize,
 }
 
 pub enum ErrorLevel {
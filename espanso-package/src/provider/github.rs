@@ -87,6 +87,6 @@ mod tests {
             })
             .unwrap();
 
-        std::thread::sleep(std::time::Duration::from_secs(300));
+        std::thread::sleep(std::time::Duration::from_mins(5));
     }
 }
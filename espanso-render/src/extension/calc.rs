@@ -0,0 +1,841 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{Extension, ExtensionOutput, ExtensionResult, Number, Params, Value};
+use thiserror::Error;
+
+// Results are rounded to this number of decimals when no explicit
+// `decimals` parameter is given, to hide floating point artifacts
+const DEFAULT_MAX_DECIMALS: usize = 10;
+
+pub struct CalcExtension {}
+
+#[allow(clippy::new_without_default)]
+impl CalcExtension {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Extension for CalcExtension {
+    fn name(&self) -> &'static str {
+        "calc"
+    }
+
+    fn calculate(
+        &self,
+        _: &crate::Context,
+        _: &crate::Scope,
+        params: &Params,
+    ) -> crate::ExtensionResult {
+        let Some(Value::String(expr)) = params.get("expr") else {
+            return ExtensionResult::Error(CalcExtensionError::MissingExpression.into());
+        };
+
+        match evaluate(expr).and_then(|result| format_result(result, params)) {
+            Ok(output) => ExtensionResult::Success(ExtensionOutput::Single(output)),
+            Err(err) => ExtensionResult::Error(err.into()),
+        }
+    }
+}
+
+fn format_result(result: f64, params: &Params) -> Result<String, CalcExtensionError> {
+    let decimals = match params.get("decimals") {
+        None => None,
+        Some(Value::Number(Number::Integer(decimals))) => Some(
+            usize::try_from(*decimals)
+                .map_err(|_| CalcExtensionError::InvalidParameter("decimals"))?,
+        ),
+        Some(Value::String(decimals)) => Some(
+            decimals
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| CalcExtensionError::InvalidParameter("decimals"))?,
+        ),
+        Some(_) => return Err(CalcExtensionError::InvalidParameter("decimals")),
+    };
+
+    let rounding = match params.get("rounding") {
+        None => Rounding::HalfUp,
+        Some(Value::String(rounding)) => Rounding::parse(rounding)
+            .ok_or_else(|| CalcExtensionError::InvalidRounding(rounding.clone()))?,
+        Some(_) => return Err(CalcExtensionError::InvalidParameter("rounding")),
+    };
+
+    let mut number = DecimalString::from_f64(result);
+    if let Some(decimals) = decimals {
+        number.round(decimals, rounding);
+        number.pad(decimals);
+    } else {
+        number.round(DEFAULT_MAX_DECIMALS, rounding);
+        number.trim();
+    }
+
+    let separators = match params.get("locale") {
+        None => Separators::default(),
+        Some(Value::String(locale)) => Separators::for_locale(locale),
+        Some(_) => return Err(CalcExtensionError::InvalidParameter("locale")),
+    };
+    let grouping = match params.get("grouping") {
+        None => params.contains_key("locale"),
+        Some(Value::Bool(grouping)) => *grouping,
+        Some(Value::String(grouping)) => grouping == "true",
+        Some(_) => return Err(CalcExtensionError::InvalidParameter("grouping")),
+    };
+
+    Ok(number.format(&separators, grouping))
+}
+
+fn evaluate(expr: &str) -> Result<f64, CalcExtensionError> {
+    let tokens = tokenize(expr)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+    };
+    let result = parser.parse_expression()?.value();
+    if let Some(token) = parser.peek() {
+        return Err(CalcExtensionError::UnexpectedToken(token.to_string()));
+    }
+
+    if result.is_finite() {
+        Ok(result)
+    } else {
+        Err(CalcExtensionError::InvalidResult)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "{number}"),
+            Token::Identifier(name) => write!(f, "{name}"),
+            Token::Operator(operator) => write!(f, "{operator}"),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, CalcExtensionError> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_ascii_digit() || c == '.' || c == '_' {
+                    if c != '_' {
+                        number.push(c);
+                    }
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let value = number
+                .parse::<f64>()
+                .map_err(|_| CalcExtensionError::InvalidNumber(number.clone()))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut identifier = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    identifier.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Identifier(identifier.to_lowercase()));
+        } else {
+            chars.next();
+            tokens.push(match c {
+                '+' | '-' | '*' | '/' | '^' | '%' => Token::Operator(c),
+                '×' => Token::Operator('*'),
+                '÷' => Token::Operator('/'),
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                ',' | ';' => Token::Comma,
+                _ => return Err(CalcExtensionError::UnexpectedToken(c.to_string())),
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+// A value followed by `%` keeps track of being a percentage, so that
+// `100 + 10%` can be evaluated as `110` like on a pocket calculator
+#[derive(Debug, Clone, Copy)]
+enum Operand {
+    Number(f64),
+    Percent(f64),
+}
+
+impl Operand {
+    fn value(self) -> f64 {
+        match self {
+            Operand::Number(value) => value,
+            Operand::Percent(value) => value / 100.0,
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn consume_operator(&mut self, operators: &[char]) -> Option<char> {
+        if let Some(Token::Operator(operator)) = self.peek() {
+            if operators.contains(operator) {
+                let operator = *operator;
+                self.position += 1;
+                return Some(operator);
+            }
+        }
+        None
+    }
+
+    fn consume_modulo(&mut self) -> bool {
+        if matches!(self.peek(), Some(Token::Identifier(name)) if name == "mod") {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), CalcExtensionError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(CalcExtensionError::UnexpectedToken(token.to_string())),
+            None => Err(CalcExtensionError::UnexpectedEnd),
+        }
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn parse_expression(&mut self) -> Result<Operand, CalcExtensionError> {
+        let mut left = self.parse_term()?;
+
+        while let Some(operator) = self.consume_operator(&['+', '-']) {
+            let right = self.parse_term()?;
+            let value = left.value();
+            let result = match (operator, right) {
+                ('+', Operand::Percent(percent)) => value + value * percent / 100.0,
+                ('-', Operand::Percent(percent)) => value - value * percent / 100.0,
+                ('+', right) => value + right.value(),
+                (_, right) => value - right.value(),
+            };
+            left = Operand::Number(result);
+        }
+
+        Ok(left)
+    }
+
+    // term := unary (('*' | '/' | 'mod') unary)*
+    fn parse_term(&mut self) -> Result<Operand, CalcExtensionError> {
+        let mut left = self.parse_unary()?;
+
+        loop {
+            let operator = if let Some(operator) = self.consume_operator(&['*', '/']) {
+                operator
+            } else if self.consume_modulo() {
+                '%'
+            } else {
+                break;
+            };
+
+            let right = self.parse_unary()?.value();
+            let value = left.value();
+            let result = match operator {
+                '*' => value * right,
+                _ if right == 0.0 => return Err(CalcExtensionError::DivisionByZero),
+                '/' => value / right,
+                _ => value % right,
+            };
+            left = Operand::Number(result);
+        }
+
+        Ok(left)
+    }
+
+    // unary := ('-' | '+') unary | power
+    fn parse_unary(&mut self) -> Result<Operand, CalcExtensionError> {
+        match self.consume_operator(&['-', '+']) {
+            Some('-') => Ok(match self.parse_unary()? {
+                Operand::Number(value) => Operand::Number(-value),
+                Operand::Percent(value) => Operand::Percent(-value),
+            }),
+            Some(_) => self.parse_unary(),
+            None => self.parse_power(),
+        }
+    }
+
+    // power := postfix ('^' unary)?
+    fn parse_power(&mut self) -> Result<Operand, CalcExtensionError> {
+        let base = self.parse_postfix()?;
+        if self.consume_operator(&['^']).is_some() {
+            let exponent = self.parse_unary()?.value();
+            return Ok(Operand::Number(base.value().powf(exponent)));
+        }
+        Ok(base)
+    }
+
+    // postfix := primary '%'?
+    fn parse_postfix(&mut self) -> Result<Operand, CalcExtensionError> {
+        let value = self.parse_primary()?;
+        if self.consume_operator(&['%']).is_some() {
+            return Ok(Operand::Percent(value));
+        }
+        Ok(Operand::Number(value))
+    }
+
+    // primary := number | '(' expression ')' | constant | function '(' arguments ')'
+    fn parse_primary(&mut self) -> Result<f64, CalcExtensionError> {
+        match self.next().cloned() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::LeftParen) => {
+                let value = self.parse_expression()?.value();
+                self.expect(&Token::RightParen)?;
+                Ok(value)
+            }
+            Some(Token::Identifier(name)) => {
+                if self.peek() == Some(&Token::LeftParen) {
+                    self.position += 1;
+                    let args = self.parse_arguments()?;
+                    call_function(&name, &args)
+                } else {
+                    match name.as_str() {
+                        "pi" => Ok(std::f64::consts::PI),
+                        "e" => Ok(std::f64::consts::E),
+                        _ => Err(CalcExtensionError::UnknownIdentifier(name)),
+                    }
+                }
+            }
+            Some(token) => Err(CalcExtensionError::UnexpectedToken(token.to_string())),
+            None => Err(CalcExtensionError::UnexpectedEnd),
+        }
+    }
+
+    fn parse_arguments(&mut self) -> Result<Vec<f64>, CalcExtensionError> {
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::RightParen) {
+            self.position += 1;
+            return Ok(args);
+        }
+
+        loop {
+            args.push(self.parse_expression()?.value());
+            match self.next() {
+                Some(Token::Comma) => {}
+                Some(Token::RightParen) => return Ok(args),
+                Some(token) => return Err(CalcExtensionError::UnexpectedToken(token.to_string())),
+                None => return Err(CalcExtensionError::UnexpectedEnd),
+            }
+        }
+    }
+}
+
+fn call_function(name: &str, args: &[f64]) -> Result<f64, CalcExtensionError> {
+    let expect_args = |min: usize, max: usize| {
+        if args.len() < min || args.len() > max {
+            Err(CalcExtensionError::InvalidArguments(name.to_string()))
+        } else {
+            Ok(())
+        }
+    };
+
+    match name {
+        "abs" | "sqrt" | "floor" | "ceil" | "trunc" => {
+            expect_args(1, 1)?;
+            let value = args[0];
+            Ok(match name {
+                "abs" => value.abs(),
+                "sqrt" if value < 0.0 => {
+                    return Err(CalcExtensionError::InvalidArguments(name.to_string()))
+                }
+                "sqrt" => value.sqrt(),
+                "floor" => value.floor(),
+                "ceil" => value.ceil(),
+                _ => value.trunc(),
+            })
+        }
+        "round" => {
+            expect_args(1, 2)?;
+            let decimals = args.get(1).copied().unwrap_or(0.0);
+            if decimals < 0.0 || decimals.fract() != 0.0 {
+                return Err(CalcExtensionError::InvalidArguments(name.to_string()));
+            }
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let decimals = decimals as usize;
+            let mut number = DecimalString::from_f64(args[0]);
+            number.round(decimals, Rounding::HalfUp);
+            number
+                .to_string()
+                .parse()
+                .map_err(|_| CalcExtensionError::InvalidResult)
+        }
+        "min" | "max" => {
+            expect_args(1, usize::MAX)?;
+            let fold = if name == "min" { f64::min } else { f64::max };
+            Ok(args[1..].iter().copied().fold(args[0], fold))
+        }
+        _ => Err(CalcExtensionError::UnknownFunction(name.to_string())),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rounding {
+    HalfUp,
+    HalfEven,
+    Ceil,
+    Floor,
+    Truncate,
+}
+
+impl Rounding {
+    fn parse(rounding: &str) -> Option<Self> {
+        match rounding {
+            "half_up" => Some(Self::HalfUp),
+            "half_even" => Some(Self::HalfEven),
+            "ceil" | "up" => Some(Self::Ceil),
+            "floor" | "down" => Some(Self::Floor),
+            "truncate" => Some(Self::Truncate),
+            _ => None,
+        }
+    }
+}
+
+// Decimal digits of a number, used to round and format results without
+// the binary representation errors of floating point arithmetic
+// (for example, `round(2.675, 2)` is `2.68`, not `2.67`)
+#[derive(Debug, PartialEq)]
+struct DecimalString {
+    negative: bool,
+    integer: String,
+    fraction: String,
+}
+
+impl DecimalString {
+    fn from_f64(value: f64) -> Self {
+        // The Display implementation produces the shortest representation
+        // that round-trips, and never uses the scientific notation
+        let repr = value.abs().to_string();
+        let (integer, fraction) = repr.split_once('.').unwrap_or((&repr, ""));
+
+        Self {
+            negative: value.is_sign_negative(),
+            integer: integer.to_string(),
+            fraction: fraction.to_string(),
+        }
+    }
+
+    fn round(&mut self, decimals: usize, rounding: Rounding) {
+        if self.fraction.len() <= decimals {
+            return;
+        }
+
+        let rest = self.fraction.split_off(decimals);
+        let first = rest.as_bytes()[0] - b'0';
+        let is_nonzero = rest.bytes().any(|digit| digit != b'0');
+
+        let increment = match rounding {
+            Rounding::Truncate => false,
+            Rounding::Ceil => is_nonzero && !self.negative,
+            Rounding::Floor => is_nonzero && self.negative,
+            Rounding::HalfUp => first >= 5,
+            Rounding::HalfEven => {
+                let is_exact_half = first == 5 && rest.bytes().skip(1).all(|digit| digit == b'0');
+                if is_exact_half {
+                    let last = self
+                        .fraction
+                        .as_bytes()
+                        .last()
+                        .or_else(|| self.integer.as_bytes().last())
+                        .map_or(0, |digit| digit - b'0');
+                    last % 2 == 1
+                } else {
+                    first >= 5
+                }
+            }
+        };
+
+        if increment {
+            self.increment_last_digit();
+        }
+    }
+
+    fn increment_last_digit(&mut self) {
+        let mut digits: Vec<u8> = format!("{}{}", self.integer, self.fraction).into_bytes();
+        let mut index = digits.len();
+        loop {
+            if index == 0 {
+                digits.insert(0, b'1');
+                break;
+            }
+            index -= 1;
+            if digits[index] == b'9' {
+                digits[index] = b'0';
+            } else {
+                digits[index] += 1;
+                break;
+            }
+        }
+
+        let split = digits.len() - self.fraction.len();
+        let fraction = digits.split_off(split);
+        self.integer = String::from_utf8(digits).expect("digits are always valid utf8");
+        self.fraction = String::from_utf8(fraction).expect("digits are always valid utf8");
+    }
+
+    fn pad(&mut self, decimals: usize) {
+        while self.fraction.len() < decimals {
+            self.fraction.push('0');
+        }
+    }
+
+    fn trim(&mut self) {
+        let trimmed_len = self.fraction.trim_end_matches('0').len();
+        self.fraction.truncate(trimmed_len);
+    }
+
+    fn is_zero(&self) -> bool {
+        self.integer
+            .bytes()
+            .chain(self.fraction.bytes())
+            .all(|digit| digit == b'0')
+    }
+
+    fn format(&self, separators: &Separators, grouping: bool) -> String {
+        let mut output = String::new();
+        if self.negative && !self.is_zero() {
+            output.push('-');
+        }
+
+        if grouping {
+            for (index, digit) in self.integer.chars().enumerate() {
+                if index > 0 && (self.integer.len() - index).is_multiple_of(3) {
+                    output.push_str(separators.group);
+                }
+                output.push(digit);
+            }
+        } else {
+            output.push_str(&self.integer);
+        }
+
+        if !self.fraction.is_empty() {
+            output.push(separators.decimal);
+            output.push_str(&self.fraction);
+        }
+
+        output
+    }
+}
+
+impl std::fmt::Display for DecimalString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(&Separators::default(), false))
+    }
+}
+
+struct Separators {
+    group: &'static str,
+    decimal: char,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Self {
+            group: ",",
+            decimal: '.',
+        }
+    }
+}
+
+impl Separators {
+    fn for_locale(locale: &str) -> Self {
+        let locale = locale.replace('_', "-").to_lowercase();
+        let (language, region) = locale.split_once('-').unwrap_or((&locale, ""));
+
+        let (group, decimal) = match (language, region) {
+            ("de" | "it" | "fr" | "rm", "ch" | "li") => ("\u{2019}", '.'),
+            ("pt", "br") => (".", ','),
+            ("pt", _) => ("\u{a0}", ','),
+            ("fr", _) => ("\u{202f}", ','),
+            (
+                "de" | "it" | "es" | "nl" | "id" | "da" | "tr" | "el" | "ro" | "hr" | "sl" | "sr"
+                | "vi",
+                _,
+            ) => (".", ','),
+            (
+                "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "hu" | "bg"
+                | "lt" | "lv" | "et",
+                _,
+            ) => ("\u{a0}", ','),
+            _ => (",", '.'),
+        };
+
+        Self { group, decimal }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CalcExtensionError {
+    #[error("missing 'expr' parameter")]
+    MissingExpression,
+
+    #[error("invalid '{0}' parameter")]
+    InvalidParameter(&'static str),
+
+    #[error(
+        "unknown rounding mode '{0}', expected one of: half_up, half_even, ceil, floor, truncate"
+    )]
+    InvalidRounding(String),
+
+    #[error("invalid number '{0}'")]
+    InvalidNumber(String),
+
+    #[error("unexpected '{0}' in expression")]
+    UnexpectedToken(String),
+
+    #[error("unexpected end of expression")]
+    UnexpectedEnd,
+
+    #[error("unknown identifier '{0}'")]
+    UnknownIdentifier(String),
+
+    #[error("unknown function '{0}'")]
+    UnknownFunction(String),
+
+    #[error("invalid arguments for function '{0}'")]
+    InvalidArguments(String),
+
+    #[error("division by zero")]
+    DivisionByZero,
+
+    #[error("the expression doesn't evaluate to a finite number")]
+    InvalidResult,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn calc(expr: &str, params: &[(&str, Value)]) -> ExtensionResult {
+        let mut params: Params = params
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), value.clone()))
+            .collect();
+        params.insert("expr".to_string(), Value::String(expr.to_string()));
+
+        CalcExtension::new().calculate(&crate::Context::default(), &HashMap::default(), &params)
+    }
+
+    fn assert_calc(expr: &str, params: &[(&str, Value)], expected: &str) {
+        assert_eq!(
+            calc(expr, params).into_success().unwrap(),
+            ExtensionOutput::Single(expected.to_string()),
+            "expression: {expr}"
+        );
+    }
+
+    fn string(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    fn integer(value: i64) -> Value {
+        Value::Number(Number::Integer(value))
+    }
+
+    #[test]
+    fn basic_arithmetic() {
+        assert_calc("1 + 2", &[], "3");
+        assert_calc("2 + 3 * 4", &[], "14");
+        assert_calc("(2 + 3) * 4", &[], "20");
+        assert_calc("10 / 4", &[], "2.5");
+        assert_calc("10 - 2 - 3", &[], "5");
+        assert_calc("2 ^ 3 ^ 2", &[], "512");
+        assert_calc("-2 ^ 2", &[], "-4");
+        assert_calc("--3", &[], "3");
+        assert_calc("10 mod 3", &[], "1");
+        assert_calc("6 × 7 ÷ 2", &[], "21");
+        assert_calc("1_000 * .5", &[], "500");
+    }
+
+    #[test]
+    fn decimals_hide_floating_point_errors() {
+        assert_calc("0.1 + 0.2", &[], "0.3");
+        assert_calc("1 / 3", &[], "0.3333333333");
+        assert_calc("-0.0000000000001", &[], "0");
+    }
+
+    #[test]
+    fn percentages() {
+        assert_calc("15%", &[], "0.15");
+        assert_calc("200 * 15%", &[], "30");
+        assert_calc("200 + 10%", &[], "220");
+        assert_calc("200 - 10%", &[], "180");
+        assert_calc("200 + 10% + 10%", &[], "242");
+        assert_calc("(50)%", &[], "0.5");
+    }
+
+    #[test]
+    fn functions_and_constants() {
+        assert_calc("round(2.675, 2)", &[], "2.68");
+        assert_calc("round(2.5)", &[], "3");
+        assert_calc("floor(-2.5) + ceil(2.1)", &[], "0");
+        assert_calc("abs(-3) + sqrt(16) + trunc(1.9)", &[], "8");
+        assert_calc("min(3, 1, 2) + max(3; 5)", &[], "6");
+        assert_calc("round(pi, 4)", &[], "3.1416");
+        assert_calc("ROUND(E, 1)", &[], "2.7");
+    }
+
+    #[test]
+    fn fixed_decimals_and_rounding() {
+        assert_calc("10 / 4", &[("decimals", integer(0))], "3");
+        assert_calc("2", &[("decimals", string("2"))], "2.00");
+        assert_calc("1.005", &[("decimals", integer(2))], "1.01");
+        assert_calc("9.995", &[("decimals", integer(2))], "10.00");
+        assert_calc(
+            "2.5",
+            &[("decimals", integer(0)), ("rounding", string("half_even"))],
+            "2",
+        );
+        assert_calc(
+            "3.5",
+            &[("decimals", integer(0)), ("rounding", string("half_even"))],
+            "4",
+        );
+        assert_calc(
+            "1.21",
+            &[("decimals", integer(1)), ("rounding", string("ceil"))],
+            "1.3",
+        );
+        assert_calc(
+            "-1.21",
+            &[("decimals", integer(1)), ("rounding", string("ceil"))],
+            "-1.2",
+        );
+        assert_calc(
+            "-1.21",
+            &[("decimals", integer(1)), ("rounding", string("floor"))],
+            "-1.3",
+        );
+        assert_calc(
+            "1.29",
+            &[("decimals", integer(1)), ("rounding", string("truncate"))],
+            "1.2",
+        );
+    }
+
+    #[test]
+    fn locale_formatting() {
+        assert_calc(
+            "1234567.891",
+            &[("locale", string("en-US"))],
+            "1,234,567.891",
+        );
+        assert_calc(
+            "1234567.5",
+            &[("locale", string("de_DE")), ("decimals", integer(2))],
+            "1.234.567,50",
+        );
+        assert_calc("-1234.5", &[("locale", string("fr-FR"))], "-1\u{202f}234,5");
+        assert_calc("1234.5", &[("locale", string("de-CH"))], "1\u{2019}234.5");
+        assert_calc(
+            "1234.5",
+            &[("locale", string("it")), ("grouping", Value::Bool(false))],
+            "1234,5",
+        );
+        assert_calc("123", &[("locale", string("en"))], "123");
+        assert_calc("1234", &[("grouping", Value::Bool(true))], "1,234");
+    }
+
+    #[test]
+    fn invalid_expressions_produce_error() {
+        for expr in [
+            "",
+            "1 +",
+            "(1 + 2",
+            "1 + 2)",
+            "1 / 0",
+            "5 mod 0",
+            "1.2.3",
+            "unknown",
+            "unknown(1)",
+            "sqrt(-1)",
+            "round(1, 2, 3)",
+            "1 $ 2",
+            "10 ^ 400",
+        ] {
+            assert!(
+                matches!(calc(expr, &[]), ExtensionResult::Error(_)),
+                "expression: {expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_params_produce_error() {
+        assert!(matches!(
+            CalcExtension::new().calculate(
+                &crate::Context::default(),
+                &HashMap::default(),
+                &Params::new()
+            ),
+            ExtensionResult::Error(_)
+        ));
+        assert!(matches!(
+            calc("1", &[("decimals", integer(-1))]),
+            ExtensionResult::Error(_)
+        ));
+        assert!(matches!(
+            calc("1", &[("rounding", string("unknown"))]),
+            ExtensionResult::Error(_)
+        ));
+    }
+}
@@ -0,0 +1,585 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use chrono::{
+    DateTime, Datelike, Days, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Weekday,
+};
+use thiserror::Error;
+
+use crate::{Params, Value};
+
+// Prevent endless loops when the weekend and holidays cover every day
+const MAX_BUSINESS_DAY_SEARCH: usize = 3660;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    BusinessDay,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    Add(i64, Unit),
+    NextWeekday(Weekday),
+    PreviousWeekday(Weekday),
+    ThisWeekday(Weekday),
+    StartOf(Period),
+    EndOf(Period),
+    IsoWeek(u32),
+}
+
+// The days considered when computing business days
+pub struct Calendar {
+    weekend: Vec<Weekday>,
+    holidays: Vec<Holiday>,
+}
+
+enum Holiday {
+    Fixed(NaiveDate),
+    // Repeats every year, like `12-25`
+    Recurring(u32, u32),
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self {
+            weekend: vec![Weekday::Sat, Weekday::Sun],
+            holidays: Vec::new(),
+        }
+    }
+}
+
+impl Calendar {
+    pub fn from_params(params: &Params) -> Result<Self, CalendarError> {
+        let mut calendar = Self::default();
+
+        if let Some(weekend) = params.get("weekend") {
+            calendar.weekend = get_strings(weekend, "weekend")?
+                .iter()
+                .map(|day| {
+                    parse_weekday(day).ok_or_else(|| CalendarError::InvalidWeekday(day.clone()))
+                })
+                .collect::<Result<_, _>>()?;
+            if calendar.weekend.len() >= 7 {
+                return Err(CalendarError::NoBusinessDays);
+            }
+        }
+
+        if let Some(holidays) = params.get("holidays") {
+            calendar.holidays = get_strings(holidays, "holidays")?
+                .iter()
+                .map(|holiday| {
+                    parse_holiday(holiday)
+                        .ok_or_else(|| CalendarError::InvalidHoliday(holiday.clone()))
+                })
+                .collect::<Result<_, _>>()?;
+        }
+
+        Ok(calendar)
+    }
+
+    fn is_business_day(&self, date: NaiveDate) -> bool {
+        if self.weekend.contains(&date.weekday()) {
+            return false;
+        }
+
+        !self.holidays.iter().any(|holiday| match holiday {
+            Holiday::Fixed(holiday) => *holiday == date,
+            Holiday::Recurring(month, day) => date.month() == *month && date.day() == *day,
+        })
+    }
+}
+
+fn get_strings(value: &Value, name: &'static str) -> Result<Vec<String>, CalendarError> {
+    match value {
+        Value::String(value) => Ok(value
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(String::from)
+            .collect()),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| {
+                value
+                    .as_string()
+                    .cloned()
+                    .ok_or(CalendarError::InvalidParameter(name))
+            })
+            .collect(),
+        _ => Err(CalendarError::InvalidParameter(name)),
+    }
+}
+
+fn parse_holiday(holiday: &str) -> Option<Holiday> {
+    if let Ok(date) = NaiveDate::parse_from_str(holiday, "%Y-%m-%d") {
+        return Some(Holiday::Fixed(date));
+    }
+
+    let (month, day) = holiday.split_once('-')?;
+    let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+    // Validate the day against a leap year, to accept 02-29
+    NaiveDate::from_ymd_opt(2000, month, day)?;
+    Some(Holiday::Recurring(month, day))
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    match day.trim().to_lowercase().as_str() {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thur" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_unit(unit: &str) -> Option<Unit> {
+    match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(Unit::Second),
+        "min" | "mins" | "minute" | "minutes" => Some(Unit::Minute),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(Unit::Hour),
+        "d" | "day" | "days" => Some(Unit::Day),
+        "bd" | "businessday" | "businessdays" => Some(Unit::BusinessDay),
+        "w" | "week" | "weeks" => Some(Unit::Week),
+        "mo" | "month" | "months" => Some(Unit::Month),
+        "q" | "quarter" | "quarters" => Some(Unit::Quarter),
+        "y" | "year" | "years" => Some(Unit::Year),
+        _ => None,
+    }
+}
+
+fn parse_period(period: &str) -> Option<Period> {
+    match period {
+        "day" => Some(Period::Day),
+        "week" => Some(Period::Week),
+        "month" => Some(Period::Month),
+        "quarter" => Some(Period::Quarter),
+        "year" => Some(Period::Year),
+        _ => None,
+    }
+}
+
+fn period_unit(period: Period) -> Unit {
+    match period {
+        Period::Day => Unit::Day,
+        Period::Week => Unit::Week,
+        Period::Month => Unit::Month,
+        Period::Quarter => Unit::Quarter,
+        Period::Year => Unit::Year,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Sign(i64),
+    Number(i64),
+    Word(String),
+}
+
+fn tokenize(offset: &str) -> Result<Vec<Token>, CalendarError> {
+    let mut tokens = Vec::new();
+    let mut chars = offset.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == ',' {
+            chars.next();
+        } else if c == '+' || c == '-' {
+            chars.next();
+            tokens.push(Token::Sign(if c == '+' { 1 } else { -1 }));
+        } else if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                number.push(c);
+                chars.next();
+            }
+            let number = number
+                .parse()
+                .map_err(|_| CalendarError::InvalidOffset(number.clone()))?;
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphabetic()) {
+                word.extend(c.to_lowercase());
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        } else {
+            return Err(CalendarError::InvalidOffset(c.to_string()));
+        }
+    }
+
+    Ok(tokens)
+}
+
+// Parse a sequence of calendar operations, applied from left to right, like
+// `+3 business days`, `next monday`, `end of month` or `first day of next quarter`
+pub fn parse_offset(offset: &str) -> Result<Vec<Operation>, CalendarError> {
+    let tokens = tokenize(offset)?;
+    let mut operations = Vec::new();
+    let mut position = 0;
+
+    let word_at = |position: usize| match tokens.get(position) {
+        Some(Token::Word(word)) => Some(word.as_str()),
+        _ => None,
+    };
+    let unexpected = |position: usize| match tokens.get(position) {
+        Some(Token::Word(word)) => CalendarError::InvalidOffset(word.clone()),
+        Some(Token::Number(number)) => CalendarError::InvalidOffset(number.to_string()),
+        Some(Token::Sign(_)) => CalendarError::InvalidOffset("sign".to_string()),
+        None => CalendarError::IncompleteOffset,
+    };
+
+    // Read a unit, joining `business day(s)` into a single word
+    let read_unit = |position: &mut usize| {
+        let word = word_at(*position).ok_or_else(|| unexpected(*position))?;
+        *position += 1;
+        if word == "business" {
+            let next = word_at(*position).ok_or_else(|| unexpected(*position))?;
+            *position += 1;
+            return parse_unit(&format!("business{next}")).ok_or_else(|| unexpected(*position - 1));
+        }
+        parse_unit(word).ok_or_else(|| unexpected(*position - 1))
+    };
+
+    // Read an optional `this`, `next` or `last` followed by a period
+    let read_relative_period = |position: &mut usize| {
+        let shift = match word_at(*position) {
+            Some("this") => Some(0),
+            Some("next") => Some(1),
+            Some("last" | "previous") => Some(-1),
+            _ => None,
+        };
+        if shift.is_some() {
+            *position += 1;
+        }
+        let word = word_at(*position).ok_or_else(|| unexpected(*position))?;
+        let period = parse_period(word).ok_or_else(|| unexpected(*position))?;
+        *position += 1;
+        Ok::<_, CalendarError>((shift.unwrap_or(0), period))
+    };
+
+    while position < tokens.len() {
+        match &tokens[position] {
+            Token::Sign(_) | Token::Number(_) => {
+                let mut sign = 1;
+                if let Token::Sign(value) = tokens[position] {
+                    sign = value;
+                    position += 1;
+                }
+                let Some(Token::Number(amount)) = tokens.get(position) else {
+                    return Err(unexpected(position));
+                };
+                let amount = sign * amount;
+                position += 1;
+                operations.push(Operation::Add(amount, read_unit(&mut position)?));
+            }
+            Token::Word(word) => match word.as_str() {
+                "today" | "now" => position += 1,
+                "tomorrow" => {
+                    position += 1;
+                    operations.push(Operation::Add(1, Unit::Day));
+                }
+                "yesterday" => {
+                    position += 1;
+                    operations.push(Operation::Add(-1, Unit::Day));
+                }
+                "week" => {
+                    let Some(Token::Number(week)) = tokens.get(position + 1) else {
+                        return Err(unexpected(position + 1));
+                    };
+                    let week = u32::try_from(*week)
+                        .ok()
+                        .filter(|week| (1..=53).contains(week))
+                        .ok_or(CalendarError::InvalidOffset(week.to_string()))?;
+                    position += 2;
+                    operations.push(Operation::IsoWeek(week));
+                }
+                "start" | "end" | "first" | "last"
+                    if word_at(position + 1) == Some("of")
+                        || (word_at(position + 1) == Some("day")
+                            && word_at(position + 2) == Some("of")) =>
+                {
+                    let is_start = word == "start" || word == "first";
+                    position += if word_at(position + 1) == Some("of") {
+                        2
+                    } else {
+                        3
+                    };
+                    let (shift, period) = read_relative_period(&mut position)?;
+                    if shift != 0 {
+                        operations.push(Operation::Add(shift, period_unit(period)));
+                    }
+                    operations.push(if is_start {
+                        Operation::StartOf(period)
+                    } else {
+                        Operation::EndOf(period)
+                    });
+                }
+                "next" | "last" | "previous" | "this" => {
+                    let relative = word.clone();
+                    position += 1;
+                    let target = word_at(position).ok_or_else(|| unexpected(position))?;
+                    if let Some(weekday) = parse_weekday(target) {
+                        position += 1;
+                        operations.push(match relative.as_str() {
+                            "next" => Operation::NextWeekday(weekday),
+                            "this" => Operation::ThisWeekday(weekday),
+                            _ => Operation::PreviousWeekday(weekday),
+                        });
+                    } else {
+                        let unit = read_unit(&mut position)?;
+                        match relative.as_str() {
+                            "next" => operations.push(Operation::Add(1, unit)),
+                            "this" => {}
+                            _ => operations.push(Operation::Add(-1, unit)),
+                        }
+                    }
+                }
+                _ => return Err(unexpected(position)),
+            },
+        }
+    }
+
+    if tokens.is_empty() {
+        return Err(CalendarError::IncompleteOffset);
+    }
+
+    Ok(operations)
+}
+
+pub fn apply_offset<T: TimeZone>(
+    date: &DateTime<T>,
+    operations: &[Operation],
+    calendar: &Calendar,
+) -> Result<DateTime<T>, CalendarError> {
+    let mut local = date.naive_local();
+    for operation in operations {
+        local = apply_operation(local, *operation, calendar).ok_or(CalendarError::OutOfRange)?;
+    }
+
+    // Times skipped by a daylight saving transition are moved forward
+    let timezone = date.timezone();
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .ok_or(CalendarError::OutOfRange)
+}
+
+fn apply_operation(
+    date: NaiveDateTime,
+    operation: Operation,
+    calendar: &Calendar,
+) -> Option<NaiveDateTime> {
+    let day = date.date();
+    let time = date.time();
+
+    match operation {
+        Operation::Add(amount, unit) => match unit {
+            Unit::Second => date.checked_add_signed(Duration::try_seconds(amount)?),
+            Unit::Minute => date.checked_add_signed(Duration::try_minutes(amount)?),
+            Unit::Hour => date.checked_add_signed(Duration::try_hours(amount)?),
+            Unit::Day => date.checked_add_signed(Duration::try_days(amount)?),
+            Unit::Week => date.checked_add_signed(Duration::try_weeks(amount)?),
+            Unit::Month => add_months(date, amount),
+            Unit::Quarter => add_months(date, amount.checked_mul(3)?),
+            Unit::Year => add_months(date, amount.checked_mul(12)?),
+            Unit::BusinessDay => {
+                add_business_days(day, amount, calendar).map(|day| day.and_time(time))
+            }
+        },
+        Operation::NextWeekday(weekday) => {
+            let days =
+                (weekday.num_days_from_monday() + 7 - day.weekday().num_days_from_monday()) % 7;
+            let days = if days == 0 { 7 } else { days };
+            day.checked_add_days(Days::new(u64::from(days)))
+                .map(|day| day.and_time(time))
+        }
+        Operation::PreviousWeekday(weekday) => {
+            let days =
+                (day.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+            let days = if days == 0 { 7 } else { days };
+            day.checked_sub_days(Days::new(u64::from(days)))
+                .map(|day| day.and_time(time))
+        }
+        Operation::ThisWeekday(weekday) => {
+            let monday =
+                day.checked_sub_days(Days::new(u64::from(day.weekday().num_days_from_monday())))?;
+            monday
+                .checked_add_days(Days::new(u64::from(weekday.num_days_from_monday())))
+                .map(|day| day.and_time(time))
+        }
+        Operation::StartOf(period) => match period {
+            Period::Day => Some(day.and_time(NaiveTime::MIN)),
+            _ => period_bounds(day, period).map(|(start, _)| start.and_time(time)),
+        },
+        Operation::EndOf(period) => match period {
+            Period::Day => day.and_hms_opt(23, 59, 59),
+            _ => period_bounds(day, period).map(|(_, end)| end.and_time(time)),
+        },
+        Operation::IsoWeek(week) => {
+            NaiveDate::from_isoywd_opt(day.iso_week().year(), week, Weekday::Mon)
+                .map(|day| day.and_time(time))
+        }
+    }
+}
+
+fn add_months(date: NaiveDateTime, months: i64) -> Option<NaiveDateTime> {
+    // Days past the end of the target month are clamped, so Jan 31 + 1 month is Feb 28/29
+    let delta = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+    if months < 0 {
+        date.checked_sub_months(delta)
+    } else {
+        date.checked_add_months(delta)
+    }
+}
+
+fn add_business_days(mut day: NaiveDate, amount: i64, calendar: &Calendar) -> Option<NaiveDate> {
+    let step = if amount < 0 { -1 } else { 1 };
+    let mut remaining = amount.unsigned_abs();
+    let mut searched = 0;
+
+    while remaining > 0 {
+        day = day.checked_add_signed(Duration::try_days(step)?)?;
+        if calendar.is_business_day(day) {
+            remaining -= 1;
+            searched = 0;
+        } else {
+            searched += 1;
+            if searched > MAX_BUSINESS_DAY_SEARCH {
+                return None;
+            }
+        }
+    }
+
+    Some(day)
+}
+
+// The first and last day of the period containing the given day.
+// Weeks start on Monday, following ISO 8601.
+fn period_bounds(day: NaiveDate, period: Period) -> Option<(NaiveDate, NaiveDate)> {
+    let (start, months) = match period {
+        Period::Day => return Some((day, day)),
+        Period::Week => {
+            let start =
+                day.checked_sub_days(Days::new(u64::from(day.weekday().num_days_from_monday())))?;
+            return Some((start, start.checked_add_days(Days::new(6))?));
+        }
+        Period::Month => (NaiveDate::from_ymd_opt(day.year(), day.month(), 1)?, 1),
+        Period::Quarter => {
+            let month = (day.month() - 1) / 3 * 3 + 1;
+            (NaiveDate::from_ymd_opt(day.year(), month, 1)?, 3)
+        }
+        Period::Year => (NaiveDate::from_ymd_opt(day.year(), 1, 1)?, 12),
+    };
+
+    let end = start
+        .checked_add_months(Months::new(months))?
+        .checked_sub_days(Days::new(1))?;
+    Some((start, end))
+}
+
+#[derive(Error, Debug)]
+pub enum CalendarError {
+    #[error("unexpected '{0}' in date offset")]
+    InvalidOffset(String),
+
+    #[error("incomplete date offset")]
+    IncompleteOffset,
+
+    #[error("invalid '{0}' parameter")]
+    InvalidParameter(&'static str),
+
+    #[error("invalid weekday '{0}'")]
+    InvalidWeekday(String),
+
+    #[error("invalid holiday '{0}', expected a date like '2024-12-25' or '12-25'")]
+    InvalidHoliday(String),
+
+    #[error("the weekend can't include every day of the week")]
+    NoBusinessDays,
+
+    #[error("the date offset produces a date out of range")]
+    OutOfRange,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_offset_works() {
+        assert_eq!(
+            parse_offset("+3 business days, end of month").unwrap(),
+            vec![
+                Operation::Add(3, Unit::BusinessDay),
+                Operation::EndOf(Period::Month)
+            ]
+        );
+        assert_eq!(
+            parse_offset("first day of next quarter").unwrap(),
+            vec![
+                Operation::Add(1, Unit::Quarter),
+                Operation::StartOf(Period::Quarter)
+            ]
+        );
+        assert_eq!(
+            parse_offset("-2w next fri").unwrap(),
+            vec![
+                Operation::Add(-2, Unit::Week),
+                Operation::NextWeekday(Weekday::Fri)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_offset() {
+        assert!(parse_offset("").is_err());
+        assert!(parse_offset("+3").is_err());
+        assert!(parse_offset("+3 parsecs").is_err());
+        assert!(parse_offset("end of").is_err());
+        assert!(parse_offset("next").is_err());
+        assert!(parse_offset("week 54").is_err());
+        assert!(parse_offset("invalid").is_err());
+    }
+}
@@ -22,6 +22,7 @@ use chrono_tz::Tz;
 use std::fmt::Write;
 use thiserror::Error;
 
+use super::calendar::{self, Calendar};
 use crate::{Extension, ExtensionOutput, ExtensionResult, Number, Params, Value};
 
 pub trait LocaleProvider {
@@ -56,21 +57,36 @@ impl Extension for DateExtension<'_> {
     ) -> crate::ExtensionResult {
         let mut now = self.get_date();
 
-        // Compute the given offset
-        let offset = params.get("offset");
-        if let Some(offset_value) = offset {
-            let offset_seconds = match offset_value {
-                Value::Number(Number::Integer(n)) => Some(*n),
-                Value::String(s) => s.parse::<i64>().ok(),
-                _ => None,
-            };
-
-            if let Some(seconds) = offset_seconds {
-                let offset = Duration::seconds(seconds);
-                now += offset;
+        // Compute the given offset. Numbers are interpreted as seconds, while other
+        // strings are parsed as calendar offsets, like "+3 business days" or "end of month"
+        let mut calendar_offset = None;
+        if let Some(offset_value) = params.get("offset") {
+            match offset_value {
+                Value::Number(Number::Integer(n)) => now += Duration::seconds(*n),
+                Value::String(s) => {
+                    if let Ok(seconds) = s.trim().parse::<i64>() {
+                        now += Duration::seconds(seconds);
+                    } else {
+                        match calendar::parse_offset(s) {
+                            Ok(operations) => calendar_offset = Some(operations),
+                            Err(err) => return ExtensionResult::Error(err.into()),
+                        }
+                    }
+                }
+                _ => {}
             }
         }
 
+        let calendar = if calendar_offset.is_some() {
+            match Calendar::from_params(params) {
+                Ok(calendar) => calendar,
+                Err(err) => return ExtensionResult::Error(err.into()),
+            }
+        } else {
+            Calendar::default()
+        };
+        let operations = calendar_offset.unwrap_or_default();
+
         // Convert to target timezone if specified
         let tz_param = params.get("tz").and_then(|val| val.as_string());
 
@@ -80,32 +96,26 @@ impl Extension for DateExtension<'_> {
             .and_then(|val| val.as_string())
             .map_or_else(|| self.locale_provider.get_system_locale(), String::from);
 
-        let date = if let Some(tz_str) = tz_param {
-            // Try to parse the timezone string
-            if let Ok(tz) = tz_str.parse::<Tz>() {
-                let converted = now.with_timezone(&tz);
-                if let Some(Value::String(format)) = format {
-                    match DateExtension::format_date_with_locale_and_tz(converted, format, &locale)
-                    {
-                        Ok(formatted) => formatted,
-                        Err(err) => return ExtensionResult::Error(err.into()),
-                    }
-                } else {
-                    converted.to_rfc2822()
+        // Invalid timezones fallback to local time
+        let date = if let Some(tz) = tz_param.and_then(|tz_str| tz_str.parse::<Tz>().ok()) {
+            let converted =
+                match calendar::apply_offset(&now.with_timezone(&tz), &operations, &calendar) {
+                    Ok(converted) => converted,
+                    Err(err) => return ExtensionResult::Error(err.into()),
+                };
+            if let Some(Value::String(format)) = format {
+                match DateExtension::format_date_with_locale_and_tz(converted, format, &locale) {
+                    Ok(formatted) => formatted,
+                    Err(err) => return ExtensionResult::Error(err.into()),
                 }
             } else {
-                // Invalid timezone, fallback to local time
-                if let Some(Value::String(format)) = format {
-                    match DateExtension::format_date_with_locale_string(now, format, &locale) {
-                        Ok(formatted) => formatted,
-                        Err(err) => return ExtensionResult::Error(err.into()),
-                    }
-                } else {
-                    now.to_rfc2822()
-                }
+                converted.to_rfc2822()
             }
         } else {
-            // No timezone specified, use local time
+            let now = match calendar::apply_offset(&now, &operations, &calendar) {
+                Ok(now) => now,
+                Err(err) => return ExtensionResult::Error(err.into()),
+            };
             if let Some(Value::String(format)) = format {
                 match DateExtension::format_date_with_locale_string(now, format, &locale) {
                     Ok(formatted) => formatted,
@@ -789,7 +799,16 @@ mod tests {
     }
 
     #[test]
-    fn offset_with_invalid_string_is_ignored() {
+    fn offset_with_invalid_string_returns_error() {
+        for offset in ["invalid", "+3 busines days", "+1 day extra"] {
+            assert!(matches!(
+                calculate_with_offset(offset, vec![]),
+                ExtensionResult::Error(_)
+            ));
+        }
+    }
+
+    fn calculate_with_offset(offset: &str, extra: Vec<(&str, Value)>) -> ExtensionResult {
         let locale_provider = MockLocaleProvider::new();
         let mut extension = DateExtension::new(&locale_provider);
         extension.fixed_date = Some(
@@ -799,19 +818,113 @@ mod tests {
                 .unwrap(),
         );
 
-        let param = vec![
-            ("format".to_string(), Value::String("%H:%M:%S".to_string())),
-            ("offset".to_string(), Value::String("invalid".to_string())),
+        let mut param = vec![
+            ("format".to_string(), Value::String("%Y-%m-%d".to_string())),
+            ("offset".to_string(), Value::String(offset.to_string())),
         ]
         .into_iter()
         .collect::<Params>();
-        // Should return time without offset if offset is invalid
+        param.extend(
+            extra
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value)),
+        );
+        extension.calculate(&crate::Context::default(), &HashMap::default(), &param)
+    }
+
+    fn offset_date(offset: &str, extra: Vec<(&str, Value)>) -> String {
+        match calculate_with_offset(offset, extra).into_success().unwrap() {
+            ExtensionOutput::Single(date) => date,
+            ExtensionOutput::Multiple(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn business_day_offset() {
+        assert_eq!(offset_date("+3 business days", vec![]), "2014-07-11");
+        assert_eq!(offset_date("+4bd", vec![]), "2014-07-14");
+        assert_eq!(offset_date("-2 business days", vec![]), "2014-07-04");
+        assert_eq!(offset_date("next business day", vec![]), "2014-07-09");
+    }
+
+    #[test]
+    fn business_day_offset_with_holidays_and_weekend() {
+        let holidays = Value::Array(vec![
+            Value::String("2014-07-10".to_string()),
+            Value::String("07-11".to_string()),
+        ]);
         assert_eq!(
-            extension
-                .calculate(&crate::Context::default(), &HashMap::default(), &param)
-                .into_success()
-                .unwrap(),
-            ExtensionOutput::Single("09:10:11".to_string())
+            offset_date("+3 business days", vec![("holidays", holidays)]),
+            "2014-07-15"
+        );
+
+        let weekend = Value::Array(vec![
+            Value::String("friday".to_string()),
+            Value::String("sat".to_string()),
+        ]);
+        assert_eq!(
+            offset_date("+3 business days", vec![("weekend", weekend)]),
+            "2014-07-13"
+        );
+    }
+
+    #[test]
+    fn invalid_calendar_params_return_error() {
+        let holidays = Value::Array(vec![Value::String("christmas".to_string())]);
+        assert!(matches!(
+            calculate_with_offset("+1bd", vec![("holidays", holidays)]),
+            ExtensionResult::Error(_)
+        ));
+
+        let weekend = Value::String("mon,tue,wed,thu,fri,sat,sun".to_string());
+        assert!(matches!(
+            calculate_with_offset("+1bd", vec![("weekend", weekend)]),
+            ExtensionResult::Error(_)
+        ));
+    }
+
+    #[test]
+    fn relative_weekday_offset() {
+        assert_eq!(offset_date("next monday", vec![]), "2014-07-14");
+        assert_eq!(offset_date("next tuesday", vec![]), "2014-07-15");
+        assert_eq!(offset_date("last friday", vec![]), "2014-07-04");
+        assert_eq!(offset_date("this friday", vec![]), "2014-07-11");
+    }
+
+    #[test]
+    fn period_boundaries_offset() {
+        assert_eq!(offset_date("end of month", vec![]), "2014-07-31");
+        assert_eq!(offset_date("start of week", vec![]), "2014-07-07");
+        assert_eq!(
+            offset_date("first day of next quarter", vec![]),
+            "2014-10-01"
+        );
+        assert_eq!(offset_date("last day of last month", vec![]), "2014-06-30");
+        assert_eq!(offset_date("end of year", vec![]), "2014-12-31");
+    }
+
+    #[test]
+    fn chained_calendar_offsets() {
+        assert_eq!(offset_date("+1 month, end of month", vec![]), "2014-08-31");
+        assert_eq!(offset_date("tomorrow +2 weeks", vec![]), "2014-07-23");
+        assert_eq!(
+            offset_date(
+                "-1 day, end of day",
+                vec![("format", Value::String("%d %H:%M:%S".to_string()))]
+            ),
+            "07 23:59:59"
+        );
+    }
+
+    #[test]
+    fn iso_week_offset_and_format() {
+        assert_eq!(
+            offset_date(
+                "next monday",
+                vec![("format", Value::String("%G-W%V".to_string()))]
+            ),
+            "2014-W29"
         );
+        assert_eq!(offset_date("week 1", vec![]), "2013-12-30");
     }
 }
@@ -0,0 +1,433 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use crate::{Extension, ExtensionOutput, ExtensionResult, Params, Value};
+use log::debug;
+use thiserror::Error;
+
+type EnvFile = HashMap<String, String>;
+
+pub struct EnvExtension {
+    home_path: PathBuf,
+    config_path: PathBuf,
+    packages_path: PathBuf,
+
+    // Parsed .env files, invalidated when their modification time changes
+    cache: Mutex<HashMap<PathBuf, (Option<SystemTime>, EnvFile)>>,
+}
+
+#[allow(clippy::new_without_default)]
+impl EnvExtension {
+    pub fn new(config_path: &Path, home_path: &Path, packages_path: &Path) -> Self {
+        Self {
+            config_path: config_path.to_owned(),
+            home_path: home_path.to_owned(),
+            packages_path: packages_path.to_owned(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lookup_in_file(&self, path: &Path, name: &str) -> anyhow::Result<Option<String>> {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            debug!("env file {path:?} does not exist, skipping");
+            return Ok(None);
+        };
+        let modified = metadata.modified().ok();
+
+        let mut cache = self.cache.lock().expect("unable to obtain env cache lock");
+        if let Some((cached_modified, values)) = cache.get(path) {
+            if modified.is_some() && *cached_modified == modified {
+                return Ok(values.get(name).cloned());
+            }
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        let values = parse_env_file(&content)
+            .map_err(|err| EnvExtensionError::InvalidFile(path.to_path_buf(), err))?;
+        let value = values.get(name).cloned();
+        cache.insert(path.to_path_buf(), (modified, values));
+
+        Ok(value)
+    }
+
+    fn lookup(&self, name: &str, params: &Params) -> anyhow::Result<Option<String>> {
+        if let Some(value) = std::env::var_os(name) {
+            return match value.into_string() {
+                Ok(value) => Ok(Some(value)),
+                Err(_) => Err(EnvExtensionError::NotUnicode(name.to_string()).into()),
+            };
+        }
+
+        let files = match params.get("files") {
+            None => Vec::new(),
+            Some(Value::String(file)) => vec![file.clone()],
+            Some(Value::Array(files)) => files
+                .iter()
+                .map(|file| {
+                    file.as_string()
+                        .cloned()
+                        .ok_or(EnvExtensionError::InvalidParameter("files"))
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => return Err(EnvExtensionError::InvalidParameter("files").into()),
+        };
+
+        // The first file that defines the variable wins, as values
+        // already loaded are never overridden by dotenv files
+        for file in files {
+            let path = super::file::resolve_path(
+                &file,
+                &self.config_path,
+                &self.home_path,
+                &self.packages_path,
+            );
+            if let Some(value) = self.lookup_in_file(&path, name)? {
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Extension for EnvExtension {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn as_concurrent(&self) -> Option<&(dyn Extension + Sync)> {
+        Some(self)
+    }
+
+    fn calculate(
+        &self,
+        _: &crate::Context,
+        _: &crate::Scope,
+        params: &Params,
+    ) -> crate::ExtensionResult {
+        let name = match params.get("name") {
+            Some(Value::String(name)) if !name.trim().is_empty() => name.trim(),
+            _ => return ExtensionResult::Error(EnvExtensionError::MissingNameParameter.into()),
+        };
+
+        let value = match self.lookup(name, params) {
+            Ok(value) => value,
+            Err(err) => return ExtensionResult::Error(err),
+        };
+
+        if let Some(value) = value {
+            return ExtensionResult::Success(ExtensionOutput::Single(value));
+        }
+
+        if let Some(Value::String(default)) = params.get("default") {
+            return ExtensionResult::Success(ExtensionOutput::Single(default.clone()));
+        }
+
+        let required = params
+            .get("required")
+            .and_then(|v| v.as_bool())
+            .copied()
+            .unwrap_or(false);
+
+        if required {
+            ExtensionResult::Error(EnvExtensionError::MissingVariable(name.to_string()).into())
+        } else {
+            ExtensionResult::Success(ExtensionOutput::Single(String::new()))
+        }
+    }
+}
+
+// Parse the content of a .env file, supporting comments, `export` prefixes
+// and both single (literal) and double (with escapes) quoted values
+fn parse_env_file(content: &str) -> Result<EnvFile, String> {
+    let mut values = HashMap::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("expected KEY=VALUE on line {}", index + 1));
+        };
+
+        let key = key.trim();
+        let is_valid_key = key
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-');
+        if !is_valid_key {
+            return Err(format!(
+                "invalid variable name '{}' on line {}",
+                key,
+                index + 1
+            ));
+        }
+
+        let value = parse_env_value(value.trim())
+            .ok_or_else(|| format!("unterminated quoted value on line {}", index + 1))?;
+        values.insert(key.to_string(), value);
+    }
+
+    Ok(values)
+}
+
+fn parse_env_value(value: &str) -> Option<String> {
+    if let Some(rest) = value.strip_prefix('\'') {
+        let end = rest.find('\'')?;
+        return Some(rest[..end].to_string());
+    }
+
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut output = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Some(output),
+                '\\' => match chars.next()? {
+                    'n' => output.push('\n'),
+                    'r' => output.push('\r'),
+                    't' => output.push('\t'),
+                    other => output.push(other),
+                },
+                _ => output.push(c),
+            }
+        }
+        return None;
+    }
+
+    // Unquoted values end at the first inline comment
+    let value = match value.find(" #") {
+        Some(index) => &value[..index],
+        None => value,
+    };
+    Some(value.trim_end().to_string())
+}
+
+#[derive(Error, Debug)]
+pub enum EnvExtensionError {
+    #[error("missing 'name' parameter")]
+    MissingNameParameter,
+
+    #[error("invalid '{0}' parameter")]
+    InvalidParameter(&'static str),
+
+    #[error("environment variable '{0}' is not set")]
+    MissingVariable(String),
+
+    #[error("environment variable '{0}' is not valid unicode")]
+    NotUnicode(String),
+
+    #[error("invalid env file {0:?}: {1}")]
+    InvalidFile(PathBuf, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn params(values: &[(&str, Value)]) -> Params {
+        values
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), value.clone()))
+            .collect()
+    }
+
+    fn string(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    fn calculate(extension: &EnvExtension, params: &Params) -> ExtensionResult {
+        extension.calculate(&crate::Context::default(), &HashMap::default(), params)
+    }
+
+    fn assert_output(result: ExtensionResult, expected: &str) {
+        assert_eq!(
+            result.into_success().unwrap(),
+            ExtensionOutput::Single(expected.to_string())
+        );
+    }
+
+    fn with_config_dir(callback: impl FnOnce(&Path, &EnvExtension)) {
+        let dir = TempDir::new("espanso-env").unwrap();
+        let extension = EnvExtension::new(dir.path(), dir.path(), dir.path());
+        callback(dir.path(), &extension);
+    }
+
+    #[test]
+    fn process_variable() {
+        with_config_dir(|_, extension| {
+            let name = if cfg!(target_os = "windows") {
+                "USERPROFILE"
+            } else {
+                "HOME"
+            };
+            let expected = std::env::var(name).unwrap();
+
+            assert_output(
+                calculate(extension, &params(&[("name", string(name))])),
+                &expected,
+            );
+        });
+    }
+
+    #[test]
+    fn missing_variable() {
+        with_config_dir(|_, extension| {
+            let name = string("ESPANSO_TEST_SURELY_MISSING_VARIABLE");
+
+            assert_output(calculate(extension, &params(&[("name", name.clone())])), "");
+            assert_output(
+                calculate(
+                    extension,
+                    &params(&[
+                        ("name", name.clone()),
+                        ("default", string("fallback")),
+                        ("required", Value::Bool(true)),
+                    ]),
+                ),
+                "fallback",
+            );
+            assert!(matches!(
+                calculate(
+                    extension,
+                    &params(&[("name", name), ("required", Value::Bool(true))])
+                ),
+                ExtensionResult::Error(_)
+            ));
+            assert!(matches!(
+                calculate(extension, &params(&[])),
+                ExtensionResult::Error(_)
+            ));
+        });
+    }
+
+    #[test]
+    fn env_files() {
+        with_config_dir(|dir, extension| {
+            std::fs::write(dir.join(".env"), "ESPANSO_TEST_HOSTNAME=first\n").unwrap();
+            std::fs::create_dir(dir.join("machine")).unwrap();
+            std::fs::write(
+                dir.join("machine").join("local.env"),
+                "ESPANSO_TEST_HOSTNAME=second\nESPANSO_TEST_EMPLOYEE_ID=42\n",
+            )
+            .unwrap();
+
+            let files = Value::Array(vec![
+                string("missing.env"),
+                string("%CONFIG%/.env"),
+                string("machine/local.env"),
+            ]);
+            assert_output(
+                calculate(
+                    extension,
+                    &params(&[
+                        ("name", string("ESPANSO_TEST_HOSTNAME")),
+                        ("files", files.clone()),
+                    ]),
+                ),
+                "first",
+            );
+            assert_output(
+                calculate(
+                    extension,
+                    &params(&[
+                        ("name", string("ESPANSO_TEST_EMPLOYEE_ID")),
+                        ("files", files),
+                    ]),
+                ),
+                "42",
+            );
+            assert_output(
+                calculate(
+                    extension,
+                    &params(&[
+                        ("name", string("ESPANSO_TEST_EMPLOYEE_ID")),
+                        ("files", string("machine/local.env")),
+                    ]),
+                ),
+                "42",
+            );
+        });
+    }
+
+    #[test]
+    fn invalid_env_file() {
+        with_config_dir(|dir, extension| {
+            std::fs::write(dir.join(".env"), "INVALID LINE\n").unwrap();
+
+            assert!(matches!(
+                calculate(
+                    extension,
+                    &params(&[
+                        ("name", string("ESPANSO_TEST_VAR")),
+                        ("files", string(".env"))
+                    ])
+                ),
+                ExtensionResult::Error(_)
+            ));
+        });
+    }
+
+    #[test]
+    fn parse_env_file_works_correctly() {
+        let values = parse_env_file(
+            r#"
+# A comment
+PLAIN=value
+export EXPORTED=exported
+SPACES = padded value  # inline comment
+HASH=abc#def
+SINGLE='literal \n # value'
+DOUBLE="line\nbreak \"quoted\""
+EMPTY=
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(values.get("PLAIN").unwrap(), "value");
+        assert_eq!(values.get("EXPORTED").unwrap(), "exported");
+        assert_eq!(values.get("SPACES").unwrap(), "padded value");
+        assert_eq!(values.get("HASH").unwrap(), "abc#def");
+        assert_eq!(values.get("SINGLE").unwrap(), "literal \\n # value");
+        assert_eq!(values.get("DOUBLE").unwrap(), "line\nbreak \"quoted\"");
+        assert_eq!(values.get("EMPTY").unwrap(), "");
+        assert_eq!(values.len(), 7);
+    }
+
+    #[test]
+    fn parse_env_file_errors() {
+        assert!(parse_env_file("NO_EQUALS").is_err());
+        assert!(parse_env_file("1INVALID=value").is_err());
+        assert!(parse_env_file("UNTERMINATED=\"value").is_err());
+    }
+}
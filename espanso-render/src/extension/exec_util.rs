@@ -17,6 +17,284 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::LazyLock,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
+use thiserror::Error;
+
+use crate::{Number, Params, Value};
+
+// How long to wait for the output pipes to close after killing a timed out process
+const PIPE_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+static ANSI_ESCAPE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\x1b(\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(\x07|\x1b\\)|[@-Z\\-_])")
+        .expect("unable to generate regex to strip ANSI escape sequences")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnError {
+    // Fail the expansion
+    Abort,
+    // Replace the output with an empty string
+    Empty,
+    // Replace the output with the standard error
+    Stderr,
+}
+
+// Options shared by the shell and script extensions
+#[derive(Debug)]
+pub struct ExecOptions {
+    pub timeout: Option<Duration>,
+    pub stdin: Option<String>,
+    pub cwd: Option<PathBuf>,
+    pub on_error: OnError,
+    pub trim: bool,
+    pub strip_ansi: bool,
+}
+
+impl ExecOptions {
+    pub fn from_params(
+        params: &Params,
+        resolve_path: impl Fn(&str) -> PathBuf,
+    ) -> Result<Self, ExecOptionsError> {
+        let timeout = match params.get("timeout_ms") {
+            None => None,
+            Some(Value::Number(Number::Integer(timeout))) => Some(*timeout),
+            Some(Value::String(timeout)) => Some(
+                timeout
+                    .trim()
+                    .parse()
+                    .map_err(|_| ExecOptionsError::Timeout(timeout.clone()))?,
+            ),
+            Some(value) => return Err(ExecOptionsError::Timeout(format!("{value:?}"))),
+        };
+        let timeout = match timeout {
+            Some(timeout) => Some(Duration::from_millis(
+                u64::try_from(timeout)
+                    .ok()
+                    .filter(|timeout| *timeout > 0)
+                    .ok_or(ExecOptionsError::Timeout(timeout.to_string()))?,
+            )),
+            None => None,
+        };
+
+        let stdin = params.get("stdin").and_then(|v| v.as_string()).cloned();
+
+        let cwd = match params.get("cwd").and_then(|v| v.as_string()) {
+            Some(cwd) => {
+                let path = resolve_path(cwd);
+                if !path.is_dir() {
+                    return Err(ExecOptionsError::Cwd(path.to_string_lossy().to_string()));
+                }
+                Some(path)
+            }
+            None => None,
+        };
+
+        let on_error = match params.get("on_error").and_then(|v| v.as_string()) {
+            None => OnError::Abort,
+            Some(on_error) => match on_error.as_str() {
+                "abort" => OnError::Abort,
+                "empty" => OnError::Empty,
+                "stderr" => OnError::Stderr,
+                _ => return Err(ExecOptionsError::OnError(on_error.clone())),
+            },
+        };
+
+        let trim = params
+            .get("trim")
+            .and_then(|v| v.as_bool())
+            .copied()
+            .unwrap_or(true);
+
+        let strip_ansi = params
+            .get("strip_ansi")
+            .and_then(|v| v.as_bool())
+            .copied()
+            .unwrap_or(false);
+
+        Ok(Self {
+            timeout,
+            stdin,
+            cwd,
+            on_error,
+            trim,
+            strip_ansi,
+        })
+    }
+
+    pub fn process_output(&self, output: &str) -> String {
+        let output = if self.strip_ansi {
+            ANSI_ESCAPE_REGEX.replace_all(output, "")
+        } else {
+            output.into()
+        };
+
+        if self.trim {
+            output.trim().to_owned()
+        } else {
+            output.into_owned()
+        }
+    }
+}
+
+pub struct ExecOutput {
+    // None if the process was killed because it exceeded the timeout
+    pub status: Option<ExitStatus>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+// Run the given command, honoring the stdin, cwd and timeout options.
+// When the timeout expires, the whole process group is killed, so that
+// commands spawned by a shell don't outlive it.
+pub fn execute(command: &mut Command, options: &ExecOptions) -> std::io::Result<ExecOutput> {
+    if let Some(cwd) = &options.cwd {
+        command.current_dir(cwd);
+    }
+
+    command
+        .stdin(if options.stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if options.timeout.is_some() {
+        set_new_process_group(command);
+    }
+
+    let mut child = command.spawn()?;
+
+    // Writing and reading happen on separate threads, to avoid deadlocks
+    // when the process fills up one of the pipes
+    if let (Some(input), Some(mut stdin)) = (options.stdin.clone(), child.stdin.take()) {
+        thread::spawn(move || {
+            // The process might exit without reading the whole input
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+    let stdout_reader = spawn_pipe_reader(child.stdout.take());
+    let stderr_reader = spawn_pipe_reader(child.stderr.take());
+
+    let status = match options.timeout {
+        Some(timeout) => wait_with_timeout(&mut child, timeout)?,
+        None => Some(child.wait()?),
+    };
+
+    if status.is_none() {
+        kill_process_group(&mut child);
+
+        // Orphaned processes that left the group might keep the pipes open
+        let deadline = Instant::now() + PIPE_DRAIN_TIMEOUT;
+        while !(stdout_reader.is_finished() && stderr_reader.is_finished())
+            && Instant::now() < deadline
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    let timed_out = status.is_none();
+    Ok(ExecOutput {
+        status,
+        stdout: join_pipe_reader(stdout_reader, timed_out),
+        stderr: join_pipe_reader(stderr_reader, timed_out),
+    })
+}
+
+fn spawn_pipe_reader(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+fn join_pipe_reader(reader: JoinHandle<Vec<u8>>, timed_out: bool) -> String {
+    // Don't block on pipes still held open after a timeout
+    if timed_out && !reader.is_finished() {
+        return String::new();
+    }
+    reader
+        .join()
+        .map(|buffer| String::from_utf8_lossy(&buffer).to_string())
+        .unwrap_or_default()
+}
+
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> std::io::Result<Option<ExitStatus>> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        let Some(remaining) = timeout
+            .checked_sub(start.elapsed())
+            .filter(|remaining| !remaining.is_zero())
+        else {
+            return Ok(None);
+        };
+        thread::sleep(remaining.min(Duration::from_millis(10)));
+    }
+}
+
+#[cfg(unix)]
+fn set_new_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn set_new_process_group(_: &mut Command) {
+    // NOOP on Windows, where the process tree is killed with taskkill
+}
+
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // The process group id matches the pid of the leader
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) {
+    let mut command = Command::new("taskkill");
+    command.args(["/T", "/F", "/PID", &child.id().to_string()]);
+    super::util::set_command_flags(&mut command);
+    let _ = command.output();
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[derive(Error, Debug)]
+pub enum ExecOptionsError {
+    #[error("invalid 'timeout_ms' parameter: `{0}`, expected a positive number of milliseconds")]
+    Timeout(String),
+
+    #[error("invalid 'cwd' parameter: `{0}` is not a directory")]
+    Cwd(String),
+
+    #[error("invalid 'on_error' parameter: `{0}`, expected one of: abort, empty, stderr")]
+    OnError(String),
+}
+
 pub enum MacShell {
     Bash,
     Nu,
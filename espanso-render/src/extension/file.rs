@@ -0,0 +1,621 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use crate::{Extension, ExtensionOutput, ExtensionResult, Number, Params, Value};
+use thiserror::Error;
+
+// Files bigger than this are rejected, unless a different `max_size` is given
+const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+
+type CachedFile = (Option<SystemTime>, u64, Arc<Vec<u8>>);
+
+pub struct FileExtension {
+    home_path: PathBuf,
+    config_path: PathBuf,
+    packages_path: PathBuf,
+
+    // File contents, invalidated when their modification time or size changes
+    cache: Mutex<HashMap<PathBuf, CachedFile>>,
+}
+
+// Resolve the %HOME%, %CONFIG% and %PACKAGES% placeholders of the given path.
+// The remaining relative paths are resolved against the config directory.
+pub fn resolve_path(
+    path: &str,
+    config_path: &Path,
+    home_path: &Path,
+    packages_path: &Path,
+) -> PathBuf {
+    let path = PathBuf::from(super::util::replace_path_placeholders(
+        path,
+        home_path,
+        config_path,
+        packages_path,
+    ));
+
+    if path.is_relative() {
+        normalize_path(&config_path.join(path))
+    } else {
+        normalize_path(&path)
+    }
+}
+
+// Remove the `.` and `..` components of the given path and canonicalize its longest
+// existing ancestor, so that equivalent paths compare equal even if the file
+// doesn't exist (anymore), for example when the file watcher reports its removal
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            // The parent of the root is the root itself
+            Component::ParentDir if normalized.has_root() => {}
+            component => normalized.push(component),
+        }
+    }
+
+    for ancestor in normalized.ancestors().filter(|ancestor| ancestor.exists()) {
+        if let Ok(canonical) = dunce::canonicalize(ancestor) {
+            let relative = normalized.strip_prefix(ancestor).unwrap_or(Path::new(""));
+            return if relative.as_os_str().is_empty() {
+                canonical
+            } else {
+                canonical.join(relative)
+            };
+        }
+    }
+
+    normalized
+}
+
+#[allow(clippy::new_without_default)]
+impl FileExtension {
+    pub fn new(config_path: &Path, home_path: &Path, packages_path: &Path) -> Self {
+        Self {
+            config_path: config_path.to_owned(),
+            home_path: home_path.to_owned(),
+            packages_path: packages_path.to_owned(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn read(&self, path: &Path, max_size: u64) -> Result<Arc<Vec<u8>>, FileExtensionError> {
+        let metadata = std::fs::metadata(path)
+            .map_err(|err| FileExtensionError::ReadFailed(path.to_path_buf(), err))?;
+        if !metadata.is_file() {
+            return Err(FileExtensionError::NotAFile(path.to_path_buf()));
+        }
+        if metadata.len() > max_size {
+            return Err(FileExtensionError::TooLarge(
+                path.to_path_buf(),
+                metadata.len(),
+                max_size,
+            ));
+        }
+        let modified = metadata.modified().ok();
+
+        let mut cache = self.cache.lock().expect("unable to obtain file cache lock");
+        if let Some((cached_modified, cached_len, content)) = cache.get(path) {
+            if modified.is_some() && *cached_modified == modified && *cached_len == metadata.len() {
+                return Ok(content.clone());
+            }
+        }
+
+        let content = Arc::new(
+            std::fs::read(path)
+                .map_err(|err| FileExtensionError::ReadFailed(path.to_path_buf(), err))?,
+        );
+        cache.insert(
+            path.to_path_buf(),
+            (modified, metadata.len(), content.clone()),
+        );
+
+        Ok(content)
+    }
+}
+
+impl Extension for FileExtension {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn as_concurrent(&self) -> Option<&(dyn Extension + Sync)> {
+        Some(self)
+    }
+
+    fn calculate(
+        &self,
+        _: &crate::Context,
+        _: &crate::Scope,
+        params: &Params,
+    ) -> crate::ExtensionResult {
+        match read_file(self, params) {
+            Ok(content) => ExtensionResult::Success(ExtensionOutput::Single(content)),
+            Err(err) => ExtensionResult::Error(err.into()),
+        }
+    }
+}
+
+fn read_file(extension: &FileExtension, params: &Params) -> Result<String, FileExtensionError> {
+    let path = match params.get("path") {
+        Some(Value::String(path)) if !path.trim().is_empty() => path,
+        _ => return Err(FileExtensionError::MissingPathParameter),
+    };
+    let path = resolve_path(
+        path,
+        &extension.config_path,
+        &extension.home_path,
+        &extension.packages_path,
+    );
+
+    let max_size = match params.get("max_size") {
+        None => DEFAULT_MAX_SIZE,
+        Some(Value::Number(Number::Integer(size))) => {
+            u64::try_from(*size).map_err(|_| FileExtensionError::InvalidParameter("max_size"))?
+        }
+        Some(_) => return Err(FileExtensionError::InvalidParameter("max_size")),
+    };
+
+    let encoding = match params.get("encoding") {
+        None => Encoding::Utf8,
+        Some(Value::String(encoding)) => Encoding::parse(encoding)
+            .ok_or_else(|| FileExtensionError::InvalidEncoding(encoding.clone()))?,
+        Some(_) => return Err(FileExtensionError::InvalidParameter("encoding")),
+    };
+
+    let range = match params.get("lines") {
+        None => None,
+        Some(Value::String(lines)) => Some(
+            LineRange::parse(lines)
+                .ok_or_else(|| FileExtensionError::InvalidLineRange(lines.clone()))?,
+        ),
+        Some(Value::Number(Number::Integer(line))) => Some(
+            LineRange::parse(&line.to_string())
+                .ok_or_else(|| FileExtensionError::InvalidLineRange(line.to_string()))?,
+        ),
+        Some(_) => return Err(FileExtensionError::InvalidParameter("lines")),
+    };
+
+    let bytes = extension.read(&path, max_size)?;
+    let mut content =
+        decode(&bytes, encoding).ok_or(FileExtensionError::InvalidContent(path, encoding))?;
+
+    if let Some(range) = range {
+        content = range.select(&content);
+    }
+
+    let trim = params
+        .get("trim")
+        .and_then(|v| v.as_bool())
+        .copied()
+        .unwrap_or(false);
+
+    if trim {
+        Ok(content.trim().to_string())
+    } else {
+        // Most editors terminate files with a newline, which
+        // is rarely meant to be part of the expansion
+        let content = content.strip_suffix('\n').unwrap_or(&content);
+        let content = content.strip_suffix('\r').unwrap_or(content);
+        Ok(content.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf8Lossy,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl Encoding {
+    fn parse(encoding: &str) -> Option<Self> {
+        match encoding.to_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Some(Self::Utf8),
+            "utf-8-lossy" | "utf8-lossy" => Some(Self::Utf8Lossy),
+            "utf-16le" | "utf-16-le" | "utf16le" => Some(Self::Utf16Le),
+            "utf-16be" | "utf-16-be" | "utf16be" => Some(Self::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" => Some(Self::Latin1),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf8Lossy => "utf-8-lossy",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin1",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// Decode the file content, honoring (and removing) the byte order marks.
+// UTF-16 files with a BOM are decoded correctly even with the default encoding.
+fn decode(bytes: &[u8], encoding: Encoding) -> Option<String> {
+    let encoding = match (encoding, bytes) {
+        (Encoding::Utf8 | Encoding::Utf16Le, [0xFF, 0xFE, ..]) => Encoding::Utf16Le,
+        (Encoding::Utf8 | Encoding::Utf16Be, [0xFE, 0xFF, ..]) => Encoding::Utf16Be,
+        (encoding, _) => encoding,
+    };
+
+    match encoding {
+        Encoding::Utf8 | Encoding::Utf8Lossy => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            if encoding == Encoding::Utf8 {
+                String::from_utf8(bytes.to_vec()).ok()
+            } else {
+                Some(String::from_utf8_lossy(bytes).to_string())
+            }
+        }
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            if !bytes.len().is_multiple_of(2) {
+                return None;
+            }
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| {
+                    if encoding == Encoding::Utf16Le {
+                        u16::from_le_bytes([pair[0], pair[1]])
+                    } else {
+                        u16::from_be_bytes([pair[0], pair[1]])
+                    }
+                })
+                .collect();
+            let units = units.strip_prefix(&[0xFEFF]).unwrap_or(&units);
+            String::from_utf16(units).ok()
+        }
+        Encoding::Latin1 => Some(bytes.iter().map(|&byte| char::from(byte)).collect()),
+    }
+}
+
+// An inclusive, 1-based range of lines, like `3`, `3-5`, `3-` or `-5`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LineRange {
+    start: usize,
+    end: Option<usize>,
+}
+
+impl LineRange {
+    fn parse(range: &str) -> Option<Self> {
+        let parse_line = |line: &str| match line.trim().parse::<usize>() {
+            Ok(0) | Err(_) => None,
+            Ok(line) => Some(line),
+        };
+
+        let range = match range.split_once('-') {
+            None => {
+                let line = parse_line(range)?;
+                Self {
+                    start: line,
+                    end: Some(line),
+                }
+            }
+            Some((start, end)) => Self {
+                start: if start.trim().is_empty() {
+                    1
+                } else {
+                    parse_line(start)?
+                },
+                end: if end.trim().is_empty() {
+                    None
+                } else {
+                    Some(parse_line(end)?)
+                },
+            },
+        };
+
+        if range.end.is_some_and(|end| end < range.start) {
+            return None;
+        }
+
+        Some(range)
+    }
+
+    fn select(&self, content: &str) -> String {
+        let take = self.end.map_or(usize::MAX, |end| end - self.start + 1);
+        content
+            .lines()
+            .skip(self.start - 1)
+            .take(take)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum FileExtensionError {
+    #[error("missing 'path' parameter")]
+    MissingPathParameter,
+
+    #[error("invalid '{0}' parameter")]
+    InvalidParameter(&'static str),
+
+    #[error(
+        "unknown encoding '{0}', expected one of: utf-8, utf-8-lossy, utf-16le, utf-16be, latin1"
+    )]
+    InvalidEncoding(String),
+
+    #[error(
+        "invalid line range '{0}', expected a line number or a range like '3-5', '3-' or '-5'"
+    )]
+    InvalidLineRange(String),
+
+    #[error("unable to read file {0:?}: {1}")]
+    ReadFailed(PathBuf, std::io::Error),
+
+    #[error("{0:?} is not a file")]
+    NotAFile(PathBuf),
+
+    #[error("file {0:?} is {1} bytes long, which exceeds the limit of {2} bytes")]
+    TooLarge(PathBuf, u64, u64),
+
+    #[error("file {0:?} is not valid {1}")]
+    InvalidContent(PathBuf, Encoding),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn params(values: &[(&str, Value)]) -> Params {
+        values
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), value.clone()))
+            .collect()
+    }
+
+    fn string(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    fn calculate(extension: &FileExtension, params: &Params) -> ExtensionResult {
+        extension.calculate(&crate::Context::default(), &HashMap::default(), params)
+    }
+
+    fn assert_output(result: ExtensionResult, expected: &str) {
+        assert_eq!(
+            result.into_success().unwrap(),
+            ExtensionOutput::Single(expected.to_string())
+        );
+    }
+
+    fn with_config_dir(callback: impl FnOnce(&Path, &FileExtension)) {
+        let dir = TempDir::new("espanso-file").unwrap();
+        let extension = FileExtension::new(dir.path(), dir.path(), dir.path());
+        callback(dir.path(), &extension);
+    }
+
+    #[test]
+    fn read_file_works_correctly() {
+        with_config_dir(|dir, extension| {
+            std::fs::create_dir(dir.join("templates")).unwrap();
+            std::fs::write(
+                dir.join("templates").join("email.md"),
+                "Hello,\n\nRegards\n",
+            )
+            .unwrap();
+
+            let absolute = dir.join("templates").join("email.md");
+            for path in [
+                "templates/email.md",
+                "%CONFIG%/templates/email.md",
+                &absolute.to_string_lossy(),
+            ] {
+                assert_output(
+                    calculate(extension, &params(&[("path", string(path))])),
+                    "Hello,\n\nRegards",
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn changed_file_is_read_again() {
+        with_config_dir(|dir, extension| {
+            let file = dir.join("snippet.txt");
+            std::fs::write(&file, "first").unwrap();
+            assert_output(
+                calculate(extension, &params(&[("path", string("snippet.txt"))])),
+                "first",
+            );
+
+            std::fs::write(&file, "second version").unwrap();
+            assert_output(
+                calculate(extension, &params(&[("path", string("snippet.txt"))])),
+                "second version",
+            );
+        });
+    }
+
+    #[test]
+    fn line_ranges() {
+        with_config_dir(|dir, extension| {
+            std::fs::write(dir.join("lines.txt"), "1\n2\n3\n4\n5\n").unwrap();
+
+            for (range, expected) in [
+                (string("2"), "2"),
+                (Value::Number(Number::Integer(4)), "4"),
+                (string("2-4"), "2\n3\n4"),
+                (string("4-"), "4\n5"),
+                (string("-2"), "1\n2"),
+                (string("4-10"), "4\n5"),
+                (string("8-"), ""),
+            ] {
+                assert_output(
+                    calculate(
+                        extension,
+                        &params(&[("path", string("lines.txt")), ("lines", range)]),
+                    ),
+                    expected,
+                );
+            }
+
+            for range in ["0", "3-2", "a-b", "1-2-3"] {
+                assert!(matches!(
+                    calculate(
+                        extension,
+                        &params(&[("path", string("lines.txt")), ("lines", string(range))]),
+                    ),
+                    ExtensionResult::Error(_)
+                ));
+            }
+        });
+    }
+
+    #[test]
+    fn trim() {
+        with_config_dir(|dir, extension| {
+            std::fs::write(dir.join("spaces.txt"), "\n  text  \r\n\r\n").unwrap();
+
+            assert_output(
+                calculate(extension, &params(&[("path", string("spaces.txt"))])),
+                "\n  text  \r\n",
+            );
+            assert_output(
+                calculate(
+                    extension,
+                    &params(&[("path", string("spaces.txt")), ("trim", Value::Bool(true))]),
+                ),
+                "text",
+            );
+        });
+    }
+
+    #[test]
+    fn encodings() {
+        with_config_dir(|dir, extension| {
+            std::fs::write(dir.join("bom.txt"), b"\xEF\xBB\xBFcaf\xC3\xA9").unwrap();
+            std::fs::write(dir.join("latin1.txt"), b"caf\xE9").unwrap();
+            std::fs::write(dir.join("utf16le.txt"), b"\xFF\xFEc\0a\0f\0\xE9\0").unwrap();
+            std::fs::write(dir.join("utf16be.txt"), b"\0c\0a\0f\0\xE9").unwrap();
+
+            let read = |file: &str, encoding: Option<&str>| {
+                let mut params = params(&[("path", string(file))]);
+                if let Some(encoding) = encoding {
+                    params.insert("encoding".to_string(), string(encoding));
+                }
+                calculate(extension, &params)
+            };
+
+            assert_output(read("bom.txt", None), "café");
+            assert_output(read("latin1.txt", Some("latin1")), "café");
+            assert_output(read("latin1.txt", Some("utf-8-lossy")), "caf\u{FFFD}");
+            assert_output(read("utf16le.txt", None), "café");
+            assert_output(read("utf16be.txt", Some("UTF-16BE")), "café");
+
+            assert!(matches!(
+                read("latin1.txt", None),
+                ExtensionResult::Error(_)
+            ));
+            assert!(matches!(
+                read("bom.txt", Some("ebcdic")),
+                ExtensionResult::Error(_)
+            ));
+        });
+    }
+
+    #[test]
+    fn size_limit() {
+        with_config_dir(|dir, extension| {
+            std::fs::write(dir.join("big.txt"), "0123456789").unwrap();
+
+            assert!(matches!(
+                calculate(
+                    extension,
+                    &params(&[
+                        ("path", string("big.txt")),
+                        ("max_size", Value::Number(Number::Integer(5)))
+                    ]),
+                ),
+                ExtensionResult::Error(_)
+            ));
+            assert_output(
+                calculate(
+                    extension,
+                    &params(&[
+                        ("path", string("big.txt")),
+                        ("max_size", Value::Number(Number::Integer(10))),
+                    ]),
+                ),
+                "0123456789",
+            );
+        });
+    }
+
+    #[test]
+    fn invalid_paths_produce_error() {
+        with_config_dir(|_, extension| {
+            for params in [
+                params(&[]),
+                params(&[("path", string(""))]),
+                params(&[("path", string("missing.txt"))]),
+                params(&[("path", string("%CONFIG%"))]),
+            ] {
+                assert!(matches!(
+                    calculate(extension, &params),
+                    ExtensionResult::Error(_)
+                ));
+            }
+        });
+    }
+
+    #[test]
+    fn resolve_path_normalizes_parent_components() {
+        let dir = TempDir::new("espanso-file").unwrap();
+        let config_dir = dir.path().join("config");
+        std::fs::create_dir(&config_dir).unwrap();
+        let base_dir = dunce::canonicalize(dir.path()).unwrap();
+
+        let resolve = |path: &str| resolve_path(path, &config_dir, dir.path(), dir.path());
+
+        // Missing files are normalized too, as their removal must be recognized
+        assert_eq!(resolve("../email.md"), base_dir.join("email.md"));
+        assert_eq!(resolve("./a/../../b/./c.md"), base_dir.join("b/c.md"));
+
+        std::fs::write(dir.path().join("email.md"), "").unwrap();
+        assert_eq!(resolve("../email.md"), base_dir.join("email.md"));
+        assert_eq!(
+            resolve("%CONFIG%/../email.md"),
+            resolve(&dir.path().join("email.md").to_string_lossy())
+        );
+    }
+}
@@ -0,0 +1,579 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Extension, ExtensionOutput, ExtensionResult, Number, Params, Value};
+use base64::{engine::general_purpose, Engine as _};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
+use sha2::{Digest, Sha256, Sha512};
+use thiserror::Error;
+
+const DEFAULT_STRING_LENGTH: usize = 16;
+const DEFAULT_PASSWORD_LENGTH: usize = 20;
+const MAX_STRING_LENGTH: usize = 4096;
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.<>?";
+const CROCKFORD_BASE32: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+pub struct GeneratorExtension {}
+
+#[allow(clippy::new_without_default)]
+impl GeneratorExtension {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Extension for GeneratorExtension {
+    fn name(&self) -> &'static str {
+        "generator"
+    }
+
+    fn calculate(
+        &self,
+        _: &crate::Context,
+        _: &crate::Scope,
+        params: &Params,
+    ) -> crate::ExtensionResult {
+        match generate(params) {
+            Ok(output) => ExtensionResult::Success(ExtensionOutput::Single(output)),
+            Err(err) => ExtensionResult::Error(err.into()),
+        }
+    }
+}
+
+fn generate(params: &Params) -> Result<String, GeneratorExtensionError> {
+    let mode = match params.get("mode") {
+        Some(Value::String(mode)) => mode.as_str(),
+        Some(_) => return Err(GeneratorExtensionError::InvalidParameter("mode")),
+        None => return Err(GeneratorExtensionError::MissingParameter("mode")),
+    };
+
+    // A fixed seed makes the random output deterministic, which is useful for tests
+    let mut rng: Box<dyn RngCore> = match params.get("seed") {
+        None => Box::new(rand::thread_rng()),
+        Some(Value::Number(Number::Integer(seed))) => {
+            Box::new(StdRng::seed_from_u64(seed.unsigned_abs()))
+        }
+        Some(Value::String(seed)) => {
+            Box::new(StdRng::from_seed(Sha256::digest(seed.as_bytes()).into()))
+        }
+        Some(_) => return Err(GeneratorExtensionError::InvalidParameter("seed")),
+    };
+
+    match mode {
+        "uuid" => match get_integer(params, "version")?.unwrap_or(4) {
+            4 => Ok(uuid_v4(&mut *rng)),
+            7 => Ok(uuid_v7(&mut *rng, get_timestamp(params)?)),
+            _ => Err(GeneratorExtensionError::InvalidParameter("version")),
+        },
+        "ulid" => Ok(ulid(&mut *rng, get_timestamp(params)?)),
+        "string" => {
+            let length = get_length(params, DEFAULT_STRING_LENGTH)?;
+            let alphabet: Vec<char> = match params.get("chars") {
+                Some(Value::String(chars)) if !chars.is_empty() => chars.chars().collect(),
+                Some(_) => return Err(GeneratorExtensionError::InvalidParameter("chars")),
+                None => get_alphabet(params)?.chars().collect(),
+            };
+            Ok((0..length)
+                .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                .collect())
+        }
+        "password" => {
+            let length = get_length(params, DEFAULT_PASSWORD_LENGTH)?;
+            let symbols = params
+                .get("symbols")
+                .and_then(|v| v.as_bool())
+                .copied()
+                .unwrap_or(true);
+            Ok(password(&mut *rng, length, symbols))
+        }
+        "hash" => {
+            let input = get_input(params)?;
+            let digest = match params.get("algorithm") {
+                None => Sha256::digest(input.as_bytes()).to_vec(),
+                Some(Value::String(algorithm)) => match algorithm.to_lowercase().as_str() {
+                    "sha256" => Sha256::digest(input.as_bytes()).to_vec(),
+                    "sha512" => Sha512::digest(input.as_bytes()).to_vec(),
+                    "md5" => md5::Md5::digest(input.as_bytes()).to_vec(),
+                    _ => return Err(GeneratorExtensionError::InvalidAlgorithm(algorithm.clone())),
+                },
+                Some(_) => return Err(GeneratorExtensionError::InvalidParameter("algorithm")),
+            };
+            match params.get("output") {
+                None => Ok(hex::encode(digest)),
+                Some(Value::String(output)) if output == "hex" => Ok(hex::encode(digest)),
+                Some(Value::String(output)) if output == "base64" => {
+                    Ok(general_purpose::STANDARD.encode(digest))
+                }
+                Some(_) => Err(GeneratorExtensionError::InvalidParameter("output")),
+            }
+        }
+        "base64" => {
+            let input = get_input(params)?;
+            let url_safe = params
+                .get("url_safe")
+                .and_then(|v| v.as_bool())
+                .copied()
+                .unwrap_or(false);
+            let engine = if url_safe {
+                general_purpose::URL_SAFE
+            } else {
+                general_purpose::STANDARD
+            };
+
+            let decode = params
+                .get("decode")
+                .and_then(|v| v.as_bool())
+                .copied()
+                .unwrap_or(false);
+            if decode {
+                let bytes = engine
+                    .decode(input.trim())
+                    .map_err(|_| GeneratorExtensionError::InvalidBase64)?;
+                String::from_utf8(bytes).map_err(|_| GeneratorExtensionError::InvalidBase64)
+            } else {
+                Ok(engine.encode(input))
+            }
+        }
+        _ => Err(GeneratorExtensionError::InvalidMode(mode.to_string())),
+    }
+}
+
+fn get_integer(
+    params: &Params,
+    name: &'static str,
+) -> Result<Option<i64>, GeneratorExtensionError> {
+    match params.get(name) {
+        None => Ok(None),
+        Some(Value::Number(Number::Integer(value))) => Ok(Some(*value)),
+        Some(Value::String(value)) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| GeneratorExtensionError::InvalidParameter(name)),
+        Some(_) => Err(GeneratorExtensionError::InvalidParameter(name)),
+    }
+}
+
+fn get_length(params: &Params, default: usize) -> Result<usize, GeneratorExtensionError> {
+    match get_integer(params, "length")? {
+        None => Ok(default),
+        Some(length) => usize::try_from(length)
+            .ok()
+            .filter(|length| (1..=MAX_STRING_LENGTH).contains(length))
+            .ok_or(GeneratorExtensionError::InvalidParameter("length")),
+    }
+}
+
+// The value to hash or encode, usually injected from other variables, like `{{form1.name}}`
+fn get_input(params: &Params) -> Result<String, GeneratorExtensionError> {
+    match params.get("input") {
+        Some(Value::String(input)) => Ok(input.clone()),
+        Some(Value::Number(Number::Integer(input))) => Ok(input.to_string()),
+        Some(Value::Number(Number::Float(input))) => Ok(input.to_string()),
+        Some(Value::Bool(input)) => Ok(input.to_string()),
+        Some(_) => Err(GeneratorExtensionError::InvalidParameter("input")),
+        None => Err(GeneratorExtensionError::MissingParameter("input")),
+    }
+}
+
+// Milliseconds since the unix epoch, either fixed by the `timestamp` parameter or the current time
+fn get_timestamp(params: &Params) -> Result<u64, GeneratorExtensionError> {
+    if let Some(timestamp) = get_integer(params, "timestamp")? {
+        return u64::try_from(timestamp)
+            .ok()
+            .filter(|timestamp| *timestamp < 1 << 48)
+            .ok_or(GeneratorExtensionError::InvalidParameter("timestamp"));
+    }
+
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX) & ((1 << 48) - 1))
+}
+
+fn get_alphabet(params: &Params) -> Result<String, GeneratorExtensionError> {
+    let alphabet = match params.get("alphabet") {
+        None => "alphanumeric",
+        Some(Value::String(alphabet)) => alphabet.as_str(),
+        Some(_) => return Err(GeneratorExtensionError::InvalidParameter("alphabet")),
+    };
+
+    Ok(match alphabet {
+        "alphanumeric" => format!("{LOWERCASE}{UPPERCASE}{DIGITS}"),
+        "alpha" => format!("{LOWERCASE}{UPPERCASE}"),
+        "lowercase" => LOWERCASE.to_string(),
+        "uppercase" => UPPERCASE.to_string(),
+        "numeric" => DIGITS.to_string(),
+        "hex" => "0123456789abcdef".to_string(),
+        "symbols" => format!("{LOWERCASE}{UPPERCASE}{DIGITS}{SYMBOLS}"),
+        _ => {
+            return Err(GeneratorExtensionError::InvalidAlphabet(
+                alphabet.to_string(),
+            ))
+        }
+    })
+}
+
+fn format_uuid(bytes: [u8; 16]) -> String {
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn set_uuid_version(bytes: &mut [u8; 16], version: u8) {
+    bytes[6] = (bytes[6] & 0x0F) | (version << 4);
+    // RFC 4122 variant
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+}
+
+fn uuid_v4(rng: &mut dyn RngCore) -> String {
+    let mut bytes = [0u8; 16];
+    rng.fill_bytes(&mut bytes);
+    set_uuid_version(&mut bytes, 4);
+    format_uuid(bytes)
+}
+
+fn uuid_v7(rng: &mut dyn RngCore, timestamp: u64) -> String {
+    let mut bytes = [0u8; 16];
+    rng.fill_bytes(&mut bytes);
+    bytes[..6].copy_from_slice(&timestamp.to_be_bytes()[2..]);
+    set_uuid_version(&mut bytes, 7);
+    format_uuid(bytes)
+}
+
+// 48 bits of timestamp followed by 80 random bits, encoded in Crockford's base32
+fn ulid(rng: &mut dyn RngCore, timestamp: u64) -> String {
+    let mut random = [0u8; 16];
+    rng.fill_bytes(&mut random[6..]);
+    let value = (u128::from(timestamp) << 80) | u128::from_be_bytes(random);
+
+    (0..26)
+        .map(|index| {
+            let digit = (value >> (125 - 5 * index)) & 0x1F;
+            char::from(CROCKFORD_BASE32[usize::try_from(digit).unwrap_or_default()])
+        })
+        .collect()
+}
+
+// Passwords contain at least one character of each class, when the length allows it
+fn password(rng: &mut dyn RngCore, length: usize, symbols: bool) -> String {
+    let mut classes = vec![LOWERCASE, UPPERCASE, DIGITS];
+    if symbols {
+        classes.push(SYMBOLS);
+    }
+    let all: Vec<char> = classes.concat().chars().collect();
+
+    let mut chars: Vec<char> = classes
+        .iter()
+        .take(length)
+        .filter_map(|class| class.chars().collect::<Vec<_>>().choose(rng).copied())
+        .collect();
+    while chars.len() < length {
+        chars.push(all[rng.gen_range(0..all.len())]);
+    }
+    chars.shuffle(rng);
+
+    chars.into_iter().collect()
+}
+
+#[derive(Error, Debug)]
+pub enum GeneratorExtensionError {
+    #[error("missing '{0}' parameter")]
+    MissingParameter(&'static str),
+
+    #[error("invalid '{0}' parameter")]
+    InvalidParameter(&'static str),
+
+    #[error("unknown mode '{0}', expected one of: uuid, ulid, string, password, hash, base64")]
+    InvalidMode(String),
+
+    #[error("unknown alphabet '{0}', expected one of: alphanumeric, alpha, lowercase, uppercase, numeric, hex, symbols")]
+    InvalidAlphabet(String),
+
+    #[error("unknown hash algorithm '{0}', expected one of: sha256, sha512, md5")]
+    InvalidAlgorithm(String),
+
+    #[error("the input is not valid base64 encoded text")]
+    InvalidBase64,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn params(values: &[(&str, Value)]) -> Params {
+        values
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), value.clone()))
+            .collect()
+    }
+
+    fn string(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    fn integer(value: i64) -> Value {
+        Value::Number(Number::Integer(value))
+    }
+
+    fn generate(values: &[(&str, Value)]) -> String {
+        match GeneratorExtension::new().calculate(
+            &crate::Context::default(),
+            &HashMap::default(),
+            &params(values),
+        ) {
+            ExtensionResult::Success(ExtensionOutput::Single(output)) => output,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    fn is_error(values: &[(&str, Value)]) -> bool {
+        matches!(
+            GeneratorExtension::new().calculate(
+                &crate::Context::default(),
+                &HashMap::default(),
+                &params(values),
+            ),
+            ExtensionResult::Error(_)
+        )
+    }
+
+    fn assert_uuid(uuid: &str, version: char) {
+        let parts: Vec<&str> = uuid.split('-').collect();
+        assert_eq!(
+            parts.iter().map(|part| part.len()).collect::<Vec<_>>(),
+            vec![8, 4, 4, 4, 12]
+        );
+        assert!(uuid.chars().all(|c| c == '-' || c.is_ascii_hexdigit()));
+        assert_eq!(parts[2].chars().next().unwrap(), version);
+        assert!("89ab".contains(parts[3].chars().next().unwrap()));
+    }
+
+    #[test]
+    fn uuid_v4_works_correctly() {
+        let first = generate(&[("mode", string("uuid"))]);
+        let second = generate(&[("mode", string("uuid"))]);
+        assert_uuid(&first, '4');
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn uuid_v7_works_correctly() {
+        let uuid = generate(&[
+            ("mode", string("uuid")),
+            ("version", integer(7)),
+            ("timestamp", integer(0x0123_4567_89AB)),
+        ]);
+        assert_uuid(&uuid, '7');
+        assert!(uuid.starts_with("01234567-89ab-7"));
+    }
+
+    #[test]
+    fn ulid_works_correctly() {
+        let ulid = generate(&[
+            ("mode", string("ulid")),
+            ("timestamp", integer(1_469_918_176_385)),
+        ]);
+        assert_eq!(ulid.len(), 26);
+        assert!(ulid.starts_with("01ARYZ6S41"));
+        assert!(ulid.bytes().all(|c| CROCKFORD_BASE32.contains(&c)));
+    }
+
+    #[test]
+    fn seed_makes_output_deterministic() {
+        for mode in ["uuid", "ulid", "string", "password"] {
+            let generate_with_seed = |seed: Value| {
+                generate(&[
+                    ("mode", string(mode)),
+                    ("seed", seed),
+                    ("timestamp", integer(1000)),
+                ])
+            };
+
+            assert_eq!(
+                generate_with_seed(integer(42)),
+                generate_with_seed(integer(42))
+            );
+            assert_eq!(
+                generate_with_seed(string("fixture")),
+                generate_with_seed(string("fixture"))
+            );
+            assert_ne!(
+                generate_with_seed(integer(42)),
+                generate_with_seed(integer(43))
+            );
+        }
+    }
+
+    #[test]
+    fn random_strings() {
+        let value = generate(&[("mode", string("string"))]);
+        assert_eq!(value.len(), DEFAULT_STRING_LENGTH);
+        assert!(value.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        let value = generate(&[
+            ("mode", string("string")),
+            ("length", integer(64)),
+            ("alphabet", string("hex")),
+        ]);
+        assert_eq!(value.len(), 64);
+        assert!(value.chars().all(|c| "0123456789abcdef".contains(c)));
+
+        let value = generate(&[
+            ("mode", string("string")),
+            ("length", string("10")),
+            ("chars", string("ab")),
+        ]);
+        assert_eq!(value.len(), 10);
+        assert!(value.chars().all(|c| c == 'a' || c == 'b'));
+    }
+
+    #[test]
+    fn passwords_contain_all_classes() {
+        for seed in 0..20 {
+            let value = generate(&[("mode", string("password")), ("seed", integer(seed))]);
+            assert_eq!(value.len(), DEFAULT_PASSWORD_LENGTH);
+            assert!(value.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(value.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(value.chars().any(|c| c.is_ascii_digit()));
+            assert!(value.chars().any(|c| SYMBOLS.contains(c)));
+        }
+
+        let value = generate(&[
+            ("mode", string("password")),
+            ("length", integer(8)),
+            ("symbols", Value::Bool(false)),
+        ]);
+        assert_eq!(value.len(), 8);
+        assert!(value.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        let value = generate(&[("mode", string("password")), ("length", integer(2))]);
+        assert_eq!(value.len(), 2);
+    }
+
+    #[test]
+    fn hashes() {
+        assert_eq!(
+            generate(&[("mode", string("hash")), ("input", string("abc"))]),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            generate(&[
+                ("mode", string("hash")),
+                ("input", string("abc")),
+                ("algorithm", string("MD5"))
+            ]),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert!(generate(&[
+            ("mode", string("hash")),
+            ("input", string("abc")),
+            ("algorithm", string("sha512"))
+        ])
+        .starts_with("ddaf35a193617aba"));
+        assert_eq!(
+            generate(&[
+                ("mode", string("hash")),
+                ("input", string("abc")),
+                ("algorithm", string("md5")),
+                ("output", string("base64"))
+            ]),
+            "kAFQmDzST7DWlj99KOF/cg=="
+        );
+        assert_eq!(
+            generate(&[("mode", string("hash")), ("input", integer(1))]),
+            "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b"
+        );
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(
+            generate(&[("mode", string("base64")), ("input", string("hello?>"))]),
+            "aGVsbG8/Pg=="
+        );
+        assert_eq!(
+            generate(&[
+                ("mode", string("base64")),
+                ("input", string("hello?>")),
+                ("url_safe", Value::Bool(true))
+            ]),
+            "aGVsbG8_Pg=="
+        );
+        assert_eq!(
+            generate(&[
+                ("mode", string("base64")),
+                ("input", string("aGVsbG8/Pg==")),
+                ("decode", Value::Bool(true))
+            ]),
+            "hello?>"
+        );
+    }
+
+    #[test]
+    fn invalid_params_produce_error() {
+        assert!(is_error(&[]));
+        assert!(is_error(&[("mode", string("unknown"))]));
+        assert!(is_error(&[
+            ("mode", string("uuid")),
+            ("version", integer(5))
+        ]));
+        assert!(is_error(&[
+            ("mode", string("string")),
+            ("length", integer(0))
+        ]));
+        assert!(is_error(&[
+            ("mode", string("string")),
+            ("alphabet", string("emoji"))
+        ]));
+        assert!(is_error(&[
+            ("mode", string("string")),
+            ("chars", string(""))
+        ]));
+        assert!(is_error(&[("mode", string("hash"))]));
+        assert!(is_error(&[
+            ("mode", string("hash")),
+            ("input", string("abc")),
+            ("algorithm", string("crc32"))
+        ]));
+        assert!(is_error(&[
+            ("mode", string("base64")),
+            ("input", string("not base64!")),
+            ("decode", Value::Bool(true))
+        ]));
+        assert!(is_error(&[
+            ("mode", string("ulid")),
+            ("timestamp", integer(-1))
+        ]));
+    }
+}
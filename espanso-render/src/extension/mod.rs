@@ -17,13 +17,20 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod calc;
+mod calendar;
 pub mod choice;
 pub mod clipboard;
 pub mod date;
 pub mod echo;
+pub mod env;
 mod exec_util;
+pub mod file;
 pub mod form;
+pub mod generator;
 pub mod random;
+pub mod rhai;
 pub mod script;
 pub mod shell;
+pub mod state;
 mod util;
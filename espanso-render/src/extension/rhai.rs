@@ -0,0 +1,443 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use ::rhai::{
+    module_resolvers::{DummyModuleResolver, FileModuleResolver},
+    Dynamic, Engine, EvalAltResult, Map, Scope,
+};
+use log::{debug, info};
+use thiserror::Error;
+
+use crate::{Extension, ExtensionOutput, ExtensionResult, Number, Params, Value};
+
+const DEFAULT_TIMEOUT_MS: u64 = 1000;
+const DEFAULT_MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_STRING_SIZE: usize = 1024 * 1024;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+
+// Evaluate Rhai scripts inside espanso, without spawning external processes.
+// Scripts can't access the filesystem unless `allow_filesystem` is set, and
+// have no network access.
+pub struct RhaiExtension {
+    config_path: PathBuf,
+    home_path: PathBuf,
+    packages_path: PathBuf,
+}
+
+#[allow(clippy::new_without_default)]
+impl RhaiExtension {
+    pub fn new(config_path: &Path, home_path: &Path, packages_path: &Path) -> Self {
+        Self {
+            config_path: config_path.to_owned(),
+            home_path: home_path.to_owned(),
+            packages_path: packages_path.to_owned(),
+        }
+    }
+
+    fn create_engine(
+        &self,
+        timeout: Duration,
+        max_operations: u64,
+        allow_filesystem: bool,
+    ) -> Engine {
+        let mut engine = Engine::new();
+
+        engine
+            .set_max_operations(max_operations)
+            .set_max_call_levels(MAX_CALL_LEVELS)
+            .set_max_string_size(MAX_STRING_SIZE)
+            .set_max_array_size(MAX_COLLECTION_SIZE)
+            .set_max_map_size(MAX_COLLECTION_SIZE);
+
+        let start = Instant::now();
+        engine.on_progress(move |_| {
+            if start.elapsed() > timeout {
+                Some(Dynamic::UNIT)
+            } else {
+                None
+            }
+        });
+
+        engine.on_print(|text| info!("rhai script> {text}"));
+        engine.on_debug(|text, _, position| debug!("rhai script ({position})> {text}"));
+
+        if allow_filesystem {
+            engine.set_module_resolver(FileModuleResolver::new_with_path(&self.config_path));
+
+            let (config_path, home_path, packages_path) = (
+                self.config_path.clone(),
+                self.home_path.clone(),
+                self.packages_path.clone(),
+            );
+            let resolve = move |path: &str| {
+                super::file::resolve_path(path, &config_path, &home_path, &packages_path)
+            };
+
+            let resolve_read = resolve.clone();
+            engine.register_fn(
+                "read_file",
+                move |path: &str| -> Result<String, Box<EvalAltResult>> {
+                    std::fs::read_to_string(resolve_read(path))
+                        .map_err(|err| format!("unable to read file '{path}': {err}").into())
+                },
+            );
+            let resolve_write = resolve.clone();
+            engine.register_fn(
+                "write_file",
+                move |path: &str, content: &str| -> Result<(), Box<EvalAltResult>> {
+                    std::fs::write(resolve_write(path), content)
+                        .map_err(|err| format!("unable to write file '{path}': {err}").into())
+                },
+            );
+            engine.register_fn("file_exists", move |path: &str| resolve(path).exists());
+        } else {
+            // By default, `import` statements would load scripts from the filesystem
+            engine.set_module_resolver(DummyModuleResolver::new());
+        }
+
+        engine
+    }
+}
+
+impl Extension for RhaiExtension {
+    fn name(&self) -> &'static str {
+        "rhai"
+    }
+
+    fn as_concurrent(&self) -> Option<&(dyn Extension + Sync)> {
+        Some(self)
+    }
+
+    fn reads_scope(&self) -> bool {
+        true
+    }
+
+    fn calculate(
+        &self,
+        _: &crate::Context,
+        scope: &crate::Scope,
+        params: &Params,
+    ) -> crate::ExtensionResult {
+        let Some(Value::String(script)) = params.get("script") else {
+            return ExtensionResult::Error(RhaiExtensionError::MissingScriptParameter.into());
+        };
+
+        let timeout_ms = match get_limit(params, "timeout_ms", DEFAULT_TIMEOUT_MS) {
+            Ok(timeout_ms) => timeout_ms,
+            Err(err) => return ExtensionResult::Error(err.into()),
+        };
+        let max_operations = match get_limit(params, "max_operations", DEFAULT_MAX_OPERATIONS) {
+            Ok(max_operations) => max_operations,
+            Err(err) => return ExtensionResult::Error(err.into()),
+        };
+        let allow_filesystem = params
+            .get("allow_filesystem")
+            .and_then(|v| v.as_bool())
+            .copied()
+            .unwrap_or(false);
+
+        let engine = self.create_engine(
+            Duration::from_millis(timeout_ms),
+            max_operations,
+            allow_filesystem,
+        );
+
+        let mut rhai_scope = convert_scope(scope);
+        match engine.eval_with_scope::<Dynamic>(&mut rhai_scope, script) {
+            Ok(result) => ExtensionResult::Success(convert_result(result)),
+            Err(err) => {
+                let err = if let EvalAltResult::ErrorTerminated(..) = *err {
+                    RhaiExtensionError::Timeout(timeout_ms)
+                } else {
+                    RhaiExtensionError::EvaluationFailed(err.to_string())
+                };
+                ExtensionResult::Error(err.into())
+            }
+        }
+    }
+}
+
+fn get_limit(params: &Params, name: &'static str, default: u64) -> Result<u64, RhaiExtensionError> {
+    let value = match params.get(name) {
+        None => return Ok(default),
+        Some(Value::Number(Number::Integer(value))) => u64::try_from(*value).ok(),
+        Some(Value::String(value)) => value.trim().parse().ok(),
+        Some(_) => None,
+    };
+    value
+        .filter(|value| *value > 0)
+        .ok_or(RhaiExtensionError::InvalidLimit(name))
+}
+
+// Expose the previous variables both directly, like `name`, and through the
+// `vars` map, like `vars["name"]`. Variables with multiple values become maps.
+fn convert_scope(scope: &crate::Scope) -> Scope<'static> {
+    let mut rhai_scope = Scope::new();
+    let mut vars = Map::new();
+
+    for (name, output) in scope {
+        let value = match output {
+            ExtensionOutput::Single(value) => Dynamic::from(value.clone()),
+            ExtensionOutput::Multiple(values) => Dynamic::from_map(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.into(), Dynamic::from(value.clone())))
+                    .collect(),
+            ),
+        };
+        rhai_scope.push_constant_dynamic(*name, value.clone());
+        vars.insert((*name).into(), value);
+    }
+
+    rhai_scope.push_constant("vars", vars);
+    rhai_scope
+}
+
+fn convert_result(result: Dynamic) -> ExtensionOutput {
+    if result.is_unit() {
+        return ExtensionOutput::Single(String::new());
+    }
+
+    match result.try_cast_result::<Map>() {
+        Ok(map) => ExtensionOutput::Multiple(
+            map.into_iter()
+                .map(|(key, value)| (key.to_string(), convert_value(value)))
+                .collect::<HashMap<_, _>>(),
+        ),
+        Err(result) => ExtensionOutput::Single(convert_value(result)),
+    }
+}
+
+fn convert_value(value: Dynamic) -> String {
+    if value.is_unit() {
+        String::new()
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum RhaiExtensionError {
+    #[error("missing 'script' parameter")]
+    MissingScriptParameter,
+
+    #[error("invalid '{0}' parameter, expected a positive number")]
+    InvalidLimit(&'static str),
+
+    #[error("script timed out after {0}ms")]
+    Timeout(u64),
+
+    #[error("script evaluation failed: {0}")]
+    EvaluationFailed(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scope;
+
+    fn get_extension() -> RhaiExtension {
+        RhaiExtension::new(&PathBuf::new(), &PathBuf::new(), &PathBuf::new())
+    }
+
+    fn params(script: &str, extra: Vec<(&str, Value)>) -> Params {
+        let mut params = vec![("script".to_string(), Value::String(script.to_string()))]
+            .into_iter()
+            .collect::<Params>();
+        params.extend(
+            extra
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value)),
+        );
+        params
+    }
+
+    #[test]
+    fn single_output() {
+        let extension = get_extension();
+        let mut scope = Scope::new();
+        scope.insert("name", ExtensionOutput::Single("John Snow".to_string()));
+
+        assert_eq!(
+            extension
+                .calculate(
+                    &crate::Context::default(),
+                    &scope,
+                    &params(
+                        r#"name.split(" ")[1].to_upper() + " " + vars["name"].len"#,
+                        vec![]
+                    )
+                )
+                .into_success()
+                .unwrap(),
+            ExtensionOutput::Single("SNOW 9".to_string())
+        );
+    }
+
+    #[test]
+    fn multiple_output_and_map_variables() {
+        let extension = get_extension();
+        let mut values = HashMap::new();
+        values.insert("first".to_string(), "John".to_string());
+        let mut scope = Scope::new();
+        scope.insert("form1", ExtensionOutput::Multiple(values));
+
+        let mut expected = HashMap::new();
+        expected.insert("greeting".to_string(), "Hi John".to_string());
+        expected.insert("count".to_string(), "2".to_string());
+
+        assert_eq!(
+            extension
+                .calculate(
+                    &crate::Context::default(),
+                    &scope,
+                    &params(
+                        r#"#{ greeting: "Hi " + form1.first, count: 1 + 1 }"#,
+                        vec![]
+                    )
+                )
+                .into_success()
+                .unwrap(),
+            ExtensionOutput::Multiple(expected)
+        );
+    }
+
+    #[test]
+    fn unit_result_is_empty() {
+        let extension = get_extension();
+
+        assert_eq!(
+            extension
+                .calculate(
+                    &crate::Context::default(),
+                    &HashMap::default(),
+                    &params("let x = 1;", vec![])
+                )
+                .into_success()
+                .unwrap(),
+            ExtensionOutput::Single(String::new())
+        );
+    }
+
+    #[test]
+    fn limits_stop_endless_scripts() {
+        let extension = get_extension();
+
+        assert!(matches!(
+            extension.calculate(
+                &crate::Context::default(),
+                &HashMap::default(),
+                &params("loop {}", vec![])
+            ),
+            ExtensionResult::Error(_)
+        ));
+
+        let start = Instant::now();
+        assert!(matches!(
+            extension.calculate(
+                &crate::Context::default(),
+                &HashMap::default(),
+                &params(
+                    "loop {}",
+                    vec![
+                        ("timeout_ms", Value::Number(Number::Integer(50))),
+                        ("max_operations", Value::Number(Number::Integer(i64::MAX))),
+                    ]
+                )
+            ),
+            ExtensionResult::Error(_)
+        ));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn filesystem_is_denied_by_default() {
+        let dir = tempdir::TempDir::new("espanso-rhai").unwrap();
+        std::fs::write(dir.path().join("file.txt"), "content").unwrap();
+        std::fs::write(dir.path().join("module.rhai"), "export const VALUE = 42;").unwrap();
+        let extension = RhaiExtension::new(dir.path(), &PathBuf::new(), &PathBuf::new());
+
+        for script in [
+            r#"read_file("file.txt")"#,
+            r#"import "module" as m; m::VALUE"#,
+        ] {
+            assert!(matches!(
+                extension.calculate(
+                    &crate::Context::default(),
+                    &HashMap::default(),
+                    &params(script, vec![])
+                ),
+                ExtensionResult::Error(_)
+            ));
+        }
+
+        let allow = || vec![("allow_filesystem", Value::Bool(true))];
+        assert_eq!(
+            extension
+                .calculate(
+                    &crate::Context::default(),
+                    &HashMap::default(),
+                    &params(r#"read_file("%CONFIG%/file.txt")"#, allow())
+                )
+                .into_success()
+                .unwrap(),
+            ExtensionOutput::Single("content".to_string())
+        );
+        assert_eq!(
+            extension
+                .calculate(
+                    &crate::Context::default(),
+                    &HashMap::default(),
+                    &params(r#"import "module" as m; m::VALUE"#, allow())
+                )
+                .into_success()
+                .unwrap(),
+            ExtensionOutput::Single("42".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_script() {
+        let extension = get_extension();
+
+        assert!(matches!(
+            extension.calculate(
+                &crate::Context::default(),
+                &HashMap::default(),
+                &params("let = ;", vec![])
+            ),
+            ExtensionResult::Error(_)
+        ));
+        assert!(matches!(
+            extension.calculate(
+                &crate::Context::default(),
+                &HashMap::default(),
+                &Params::new()
+            ),
+            ExtensionResult::Error(_)
+        ));
+    }
+}
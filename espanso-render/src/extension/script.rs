@@ -22,6 +22,7 @@ use std::{
     process::Command,
 };
 
+use super::exec_util::{ExecOptions, OnError};
 use crate::{Extension, ExtensionOutput, ExtensionResult, Params, Value};
 use log::{info, warn};
 use thiserror::Error;
@@ -48,6 +49,14 @@ impl Extension for ScriptExtension {
         "script"
     }
 
+    fn as_concurrent(&self) -> Option<&(dyn Extension + Sync)> {
+        Some(self)
+    }
+
+    fn reads_scope(&self) -> bool {
+        true
+    }
+
     fn calculate(
         &self,
         _: &crate::Context,
@@ -61,19 +70,28 @@ impl Extension for ScriptExtension {
                 .cloned()
                 .collect();
 
+            let options = match ExecOptions::from_params(params, |path| {
+                super::file::resolve_path(
+                    path,
+                    &self.config_path,
+                    &self.home_path,
+                    &self.packages_path,
+                )
+            }) {
+                Ok(options) => options,
+                Err(err) => return ExtensionResult::Error(err.into()),
+            };
+
             // Replace %HOME% with current user home directory to
             // create cross-platform paths. See issue #265
             // Also replace %CONFIG% and %PACKAGES% path. See issue #380
             for arg in &mut args {
-                if arg.contains("%HOME%") {
-                    *arg = arg.replace("%HOME%", &self.home_path.to_string_lossy());
-                }
-                if arg.contains("%CONFIG%") {
-                    *arg = arg.replace("%CONFIG%", &self.config_path.to_string_lossy());
-                }
-                if arg.contains("%PACKAGES%") {
-                    *arg = arg.replace("%PACKAGES%", &self.packages_path.to_string_lossy());
-                }
+                *arg = super::util::replace_path_placeholders(
+                    arg,
+                    &self.home_path,
+                    &self.config_path,
+                    &self.packages_path,
+                );
 
                 // On Windows, correct paths separators
                 if cfg!(target_os = "windows") {
@@ -93,16 +111,12 @@ impl Extension for ScriptExtension {
             // Set the OS-specific flags
             super::util::set_command_flags(&mut command);
 
-            let output = if args.len() > 1 {
-                command.args(&args[1..]).output()
-            } else {
-                command.output()
-            };
+            command.args(&args[1..]);
 
-            match output {
+            match super::exec_util::execute(&mut command, &options) {
                 Ok(output) => {
-                    let output_str = String::from_utf8_lossy(&output.stdout);
-                    let error_str = String::from_utf8_lossy(&output.stderr);
+                    let output_str = &output.stdout;
+                    let error_str = &output.stderr;
 
                     let debug = params
                         .get("debug")
@@ -112,7 +126,12 @@ impl Extension for ScriptExtension {
 
                     if debug {
                         info!("debug information for script> {args:?}");
-                        info!("exit status: '{}'", output.status);
+                        info!(
+                            "exit status: '{}'",
+                            output
+                                .status
+                                .map_or_else(|| "timed out".to_string(), |s| s.to_string())
+                        );
                         info!("stdout: '{output_str}'");
                         info!("stderr: '{error_str}'");
                         info!(
@@ -126,31 +145,31 @@ impl Extension for ScriptExtension {
                         .copied()
                         .unwrap_or(false);
 
-                    if !output.status.success() || !error_str.trim().is_empty() {
-                        warn!(
-                            "script command exited with code: {} and error: {}",
-                            output.status, error_str
-                        );
-
-                        if !ignore_error {
-                            return ExtensionResult::Error(
-                                ScriptExtensionError::ExecutionError(error_str.to_string()).into(),
+                    let failure = match output.status {
+                        Some(status) if status.success() && error_str.trim().is_empty() => None,
+                        Some(status) => {
+                            warn!(
+                                "script command exited with code: {status} and error: {error_str}"
                             );
+                            Some(ScriptExtensionError::ExecutionError(error_str.clone()))
+                        }
+                        None => {
+                            warn!("script command timed out: {args:?}");
+                            Some(ScriptExtensionError::Timeout(args[0].clone()))
                         }
-                    }
-
-                    let trim = params
-                        .get("trim")
-                        .and_then(|v| v.as_bool())
-                        .copied()
-                        .unwrap_or(true);
-
-                    let output = if trim {
-                        output_str.trim().to_owned()
-                    } else {
-                        output_str.to_string()
                     };
 
+                    // With 'ignore_error', the output is used regardless of the failure
+                    if let Some(err) = failure.filter(|_| !ignore_error) {
+                        let output = match options.on_error {
+                            OnError::Abort => return ExtensionResult::Error(err.into()),
+                            OnError::Empty => String::new(),
+                            OnError::Stderr => options.process_output(error_str),
+                        };
+                        return ExtensionResult::Success(ExtensionOutput::Single(output));
+                    }
+
+                    let output = options.process_output(output_str);
                     ExtensionResult::Success(ExtensionOutput::Single(output))
                 }
                 Err(error) => ExtensionResult::Error(
@@ -173,6 +192,9 @@ pub enum ScriptExtensionError {
 
     #[error("script reported error: '`{0}`'")]
     ExecutionError(String),
+
+    #[error("script timed out: '`{0}`'")]
+    Timeout(String),
 }
 
 #[cfg(test)]
@@ -335,4 +357,52 @@ mod tests {
             ExtensionOutput::Single(String::new())
         );
     }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn stdin_and_timeout() {
+        let extension = get_extension();
+
+        let param = vec![
+            (
+                "args".to_string(),
+                Value::Array(vec![Value::String("cat".to_string())]),
+            ),
+            (
+                "stdin".to_string(),
+                Value::String("hello world".to_string()),
+            ),
+            ("timeout_ms".to_string(), Value::String("5000".to_string())),
+        ]
+        .into_iter()
+        .collect::<Params>();
+        assert_eq!(
+            extension
+                .calculate(&crate::Context::default(), &HashMap::default(), &param)
+                .into_success()
+                .unwrap(),
+            ExtensionOutput::Single("hello world".to_string())
+        );
+
+        let param = vec![
+            (
+                "args".to_string(),
+                Value::Array(vec![
+                    Value::String("sleep".to_string()),
+                    Value::String("10".to_string()),
+                ]),
+            ),
+            ("timeout_ms".to_string(), Value::String("200".to_string())),
+            ("on_error".to_string(), Value::String("empty".to_string())),
+        ]
+        .into_iter()
+        .collect::<Params>();
+        assert_eq!(
+            extension
+                .calculate(&crate::Context::default(), &HashMap::default(), &param)
+                .into_success()
+                .unwrap(),
+            ExtensionOutput::Single(String::new())
+        );
+    }
 }
@@ -20,11 +20,11 @@
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
-    process::{Command, Output},
+    process::Command,
     sync::LazyLock,
 };
 
-use super::exec_util::{determine_default_macos_shell, MacShell};
+use super::exec_util::{determine_default_macos_shell, ExecOptions, ExecOutput, MacShell, OnError};
 use crate::{Extension, ExtensionOutput, ExtensionResult, Params, Value};
 use log::{debug, error, info};
 use thiserror::Error;
@@ -48,7 +48,8 @@ impl Shell {
         cmd: &str,
         vars: &HashMap<String, String>,
         override_path_on_macos: bool,
-    ) -> std::io::Result<Output> {
+        options: &ExecOptions,
+    ) -> std::io::Result<ExecOutput> {
         let mut is_wsl = false;
 
         let mut command = match self {
@@ -148,7 +149,7 @@ impl Shell {
             command.env("WSLENV", wsl_env);
         }
 
-        command.output()
+        super::exec_util::execute(&mut command, options)
     }
 
     fn from_string(shell: &str) -> Option<Self> {
@@ -192,13 +193,17 @@ impl Default for Shell {
 
 pub struct ShellExtension {
     config_path: PathBuf,
+    home_path: PathBuf,
+    packages_path: PathBuf,
 }
 
 #[allow(clippy::new_without_default)]
 impl ShellExtension {
-    pub fn new(config_path: &Path) -> Self {
+    pub fn new(config_path: &Path, home_path: &Path, packages_path: &Path) -> Self {
         Self {
             config_path: config_path.to_owned(),
+            home_path: home_path.to_owned(),
+            packages_path: packages_path.to_owned(),
         }
     }
 }
@@ -208,6 +213,14 @@ impl Extension for ShellExtension {
         "shell"
     }
 
+    fn as_concurrent(&self) -> Option<&(dyn Extension + Sync)> {
+        Some(self)
+    }
+
+    fn reads_scope(&self) -> bool {
+        true
+    }
+
     fn calculate(
         &self,
         _: &crate::Context,
@@ -227,6 +240,18 @@ impl Extension for ShellExtension {
                 Shell::default()
             };
 
+            let options = match ExecOptions::from_params(params, |path| {
+                super::file::resolve_path(
+                    path,
+                    &self.config_path,
+                    &self.home_path,
+                    &self.packages_path,
+                )
+            }) {
+                Ok(options) => options,
+                Err(err) => return ExtensionResult::Error(err.into()),
+            };
+
             let mut env_variables = super::util::convert_to_env_variables(scope);
             env_variables.insert(
                 "CONFIG".to_string(),
@@ -239,10 +264,10 @@ impl Extension for ShellExtension {
                 .copied()
                 .unwrap_or(true);
 
-            match shell.execute_cmd(cmd, &env_variables, macos_override_path) {
+            match shell.execute_cmd(cmd, &env_variables, macos_override_path, &options) {
                 Ok(output) => {
-                    let output_str = String::from_utf8_lossy(&output.stdout);
-                    let error_str = String::from_utf8_lossy(&output.stderr);
+                    let output_str = &output.stdout;
+                    let error_str = &output.stderr;
 
                     let debug = params
                         .get("debug")
@@ -252,7 +277,12 @@ impl Extension for ShellExtension {
 
                     if debug {
                         info!("debug information for command> {cmd}");
-                        info!("exit status: '{}'", output.status);
+                        info!(
+                            "exit status: '{}'",
+                            output
+                                .status
+                                .map_or_else(|| "timed out".to_string(), |s| s.to_string())
+                        );
                         info!("stdout: '{output_str}'");
                         info!("stderr: '{error_str}'");
                         info!(
@@ -260,29 +290,26 @@ impl Extension for ShellExtension {
                         );
                     }
 
-                    if !output.status.success() {
-                        error!(
-                            "shell command exited with code: {} and error: {}",
-                            output.status, error_str
-                        );
-
-                        return ExtensionResult::Error(
-                            ShellExtensionError::ExecutionError(error_str.to_string()).into(),
-                        );
+                    if !output.status.is_some_and(|status| status.success()) {
+                        let err = if let Some(status) = output.status {
+                            error!(
+                                "shell command exited with code: {status} and error: {error_str}"
+                            );
+                            ShellExtensionError::ExecutionError(error_str.clone())
+                        } else {
+                            error!("shell command timed out: {cmd}");
+                            ShellExtensionError::Timeout(cmd.clone())
+                        };
+
+                        let output = match options.on_error {
+                            OnError::Abort => return ExtensionResult::Error(err.into()),
+                            OnError::Empty => String::new(),
+                            OnError::Stderr => options.process_output(error_str),
+                        };
+                        return ExtensionResult::Success(ExtensionOutput::Single(output));
                     }
 
-                    let trim = params
-                        .get("trim")
-                        .and_then(|v| v.as_bool())
-                        .copied()
-                        .unwrap_or(true);
-
-                    let output = if trim {
-                        output_str.trim().to_owned()
-                    } else {
-                        output_str.to_string()
-                    };
-
+                    let output = options.process_output(output_str);
                     ExtensionResult::Success(ExtensionOutput::Single(output))
                 }
                 Err(error) => ExtensionResult::Error(
@@ -308,16 +335,23 @@ pub enum ShellExtensionError {
 
     #[error("command reported error: '`{0}`'")]
     ExecutionError(String),
+
+    #[error("command timed out: '`{0}`'")]
+    Timeout(String),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Scope;
+    use crate::{Number, Scope};
+
+    fn get_extension() -> ShellExtension {
+        ShellExtension::new(&PathBuf::new(), &PathBuf::new(), &PathBuf::new())
+    }
 
     #[test]
     fn shell_not_trimmed() {
-        let extension = ShellExtension::new(&PathBuf::new());
+        let extension = get_extension();
 
         let param = vec![
             (
@@ -349,7 +383,7 @@ mod tests {
 
     #[test]
     fn shell_trimmed() {
-        let extension = ShellExtension::new(&PathBuf::new());
+        let extension = get_extension();
 
         let param = vec![(
             "cmd".to_string(),
@@ -370,7 +404,7 @@ mod tests {
     #[test]
     #[cfg(not(target_os = "windows"))]
     fn pipes() {
-        let extension = ShellExtension::new(&PathBuf::new());
+        let extension = get_extension();
 
         let param = vec![(
             "cmd".to_string(),
@@ -389,7 +423,7 @@ mod tests {
 
     #[test]
     fn var_injection() {
-        let extension = ShellExtension::new(&PathBuf::new());
+        let extension = get_extension();
 
         let param = if cfg!(not(target_os = "windows")) {
             vec![(
@@ -422,7 +456,7 @@ mod tests {
 
     #[test]
     fn invalid_command() {
-        let extension = ShellExtension::new(&PathBuf::new());
+        let extension = get_extension();
 
         let param = vec![(
             "cmd".to_string(),
@@ -439,7 +473,7 @@ mod tests {
     #[test]
     #[cfg(not(target_os = "windows"))]
     fn exit_error() {
-        let extension = ShellExtension::new(&PathBuf::new());
+        let extension = get_extension();
 
         let param = vec![("cmd".to_string(), Value::String("exit 1".to_string()))]
             .into_iter()
@@ -449,4 +483,128 @@ mod tests {
             ExtensionResult::Error(_)
         ));
     }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn timeout_kills_process_group() {
+        let extension = get_extension();
+
+        let param = vec![
+            (
+                "cmd".to_string(),
+                Value::String("sleep 10 & sleep 10".to_string()),
+            ),
+            (
+                "timeout_ms".to_string(),
+                Value::Number(Number::Integer(200)),
+            ),
+        ]
+        .into_iter()
+        .collect::<Params>();
+        let start = std::time::Instant::now();
+        assert!(matches!(
+            extension.calculate(&crate::Context::default(), &HashMap::default(), &param),
+            ExtensionResult::Error(_)
+        ));
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn stdin_and_cwd() {
+        let dir = tempdir::TempDir::new("espanso-shell").unwrap();
+        std::fs::write(dir.path().join("file.txt"), "").unwrap();
+
+        let extension = get_extension();
+
+        let param = vec![
+            ("cmd".to_string(), Value::String("cat; ls".to_string())),
+            ("stdin".to_string(), Value::String("hello ".to_string())),
+            (
+                "cwd".to_string(),
+                Value::String(dir.path().to_string_lossy().to_string()),
+            ),
+        ]
+        .into_iter()
+        .collect::<Params>();
+        assert_eq!(
+            extension
+                .calculate(&crate::Context::default(), &HashMap::default(), &param)
+                .into_success()
+                .unwrap(),
+            ExtensionOutput::Single("hello file.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_cwd() {
+        let extension = get_extension();
+
+        let param = vec![
+            ("cmd".to_string(), Value::String("echo hello".to_string())),
+            (
+                "cwd".to_string(),
+                Value::String("/nonexistent/directory".to_string()),
+            ),
+        ]
+        .into_iter()
+        .collect::<Params>();
+        assert!(matches!(
+            extension.calculate(&crate::Context::default(), &HashMap::default(), &param),
+            ExtensionResult::Error(_)
+        ));
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn on_error_policy() {
+        let extension = get_extension();
+
+        let calculate = |on_error: &str| {
+            let param = vec![
+                (
+                    "cmd".to_string(),
+                    Value::String("echo output; echo failure >&2; exit 1".to_string()),
+                ),
+                ("on_error".to_string(), Value::String(on_error.to_string())),
+            ]
+            .into_iter()
+            .collect::<Params>();
+            extension.calculate(&crate::Context::default(), &HashMap::default(), &param)
+        };
+
+        assert!(matches!(calculate("abort"), ExtensionResult::Error(_)));
+        assert_eq!(
+            calculate("empty").into_success().unwrap(),
+            ExtensionOutput::Single(String::new())
+        );
+        assert_eq!(
+            calculate("stderr").into_success().unwrap(),
+            ExtensionOutput::Single("failure".to_string())
+        );
+        assert!(matches!(calculate("invalid"), ExtensionResult::Error(_)));
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn strip_ansi() {
+        let extension = get_extension();
+
+        let param = vec![
+            (
+                "cmd".to_string(),
+                Value::String(r"printf '\033[1;31mred\033[0m text\n'".to_string()),
+            ),
+            ("strip_ansi".to_string(), Value::Bool(true)),
+        ]
+        .into_iter()
+        .collect::<Params>();
+        assert_eq!(
+            extension
+                .calculate(&crate::Context::default(), &HashMap::default(), &param)
+                .into_success()
+                .unwrap(),
+            ExtensionOutput::Single("red text".to_string())
+        );
+    }
 }
@@ -0,0 +1,364 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{Extension, ExtensionOutput, ExtensionResult, Number, Params, Value};
+use anyhow::Result;
+use thiserror::Error;
+
+// Persistent storage for named values, shared across renders and worker restarts
+pub trait StateProvider {
+    fn get(&self, name: &str) -> Result<Option<String>>;
+    fn set(&self, name: &str, value: &str) -> Result<()>;
+
+    // Atomically add `step` to the given value, starting from `initial`
+    // if missing, and return the updated value
+    fn increment(&self, name: &str, step: i64, initial: i64) -> Result<i64>;
+}
+
+pub struct StateExtension<'a> {
+    provider: &'a dyn StateProvider,
+}
+
+#[allow(clippy::new_without_default)]
+impl<'a> StateExtension<'a> {
+    pub fn new(provider: &'a dyn StateProvider) -> Self {
+        Self { provider }
+    }
+
+    fn get(&self, name: &str, params: &Params) -> Result<String> {
+        if let Some(value) = self.provider.get(name)? {
+            return Ok(value);
+        }
+
+        match params.get("default") {
+            Some(default) => value_to_string(default)
+                .ok_or_else(|| StateExtensionError::InvalidParameter("default").into()),
+            None => Err(StateExtensionError::NotFound(name.to_string()).into()),
+        }
+    }
+
+    fn set(&self, name: &str, params: &Params) -> Result<String> {
+        let value = params
+            .get("value")
+            .ok_or(StateExtensionError::MissingParameter("value"))?;
+        let value = value_to_string(value).ok_or(StateExtensionError::InvalidParameter("value"))?;
+
+        self.provider.set(name, &value)?;
+        Ok(value)
+    }
+
+    fn increment(&self, name: &str, params: &Params, sign: i64) -> Result<String> {
+        let step = get_integer(params, "step")?.unwrap_or(1);
+        let initial = get_integer(params, "initial")?.unwrap_or(0);
+
+        let step = step
+            .checked_mul(sign)
+            .ok_or(StateExtensionError::InvalidParameter("step"))?;
+        let value = self.provider.increment(name, step, initial)?;
+        Ok(value.to_string())
+    }
+}
+
+impl Extension for StateExtension<'_> {
+    fn name(&self) -> &'static str {
+        "state"
+    }
+
+    fn calculate(
+        &self,
+        _: &crate::Context,
+        _: &crate::Scope,
+        params: &Params,
+    ) -> crate::ExtensionResult {
+        let Some(Value::String(name)) = params.get("name") else {
+            return ExtensionResult::Error(StateExtensionError::MissingParameter("name").into());
+        };
+
+        let action = params
+            .get("action")
+            .and_then(|action| action.as_string())
+            .map_or("get", String::as_str);
+
+        let result = match action {
+            "get" => self.get(name, params),
+            "set" => self.set(name, params),
+            "increment" => self.increment(name, params, 1),
+            "decrement" => self.increment(name, params, -1),
+            _ => Err(StateExtensionError::InvalidAction(action.to_string()).into()),
+        };
+
+        let value = match result.and_then(|value| format_value(value, params)) {
+            Ok(value) => value,
+            Err(err) => return ExtensionResult::Error(err),
+        };
+
+        ExtensionResult::Success(ExtensionOutput::Single(value))
+    }
+}
+
+// Numeric values are left-padded with zeros up to the `pad` width, so that
+// `7` becomes `0007` with `pad: 4`
+fn format_value(value: String, params: &Params) -> Result<String> {
+    let Some(width) = get_integer(params, "pad")? else {
+        return Ok(value);
+    };
+    let width = usize::try_from(width).map_err(|_| StateExtensionError::InvalidParameter("pad"))?;
+
+    match value.parse::<i64>() {
+        Ok(number) if number < 0 => Ok(format!("-{:0>width$}", number.unsigned_abs())),
+        Ok(number) => Ok(format!("{number:0>width$}")),
+        Err(_) => Ok(value),
+    }
+}
+
+fn get_integer(params: &Params, name: &'static str) -> Result<Option<i64>> {
+    match params.get(name) {
+        None => Ok(None),
+        Some(Value::Number(Number::Integer(number))) => Ok(Some(*number)),
+        Some(Value::String(string)) => string
+            .trim()
+            .parse::<i64>()
+            .map(Some)
+            .map_err(|_| StateExtensionError::InvalidParameter(name).into()),
+        Some(_) => Err(StateExtensionError::InvalidParameter(name).into()),
+    }
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.clone()),
+        Value::Number(Number::Integer(number)) => Some(number.to_string()),
+        Value::Number(Number::Float(number)) => Some(number.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum StateExtensionError {
+    #[error("missing '{0}' parameter")]
+    MissingParameter(&'static str),
+
+    #[error("invalid '{0}' parameter")]
+    InvalidParameter(&'static str),
+
+    #[error("unknown action '{0}', expected one of: get, set, increment, decrement")]
+    InvalidAction(String),
+
+    #[error("state value '{0}' is not set and no default was provided")]
+    NotFound(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MockStateProvider {
+        values: RefCell<HashMap<String, String>>,
+    }
+
+    impl StateProvider for MockStateProvider {
+        fn get(&self, name: &str) -> Result<Option<String>> {
+            Ok(self.values.borrow().get(name).cloned())
+        }
+
+        fn set(&self, name: &str, value: &str) -> Result<()> {
+            self.values
+                .borrow_mut()
+                .insert(name.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn increment(&self, name: &str, step: i64, initial: i64) -> Result<i64> {
+            let mut values = self.values.borrow_mut();
+            let current = match values.get(name) {
+                Some(value) => value.parse::<i64>()?,
+                None => initial,
+            };
+            let updated = current + step;
+            values.insert(name.to_string(), updated.to_string());
+            Ok(updated)
+        }
+    }
+
+    fn params(values: &[(&str, Value)]) -> Params {
+        values
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), value.clone()))
+            .collect()
+    }
+
+    fn string(value: &str) -> Value {
+        Value::String(value.to_string())
+    }
+
+    fn calculate(provider: &MockStateProvider, params: &Params) -> ExtensionResult {
+        StateExtension::new(provider).calculate(
+            &crate::Context::default(),
+            &HashMap::default(),
+            params,
+        )
+    }
+
+    fn assert_output(result: ExtensionResult, expected: &str) {
+        assert_eq!(
+            result.into_success().unwrap(),
+            ExtensionOutput::Single(expected.to_string())
+        );
+    }
+
+    #[test]
+    fn increment_works_correctly() {
+        let provider = MockStateProvider::default();
+        let params = params(&[("name", string("invoice")), ("action", string("increment"))]);
+
+        assert_output(calculate(&provider, &params), "1");
+        assert_output(calculate(&provider, &params), "2");
+        assert_eq!(provider.get("invoice").unwrap().unwrap(), "2");
+    }
+
+    #[test]
+    fn increment_with_step_and_initial() {
+        let provider = MockStateProvider::default();
+        let params = params(&[
+            ("name", string("ticket")),
+            ("action", string("increment")),
+            ("step", Value::Number(Number::Integer(10))),
+            ("initial", string("100")),
+        ]);
+
+        assert_output(calculate(&provider, &params), "110");
+        assert_output(calculate(&provider, &params), "120");
+    }
+
+    #[test]
+    fn decrement_works_correctly() {
+        let provider = MockStateProvider::default();
+        provider.set("remaining", "3").unwrap();
+        let params = params(&[
+            ("name", string("remaining")),
+            ("action", string("decrement")),
+        ]);
+
+        assert_output(calculate(&provider, &params), "2");
+    }
+
+    #[test]
+    fn set_and_get_work_correctly() {
+        let provider = MockStateProvider::default();
+
+        assert_output(
+            calculate(
+                &provider,
+                &params(&[
+                    ("name", string("project")),
+                    ("action", string("set")),
+                    ("value", string("espanso")),
+                ]),
+            ),
+            "espanso",
+        );
+        assert_output(
+            calculate(&provider, &params(&[("name", string("project"))])),
+            "espanso",
+        );
+    }
+
+    #[test]
+    fn get_missing_value() {
+        let provider = MockStateProvider::default();
+
+        assert!(matches!(
+            calculate(&provider, &params(&[("name", string("missing"))])),
+            ExtensionResult::Error(_)
+        ));
+        assert_output(
+            calculate(
+                &provider,
+                &params(&[
+                    ("name", string("missing")),
+                    ("default", Value::Number(Number::Integer(0))),
+                ]),
+            ),
+            "0",
+        );
+    }
+
+    #[test]
+    fn pad_formats_numeric_values() {
+        let provider = MockStateProvider::default();
+        provider.set("negative", "-7").unwrap();
+        provider.set("text", "abc").unwrap();
+
+        assert_output(
+            calculate(
+                &provider,
+                &params(&[
+                    ("name", string("invoice")),
+                    ("action", string("increment")),
+                    ("pad", Value::Number(Number::Integer(5))),
+                ]),
+            ),
+            "00001",
+        );
+        assert_output(
+            calculate(
+                &provider,
+                &params(&[("name", string("negative")), ("pad", string("3"))]),
+            ),
+            "-007",
+        );
+        assert_output(
+            calculate(
+                &provider,
+                &params(&[("name", string("text")), ("pad", string("5"))]),
+            ),
+            "abc",
+        );
+    }
+
+    #[test]
+    fn invalid_params_produce_error() {
+        let provider = MockStateProvider::default();
+
+        for params in [
+            params(&[]),
+            params(&[("name", string("a")), ("action", string("unknown"))]),
+            params(&[("name", string("a")), ("action", string("set"))]),
+            params(&[
+                ("name", string("a")),
+                ("action", string("increment")),
+                ("step", string("one")),
+            ]),
+            params(&[
+                ("name", string("a")),
+                ("action", string("decrement")),
+                ("step", Value::Number(Number::Integer(i64::MIN))),
+            ]),
+        ] {
+            assert!(matches!(
+                calculate(&provider, &params),
+                ExtensionResult::Error(_)
+            ));
+        }
+    }
+}
@@ -18,7 +18,7 @@
  */
 
 use crate::{ExtensionOutput, Scope};
-use std::{collections::HashMap, process::Command};
+use std::{collections::HashMap, path::Path, process::Command};
 
 pub fn convert_to_env_variables(scope: &Scope) -> HashMap<String, String> {
     let mut output = HashMap::new();
@@ -41,6 +41,27 @@ pub fn convert_to_env_variables(scope: &Scope) -> HashMap<String, String> {
     output
 }
 
+// Replace the %HOME%, %CONFIG% and %PACKAGES% placeholders with the
+// corresponding directories, to create cross-platform paths
+pub fn replace_path_placeholders(
+    value: &str,
+    home_path: &Path,
+    config_path: &Path,
+    packages_path: &Path,
+) -> String {
+    let mut value = value.to_string();
+    if value.contains("%HOME%") {
+        value = value.replace("%HOME%", &home_path.to_string_lossy());
+    }
+    if value.contains("%CONFIG%") {
+        value = value.replace("%CONFIG%", &config_path.to_string_lossy());
+    }
+    if value.contains("%PACKAGES%") {
+        value = value.replace("%PACKAGES%", &packages_path.to_string_lossy());
+    }
+    value
+}
+
 #[cfg(target_os = "windows")]
 pub fn set_command_flags(command: &mut Command) {
     use std::os::windows::process::CommandExt;
@@ -72,4 +93,17 @@ mod tests {
         assert_eq!(output.get("ESPANSO_FORM1_LASTNAME").unwrap(), "Snow");
         assert_eq!(output.get("ESPANSO_VAR1").unwrap(), "test");
     }
+
+    #[test]
+    fn test_replace_path_placeholders() {
+        assert_eq!(
+            replace_path_placeholders(
+                "%HOME%:%CONFIG%:%PACKAGES%:%OTHER%",
+                Path::new("home"),
+                Path::new("config"),
+                Path::new("packages"),
+            ),
+            "home:config:packages:%OTHER%"
+        );
+    }
 }
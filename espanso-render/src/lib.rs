@@ -75,6 +75,9 @@ pub struct Template {
     pub ids: Vec<String>,
     pub body: String,
     pub vars: Vec<Variable>,
+    // Evaluate the variables one after the other, in declaration order, instead of
+    // evaluating the independent ones concurrently
+    pub sequential_vars: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -120,6 +123,30 @@ pub enum Number {
 pub trait Extension {
     fn name(&self) -> &str;
     fn calculate(&self, context: &Context, scope: &Scope, params: &Params) -> ExtensionResult;
+
+    // Filters that can be applied to variables with the `{{var|filter}}` syntax
+    fn filters(&self) -> Vec<&dyn Filter> {
+        Vec::new()
+    }
+
+    // Extensions that can be safely evaluated from multiple threads return themselves,
+    // so that independent variables can be computed concurrently. Interactive extensions
+    // should keep the default, to be evaluated one at a time.
+    fn as_concurrent(&self) -> Option<&(dyn Extension + Sync)> {
+        None
+    }
+
+    // Extensions reading the previous variables without the `{{name}}` syntax, for
+    // example through the ESPANSO_NAME env variables, return true. Their variables
+    // are then evaluated after the previous local variables mentioned in their params.
+    fn reads_scope(&self) -> bool {
+        false
+    }
+}
+
+pub trait Filter {
+    fn name(&self) -> &str;
+    fn apply(&self, input: &str, args: &[String]) -> anyhow::Result<String>;
 }
 
 pub type Scope<'a> = HashMap<&'a str, ExtensionOutput>;
@@ -0,0 +1,516 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::LazyLock,
+};
+
+use anyhow::Result;
+use log::warn;
+use regex::Regex;
+
+use super::{filter::FilterRegistry, util::render_variables, RendererError};
+use crate::{ExtensionOutput, Scope};
+
+// Names of the variables available inside an `{{#each}}` block
+const ITEM_VAR: &str = "item";
+const LOOP_VAR: &str = "loop";
+
+static BLOCK_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\{\{\s*(#(?P<open>if|each)\s+(?P<name>\w+)(\.(?P<subname>\w+))?|(?P<else>else)|/(?P<close>if|each))\s*\}\}",
+    )
+    .unwrap()
+});
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Block<'a> {
+    Text(&'a str),
+    // A tag that isn't part of a block, rendered as is
+    Literal(&'a str),
+    If {
+        name: &'a str,
+        subname: Option<&'a str>,
+        then: Vec<Block<'a>>,
+        otherwise: Vec<Block<'a>>,
+    },
+    Each {
+        name: &'a str,
+        subname: Option<&'a str>,
+        body: Vec<Block<'a>>,
+    },
+}
+
+enum Tag<'a> {
+    Open {
+        kind: &'a str,
+        name: &'a str,
+        subname: Option<&'a str>,
+    },
+    Else,
+    Close(&'a str),
+}
+
+// A block being parsed, along with the nodes collected so far.
+// The root of the body has no tag.
+struct OpenBlock<'a> {
+    tag: Option<Tag<'a>>,
+    nodes: Vec<Block<'a>>,
+    otherwise: Option<Vec<Block<'a>>>,
+}
+
+pub fn has_blocks(body: &str) -> bool {
+    BLOCK_TAG_REGEX.is_match(body)
+}
+
+/// Parse the `{{#if}}` and `{{#each}}` blocks of the given body, keeping it
+/// literal if the blocks are invalid, as it might not be meant as a template.
+pub fn parse_or_literal(body: &str) -> Vec<Block<'_>> {
+    parse(body).unwrap_or_else(|err| {
+        warn!("unable to parse the blocks of the match, rendering them literally: {err}");
+        parse_literal(body)
+    })
+}
+
+fn parse_literal(body: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut last_end = 0;
+    for tag_match in BLOCK_TAG_REGEX.find_iter(body) {
+        if tag_match.start() > last_end {
+            blocks.push(Block::Text(&body[last_end..tag_match.start()]));
+        }
+        blocks.push(Block::Literal(tag_match.as_str()));
+        last_end = tag_match.end();
+    }
+    if last_end < body.len() {
+        blocks.push(Block::Text(&body[last_end..]));
+    }
+    blocks
+}
+
+/// Parse the `{{#if}}` and `{{#each}}` blocks of the given body.
+/// Tags written on a line of their own don't leave an empty line in the output,
+/// while `{{else}}` and closing tags without a matching block are kept literally.
+pub fn parse(body: &str) -> Result<Vec<Block<'_>>> {
+    let mut stack = vec![OpenBlock {
+        tag: None,
+        nodes: Vec::new(),
+        otherwise: None,
+    }];
+    let mut last_end = 0;
+
+    for caps in BLOCK_TAG_REGEX.captures_iter(body) {
+        let tag_match = caps.get(0).unwrap();
+
+        let tag = if let Some(kind) = caps.name("open") {
+            Tag::Open {
+                kind: kind.as_str(),
+                name: caps.name("name").unwrap().as_str(),
+                subname: caps.name("subname").map(|subname| subname.as_str()),
+            }
+        } else if caps.name("else").is_some() {
+            Tag::Else
+        } else {
+            Tag::Close(caps.name("close").unwrap().as_str())
+        };
+
+        let is_literal = match tag {
+            Tag::Open { .. } => false,
+            Tag::Else => !matches!(
+                stack.last(),
+                Some(OpenBlock {
+                    tag: Some(Tag::Open { kind: "if", .. }),
+                    otherwise: None,
+                    ..
+                })
+            ),
+            Tag::Close(kind) => !stack.iter().any(|open| {
+                matches!(open.tag, Some(Tag::Open { kind: open_kind, .. }) if open_kind == kind)
+            }),
+        };
+
+        let (start, end) = if is_literal {
+            (tag_match.start(), tag_match.end())
+        } else {
+            standalone_span(body, tag_match.start(), tag_match.end())
+        };
+        let current = stack.last_mut().unwrap();
+        let nodes = current.otherwise.as_mut().unwrap_or(&mut current.nodes);
+        if start > last_end {
+            nodes.push(Block::Text(&body[last_end..start]));
+        }
+        last_end = end;
+
+        if is_literal {
+            nodes.push(Block::Literal(tag_match.as_str()));
+            continue;
+        }
+
+        match tag {
+            Tag::Open { .. } => stack.push(OpenBlock {
+                tag: Some(tag),
+                nodes: Vec::new(),
+                otherwise: None,
+            }),
+            Tag::Else => current.otherwise = Some(Vec::new()),
+            Tag::Close(kind) => {
+                let Some(OpenBlock {
+                    tag:
+                        Some(Tag::Open {
+                            kind: open_kind,
+                            name,
+                            subname,
+                        }),
+                    nodes,
+                    otherwise,
+                }) = stack.pop()
+                else {
+                    unreachable!("the root block has no tag");
+                };
+                if open_kind != kind {
+                    return Err(invalid_block(&format!(
+                        "expected {{{{/{open_kind}}}}} but found {{{{/{kind}}}}}"
+                    )));
+                }
+
+                let block = if kind == "if" {
+                    Block::If {
+                        name,
+                        subname,
+                        then: nodes,
+                        otherwise: otherwise.unwrap_or_default(),
+                    }
+                } else {
+                    Block::Each {
+                        name,
+                        subname,
+                        body: nodes,
+                    }
+                };
+                let parent = stack.last_mut().unwrap();
+                parent
+                    .otherwise
+                    .as_mut()
+                    .unwrap_or(&mut parent.nodes)
+                    .push(block);
+            }
+        }
+    }
+
+    if let Some(OpenBlock {
+        tag: Some(Tag::Open { kind, .. }),
+        ..
+    }) = stack.last()
+    {
+        return Err(invalid_block(&format!("missing {{{{/{kind}}}}}")));
+    }
+
+    let mut root = stack.pop().unwrap();
+    if last_end < body.len() {
+        root.nodes.push(Block::Text(&body[last_end..]));
+    }
+    Ok(root.nodes)
+}
+
+fn invalid_block(message: &str) -> anyhow::Error {
+    RendererError::InvalidBlock(message.to_string()).into()
+}
+
+// If the tag is the only content of its line, extend its span to the whole line
+fn standalone_span(body: &str, start: usize, end: usize) -> (usize, usize) {
+    let line_start = body[..start].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = body[end..]
+        .find('\n')
+        .map_or(body.len(), |index| end + index + 1);
+
+    if body[line_start..start].trim().is_empty() && body[end..line_end].trim().is_empty() {
+        (line_start, line_end)
+    } else {
+        (start, end)
+    }
+}
+
+/// Collect the names of the variables referenced by the given blocks,
+/// excluding the ones defined by `{{#each}}` blocks.
+pub fn get_variable_names<'a>(blocks: &[Block<'a>], names: &mut HashSet<&'a str>) {
+    for block in blocks {
+        match block {
+            Block::Text(text) => names.extend(super::util::get_body_variable_names(text)),
+            Block::Literal(_) => {}
+            Block::If {
+                name,
+                then,
+                otherwise,
+                ..
+            } => {
+                names.insert(name);
+                get_variable_names(then, names);
+                get_variable_names(otherwise, names);
+            }
+            Block::Each { name, body, .. } => {
+                names.insert(name);
+                let mut body_names = HashSet::new();
+                get_variable_names(body, &mut body_names);
+                body_names.remove(ITEM_VAR);
+                body_names.remove(LOOP_VAR);
+                names.extend(body_names);
+            }
+        }
+    }
+}
+
+pub fn render<'a>(
+    blocks: &[Block<'a>],
+    scope: &mut Scope<'a>,
+    filters: &FilterRegistry,
+) -> Result<String> {
+    let mut output = String::new();
+
+    for block in blocks {
+        match block {
+            Block::Text(text) => output.push_str(&render_variables(text, scope, filters)?),
+            Block::Literal(tag) => output.push_str(tag),
+            Block::If {
+                name,
+                subname,
+                then,
+                otherwise,
+            } => {
+                let branch = if is_truthy(scope, name, *subname) {
+                    then
+                } else {
+                    otherwise
+                };
+                output.push_str(&render(branch, scope, filters)?);
+            }
+            Block::Each {
+                name,
+                subname,
+                body,
+            } => {
+                let items = get_items(scope, name, *subname);
+                let previous_item = scope.remove(ITEM_VAR);
+                let previous_loop = scope.remove(LOOP_VAR);
+
+                let result = render_items(body, items, scope, filters);
+
+                scope.remove(ITEM_VAR);
+                scope.remove(LOOP_VAR);
+                if let Some(previous_item) = previous_item {
+                    scope.insert(ITEM_VAR, previous_item);
+                }
+                if let Some(previous_loop) = previous_loop {
+                    scope.insert(LOOP_VAR, previous_loop);
+                }
+                output.push_str(&result?);
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+fn render_items<'a>(
+    body: &[Block<'a>],
+    items: Vec<(String, String)>,
+    scope: &mut Scope<'a>,
+    filters: &FilterRegistry,
+) -> Result<String> {
+    let mut output = String::new();
+    let count = items.len();
+
+    for (index, (key, value)) in items.into_iter().enumerate() {
+        scope.insert(ITEM_VAR, ExtensionOutput::Single(value));
+        scope.insert(
+            LOOP_VAR,
+            ExtensionOutput::Multiple(HashMap::from([
+                ("key".to_string(), key),
+                ("index".to_string(), (index + 1).to_string()),
+                ("first".to_string(), (index == 0).to_string()),
+                ("last".to_string(), (index + 1 == count).to_string()),
+            ])),
+        );
+        output.push_str(&render(body, scope, filters)?);
+    }
+
+    Ok(output)
+}
+
+// A value is falsy if it's missing, empty, "false" or "0"
+fn is_truthy(scope: &Scope, name: &str, subname: Option<&str>) -> bool {
+    let value = match (scope.get(name), subname) {
+        (Some(ExtensionOutput::Single(value)), None) => Some(value.as_str()),
+        (Some(ExtensionOutput::Multiple(values)), None) => return !values.is_empty(),
+        (Some(ExtensionOutput::Multiple(values)), Some(subname)) => {
+            values.get(subname).map(String::as_str)
+        }
+        _ => None,
+    };
+
+    value.is_some_and(|value| {
+        let value = value.trim();
+        !value.is_empty() && value != "false" && value != "0"
+    })
+}
+
+// Multi-value variables are iterated by key, while other values line by line
+fn get_items(scope: &Scope, name: &str, subname: Option<&str>) -> Vec<(String, String)> {
+    let value = match (scope.get(name), subname) {
+        (Some(ExtensionOutput::Multiple(values)), None) => {
+            let mut items: Vec<(String, String)> = values
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            items.sort();
+            return items;
+        }
+        (Some(ExtensionOutput::Single(value)), None) => value,
+        (Some(ExtensionOutput::Multiple(values)), Some(subname)) => match values.get(subname) {
+            Some(value) => value,
+            None => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+
+    value
+        .lines()
+        .enumerate()
+        .map(|(index, line)| ((index + 1).to_string(), line.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(body: &str) -> String {
+        match parse(body).unwrap_err().downcast::<RendererError>() {
+            Ok(RendererError::InvalidBlock(message)) => message,
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_without_blocks() {
+        assert_eq!(
+            parse("hello {{name}}").unwrap(),
+            vec![Block::Text("hello {{name}}")]
+        );
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_nested_blocks() {
+        assert_eq!(
+            parse("a{{#if form.cc}}b{{ #each list }}{{item}}{{/each}}{{else}}c{{/if}}d").unwrap(),
+            vec![
+                Block::Text("a"),
+                Block::If {
+                    name: "form",
+                    subname: Some("cc"),
+                    then: vec![
+                        Block::Text("b"),
+                        Block::Each {
+                            name: "list",
+                            subname: None,
+                            body: vec![Block::Text("{{item}}")],
+                        },
+                    ],
+                    otherwise: vec![Block::Text("c")],
+                },
+                Block::Text("d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_standalone_tags_removes_their_lines() {
+        assert_eq!(
+            parse("a\n  {{#if x}}  \nb\n{{/if}}\nc").unwrap(),
+            vec![
+                Block::Text("a\n"),
+                Block::If {
+                    name: "x",
+                    subname: None,
+                    then: vec![Block::Text("b\n")],
+                    otherwise: vec![],
+                },
+                Block::Text("c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_blocks() {
+        assert_eq!(parse_error("{{#if x}}a"), "missing {{/if}}");
+        assert_eq!(
+            parse_error("{{#each y}}{{#if x}}{{/each}}{{/if}}"),
+            "expected {{/if}} but found {{/each}}"
+        );
+    }
+
+    #[test]
+    fn parse_unmatched_tags_as_literals() {
+        assert_eq!(
+            parse("a {{/each}}\n{{else}}").unwrap(),
+            vec![
+                Block::Text("a "),
+                Block::Literal("{{/each}}"),
+                Block::Text("\n"),
+                Block::Literal("{{else}}"),
+            ]
+        );
+        assert_eq!(
+            parse("{{#each x}}{{else}}{{/if}}{{/each}}").unwrap(),
+            vec![Block::Each {
+                name: "x",
+                subname: None,
+                body: vec![Block::Literal("{{else}}"), Block::Literal("{{/if}}")],
+            }]
+        );
+        assert_eq!(
+            parse("{{#if x}}{{else}}{{else}}{{/if}}").unwrap(),
+            vec![Block::If {
+                name: "x",
+                subname: None,
+                then: vec![],
+                otherwise: vec![Block::Literal("{{else}}")],
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_or_literal_falls_back_on_invalid_blocks() {
+        assert_eq!(
+            parse_or_literal("{{#if x}}\n{{a}}"),
+            vec![Block::Literal("{{#if x}}"), Block::Text("\n{{a}}")]
+        );
+    }
+
+    #[test]
+    fn variable_names_exclude_loop_variables() {
+        let blocks =
+            parse("{{a}}{{#if b.x}}{{#each c}}{{item}} {{loop.index}} {{d}}{{/each}}{{/if}}")
+                .unwrap();
+        let mut names = HashSet::new();
+        get_variable_names(&blocks, &mut names);
+        assert_eq!(names, HashSet::from(["a", "b", "c", "d"]));
+    }
+}
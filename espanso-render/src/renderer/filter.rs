@@ -0,0 +1,422 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, fmt::Write};
+
+use anyhow::{bail, Context, Result};
+use log::warn;
+
+use super::RendererError;
+use crate::{Extension, Filter};
+
+// Matches a single filter argument, either a double-quoted string or a bare word
+pub const FILTER_ARG_PATTERN: &str = r#"("([^"\\]|\\.)*"|[^\s|,}"]+)"#;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterCall {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+pub struct FilterRegistry<'a> {
+    filters: HashMap<String, &'a dyn Filter>,
+}
+
+impl<'a> FilterRegistry<'a> {
+    pub fn new(extensions: &[&'a dyn Extension]) -> Self {
+        let mut filters: HashMap<String, &'a dyn Filter> = BUILTIN_FILTERS
+            .iter()
+            .map(|filter| (filter.name.to_string(), filter as &dyn Filter))
+            .collect();
+
+        for extension in extensions {
+            for filter in extension.filters() {
+                if filters.insert(filter.name().to_string(), filter).is_some() {
+                    warn!(
+                        "filter '{}' provided by extension '{}' overrides an existing one",
+                        filter.name(),
+                        extension.name()
+                    );
+                }
+            }
+        }
+
+        Self { filters }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.filters.contains_key(name)
+    }
+
+    pub fn apply(&self, input: String, calls: &[FilterCall]) -> Result<String> {
+        calls.iter().try_fold(input, |value, call| {
+            let Some(filter) = self.filters.get(&call.name) else {
+                return Err(RendererError::UnknownFilter(call.name.clone()).into());
+            };
+            filter
+                .apply(&value, &call.args)
+                .with_context(|| format!("unable to apply filter '{}'", call.name))
+        })
+    }
+}
+
+// Parse the filters section of a variable, such as `|trim|replace:"a","b"`.
+// The syntax has already been validated by the variable regex.
+pub fn parse_filters(filters: &str) -> Vec<FilterCall> {
+    split_unquoted(filters, '|')
+        .into_iter()
+        .map(str::trim)
+        .filter(|call| !call.is_empty())
+        .map(|call| {
+            let (name, args) = call.split_once(':').unwrap_or((call, ""));
+            let args = if args.trim().is_empty() {
+                Vec::new()
+            } else {
+                split_unquoted(args, ',')
+                    .into_iter()
+                    .map(|arg| unquote(arg.trim()))
+                    .collect()
+            };
+            FilterCall {
+                name: name.trim().to_string(),
+                args,
+            }
+        })
+        .collect()
+}
+
+fn split_unquoted(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for (index, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            c if c == separator && !in_quotes => {
+                parts.push(&input[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+fn unquote(arg: &str) -> String {
+    let Some(quoted) = arg.strip_prefix('"').and_then(|arg| arg.strip_suffix('"')) else {
+        return arg.to_string();
+    };
+
+    let mut output = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                output.push('\n');
+                chars.next();
+            }
+            ('\\', Some(next)) => {
+                output.push(next);
+                chars.next();
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+struct BuiltinFilter {
+    name: &'static str,
+    apply: fn(&str, &[String]) -> Result<String>,
+}
+
+impl Filter for BuiltinFilter {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn apply(&self, input: &str, args: &[String]) -> Result<String> {
+        (self.apply)(input, args)
+    }
+}
+
+static BUILTIN_FILTERS: &[BuiltinFilter] = &[
+    BuiltinFilter {
+        name: "upper",
+        apply: |input, args| no_args(args).map(|()| input.to_uppercase()),
+    },
+    BuiltinFilter {
+        name: "lower",
+        apply: |input, args| no_args(args).map(|()| input.to_lowercase()),
+    },
+    BuiltinFilter {
+        name: "capitalize",
+        apply: |input, args| no_args(args).map(|()| capitalize(input)),
+    },
+    BuiltinFilter {
+        name: "title",
+        apply: |input, args| {
+            no_args(args)?;
+            let mut output = String::new();
+            let mut is_word_start = true;
+            for c in input.chars() {
+                if is_word_start && c.is_alphanumeric() {
+                    output.extend(c.to_uppercase());
+                } else {
+                    output.push(c);
+                }
+                is_word_start = !c.is_alphanumeric();
+            }
+            Ok(output)
+        },
+    },
+    BuiltinFilter {
+        name: "trim",
+        apply: |input, args| no_args(args).map(|()| input.trim().to_string()),
+    },
+    BuiltinFilter {
+        name: "trim_start",
+        apply: |input, args| no_args(args).map(|()| input.trim_start().to_string()),
+    },
+    BuiltinFilter {
+        name: "trim_end",
+        apply: |input, args| no_args(args).map(|()| input.trim_end().to_string()),
+    },
+    BuiltinFilter {
+        name: "replace",
+        apply: |input, args| match args {
+            [from, to] if !from.is_empty() => Ok(input.replace(from.as_str(), to)),
+            _ => bail!("expected two arguments, the text to replace and its replacement"),
+        },
+    },
+    BuiltinFilter {
+        name: "slugify",
+        apply: |input, args| {
+            no_args(args)?;
+            let slug: Vec<String> = input
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase)
+                .collect();
+            Ok(slug.join("-"))
+        },
+    },
+    BuiltinFilter {
+        name: "url_encode",
+        apply: |input, args| {
+            no_args(args)?;
+            let mut output = String::new();
+            for byte in input.bytes() {
+                if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+                    output.push(char::from(byte));
+                } else {
+                    write!(output, "%{byte:02X}")?;
+                }
+            }
+            Ok(output)
+        },
+    },
+    BuiltinFilter {
+        name: "html_escape",
+        apply: |input, args| {
+            no_args(args)?;
+            let mut output = String::new();
+            for c in input.chars() {
+                match c {
+                    '&' => output.push_str("&amp;"),
+                    '<' => output.push_str("&lt;"),
+                    '>' => output.push_str("&gt;"),
+                    '"' => output.push_str("&quot;"),
+                    '\'' => output.push_str("&#39;"),
+                    c => output.push(c),
+                }
+            }
+            Ok(output)
+        },
+    },
+    BuiltinFilter {
+        name: "json_escape",
+        apply: |input, args| {
+            no_args(args)?;
+            let mut output = String::new();
+            for c in input.chars() {
+                match c {
+                    '"' => output.push_str("\\\""),
+                    '\\' => output.push_str("\\\\"),
+                    '\n' => output.push_str("\\n"),
+                    '\r' => output.push_str("\\r"),
+                    '\t' => output.push_str("\\t"),
+                    c if c.is_control() => write!(output, "\\u{:04x}", u32::from(c))?,
+                    c => output.push(c),
+                }
+            }
+            Ok(output)
+        },
+    },
+    BuiltinFilter {
+        name: "truncate",
+        apply: |input, args| {
+            let (length, suffix) = match args {
+                [length] => (length, ""),
+                [length, suffix] => (length, suffix.as_str()),
+                _ => bail!("expected the maximum length and an optional suffix"),
+            };
+            let length: usize = length
+                .parse()
+                .with_context(|| format!("invalid length: {length}"))?;
+
+            if input.chars().count() <= length {
+                Ok(input.to_string())
+            } else {
+                let truncated: String = input.chars().take(length).collect();
+                Ok(format!("{truncated}{suffix}"))
+            }
+        },
+    },
+    BuiltinFilter {
+        name: "default",
+        apply: |input, args| match args {
+            [default] if input.is_empty() => Ok(default.clone()),
+            [_] => Ok(input.to_string()),
+            _ => bail!("expected the default value"),
+        },
+    },
+];
+
+fn no_args(args: &[String]) -> Result<()> {
+    if args.is_empty() {
+        Ok(())
+    } else {
+        bail!("this filter doesn't accept arguments")
+    }
+}
+
+fn capitalize(input: &str) -> String {
+    let mut chars = input.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(input: &str, filters: &str) -> Result<String> {
+        FilterRegistry::new(&[]).apply(input.to_string(), &parse_filters(filters))
+    }
+
+    #[test]
+    fn parse_filters_with_arguments() {
+        assert_eq!(
+            parse_filters(r#"| trim |replace: "a|b", "c\"d" |truncate:10"#),
+            vec![
+                FilterCall {
+                    name: "trim".to_string(),
+                    args: vec![],
+                },
+                FilterCall {
+                    name: "replace".to_string(),
+                    args: vec!["a|b".to_string(), "c\"d".to_string()],
+                },
+                FilterCall {
+                    name: "truncate".to_string(),
+                    args: vec!["10".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn case_filters() {
+        assert_eq!(apply("hello World", "|upper").unwrap(), "HELLO WORLD");
+        assert_eq!(apply("hello World", "|lower").unwrap(), "hello world");
+        assert_eq!(apply("hello world", "|capitalize").unwrap(), "Hello world");
+        assert_eq!(
+            apply("hello big-world", "|title").unwrap(),
+            "Hello Big-World"
+        );
+    }
+
+    #[test]
+    fn trim_filters() {
+        assert_eq!(apply("  a b \n", "|trim").unwrap(), "a b");
+        assert_eq!(apply("  a b \n", "|trim_start").unwrap(), "a b \n");
+        assert_eq!(apply("  a b \n", "|trim_end").unwrap(), "  a b");
+    }
+
+    #[test]
+    fn replace_filter() {
+        assert_eq!(apply("a-b-c", r#"|replace:"-"," ""#).unwrap(), "a b c");
+        assert!(apply("abc", "|replace:a").is_err());
+    }
+
+    #[test]
+    fn slugify_filter() {
+        assert_eq!(
+            apply("  Hello, World! Ünïcode 2 ", "|slugify").unwrap(),
+            "hello-world-ünïcode-2"
+        );
+    }
+
+    #[test]
+    fn escape_filters() {
+        assert_eq!(
+            apply("a b&c/ü", "|url_encode").unwrap(),
+            "a%20b%26c%2F%C3%BC"
+        );
+        assert_eq!(
+            apply(r#"<a href="x">'&'</a>"#, "|html_escape").unwrap(),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+        assert_eq!(
+            apply("say \"hi\"\n\\\u{1}", "|json_escape").unwrap(),
+            r#"say \"hi\"\n\\\u0001"#
+        );
+    }
+
+    #[test]
+    fn truncate_filter() {
+        assert_eq!(apply("hello", "|truncate:5").unwrap(), "hello");
+        assert_eq!(apply("hello world", "|truncate:5").unwrap(), "hello");
+        assert_eq!(
+            apply("hello world", r#"|truncate:5,"…""#).unwrap(),
+            "hello…"
+        );
+        assert!(apply("hello", "|truncate:x").is_err());
+    }
+
+    #[test]
+    fn default_filter() {
+        assert_eq!(apply("", "|default:none").unwrap(), "none");
+        assert_eq!(apply("value", "|default:none").unwrap(), "value");
+        assert_eq!(apply("  ", r#"|trim|default:"n/a""#).unwrap(), "n/a");
+    }
+
+    #[test]
+    fn filter_without_arguments_rejects_them() {
+        assert!(apply("a", "|upper:1").is_err());
+    }
+}
@@ -20,34 +20,50 @@
 use std::{borrow::Cow, collections::HashMap};
 
 use crate::{
-    CasingStyle, Context, Extension, ExtensionOutput, ExtensionResult, RenderOptions, RenderResult,
-    Renderer, Scope, Template, Value, Variable,
+    CasingStyle, Context, Extension, ExtensionOutput, ExtensionResult, Params, RenderOptions,
+    RenderResult, Renderer, Scope, Template, Value, Variable,
 };
 use log::{error, warn};
 use regex::{Captures, Regex};
 use std::sync::LazyLock;
 use thiserror::Error;
 
-use self::util::{inject_variables_into_params, render_variables};
+use self::{
+    filter::{FilterRegistry, FILTER_ARG_PATTERN},
+    util::inject_variables_into_params,
+};
 
+mod block;
+mod filter;
 mod resolve;
 mod util;
 
-pub static VAR_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"\{\{\s*((?P<name>\w+)(\.(?P<subname>(\w+)))?)\s*\}\}").unwrap());
+// Matches variables such as `{{name}}` or `{{name.subname}}`, optionally followed
+// by a chain of filters like `{{name|trim|truncate:10,"..."}}`
+pub static VAR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"\{{\{{\s*((?P<name>\w+)(\.(?P<subname>(\w+)))?)\s*(?P<filters>(\|\s*\w+\s*(:\s*{FILTER_ARG_PATTERN}\s*(,\s*{FILTER_ARG_PATTERN}\s*)*)?)*)\}}\}}"
+    ))
+    .unwrap()
+});
 static WORD_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\w+)").unwrap());
 
 pub struct DefaultRenderer<'a> {
     extensions: HashMap<String, &'a dyn Extension>,
+    filters: FilterRegistry<'a>,
 }
 
 impl<'a> DefaultRenderer<'a> {
     pub fn new(extensions: Vec<&'a dyn Extension>) -> Self {
+        let filters = FilterRegistry::new(&extensions);
         let extensions = extensions
             .into_iter()
             .map(|ext| (ext.name().to_string(), ext))
             .collect();
-        Self { extensions }
+        Self {
+            extensions,
+            filters,
+        }
     }
 }
 
@@ -58,7 +74,9 @@ impl Renderer for DefaultRenderer<'_> {
         context: &Context,
         options: &RenderOptions,
     ) -> RenderResult {
-        let body = if VAR_REGEX.is_match(&template.body) {
+        let body = if VAR_REGEX.is_match(&template.body) || block::has_blocks(&template.body) {
+            let blocks = block::parse_or_literal(&template.body);
+
             // Convert "global" variable type aliases when needed
             let local_variables: Vec<&Variable> =
                 if template.vars.iter().any(|var| var.var_type == "global") {
@@ -84,88 +102,55 @@ impl Renderer for DefaultRenderer<'_> {
 
             // Here we execute a graph dependency resolution algorithm to determine a valid
             // evaluation order for variables.
-            let variables = match resolve::resolve_evaluation_order(
+            let batches = match resolve::resolve_evaluation_batches(
                 &template.body,
                 &local_variables,
                 &context.global_vars,
+                template.sequential_vars,
+                &|variable| {
+                    self.extensions
+                        .get(&variable.var_type)
+                        .is_some_and(|extension| extension.reads_scope())
+                },
             ) {
-                Ok(variables) => variables,
+                Ok(batches) => batches,
                 Err(err) => return RenderResult::Error(err),
             };
 
             // Compute the variable outputs
             let mut scope = Scope::new();
-            for variable in variables {
-                if variable.var_type == "match" {
-                    // Recursive call
-                    // Call render recursively
-                    if let Some(sub_template) =
-                        get_matching_template(variable, context.templates.as_slice())
+            for batch in batches {
+                let mut concurrent = Vec::new();
+                let mut serial = Vec::new();
+                for variable in batch {
+                    if let Some(extension) = self
+                        .extensions
+                        .get(&variable.var_type)
+                        .and_then(|extension| extension.as_concurrent())
                     {
-                        match self.render(sub_template, context, options) {
-                            RenderResult::Success(output) => {
-                                scope.insert(&variable.name, ExtensionOutput::Single(output));
-                            }
-                            result => return result,
-                        }
+                        concurrent.push((variable, extension));
                     } else {
-                        error!("unable to find sub-match: {}", variable.name);
-                        return RenderResult::Error(RendererError::MissingSubMatch.into());
+                        serial.push(variable);
                     }
-                } else if let Some(extension) = self.extensions.get(&variable.var_type) {
-                    let variable_params = if variable.inject_vars {
-                        match inject_variables_into_params(&variable.params, &scope) {
-                            Ok(augmented_params) => Cow::Owned(augmented_params),
-                            Err(err) => {
-                                error!(
-                                    "unable to inject variables into params of variable '{}': {}",
-                                    variable.name, err
-                                );
-
-                                if variable.var_type == "form" {
-                                    if let Some(RendererError::MissingVariable(_)) =
-                                        err.downcast_ref::<RendererError>()
-                                    {
-                                        log_new_form_syntax_tip();
-                                    }
-                                }
-
-                                return RenderResult::Error(err);
-                            }
-                        }
-                    } else {
-                        Cow::Borrowed(&variable.params)
-                    };
+                }
 
-                    match extension.calculate(context, &scope, &variable_params) {
-                        ExtensionResult::Success(output) => {
-                            scope.insert(&variable.name, output);
-                        }
-                        ExtensionResult::Aborted => {
-                            warn!(
-                                "rendering was aborted by extension: {}, on var: {}",
-                                variable.var_type, variable.name
-                            );
-                            return RenderResult::Aborted;
-                        }
-                        ExtensionResult::Error(err) => {
-                            warn!(
-                                "extension '{}' on var: '{}' reported an error: {}",
-                                variable.var_type, variable.name, err
-                            );
-                            return RenderResult::Error(err);
-                        }
+                // Variables that can't be evaluated concurrently, such as the interactive ones,
+                // go first, so that aborting them prevents the other commands from running
+                for variable in serial {
+                    if let Some(result) =
+                        self.evaluate_variable(variable, context, options, &mut scope)
+                    {
+                        return result;
                     }
-                } else {
-                    error!(
-                        "no extension found for variable type: {}",
-                        variable.var_type
-                    );
+                }
+
+                if let Some(result) = self.evaluate_concurrently(&concurrent, context, &mut scope) {
+                    return result;
                 }
             }
 
-            // Replace the variables
-            match render_variables(&template.body, &scope) {
+            // Evaluate the blocks and replace the variables
+            match block::render(&blocks, &mut scope, &self.filters) {
                 Ok(output) => output,
                 Err(error) => {
                     return RenderResult::Error(error);
@@ -208,6 +193,155 @@ impl Renderer for DefaultRenderer<'_> {
     }
 }
 
+impl DefaultRenderer<'_> {
+    // Evaluate the variable and store its output in the scope. If the rendering
+    // can't continue, the result is returned.
+    fn evaluate_variable<'v>(
+        &self,
+        variable: &'v Variable,
+        context: &Context,
+        options: &RenderOptions,
+        scope: &mut Scope<'v>,
+    ) -> Option<RenderResult> {
+        if variable.var_type == "match" {
+            // Recursive call
+            // Call render recursively
+            if let Some(sub_template) =
+                get_matching_template(variable, context.templates.as_slice())
+            {
+                match self.render(sub_template, context, options) {
+                    RenderResult::Success(output) => {
+                        scope.insert(&variable.name, ExtensionOutput::Single(output));
+                    }
+                    result => return Some(result),
+                }
+            } else {
+                error!("unable to find sub-match: {}", variable.name);
+                return Some(RenderResult::Error(RendererError::MissingSubMatch.into()));
+            }
+        } else if let Some(extension) = self.extensions.get(&variable.var_type) {
+            let variable_params = match self.inject_params(variable, scope) {
+                Ok(params) => params,
+                Err(result) => return Some(result),
+            };
+
+            let result = extension.calculate(context, scope, &variable_params);
+            return store_output(variable, result, scope);
+        } else {
+            error!(
+                "no extension found for variable type: {}",
+                variable.var_type
+            );
+        }
+
+        None
+    }
+
+    // Evaluate independent variables on separate threads. The outputs are stored
+    // in the given order, which also determines the reported error if many fail.
+    fn evaluate_concurrently<'v>(
+        &self,
+        variables: &[(&'v Variable, &(dyn Extension + Sync))],
+        context: &Context,
+        scope: &mut Scope<'v>,
+    ) -> Option<RenderResult> {
+        let mut pending = Vec::new();
+        for (variable, extension) in variables {
+            match self.inject_params(variable, scope) {
+                Ok(params) => pending.push((*variable, *extension, params)),
+                Err(result) => return Some(result),
+            }
+        }
+
+        let results: Vec<ExtensionResult> = if let [(_, extension, params)] = pending.as_slice() {
+            vec![extension.calculate(context, scope, params)]
+        } else {
+            let scope = &*scope;
+            std::thread::scope(|threads| {
+                let handles: Vec<_> = pending
+                    .iter()
+                    .map(|(_, extension, params)| {
+                        threads.spawn(move || extension.calculate(context, scope, params))
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                    })
+                    .collect()
+            })
+        };
+
+        for ((variable, ..), result) in pending.into_iter().zip(results) {
+            if let Some(result) = store_output(variable, result, scope) {
+                return Some(result);
+            }
+        }
+
+        None
+    }
+
+    fn inject_params<'v>(
+        &self,
+        variable: &'v Variable,
+        scope: &Scope,
+    ) -> Result<Cow<'v, Params>, RenderResult> {
+        if !variable.inject_vars {
+            return Ok(Cow::Borrowed(&variable.params));
+        }
+
+        match inject_variables_into_params(&variable.params, scope, &self.filters) {
+            Ok(augmented_params) => Ok(Cow::Owned(augmented_params)),
+            Err(err) => {
+                error!(
+                    "unable to inject variables into params of variable '{}': {}",
+                    variable.name, err
+                );
+
+                if variable.var_type == "form" {
+                    if let Some(RendererError::MissingVariable(_)) =
+                        err.downcast_ref::<RendererError>()
+                    {
+                        log_new_form_syntax_tip();
+                    }
+                }
+
+                Err(RenderResult::Error(err))
+            }
+        }
+    }
+}
+
+fn store_output<'v>(
+    variable: &'v Variable,
+    result: ExtensionResult,
+    scope: &mut Scope<'v>,
+) -> Option<RenderResult> {
+    match result {
+        ExtensionResult::Success(output) => {
+            scope.insert(&variable.name, output);
+            None
+        }
+        ExtensionResult::Aborted => {
+            warn!(
+                "rendering was aborted by extension: {}, on var: {}",
+                variable.var_type, variable.name
+            );
+            Some(RenderResult::Aborted)
+        }
+        ExtensionResult::Error(err) => {
+            warn!(
+                "extension '{}' on var: '{}' reported an error: {}",
+                variable.var_type, variable.name, err
+            );
+            Some(RenderResult::Error(err))
+        }
+    }
+}
+
 fn get_matching_template<'a>(
     variable: &Variable,
     templates: &'a [&Template],
@@ -252,13 +386,20 @@ pub enum RendererError {
 
     #[error("circular dependency: `{0}` -> `{1}`")]
     CircularDependency(String, String),
+
+    #[error("unknown filter: `{0}`")]
+    UnknownFilter(String),
+
+    #[error("invalid block: {0}")]
+    InvalidBlock(String),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Params;
+    use crate::{Filter, Params};
     use std::iter::FromIterator;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     struct MockExtension {}
 
@@ -310,6 +451,7 @@ mod tests {
             ids: vec!["id".to_string()],
             body: str.to_string(),
             vars: Vec::new(),
+            ..Default::default()
         }
     }
 
@@ -329,6 +471,7 @@ mod tests {
             ids: vec!["id".to_string()],
             body: body.to_string(),
             vars,
+            ..Default::default()
         }
     }
 
@@ -947,4 +1090,521 @@ mod tests {
         let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
         assert!(matches!(res, RenderResult::Success(str) if str == "hello {{var}}"));
     }
+
+    #[test]
+    fn variable_with_filters() {
+        let renderer = get_renderer();
+        let template = template(
+            "hello {{ var | trim | upper }} {{var|truncate:3,\"...\"}}",
+            &[("var", "  world  ")],
+        );
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "hello WORLD   w..."));
+    }
+
+    #[test]
+    fn dict_variable_with_filters() {
+        let renderer = get_renderer();
+        let template = Template {
+            body: "hello {{var.nested|replace:\"d\",\"D\"|default:none}}".to_string(),
+            vars: vec![Variable {
+                name: "var".to_string(),
+                var_type: "mock".to_string(),
+                params: vec![
+                    ("name".to_string(), Value::String("nested".to_string())),
+                    ("value".to_string(), Value::String("dict".to_string())),
+                ]
+                .into_iter()
+                .collect::<Params>(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "hello Dict"));
+    }
+
+    #[test]
+    fn variable_injection_with_filters() {
+        let renderer = get_renderer();
+        let mut template = template_for_str("hello {{output}}");
+        template.vars = vec![
+            Variable {
+                name: "var".to_string(),
+                var_type: "mock".to_string(),
+                params: Params::from_iter(vec![(
+                    "echo".to_string(),
+                    Value::String("My Title".to_string()),
+                )]),
+                ..Default::default()
+            },
+            Variable {
+                name: "output".to_string(),
+                var_type: "mock".to_string(),
+                params: Params::from_iter(vec![(
+                    "echo".to_string(),
+                    Value::String("{{var|slugify}}".to_string()),
+                )]),
+                ..Default::default()
+            },
+        ];
+
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "hello my-title"));
+    }
+
+    #[test]
+    fn unknown_filter_renders_literally() {
+        let renderer = get_renderer();
+        let template = template("hello {{var|missing}} {{a|b}} {{var}}", &[("var", "world")]);
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(
+            matches!(res, RenderResult::Success(str) if str == "hello {{var|missing}} {{a|b}} world")
+        );
+    }
+
+    #[test]
+    fn missing_variable_with_filters_renders_literally() {
+        let renderer = get_renderer();
+        let template = template_for_str("hello {{ value|upper }}");
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "hello {{ value|upper }}"));
+    }
+
+    #[test]
+    fn default_filter_applies_to_missing_variable() {
+        let renderer = get_renderer();
+        let template = template_for_str("hello {{name|upper|default:\"there\"}}");
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "hello there"));
+    }
+
+    #[test]
+    fn default_filter_applies_to_missing_injected_variable() {
+        let renderer = get_renderer();
+        let mut template = template_for_str("hello {{output}}");
+        template.vars = vec![Variable {
+            name: "output".to_string(),
+            var_type: "mock".to_string(),
+            params: Params::from_iter(vec![(
+                "echo".to_string(),
+                Value::String("{{name|default:there}}".to_string()),
+            )]),
+            ..Default::default()
+        }];
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "hello there"));
+    }
+
+    #[test]
+    fn missing_variable_without_filters_fails() {
+        let renderer = get_renderer();
+        let template = template_for_str("hello {{name}} {{name|default:there}}");
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(
+            res,
+            RenderResult::Error(err) if matches!(
+                err.downcast_ref::<RendererError>(),
+                Some(RendererError::MissingVariable(_))
+            )
+        ));
+    }
+
+    fn form_template(body: &str, fields: &[(&str, &str)]) -> Template {
+        let vars = fields
+            .iter()
+            .map(|(name, value)| Variable {
+                name: format!("var_{name}"),
+                var_type: "mock".to_string(),
+                params: vec![
+                    ("name".to_string(), Value::String((*name).to_string())),
+                    ("value".to_string(), Value::String((*value).to_string())),
+                ]
+                .into_iter()
+                .collect::<Params>(),
+                ..Default::default()
+            })
+            .collect();
+        Template {
+            ids: vec!["id".to_string()],
+            body: body.to_string(),
+            vars,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn if_block() {
+        let renderer = get_renderer();
+        let body = "To: a\n{{#if var_cc.cc}}\nCC: {{var_cc.cc}}\n{{else}}\nNo CC\n{{/if}}\nBye";
+
+        let res = renderer.render(
+            &form_template(body, &[("cc", "b")]),
+            &Context::default(),
+            &RenderOptions::default(),
+        );
+        assert!(matches!(res, RenderResult::Success(str) if str == "To: a\nCC: b\nBye"));
+
+        for falsy in ["", "false", "0"] {
+            let res = renderer.render(
+                &form_template(body, &[("cc", falsy)]),
+                &Context::default(),
+                &RenderOptions::default(),
+            );
+            assert!(matches!(res, RenderResult::Success(str) if str == "To: a\nNo CC\nBye"));
+        }
+    }
+
+    #[test]
+    fn if_block_on_single_variable() {
+        let renderer = get_renderer();
+        let template = template(
+            "{{#if var}}yes{{/if}}{{#if other}}no{{/if}}",
+            &[("var", "x"), ("other", "")],
+        );
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "yes"));
+    }
+
+    #[test]
+    fn each_block_on_multiple_output() {
+        struct MultiExtension {}
+        impl Extension for MultiExtension {
+            fn name(&self) -> &'static str {
+                "multi"
+            }
+
+            fn calculate(&self, _: &Context, _: &Scope, _: &Params) -> ExtensionResult {
+                ExtensionResult::Success(ExtensionOutput::Multiple(HashMap::from([
+                    ("b".to_string(), "two".to_string()),
+                    ("a".to_string(), "one".to_string()),
+                ])))
+            }
+        }
+
+        let renderer = DefaultRenderer::new(vec![&MultiExtension {}]);
+        let template = Template {
+            ids: vec!["id".to_string()],
+            body: "{{#each var}}{{loop.index}}.{{loop.key}}={{item|upper}}{{#if loop.last}}!{{else}}, {{/if}}{{/each}}".to_string(),
+            vars: vec![Variable {
+                name: "var".to_string(),
+                var_type: "multi".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "1.a=ONE, 2.b=TWO!"));
+    }
+
+    #[test]
+    fn each_block_on_lines() {
+        let renderer = get_renderer();
+        let template = template(
+            "Items:\n{{#each var}}\n- {{item}} ({{outer}})\n{{/each}}\nEnd {{item}}",
+            &[("var", "a\nb"), ("outer", "x"), ("item", "global")],
+        );
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(
+            matches!(res, RenderResult::Success(str) if str == "Items:\n- a (x)\n- b (x)\nEnd global")
+        );
+    }
+
+    #[test]
+    fn escaped_blocks_are_not_evaluated() {
+        let renderer = get_renderer();
+        let template = template("\\{\\{#if var\\}\\}{{var}}", &[("var", "x")]);
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "{{#if var}}x"));
+    }
+
+    #[test]
+    fn invalid_block_renders_literally() {
+        let renderer = get_renderer();
+        let template = template("{{#if var}}{{var}}", &[("var", "x")]);
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "{{#if var}}x"));
+    }
+
+    #[test]
+    fn unmatched_tags_render_literally() {
+        let renderer = get_renderer();
+        for body in ["{{else}}", "{{/if}}", "a {{/each}} b"] {
+            let template = template_for_str(body);
+            let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+            assert!(matches!(res, RenderResult::Success(str) if str == body));
+        }
+    }
+
+    #[test]
+    fn extension_filters_are_available() {
+        struct ReverseFilter {}
+
+        impl Filter for ReverseFilter {
+            fn name(&self) -> &'static str {
+                "reverse"
+            }
+
+            fn apply(&self, input: &str, _args: &[String]) -> anyhow::Result<String> {
+                Ok(input.chars().rev().collect())
+            }
+        }
+
+        struct FilterExtension {
+            filter: ReverseFilter,
+        }
+
+        impl Extension for FilterExtension {
+            fn name(&self) -> &'static str {
+                "filter"
+            }
+
+            fn calculate(&self, _: &Context, _: &Scope, _: &Params) -> ExtensionResult {
+                ExtensionResult::Aborted
+            }
+
+            fn filters(&self) -> Vec<&dyn Filter> {
+                vec![&self.filter]
+            }
+        }
+
+        let extension = FilterExtension {
+            filter: ReverseFilter {},
+        };
+        let renderer = DefaultRenderer::new(vec![&MockExtension {}, &extension]);
+        let template = template("hello {{var|reverse|capitalize}}", &[("var", "world")]);
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "hello Dlrow"));
+    }
+
+    #[derive(Default)]
+    struct ConcurrentExtension {
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+        calls: AtomicUsize,
+        reads_scope: bool,
+    }
+
+    impl Extension for ConcurrentExtension {
+        fn name(&self) -> &'static str {
+            "concurrent"
+        }
+
+        fn calculate(&self, _: &Context, scope: &Scope, params: &Params) -> ExtensionResult {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+
+            // Read the variable from the scope, without the `{{name}}` syntax
+            if let Some(Value::String(name)) = params.get("read") {
+                return match scope.get(name.as_str()) {
+                    Some(ExtensionOutput::Single(value)) => {
+                        ExtensionResult::Success(ExtensionOutput::Single(format!("read {value}")))
+                    }
+                    _ => {
+                        ExtensionResult::Error(RendererError::MissingVariable(name.clone()).into())
+                    }
+                };
+            }
+            if let Some(Value::String(string)) = params.get("echo") {
+                return ExtensionResult::Success(ExtensionOutput::Single(string.clone()));
+            }
+            ExtensionResult::Aborted
+        }
+
+        fn as_concurrent(&self) -> Option<&(dyn Extension + Sync)> {
+            Some(self)
+        }
+
+        fn reads_scope(&self) -> bool {
+            self.reads_scope
+        }
+    }
+
+    fn concurrent_template(body: &str, vars: &[(&str, &str, &str)]) -> Template {
+        let vars = vars
+            .iter()
+            .map(|(name, param, value)| Variable {
+                name: (*name).to_string(),
+                var_type: "concurrent".to_string(),
+                params: Params::from_iter(vec![(
+                    (*param).to_string(),
+                    Value::String((*value).to_string()),
+                )]),
+                ..Default::default()
+            })
+            .collect();
+        Template {
+            ids: vec!["id".to_string()],
+            body: body.to_string(),
+            vars,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn independent_variables_are_evaluated_concurrently() {
+        let extension = ConcurrentExtension::default();
+        let renderer = DefaultRenderer::new(vec![&extension]);
+        let template = concurrent_template(
+            "{{a}}{{b}}{{c}}",
+            &[("a", "echo", "A"), ("b", "echo", "B"), ("c", "echo", "C")],
+        );
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "ABC"));
+        assert!(extension.max_active.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn sequential_vars_are_evaluated_one_at_a_time() {
+        let extension = ConcurrentExtension::default();
+        let renderer = DefaultRenderer::new(vec![&extension]);
+        let mut template = concurrent_template(
+            "{{a}}{{b}}{{c}}",
+            &[("a", "echo", "A"), ("b", "echo", "B"), ("c", "echo", "C")],
+        );
+        template.sequential_vars = true;
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "ABC"));
+        assert_eq!(extension.max_active.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn dependent_variables_are_evaluated_in_order() {
+        let extension = ConcurrentExtension::default();
+        let renderer = DefaultRenderer::new(vec![&extension]);
+        let template = concurrent_template(
+            "{{a}} {{b}} {{c}}",
+            &[
+                ("a", "echo", "A"),
+                ("b", "echo", "{{a}}!"),
+                ("c", "echo", "C"),
+            ],
+        );
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "A A! C"));
+        assert!(extension.max_active.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn scope_reading_variables_follow_the_previous_ones() {
+        let extension = ConcurrentExtension {
+            reads_scope: true,
+            ..Default::default()
+        };
+        let renderer = DefaultRenderer::new(vec![&extension]);
+        // The read variables are not referenced with the `{{name}}` syntax
+        let template = concurrent_template(
+            "{{a}} {{b}} {{c}}",
+            &[("a", "echo", "A"), ("b", "read", "a"), ("c", "read", "b")],
+        );
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "A read A read read A"));
+        assert_eq!(extension.max_active.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn scope_reading_variables_without_mentions_are_evaluated_concurrently() {
+        let extension = ConcurrentExtension {
+            reads_scope: true,
+            ..Default::default()
+        };
+        let renderer = DefaultRenderer::new(vec![&extension]);
+        let template = concurrent_template(
+            "{{a}}{{b}}{{c}}",
+            &[("a", "echo", "A"), ("b", "echo", "B"), ("c", "echo", "C")],
+        );
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "ABC"));
+        assert!(extension.max_active.load(Ordering::SeqCst) > 1);
+    }
+
+    fn shell_template(body: &str, vars: &[(&str, &str)]) -> Template {
+        let vars = vars
+            .iter()
+            .map(|(name, cmd)| Variable {
+                name: (*name).to_string(),
+                var_type: "shell".to_string(),
+                params: Params::from_iter(vec![(
+                    "cmd".to_string(),
+                    Value::String((*cmd).to_string()),
+                )]),
+                ..Default::default()
+            })
+            .collect();
+        Template {
+            ids: vec!["id".to_string()],
+            body: body.to_string(),
+            vars,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn independent_shell_variables_are_evaluated_concurrently() {
+        let extension = crate::extension::shell::ShellExtension::new(
+            &std::path::PathBuf::new(),
+            &std::path::PathBuf::new(),
+            &std::path::PathBuf::new(),
+        );
+        let renderer = DefaultRenderer::new(vec![&extension]);
+        let template = shell_template(
+            "{{first}}{{second}}{{third}}",
+            &[
+                ("first", "sleep 0.5; echo x"),
+                ("second", "sleep 0.5; echo y"),
+                ("third", "sleep 0.5; echo z"),
+            ],
+        );
+
+        let start = std::time::Instant::now();
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        let elapsed = start.elapsed();
+
+        assert!(matches!(res, RenderResult::Success(str) if str == "xyz"));
+        // Evaluating them one after the other would take at least 1.5 seconds
+        assert!(
+            elapsed < std::time::Duration::from_millis(1200),
+            "{elapsed:?}"
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn shell_variables_reading_env_variables_follow_them() {
+        let extension = crate::extension::shell::ShellExtension::new(
+            &std::path::PathBuf::new(),
+            &std::path::PathBuf::new(),
+            &std::path::PathBuf::new(),
+        );
+        let renderer = DefaultRenderer::new(vec![&extension]);
+        let template = shell_template(
+            "{{second}}",
+            &[
+                ("first", "sleep 0.2; echo x"),
+                ("second", "echo \"$ESPANSO_FIRST!\""),
+            ],
+        );
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Success(str) if str == "x!"));
+    }
+
+    #[test]
+    fn serial_variables_are_evaluated_first() {
+        let extension = ConcurrentExtension::default();
+        let renderer = DefaultRenderer::new(vec![&MockExtension {}, &extension]);
+        let mut template = concurrent_template("{{a}}{{b}}", &[("a", "echo", "A")]);
+        template.vars.push(Variable {
+            name: "b".to_string(),
+            var_type: "mock".to_string(),
+            params: Params::from_iter(vec![("abort".to_string(), Value::Null)]),
+            ..Default::default()
+        });
+        let res = renderer.render(&template, &Context::default(), &RenderOptions::default());
+        assert!(matches!(res, RenderResult::Aborted));
+        assert_eq!(extension.calls.load(Ordering::SeqCst), 0);
+    }
 }
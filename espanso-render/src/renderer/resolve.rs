@@ -33,14 +33,24 @@ struct Node<'a> {
     name: &'a str,
     variable: Option<&'a Variable>,
     dependencies: Option<HashSet<&'a str>>,
+    // Dependencies that are only referenced with filters, which can be missing
+    optional_dependencies: HashSet<&'a str>,
 }
 
-pub fn resolve_evaluation_order<'a>(
+// Determine a valid evaluation order for the variables, grouped in batches.
+// Each variable only depends on the ones in previous batches, so the variables
+// in the same batch can be evaluated concurrently. In sequential mode, every
+// batch contains a single variable and local variables follow their declaration order.
+// Otherwise, the variables that read the scope directly follow the local
+// variables declared before them that their params mention.
+pub fn resolve_evaluation_batches<'a>(
     body: &'a str,
     local_vars: &'a [&'a Variable],
     global_vars: &'a [&'a Variable],
-) -> Result<Vec<&'a Variable>> {
-    let node_map = generate_nodes(body, local_vars, global_vars);
+    sequential: bool,
+    reads_scope: &dyn Fn(&Variable) -> bool,
+) -> Result<Vec<Vec<&'a Variable>>> {
+    let node_map = generate_nodes(body, local_vars, global_vars, sequential, reads_scope);
 
     let body_node = node_map
         .get("__match_body")
@@ -61,17 +71,63 @@ pub fn resolve_evaluation_order<'a>(
             .get(var_name)
             .ok_or_else(|| anyhow!("could not find dependency node for variable: {}", var_name))?;
         if let Some(var) = node.variable {
-            ordered_variables.push(var);
+            ordered_variables.push((node, var));
         }
     }
 
-    Ok(ordered_variables)
+    if sequential {
+        return Ok(ordered_variables
+            .into_iter()
+            .map(|(_, var)| vec![var])
+            .collect());
+    }
+
+    // The batch of a variable comes right after the ones of its dependencies
+    let mut levels: HashMap<&str, usize> = HashMap::new();
+    let mut batches: Vec<Vec<&Variable>> = Vec::new();
+    for (node, var) in ordered_variables {
+        let level = node
+            .dependencies
+            .iter()
+            .flatten()
+            .filter_map(|dependency| levels.get(dependency))
+            .map(|level| level + 1)
+            .max()
+            .unwrap_or(0);
+        levels.insert(node.name, level);
+
+        if batches.len() <= level {
+            batches.resize_with(level + 1, Vec::new);
+        }
+        batches[level].push(var);
+    }
+
+    // Make the order within each batch deterministic, local variables first
+    let position = |var: &Variable| {
+        local_vars
+            .iter()
+            .position(|local| std::ptr::eq(*local, var))
+            .or_else(|| {
+                global_vars
+                    .iter()
+                    .position(|global| std::ptr::eq(*global, var))
+                    .map(|index| local_vars.len() + index)
+            })
+            .unwrap_or(usize::MAX)
+    };
+    for batch in &mut batches {
+        batch.sort_by_key(|var| position(var));
+    }
+
+    Ok(batches)
 }
 
 fn generate_nodes<'a>(
     body: &'a str,
     local_vars: &'a [&'a Variable],
     global_vars: &'a [&'a Variable],
+    sequential: bool,
+    reads_scope: &dyn Fn(&Variable) -> bool,
 ) -> HashMap<&'a str, Node<'a>> {
     let mut local_vars_nodes = Vec::new();
     for (index, var) in local_vars.iter().enumerate() {
@@ -80,20 +136,36 @@ fn generate_nodes<'a>(
             dependencies.extend(super::util::get_params_variable_names(&var.params));
         }
         dependencies.extend(var.depends_on.iter().map(String::as_str));
+        let optional_dependencies = get_optional_dependencies(var);
 
-        // Every local variable depends on the one before it.
-        // Needed to guarantee execution order within local vars.
-        if index > 0 {
-            let previous_var = local_vars.get(index - 1);
-            if let Some(previous_var) = previous_var {
-                dependencies.insert(&previous_var.name);
+        if sequential {
+            // Every local variable depends on the one before it.
+            // Needed to guarantee execution order within local vars.
+            if index > 0 {
+                let previous_var = local_vars.get(index - 1);
+                if let Some(previous_var) = previous_var {
+                    dependencies.insert(&previous_var.name);
+                }
             }
+        } else if reads_scope(var) {
+            // The variable can read the previous ones without the `{{name}}`
+            // syntax, for example through env variables, so it must be
+            // evaluated after the ones it mentions
+            dependencies.extend(
+                local_vars[..index]
+                    .iter()
+                    .filter(|previous_var| {
+                        super::util::params_mention_variable(&var.params, &previous_var.name)
+                    })
+                    .map(|previous_var| previous_var.name.as_str()),
+            );
         }
 
         local_vars_nodes.push(Node {
             name: &var.name,
             variable: Some(var),
             dependencies: Some(dependencies),
+            optional_dependencies,
         });
     }
 
@@ -102,12 +174,14 @@ fn generate_nodes<'a>(
     // The body depends on all local variables + the variables read inside it (which might be global)
     let mut body_dependencies: HashSet<&str> =
         local_vars_nodes.iter().map(|node| node.name).collect();
-    body_dependencies.extend(super::util::get_body_variable_names(body));
+    let blocks = super::block::parse_or_literal(body);
+    super::block::get_variable_names(&blocks, &mut body_dependencies);
 
     let body_node = Node {
         name: "__match_body",
         variable: None,
         dependencies: Some(body_dependencies),
+        optional_dependencies: super::util::get_body_optional_variable_names(body),
     };
 
     let mut node_map = HashMap::new();
@@ -142,9 +216,20 @@ fn create_node_from_var(var: &'_ Variable) -> Node<'_> {
         name: &var.name,
         variable: Some(var),
         dependencies,
+        optional_dependencies: get_optional_dependencies(var),
     }
 }
 
+fn get_optional_dependencies(var: &Variable) -> HashSet<&str> {
+    if !var.inject_vars {
+        return HashSet::new();
+    }
+
+    let mut optional = super::util::get_params_optional_variable_names(&var.params);
+    optional.retain(|name| !var.depends_on.iter().any(|dependency| dependency == name));
+    optional
+}
+
 fn resolve_dependencies<'a>(
     node: &'a Node,
     node_map: &'a HashMap<&'a str, Node<'a>>,
@@ -179,7 +264,7 @@ fn resolve_dependencies<'a>(
 
                 if let Some(dependency_node) = node_map.get(dependency) {
                     resolve_dependencies(dependency_node, node_map, eval_order, resolved, seen)?;
-                } else {
+                } else if !node.optional_dependencies.contains(dependency) {
                     error!("could not resolve variable {dependency:?}");
                     if let Some(variable) = &node.variable {
                         if variable.var_type == "form" {
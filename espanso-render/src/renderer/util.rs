@@ -22,7 +22,10 @@ use anyhow::Result;
 use log::error;
 use regex::Captures;
 
-use super::VAR_REGEX;
+use super::{
+    filter::{parse_filters, FilterRegistry},
+    VAR_REGEX,
+};
 use std::collections::HashSet;
 
 pub fn get_body_variable_names(body: &str) -> HashSet<&str> {
@@ -66,13 +69,129 @@ fn get_value_variable_names_recursively(value: &Value) -> HashSet<&str> {
     }
 }
 
-pub fn render_variables(body: &str, scope: &Scope) -> Result<String> {
+// Variables that are only referenced with filters, such as `{{name|default:"none"}}`,
+// are optional. When they are missing, the reference is either rendered through its
+// `default` filter or kept literally, as it might not be a variable at all.
+pub fn get_body_optional_variable_names(body: &str) -> HashSet<&str> {
+    let mut required = HashSet::new();
+    let mut filtered = HashSet::new();
+    collect_variable_references(body, &mut required, &mut filtered);
+    filtered.retain(|name| !required.contains(name));
+    filtered
+}
+
+pub fn get_params_optional_variable_names(params: &Params) -> HashSet<&str> {
+    let mut required = HashSet::new();
+    let mut filtered = HashSet::new();
+    for value in params.values() {
+        collect_value_variable_references(value, &mut required, &mut filtered);
+    }
+    filtered.retain(|name| !required.contains(name));
+    filtered
+}
+
+fn collect_variable_references<'a>(
+    body: &'a str,
+    required: &mut HashSet<&'a str>,
+    filtered: &mut HashSet<&'a str>,
+) {
+    for caps in VAR_REGEX.captures_iter(body) {
+        let var_name = caps.name("name").unwrap().as_str();
+        if caps
+            .name("filters")
+            .is_some_and(|filters| !filters.as_str().is_empty())
+        {
+            filtered.insert(var_name);
+        } else {
+            required.insert(var_name);
+        }
+    }
+}
+
+fn collect_value_variable_references<'a>(
+    value: &'a Value,
+    required: &mut HashSet<&'a str>,
+    filtered: &mut HashSet<&'a str>,
+) {
+    match value {
+        Value::String(s_value) => collect_variable_references(s_value, required, filtered),
+        Value::Array(values) => {
+            for value in values {
+                collect_value_variable_references(value, required, filtered);
+            }
+        }
+        Value::Object(fields) => {
+            for value in fields.values() {
+                collect_value_variable_references(value, required, filtered);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Check if any of the params mentions the given variable name, even without the
+// `{{name}}` syntax. Shell and script vars, for example, can read it from the
+// ESPANSO_NAME env variable, while rhai ones can use the identifier or vars["name"].
+// The check is conservative, false positives only prevent a variable from being
+// evaluated concurrently.
+pub fn params_mention_variable(params: &Params, name: &str) -> bool {
+    let name = name.to_lowercase();
+    params
+        .values()
+        .any(|value| value_mentions_variable_recursively(value, &name))
+}
+
+fn value_mentions_variable_recursively(value: &Value, name: &str) -> bool {
+    match value {
+        Value::String(s_value) => string_mentions_variable(&s_value.to_lowercase(), name),
+        Value::Array(values) => values
+            .iter()
+            .any(|value| value_mentions_variable_recursively(value, name)),
+        Value::Object(fields) => fields
+            .values()
+            .any(|value| value_mentions_variable_recursively(value, name)),
+        _ => false,
+    }
+}
+
+// The name must not be part of a longer identifier, except for a prefix
+// such as the `ESPANSO_` one of the env variables
+fn string_mentions_variable(string: &str, name: &str) -> bool {
+    if name.is_empty() {
+        return false;
+    }
+
+    string.match_indices(name).any(|(start, _)| {
+        let end = start + name.len();
+        let is_prefixed = string[..start]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric);
+        let is_suffixed = string[end..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        !is_prefixed && !is_suffixed
+    })
+}
+
+pub fn render_variables(body: &str, scope: &Scope, filters: &FilterRegistry) -> Result<String> {
     let mut replacing_error = None;
     let output = VAR_REGEX
         .replace_all(body, |caps: &Captures| {
             let var_name = caps.name("name").unwrap().as_str();
             let var_subname = caps.name("subname");
-            if let Some(output) = scope.get(var_name) {
+            let calls = caps
+                .name("filters")
+                .map(|filters| parse_filters(filters.as_str()))
+                .unwrap_or_default();
+
+            // References with unknown filters, such as `{{a|b}}`, are not variables
+            if calls.iter().any(|call| !filters.contains(&call.name)) {
+                return caps[0].to_string();
+            }
+
+            let value = if let Some(output) = scope.get(var_name) {
                 match output {
                     ExtensionOutput::Single(output) => output,
                     ExtensionOutput::Multiple(results) => {
@@ -81,24 +200,43 @@ pub fn render_variables(body: &str, scope: &Scope) -> Result<String> {
                             results.get(var_subname).map_or("", |value| value)
                         } else {
                             error!("nested name missing from multi-value variable: {var_name}");
-                            replacing_error = Some(RendererError::MissingVariable(format!(
-                                "nested name missing from multi-value variable: {var_name}"
-                            )));
+                            replacing_error = Some(
+                                RendererError::MissingVariable(format!(
+                                    "nested name missing from multi-value variable: {var_name}"
+                                ))
+                                .into(),
+                            );
                             ""
                         }
                     }
                 }
+            } else if calls.iter().any(|call| call.name == "default") {
+                ""
+            } else if !calls.is_empty() {
+                return caps[0].to_string();
             } else {
-                replacing_error = Some(RendererError::MissingVariable(format!(
-                    "variable '{var_name}' is missing"
-                )));
+                replacing_error = Some(
+                    RendererError::MissingVariable(format!("variable '{var_name}' is missing"))
+                        .into(),
+                );
                 ""
+            };
+
+            if calls.is_empty() {
+                value.to_string()
+            } else {
+                filters
+                    .apply(value.to_string(), &calls)
+                    .unwrap_or_else(|err| {
+                        replacing_error = Some(err);
+                        String::new()
+                    })
             }
         })
         .to_string();
 
     if let Some(error) = replacing_error {
-        return Err(error.into());
+        return Err(error);
     }
 
     let unescaped_output = unescape_variable_inections(&output);
@@ -109,20 +247,28 @@ pub fn unescape_variable_inections(body: &str) -> String {
     body.replace("\\{\\{", "{{").replace("\\}\\}", "}}")
 }
 
-pub fn inject_variables_into_params(params: &Params, scope: &Scope) -> Result<Params> {
+pub fn inject_variables_into_params(
+    params: &Params,
+    scope: &Scope,
+    filters: &FilterRegistry,
+) -> Result<Params> {
     let mut params = params.clone();
 
     for value in &mut params.values_mut() {
-        inject_variables_into_value(value, scope)?;
+        inject_variables_into_value(value, scope, filters)?;
     }
 
     Ok(params)
 }
 
-fn inject_variables_into_value(value: &mut Value, scope: &Scope) -> Result<()> {
+fn inject_variables_into_value(
+    value: &mut Value,
+    scope: &Scope,
+    filters: &FilterRegistry,
+) -> Result<()> {
     match value {
         Value::String(s_value) => {
-            let new_value = render_variables(s_value, scope)?;
+            let new_value = render_variables(s_value, scope, filters)?;
 
             if &new_value != s_value {
                 s_value.clear();
@@ -131,12 +277,12 @@ fn inject_variables_into_value(value: &mut Value, scope: &Scope) -> Result<()> {
         }
         Value::Array(values) => {
             for value in values {
-                inject_variables_into_value(value, scope)?;
+                inject_variables_into_value(value, scope, filters)?;
             }
         }
         Value::Object(fields) => {
             for value in fields.values_mut() {
-                inject_variables_into_value(value, scope)?;
+                inject_variables_into_value(value, scope, filters)?;
             }
         }
         _ => {}
@@ -166,6 +312,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn params_mention_variable_matches_whole_names() {
+        let params = |value: &str| {
+            Params::from_iter(vec![("cmd".to_string(), Value::String(value.to_string()))])
+        };
+        assert!(params_mention_variable(
+            &params("echo $ESPANSO_NAME"),
+            "name"
+        ));
+        assert!(params_mention_variable(
+            &params("vars[\"name\"] + 1"),
+            "name"
+        ));
+        assert!(params_mention_variable(&params("name * 2"), "NAME"));
+        assert!(params_mention_variable(
+            &params("echo %ESPANSO_FIRST_NAME%"),
+            "first_name"
+        ));
+        assert!(!params_mention_variable(
+            &params("echo $ESPANSO_NAMES"),
+            "name"
+        ));
+        assert!(!params_mention_variable(&params("rename foo"), "name"));
+        assert!(!params_mention_variable(&params("sleep 0.3; echo x"), "a"));
+    }
+
     #[test]
     fn test_inject_variables_into_params() {
         let mut params = Params::new();
@@ -189,7 +361,8 @@ mod tests {
         let mut scope = Scope::new();
         scope.insert("first", ExtensionOutput::Single("one".to_string()));
 
-        let result = inject_variables_into_params(&params, &scope).unwrap();
+        let result =
+            inject_variables_into_params(&params, &scope, &FilterRegistry::new(&[])).unwrap();
 
         assert_eq!(result.len(), 4);
         assert_eq!(
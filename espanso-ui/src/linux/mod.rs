@@ -117,7 +117,7 @@ impl UIEventLoop for LinuxEventLoop {
                   }
                 }
               },
-              default(Duration::from_millis(1000)) => {
+              default(Duration::from_secs(1)) => {
                 (*callback)(UIEvent::Heartbeat);
               }
             }
@@ -0,0 +1,94 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::Path;
+
+use espanso_config::error::{locate_error, ErrorLevel, ErrorRecord};
+
+use super::{CliModule, CliModuleArgs};
+use crate::exit_code::{CHECK_ERRORS_FOUND, CHECK_FATAL_ERROR, CHECK_SUCCESS};
+
+pub fn new() -> CliModule {
+    CliModule {
+        requires_paths: true,
+        subcommand: "check".to_string(),
+        entry: check_main,
+        ..Default::default()
+    }
+}
+
+fn check_main(args: CliModuleArgs) -> i32 {
+    let paths = args.paths.expect("missing paths argument");
+    let cli_args = args.cli_args.expect("missing cli_args argument");
+    let strict = cli_args.is_present("strict");
+
+    // The config is loaded here instead of relying on `requires_config`,
+    // because fatal errors must be reported rather than aborting the process
+    let non_fatal_errors = match espanso_config::load(&paths.config) {
+        Ok((_, _, non_fatal_errors)) => non_fatal_errors,
+        Err(err) => {
+            match locate_error(&err) {
+                Some(location) => println!("error: {err:#} ({location})"),
+                None => println!("error: {err:#}"),
+            }
+            return CHECK_FATAL_ERROR;
+        }
+    };
+
+    let mut error_count = 0;
+    let mut warning_count = 0;
+    for error_set in &non_fatal_errors {
+        for record in &error_set.errors {
+            match record.level {
+                ErrorLevel::Error => error_count += 1,
+                ErrorLevel::Warning => warning_count += 1,
+            }
+            println!("{}", format_record(&error_set.file, record));
+        }
+    }
+
+    println!("{error_count} error(s), {warning_count} warning(s)");
+
+    if error_count > 0 || (strict && warning_count > 0) {
+        CHECK_ERRORS_FOUND
+    } else {
+        CHECK_SUCCESS
+    }
+}
+
+// Use the `file:line:column: level: message` format, so that editors
+// and other tools can jump straight to the reported position
+fn format_record(file: &Path, record: &ErrorRecord) -> String {
+    let level = match record.level {
+        ErrorLevel::Error => "error",
+        ErrorLevel::Warning => "warning",
+    };
+
+    match record.location {
+        Some(location) => format!(
+            "{}:{}:{}: {}: {:#}",
+            file.to_string_lossy(),
+            location.line,
+            location.column,
+            level,
+            record.error
+        ),
+        None => format!("{}: {}: {:#}", file.to_string_lossy(), level, record.error),
+    }
+}
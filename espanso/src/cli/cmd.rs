@@ -50,6 +50,8 @@ fn cmd_main(args: CliModuleArgs) -> i32 {
         IPCEvent::ToggleRequest
     } else if cli_args.subcommand_matches("search").is_some() {
         IPCEvent::OpenSearchBar
+    } else if cli_args.subcommand_matches("undo").is_some() {
+        IPCEvent::UndoRequest
     } else if let Some(subcommand_matches) = cli_args.subcommand_matches("search") {
         IPCEvent::OpenConfigFolder
     } else {
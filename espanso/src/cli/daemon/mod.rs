@@ -17,13 +17,18 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{path::Path, process::Command, time::Instant};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::Instant,
+};
 
 use crate::path::Paths;
 use crossbeam::{
     channel::{unbounded, Sender},
     select,
 };
+use espanso_config::matches::store::MatchStore;
 use espanso_ipc::IPCClient;
 use log::{error, info, warn};
 
@@ -85,8 +90,9 @@ fn daemon_main(args: CliModuleArgs) -> i32 {
     let mut _current_troubleshoot_guard = None;
 
     let (watcher_notify, watcher_signal) = unbounded::<()>();
+    let (referenced_files_notify, referenced_files_signal) = unbounded();
 
-    watcher::initialize_and_spawn(&paths.config, watcher_notify)
+    watcher::initialize_and_spawn(&paths.config, watcher_notify, referenced_files_signal)
         .expect("unable to initialize config watcher thread");
 
     let (keyboard_layout_watcher_notify, keyboard_layout_watcher_signal) = unbounded::<()>();
@@ -107,6 +113,7 @@ fn daemon_main(args: CliModuleArgs) -> i32 {
         ) {
             Ok((result, guard)) => {
                 _current_troubleshoot_guard = guard;
+                notify_referenced_files(&referenced_files_notify, &*result.match_store, &paths);
                 result.config_store
             }
             Err(err) => {
@@ -136,28 +143,30 @@ fn daemon_main(args: CliModuleArgs) -> i32 {
               continue;
             }
 
-            info!("configuration change detected, restarting worker process...");
+            info!("configuration change detected, reloading worker configuration...");
 
-            // Before killing the previous worker, we make sure there is no fatal error
+            // Before reloading the worker, we make sure there is no fatal error
             // in the configs.
-            let should_restart_worker = match troubleshoot::load_config_or_troubleshoot(&paths, &paths_overrides) {
-              troubleshoot::LoadResult::Correct(_) => {
+            let should_reload_worker = match troubleshoot::load_config_or_troubleshoot(&paths, &paths_overrides) {
+              troubleshoot::LoadResult::Correct(result) => {
                 _current_troubleshoot_guard = None;
+                notify_referenced_files(&referenced_files_notify, &*result.match_store, &paths);
                 true
               },
-              troubleshoot::LoadResult::Warning(_, guard) => {
+              troubleshoot::LoadResult::Warning(result, guard) => {
                 _current_troubleshoot_guard = guard;
+                notify_referenced_files(&referenced_files_notify, &*result.match_store, &paths);
                 true
               }
               troubleshoot::LoadResult::Fatal(guard) => {
                 _current_troubleshoot_guard = Some(guard);
-                error!("critical error while loading config, could not restart worker");
+                error!("critical error while loading config, could not reload worker");
                 false
               }
             };
 
-            if should_restart_worker {
-              restart_worker(&paths, &paths_overrides, exit_notify.clone(), Some(WORKER_START_REASON_CONFIG_CHANGED.to_string()));
+            if should_reload_worker {
+              reload_worker(&paths, &paths_overrides, exit_notify.clone());
             }
           }
           recv(keyboard_layout_watcher_signal) -> _ => {
@@ -194,6 +203,17 @@ fn daemon_main(args: CliModuleArgs) -> i32 {
     DAEMON_SUCCESS
 }
 
+fn notify_referenced_files(
+    referenced_files_notify: &Sender<Vec<PathBuf>>,
+    match_store: &dyn MatchStore,
+    paths: &Paths,
+) {
+    let files = watcher::get_referenced_files(match_store, paths);
+    if let Err(err) = referenced_files_notify.send(files) {
+        error!("unable to send referenced files to the watcher: {err}");
+    }
+}
+
 fn terminate_worker_if_already_running(runtime_dir: &Path) {
     let lock_file = acquire_worker_lock(runtime_dir);
     if lock_file.is_some() {
@@ -273,6 +293,28 @@ fn spawn_worker(
         .expect("Unable to spawn worker monitor thread");
 }
 
+// Ask the worker to apply the new configuration without terminating, falling back
+// to a restart if it can't be reached. If some of the changes can only be applied
+// with a restart, the worker takes care of requesting it.
+fn reload_worker(paths: &Paths, paths_overrides: &PathsOverrides, exit_notify: Sender<i32>) {
+    match create_ipc_client_to_worker(&paths.runtime) {
+        Ok(mut worker_ipc) => match worker_ipc.send_async(IPCEvent::ReloadConfig) {
+            Ok(()) => return,
+            Err(err) => error!("unable to send reload signal to worker process: {err}"),
+        },
+        Err(err) => {
+            error!("could not establish IPC connection with worker: {err}");
+        }
+    }
+
+    restart_worker(
+        paths,
+        paths_overrides,
+        exit_notify,
+        Some(WORKER_START_REASON_CONFIG_CHANGED.to_string()),
+    );
+}
+
 fn restart_worker(
     paths: &Paths,
     paths_overrides: &PathsOverrides,
@@ -17,18 +17,37 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{path::Path, time::Duration};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc::RecvTimeoutError,
+    time::Duration,
+};
 
 use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 
 use anyhow::Result;
-use crossbeam::{channel::Sender, select};
+use crossbeam::{
+    channel::{Receiver, Sender},
+    select,
+};
+use espanso_config::matches::{store::MatchStore, MatchEffect, Value};
+use espanso_render::extension::file::normalize_path;
 use log::{error, info, warn};
 
+use crate::path::Paths;
+
 const WATCHER_NOTIFY_DELAY_MS: u64 = 500;
 const WATCHER_DEBOUNCE_DURATION_MS: u64 = 1000;
 
-pub fn initialize_and_spawn(config_dir: &Path, watcher_notify: Sender<()>) -> Result<()> {
+// How often the watcher checks for updates of the referenced files
+const WATCHER_REFERENCED_FILES_POLL_MS: u64 = 500;
+
+pub fn initialize_and_spawn(
+    config_dir: &Path,
+    watcher_notify: Sender<()>,
+    referenced_files_rx: Receiver<Vec<PathBuf>>,
+) -> Result<()> {
     let config_dir = config_dir.to_path_buf();
 
     let (debounce_tx, debounce_rx) = crossbeam::channel::unbounded();
@@ -36,7 +55,7 @@ pub fn initialize_and_spawn(config_dir: &Path, watcher_notify: Sender<()>) -> Re
     std::thread::Builder::new()
         .name("watcher".to_string())
         .spawn(move || {
-            watcher_main(&config_dir, debounce_tx);
+            watcher_main(&config_dir, debounce_tx, &referenced_files_rx);
         })?;
 
     std::thread::Builder::new()
@@ -48,7 +67,55 @@ pub fn initialize_and_spawn(config_dir: &Path, watcher_notify: Sender<()>) -> Re
     Ok(())
 }
 
-fn watcher_main(config_dir: &Path, debounce_tx: Sender<()>) {
+// Collect the files read by the `file` variables of the given matches, along
+// with the loaded match files, so that the watcher can reload the configuration
+// when they change
+pub fn get_referenced_files(match_store: &dyn MatchStore, paths: &Paths) -> Vec<PathBuf> {
+    let home_path = dirs::home_dir().unwrap_or_default();
+    let match_set = match_store.query(&match_store.loaded_paths());
+
+    let match_vars = match_set
+        .matches
+        .iter()
+        .filter_map(|m| match &m.effect {
+            MatchEffect::Text(effect) => Some(effect.vars.iter()),
+            _ => None,
+        })
+        .flatten();
+
+    let mut files: Vec<PathBuf> = match_vars
+        .chain(match_set.global_vars.iter().copied())
+        .filter(|var| var.var_type == "file")
+        .filter_map(|var| match var.params.get("path") {
+            // Paths depending on other variables can only be known when rendering
+            Some(Value::String(path)) if !path.contains("{{") => {
+                Some(espanso_render::extension::file::resolve_path(
+                    path,
+                    &paths.config,
+                    &home_path,
+                    &paths.packages,
+                ))
+            }
+            _ => None,
+        })
+        .collect();
+    // Match files can import others whose names aren't picked up automatically
+    files.extend(
+        match_store
+            .loaded_paths()
+            .iter()
+            .map(|path| normalize_path(Path::new(path))),
+    );
+    files.sort();
+    files.dedup();
+    files
+}
+
+fn watcher_main(
+    config_dir: &Path,
+    debounce_tx: Sender<()>,
+    referenced_files_rx: &Receiver<Vec<PathBuf>>,
+) {
     let (tx, rx) = std::sync::mpsc::channel();
 
     let mut watcher: RecommendedWatcher =
@@ -67,8 +134,26 @@ fn watcher_main(config_dir: &Path, debounce_tx: Sender<()>) {
 
     info!("watching for changes in path: {}", config_dir.display());
 
+    // The referenced files are normalized, so the config dir must be too
+    let config_dir = normalize_path(config_dir);
+
+    let mut referenced_files = HashSet::new();
+    let mut external_dirs = HashSet::new();
+
     loop {
-        let should_reload = match rx.recv() {
+        while let Ok(files) = referenced_files_rx.try_recv() {
+            update_referenced_files(
+                &mut watcher,
+                &config_dir,
+                files,
+                &mut referenced_files,
+                &mut external_dirs,
+            );
+        }
+
+        let should_reload = match rx
+            .recv_timeout(Duration::from_millis(WATCHER_REFERENCED_FILES_POLL_MS))
+        {
             Ok(event) => {
                 let path = match event {
                     DebouncedEvent::Create(path) => Some(path),
@@ -78,24 +163,9 @@ fn watcher_main(config_dir: &Path, debounce_tx: Sender<()>) {
                     _ => None,
                 };
 
-                if let Some(path) = path {
-                    let extension = path
-                        .extension()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_ascii_lowercase();
-
-                    if ["yml", "yaml"].iter().any(|ext| ext == &extension) {
-                        // Only load non-hidden yml files
-                        !is_file_hidden(&path)
-                    } else {
-                        // If there is no extension, it's probably a folder
-                        extension.is_empty()
-                    }
-                } else {
-                    false
-                }
+                path.is_some_and(|path| is_reload_trigger(&path, &config_dir, &referenced_files))
             }
+            Err(RecvTimeoutError::Timeout) => false,
             Err(e) => {
                 warn!("error while watching files: {e:?}");
                 false
@@ -110,6 +180,89 @@ fn watcher_main(config_dir: &Path, debounce_tx: Sender<()>) {
     }
 }
 
+// Check if a change to the given path requires reloading the configuration.
+// Both the config dir and the referenced files must be normalized.
+fn is_reload_trigger(path: &Path, config_dir: &Path, referenced_files: &HashSet<PathBuf>) -> bool {
+    let path = normalize_path(path);
+    if referenced_files.contains(&path) {
+        true
+    } else if !path.starts_with(config_dir) {
+        // Other files in the directories of the referenced files
+        false
+    } else {
+        is_config_file_or_folder(&path)
+    }
+}
+
+// Referenced files outside of the config directory are watched through their
+// parent folder, as many editors save files by replacing them
+fn update_referenced_files(
+    watcher: &mut RecommendedWatcher,
+    config_dir: &Path,
+    files: Vec<PathBuf>,
+    referenced_files: &mut HashSet<PathBuf>,
+    external_dirs: &mut HashSet<PathBuf>,
+) {
+    let dirs: HashSet<PathBuf> = files
+        .iter()
+        .filter(|file| !file.starts_with(config_dir))
+        .filter_map(|file| file.parent())
+        .filter(|dir| dir.is_dir())
+        .map(Path::to_path_buf)
+        .collect();
+
+    external_dirs.retain(|dir| {
+        if dirs.contains(dir) {
+            return true;
+        }
+        if let Err(err) = watcher.unwatch(dir) {
+            warn!("unable to stop watching path {}: {err}", dir.display());
+        }
+        false
+    });
+
+    for dir in dirs {
+        if external_dirs.contains(&dir) {
+            continue;
+        }
+        if let Err(err) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            warn!("unable to watch path {}: {err}", dir.display());
+        } else {
+            info!("watching for changes in path: {}", dir.display());
+            external_dirs.insert(dir);
+        }
+    }
+
+    *referenced_files = files.into_iter().collect();
+}
+
+// Mirror the files loaded automatically from the config dir: TOML and JSON
+// files need the `.match` suffix, as the other ones (such as package manifests)
+// aren't loaded. Files imported explicitly are tracked as referenced files.
+fn is_config_file_or_folder(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_ascii_lowercase();
+
+    let is_config_file = match extension.as_str() {
+        "yml" | "yaml" => true,
+        "toml" | "json" => path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_ascii_lowercase()
+            .ends_with(".match"),
+        // If there is no extension, it's probably a folder
+        "" => return true,
+        _ => false,
+    };
+
+    // Only load non-hidden config and match files
+    is_config_file && !is_file_hidden(path)
+}
+
 fn debouncer_main(debounce_rx: crossbeam::channel::Receiver<()>, watcher_notify: &Sender<()>) {
     let mut has_received_event = false;
 
@@ -157,3 +310,89 @@ fn has_hidden_attribute(path: &Path) -> bool {
 fn has_hidden_attribute(_: &Path) -> bool {
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn referenced_files_with_parent_components_trigger_reload() {
+        let dir = TempDir::new("espanso-watcher").unwrap();
+        let config_dir = dir.path().join("config");
+        std::fs::create_dir_all(config_dir.join("match")).unwrap();
+        std::fs::write(dir.path().join("email.md"), "").unwrap();
+
+        let referenced_files: HashSet<PathBuf> = [espanso_render::extension::file::resolve_path(
+            "../email.md",
+            &config_dir,
+            dir.path(),
+            dir.path(),
+        )]
+        .into_iter()
+        .collect();
+        let config_dir = normalize_path(&config_dir);
+
+        // The watcher reports the path of the parent folder it watches
+        assert!(is_reload_trigger(
+            &dir.path().join("email.md"),
+            &config_dir,
+            &referenced_files
+        ));
+        assert!(is_reload_trigger(
+            &dir.path().join("config/match/../../email.md"),
+            &config_dir,
+            &referenced_files
+        ));
+
+        // Files outside of the config dir can't sneak in with `..` components
+        assert!(!is_reload_trigger(
+            &dir.path().join("config/../other.yml"),
+            &config_dir,
+            &referenced_files
+        ));
+        assert!(!is_reload_trigger(
+            &dir.path().join("other.md"),
+            &config_dir,
+            &referenced_files
+        ));
+        assert!(is_reload_trigger(
+            &dir.path().join("config/match/base.yml"),
+            &config_dir,
+            &referenced_files
+        ));
+    }
+
+    #[test]
+    fn only_toml_and_json_match_files_trigger_reload() {
+        let dir = TempDir::new("espanso-watcher").unwrap();
+        let config_dir = normalize_path(dir.path());
+        let imported = config_dir.join("match/imported.toml");
+        let referenced_files: HashSet<PathBuf> = [imported.clone()].into_iter().collect();
+
+        for path in [
+            "match/team.match.toml",
+            "match/sub/team.MATCH.json",
+            "match",
+        ] {
+            assert!(
+                is_reload_trigger(&config_dir.join(path), &config_dir, &referenced_files),
+                "{path}"
+            );
+        }
+        for path in [
+            "match/data.toml",
+            "match/package.json",
+            ".vscode/settings.json",
+            "match/notes.txt",
+        ] {
+            assert!(
+                !is_reload_trigger(&config_dir.join(path), &config_dir, &referenced_files),
+                "{path}"
+            );
+        }
+
+        // Explicitly imported files are reloaded regardless of their name
+        assert!(is_reload_trigger(&imported, &config_dir, &referenced_files));
+    }
+}
@@ -0,0 +1,177 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::{anyhow, Result};
+use espanso_clipboard::ClipboardOptions;
+use espanso_info::{AppInfo, AppInfoProvider};
+use espanso_render::extension::{
+    choice::{ChoiceSelector, ChoiceSelectorResult},
+    clipboard::ClipboardProvider,
+    form::{FormProvider, FormProviderResult},
+    state::StateProvider,
+};
+
+use crate::{
+    cli::worker::{
+        config::ConfigManager,
+        engine::process::middleware::render::{
+            extension::{clipboard::ClipboardAdapter, state, ExtensionProviders, RenderExtensions},
+            RendererAdapter,
+        },
+        match_cache::MatchCache,
+    },
+    path::Paths,
+};
+
+// Pretends the given application is always focused, so that the
+// worker components pick the config (and matches) active for it
+pub struct FixedAppInfoProvider {
+    info: AppInfo,
+}
+
+impl FixedAppInfoProvider {
+    pub fn new(title: Option<&str>, class: Option<&str>, exec: Option<&str>) -> Self {
+        Self {
+            info: AppInfo {
+                title: title.map(String::from),
+                exec: exec.map(String::from),
+                class: class.map(String::from),
+            },
+        }
+    }
+}
+
+impl AppInfoProvider for FixedAppInfoProvider {
+    fn get_info(&self) -> AppInfo {
+        self.info.clone()
+    }
+}
+
+// Build the renderer with the same extensions as the worker and pass it to the callback.
+// Forms and choices are interactive, so they produce an error instead
+// of blocking a dry run, while state changes are never persisted.
+pub fn with_renderer<R>(
+    paths: &Paths,
+    config_manager: &ConfigManager,
+    match_cache: &MatchCache,
+    callback: impl for<'a> FnOnce(&'a RendererAdapter<'a>) -> R,
+) -> R {
+    let home_path = dirs::home_dir().expect("unable to obtain home dir path");
+
+    let clipboard = espanso_clipboard::get_clipboard(ClipboardOptions::default()).ok();
+    let clipboard_adapter = clipboard
+        .as_deref()
+        .map(|clipboard| ClipboardAdapter::new(clipboard, config_manager));
+    let clipboard_provider = OptionalClipboardProvider {
+        adapter: clipboard_adapter.as_ref(),
+    };
+    let state_adapter = state::create(&paths.runtime).ok();
+    let state_provider = DryRunStateProvider {
+        adapter: state_adapter
+            .as_ref()
+            .map(|adapter| adapter as &dyn StateProvider),
+        changes: RefCell::new(HashMap::new()),
+    };
+    let extensions = RenderExtensions::new(
+        paths,
+        &home_path,
+        &ExtensionProviders {
+            clipboard: &clipboard_provider,
+            form: &NonInteractiveProvider,
+            choice: &NonInteractiveProvider,
+            state: &state_provider,
+        },
+    );
+    let renderer = extensions.create_renderer();
+    let renderer_adapter = RendererAdapter::new(match_cache, config_manager, &renderer);
+
+    callback(&renderer_adapter)
+}
+
+struct OptionalClipboardProvider<'a> {
+    adapter: Option<&'a ClipboardAdapter<'a>>,
+}
+
+impl ClipboardProvider for OptionalClipboardProvider<'_> {
+    fn get_text(&self) -> Option<String> {
+        self.adapter.and_then(ClipboardProvider::get_text)
+    }
+}
+
+// Reads the persisted state, but keeps any change in memory
+struct DryRunStateProvider<'a> {
+    adapter: Option<&'a dyn StateProvider>,
+    changes: RefCell<HashMap<String, String>>,
+}
+
+impl StateProvider for DryRunStateProvider<'_> {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        if let Some(value) = self.changes.borrow().get(name) {
+            return Ok(Some(value.clone()));
+        }
+
+        match self.adapter {
+            Some(adapter) => adapter.get(name),
+            None => Ok(None),
+        }
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        self.changes
+            .borrow_mut()
+            .insert(name.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn increment(&self, name: &str, step: i64, initial: i64) -> Result<i64> {
+        let current = match self.get(name)? {
+            Some(value) => value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| anyhow!("state value '{}' is not a number: '{}'", name, value))?,
+            None => initial,
+        };
+        let updated = current
+            .checked_add(step)
+            .ok_or_else(|| anyhow!("state value '{}' overflowed", name))?;
+        self.set(name, &updated.to_string())?;
+        Ok(updated)
+    }
+}
+
+struct NonInteractiveProvider;
+
+impl FormProvider for NonInteractiveProvider {
+    fn show(
+        &self,
+        _: &str,
+        _: &espanso_render::Params,
+        _: &espanso_render::Params,
+    ) -> FormProviderResult {
+        FormProviderResult::Error(anyhow!("forms can't be shown during a dry run"))
+    }
+}
+
+impl ChoiceSelector for NonInteractiveProvider {
+    fn show(&self, _: &[espanso_render::extension::choice::Choice]) -> ChoiceSelectorResult {
+        ChoiceSelectorResult::Error(anyhow!("choices can't be shown during a dry run"))
+    }
+}
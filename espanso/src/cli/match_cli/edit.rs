@@ -0,0 +1,147 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use espanso_config::{
+    config::ConfigStore,
+    matches::{
+        edit::{add_match, remove_match, update_match, MatchEditError, MatchFields},
+        store::MatchStore,
+        MatchCause,
+    },
+};
+
+use crate::path::Paths;
+
+pub fn add_main(
+    cli_args: &ArgMatches,
+    paths: &Paths,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+) -> Result<()> {
+    let trigger = required_trigger(cli_args, "add")?;
+    check_trigger_is_unused(trigger, config_store, match_store)?;
+
+    let target = determine_target_path(&paths.config, cli_args.value_of("file"));
+    let content = if target.is_file() {
+        std::fs::read_to_string(&target)?
+    } else {
+        String::new()
+    };
+
+    let edited = add_match(&content, trigger, &parse_fields(cli_args))?;
+    write_target(&target, &edited)?;
+    println!("match '{trigger}' added to {}", target.display());
+    Ok(())
+}
+
+pub fn update_main(
+    cli_args: &ArgMatches,
+    paths: &Paths,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+) -> Result<()> {
+    let trigger = required_trigger(cli_args, "update")?;
+    let fields = parse_fields(cli_args);
+    if let Some(new_trigger) = fields.trigger.as_deref().filter(|new| *new != trigger) {
+        check_trigger_is_unused(new_trigger, config_store, match_store)?;
+    }
+
+    let target = determine_target_path(&paths.config, cli_args.value_of("file"));
+    let content = read_target(&target)?;
+    let edited = update_match(&content, trigger, &fields)?;
+    write_target(&target, &edited)?;
+    println!("match '{trigger}' updated in {}", target.display());
+    Ok(())
+}
+
+pub fn remove_main(cli_args: &ArgMatches, paths: &Paths) -> Result<()> {
+    let trigger = required_trigger(cli_args, "remove")?;
+
+    let target = determine_target_path(&paths.config, cli_args.value_of("file"));
+    let content = read_target(&target)?;
+    let edited = remove_match(&content, trigger)?;
+    write_target(&target, &edited)?;
+    println!("match '{trigger}' removed from {}", target.display());
+    Ok(())
+}
+
+fn required_trigger<'a>(cli_args: &'a ArgMatches, command: &str) -> Result<&'a str> {
+    match cli_args.value_of("trigger") {
+        Some(trigger) if !trigger.trim().is_empty() => Ok(trigger),
+        _ => bail!("You need to specify the --trigger 'trigger' option. Run `espanso match {} --help` for more information.", command),
+    }
+}
+
+fn parse_fields(cli_args: &ArgMatches) -> MatchFields {
+    MatchFields {
+        trigger: cli_args.value_of("new-trigger").map(String::from),
+        replace: cli_args.value_of("replace").map(String::from),
+        label: cli_args.value_of("label").map(String::from),
+        word: cli_args.value_of("word").map(|word| word == "true"),
+    }
+}
+
+// Matches defined in other files are not visible while editing a single
+// file, so check the loaded configuration to avoid shadowing them
+fn check_trigger_is_unused(
+    trigger: &str,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+) -> Result<()> {
+    let match_set = match_store.query(config_store.default().match_paths());
+    let is_used = match_set.matches.iter().any(|m| match &m.cause {
+        MatchCause::Trigger(cause) => cause.triggers.iter().any(|t| t == trigger),
+        _ => false,
+    });
+
+    if is_used {
+        return Err(MatchEditError::DuplicateTrigger(trigger.to_string()).into());
+    }
+    Ok(())
+}
+
+fn determine_target_path(config_path: &Path, target_file: Option<&str>) -> PathBuf {
+    let target_file = target_file.unwrap_or("base.yml");
+    let is_yaml = Path::new(target_file)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yml") || ext.eq_ignore_ascii_case("yaml"));
+
+    if is_yaml {
+        config_path.join("match").join(target_file)
+    } else {
+        config_path.join("match").join(format!("{target_file}.yml"))
+    }
+}
+
+fn read_target(target: &Path) -> Result<String> {
+    std::fs::read_to_string(target)
+        .with_context(|| format!("unable to read match file: {}", target.display()))
+}
+
+fn write_target(target: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(target, content)
+        .with_context(|| format!("unable to write match file: {}", target.display()))
+}
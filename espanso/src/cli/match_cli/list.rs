@@ -37,8 +37,10 @@ pub fn list_main(
     let title = cli_args.value_of("title");
     let exec = cli_args.value_of("exec");
 
-    let config = config_store.active(&AppProperties { title, class, exec });
-    let match_set = match_store.query(config.match_paths());
+    let app = AppProperties { title, class, exec };
+    let config = config_store.active(&app);
+    let mut match_set = match_store.query(config.match_paths());
+    match_set.retain_for_app(&app);
 
     if cli_args.is_present("json") {
         print_matches_as_json(&match_set.matches)?;
@@ -59,6 +61,7 @@ pub fn print_matches_as_plain(
             MatchCause::None => vec!["(none)".to_string()],
             MatchCause::Trigger(trigger_cause) => trigger_cause.triggers.clone(),
             MatchCause::Regex(regex_cause) => vec![regex_cause.regex.clone()],
+            MatchCause::Hotkey(hotkey_cause) => vec![hotkey_cause.hotkey.clone()],
         };
 
         for trigger in triggers {
@@ -103,11 +106,13 @@ pub fn print_matches_as_json(match_list: &[&Match]) -> Result<()> {
             MatchCause::None => vec!["(none)".to_string()],
             MatchCause::Trigger(trigger_cause) => trigger_cause.triggers.clone(),
             MatchCause::Regex(regex_cause) => vec![regex_cause.regex.clone()],
+            MatchCause::Hotkey(hotkey_cause) => vec![hotkey_cause.hotkey.clone()],
         };
 
         let replace = match &m.effect {
             espanso_config::matches::MatchEffect::Text(text_effect) => text_effect.replace.clone(),
             espanso_config::matches::MatchEffect::Image(image_effect) => image_effect.path.clone(),
+            espanso_config::matches::MatchEffect::Macro(_) => m.description().to_string(),
             espanso_config::matches::MatchEffect::None => String::new(),
         };
 
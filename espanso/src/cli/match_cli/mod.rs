@@ -19,8 +19,13 @@
 
 use super::{CliModule, CliModuleArgs};
 
+mod dry_run;
+mod edit;
 mod exec;
 mod list;
+mod render;
+mod replay;
+mod simulate;
 
 pub fn new() -> CliModule {
     CliModule {
@@ -48,6 +53,36 @@ fn match_main(args: CliModuleArgs) -> i32 {
             eprintln!("unable to exec match: {err:?}");
             return 1;
         }
+    } else if let Some(sub_args) = cli_args.subcommand_matches("add") {
+        if let Err(err) = edit::add_main(sub_args, &paths, &*config_store, &*match_store) {
+            eprintln!("unable to add match: {err:?}");
+            return 1;
+        }
+    } else if let Some(sub_args) = cli_args.subcommand_matches("update") {
+        if let Err(err) = edit::update_main(sub_args, &paths, &*config_store, &*match_store) {
+            eprintln!("unable to update match: {err:?}");
+            return 1;
+        }
+    } else if let Some(sub_args) = cli_args.subcommand_matches("remove") {
+        if let Err(err) = edit::remove_main(sub_args, &paths) {
+            eprintln!("unable to remove match: {err:?}");
+            return 1;
+        }
+    } else if let Some(sub_args) = cli_args.subcommand_matches("render") {
+        if let Err(err) = render::render_main(sub_args, &paths, &*config_store, &*match_store) {
+            eprintln!("unable to render match: {err:?}");
+            return 1;
+        }
+    } else if let Some(sub_args) = cli_args.subcommand_matches("test") {
+        if let Err(err) = simulate::test_main(sub_args, &paths, &*config_store, &*match_store) {
+            eprintln!("unable to test matches: {err:?}");
+            return 1;
+        }
+    } else if let Some(sub_args) = cli_args.subcommand_matches("replay") {
+        if let Err(err) = replay::replay_main(sub_args, &paths, &*config_store, &*match_store) {
+            eprintln!("unable to replay events: {err:?}");
+            return 1;
+        }
     } else {
         eprintln!("Invalid use, please run 'espanso match --help' to get more information.");
         return 1;
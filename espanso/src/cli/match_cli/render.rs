@@ -0,0 +1,253 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use clap::ArgMatches;
+use espanso_config::{config::ConfigStore, matches::store::MatchStore};
+use espanso_engine::process::{MatchFilter, MatchResolver, Renderer, RendererError};
+use serde::Serialize;
+
+use super::dry_run::{with_renderer, FixedAppInfoProvider};
+use crate::{
+    cli::worker::{
+        config::ConfigManager,
+        match_cache::{CombinedMatchCache, MatchCache},
+    },
+    path::Paths,
+};
+
+#[derive(Serialize)]
+struct RenderOutput {
+    trigger: String,
+    output: Option<String>,
+    error: Option<RenderErrorOutput>,
+}
+
+#[derive(Serialize)]
+struct RenderErrorOutput {
+    kind: &'static str,
+    message: String,
+    causes: Vec<String>,
+}
+
+pub fn render_main(
+    cli_args: &ArgMatches,
+    paths: &Paths,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+) -> Result<()> {
+    let trigger = match cli_args.value_of("trigger") {
+        Some(trigger) if !trigger.is_empty() => trigger,
+        _ => bail!("You need to specify the --trigger 'trigger' option. Run `espanso match render --help` for more information."),
+    };
+
+    let mut match_args = HashMap::new();
+    for arg in cli_args.values_of("arg").into_iter().flatten() {
+        let Some((key, value)) = arg.split_once('=') else {
+            bail!(
+                "invalid format for argument '{}', you should follow the 'name=value' format",
+                arg
+            );
+        };
+        match_args.insert(key.to_string(), value.to_string());
+    }
+
+    let app_info_provider = FixedAppInfoProvider::new(
+        cli_args.value_of("title"),
+        cli_args.value_of("class"),
+        cli_args.value_of("exec"),
+    );
+    let result = render_trigger(
+        trigger,
+        match_args,
+        paths,
+        config_store,
+        match_store,
+        &app_info_provider,
+    );
+
+    if cli_args.is_present("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&convert_output(trigger, &result))?
+        );
+    } else if let Ok(output) = &result {
+        println!("{output}");
+    }
+
+    result.map(|_| ())
+}
+
+fn render_trigger(
+    trigger: &str,
+    match_args: HashMap<String, String>,
+    paths: &Paths,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+    app_info_provider: &FixedAppInfoProvider,
+) -> Result<String> {
+    let config_manager = ConfigManager::new(config_store, match_store, app_info_provider);
+    let match_cache = MatchCache::load(config_store, match_store);
+    let combined_match_cache = CombinedMatchCache::load(&match_cache, &[]);
+
+    // Resolve the trigger like the worker does when handling `espanso match exec`
+    let candidates: Vec<i32> = combined_match_cache
+        .find_matches_from_trigger(trigger)
+        .into_iter()
+        .map(|m| m.id)
+        .collect();
+    let Some(match_id) = config_manager.filter_active(&candidates).first().copied() else {
+        bail!("no active match found for trigger '{}'", trigger);
+    };
+
+    with_renderer(paths, &config_manager, &match_cache, |renderer| {
+        renderer.render(match_id, Some(trigger), match_args)
+    })
+}
+
+fn convert_output(trigger: &str, result: &Result<String>) -> RenderOutput {
+    match result {
+        Ok(output) => RenderOutput {
+            trigger: trigger.to_string(),
+            output: Some(output.clone()),
+            error: None,
+        },
+        Err(err) => RenderOutput {
+            trigger: trigger.to_string(),
+            output: None,
+            error: Some(convert_error(err)),
+        },
+    }
+}
+
+fn convert_error(err: &anyhow::Error) -> RenderErrorOutput {
+    let (kind, error) = match err.downcast_ref::<RendererError>() {
+        Some(RendererError::RenderingError(inner)) => ("rendering_error", inner),
+        Some(RendererError::NotFound) => ("not_found", err),
+        Some(RendererError::Aborted) => ("aborted", err),
+        None => ("unknown", err),
+    };
+
+    RenderErrorOutput {
+        kind,
+        message: error.to_string(),
+        causes: error.chain().skip(1).map(ToString::to_string).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempdir::TempDir;
+
+    fn render_with_matches(
+        matches_yaml: &str,
+        trigger: &str,
+        match_args: &[(&str, &str)],
+    ) -> serde_json::Value {
+        let dir = TempDir::new("espanso-render").unwrap();
+        let config_dir = dir.path().join("config");
+        let match_dir = dir.path().join("match");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::create_dir_all(&match_dir).unwrap();
+        std::fs::write(config_dir.join("default.yml"), "").unwrap();
+        std::fs::write(match_dir.join("base.yml"), matches_yaml).unwrap();
+
+        let (config_store, match_store, _) = espanso_config::load(dir.path()).unwrap();
+        let paths = Paths {
+            config: dir.path().to_path_buf(),
+            runtime: dir.path().join("runtime"),
+            packages: dir.path().join("packages"),
+            logs: dir.path().join("runtime"),
+            is_portable_mode: false,
+        };
+        let match_args = match_args
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+
+        let result = render_trigger(
+            trigger,
+            match_args,
+            &paths,
+            &*config_store,
+            &*match_store,
+            &FixedAppInfoProvider::new(None, None, None),
+        );
+        serde_json::to_value(convert_output(trigger, &result)).unwrap()
+    }
+
+    #[test]
+    fn json_output_on_success() {
+        assert_eq!(
+            render_with_matches(
+                "matches:\n  - trigger: \":greet\"\n    replace: \"hello {{name}}\"\n",
+                ":greet",
+                &[("name", "John")],
+            ),
+            json!({
+              "trigger": ":greet",
+              "output": "hello John",
+              "error": null,
+            })
+        );
+    }
+
+    #[test]
+    fn json_output_on_rendering_error() {
+        let output = render_with_matches(
+            "matches:\n  - trigger: \":broken\"\n    replace: \"{{missing}}\"\n",
+            ":broken",
+            &[],
+        );
+
+        assert_eq!(output["trigger"], json!(":broken"));
+        assert_eq!(output["output"], json!(null));
+        assert_eq!(output["error"]["kind"], json!("rendering_error"));
+        assert!(output["error"]["message"].is_string());
+        assert!(output["error"]["causes"].is_array());
+    }
+
+    #[test]
+    fn json_output_on_unknown_error() {
+        let result = Err(anyhow::anyhow!("no active match found for trigger ':foo'"));
+        assert_eq!(
+            serde_json::to_value(convert_output(":foo", &result)).unwrap(),
+            json!({
+              "trigger": ":foo",
+              "output": null,
+              "error": {
+                "kind": "unknown",
+                "message": "no active match found for trigger ':foo'",
+                "causes": [],
+              },
+            })
+        );
+    }
+
+    #[test]
+    fn json_output_on_not_found() {
+        let result = Err(RendererError::NotFound.into());
+        let output = serde_json::to_value(convert_output(":foo", &result)).unwrap();
+        assert_eq!(output["error"]["kind"], json!("not_found"));
+    }
+}
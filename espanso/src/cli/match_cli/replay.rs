@@ -0,0 +1,334 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, fs::File, io::BufReader};
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use espanso_config::{config::ConfigStore, matches::store::MatchStore};
+use espanso_engine::{
+    event::Event,
+    process::{
+        EventSequenceProvider, MatchSelector, Matcher, NotificationManager, Processor,
+        ProcessorState, Renderer,
+    },
+    record::{read_recording, replay, RecordedEvent, ReplaySequenceProvider},
+};
+
+use super::{
+    dry_run::{with_renderer, FixedAppInfoProvider},
+    simulate::NoModifiers,
+};
+use crate::cli::worker::{
+    builtin::get_builtin_matches,
+    config::ConfigManager,
+    context::DefaultContext,
+    engine::process::{
+        create_processor,
+        middleware::{
+            cursor_hint::CursorHintStrategyAdapter,
+            image_resolve::PathProviderAdapter,
+            matcher::{
+                convert::MatchConverter,
+                regex::{RegexMatcherAdapter, RegexMatcherAdapterOptions},
+                rolling::{RollingMatcherAdapter, RollingMatcherAdapterOptions},
+                MatcherState,
+            },
+            multiplex::MultiplexAdapter,
+            open_config::ConfigPathProviderAdapter,
+            render::RendererAdapter,
+        },
+        ProcessorProviders,
+    },
+    match_cache::{CombinedMatchCache, MatchCache},
+};
+use crate::path::Paths;
+
+pub fn replay_main(
+    cli_args: &ArgMatches,
+    paths: &Paths,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+) -> Result<()> {
+    let file = cli_args.value_of("file").expect("missing file argument");
+    let recording = read_recording(BufReader::new(
+        File::open(file).with_context(|| format!("unable to open recording: {file}"))?,
+    ))
+    .with_context(|| format!("unable to read recording: {file}"))?;
+
+    let app_info_provider = FixedAppInfoProvider::new(
+        cli_args.value_of("title"),
+        cli_args.value_of("class"),
+        cli_args.value_of("exec"),
+    );
+    let effects = replay_recording(
+        recording,
+        paths,
+        config_store,
+        match_store,
+        &app_info_provider,
+    );
+
+    if effects.is_empty() {
+        println!("no effect produced");
+    }
+
+    for effect in effects {
+        println!("{}: {:?}", effect.source_id, effect.etype);
+    }
+
+    Ok(())
+}
+
+fn replay_recording(
+    recording: Vec<RecordedEvent>,
+    paths: &Paths,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+    app_info_provider: &FixedAppInfoProvider,
+) -> Vec<Event> {
+    let sequence_provider = ReplaySequenceProvider::new();
+    with_processor(
+        paths,
+        config_store,
+        match_store,
+        app_info_provider,
+        &sequence_provider,
+        |processor| replay(processor, &sequence_provider, recording),
+    )
+}
+
+// The processor is built exactly like the worker does, but without
+// any interactive UI and without executing the resulting effects
+fn with_processor<R>(
+    paths: &Paths,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+    app_info_provider: &FixedAppInfoProvider,
+    event_sequence_provider: &dyn EventSequenceProvider,
+    callback: impl FnOnce(&mut dyn Processor) -> R,
+) -> R {
+    let config_manager = ConfigManager::new(config_store, match_store, app_info_provider);
+    let match_cache = MatchCache::load(config_store, match_store);
+
+    let default_config = config_store.default();
+    let builtin_matches = get_builtin_matches(&*default_config);
+    let combined_match_cache = CombinedMatchCache::load(&match_cache, &builtin_matches);
+    let context = DefaultContext::new(&config_manager, app_info_provider);
+    let match_converter = MatchConverter::new(config_store, match_store, &builtin_matches);
+    let rolling_matcher = RollingMatcherAdapter::new(
+        &match_converter.get_rolling_matches(),
+        RollingMatcherAdapterOptions {
+            char_word_separators: default_config.word_separators(),
+        },
+    );
+    let regex_matcher = RegexMatcherAdapter::new(
+        &match_converter.get_regex_matches(),
+        &RegexMatcherAdapterOptions {
+            max_buffer_size: default_config.max_regex_buffer_size(),
+        },
+    );
+    let multiplexer = MultiplexAdapter::new(&combined_match_cache, &context);
+    let path_provider = PathProviderAdapter::new(paths);
+    let config_path_provider = ConfigPathProviderAdapter::new(paths);
+    let cursor_hint_strategy_adapter =
+        CursorHintStrategyAdapter::new(&match_cache, &config_manager);
+    let processor_state = ProcessorState::new();
+
+    with_renderer(paths, &config_manager, &match_cache, |renderer| {
+        let renderer = ForwardingRenderer { renderer };
+        let matchers: Vec<&dyn Matcher<MatcherState>> = vec![&rolling_matcher, &regex_matcher];
+        let mut processor = create_processor(
+            &matchers,
+            &config_manager,
+            &match_cache,
+            &combined_match_cache,
+            &cursor_hint_strategy_adapter,
+            &ProcessorProviders {
+                match_selector: &FirstMatchSelector,
+                multiplexer: &multiplexer,
+                renderer: &renderer,
+                modifier_status_provider: &NoModifiers,
+                modifier_state_provider: &NoModifiers,
+                event_sequence_provider,
+                path_provider: &path_provider,
+                config_path_provider: &config_path_provider,
+                notification_manager: &NoNotifications,
+            },
+            &processor_state,
+        );
+
+        callback(&mut processor)
+    })
+}
+
+// Decouples the lifetime of the processor from the one of the renderer
+struct ForwardingRenderer<'a> {
+    renderer: &'a RendererAdapter<'a>,
+}
+
+impl<'b> Renderer<'b> for ForwardingRenderer<'_> {
+    fn render(
+        &'b self,
+        match_id: i32,
+        trigger: Option<&str>,
+        trigger_args: HashMap<String, String>,
+    ) -> Result<String> {
+        self.renderer.render(match_id, trigger, trigger_args)
+    }
+}
+
+// Picks the first match instead of showing the selection UI, while the
+// search bar is always dismissed
+struct FirstMatchSelector;
+
+impl MatchSelector for FirstMatchSelector {
+    fn select(&self, matches_ids: &[i32], is_search: bool) -> Option<i32> {
+        if is_search {
+            None
+        } else {
+            matches_ids.first().copied()
+        }
+    }
+}
+
+struct NoNotifications;
+
+impl NotificationManager for NoNotifications {
+    fn notify_status_change(&self, _: bool) {}
+
+    fn notify_rendering_error(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use espanso_engine::{
+        event::{
+            input::{Key, KeyboardEvent, Status},
+            EventType, SourceId,
+        },
+        record::{EventRecorder, RecordingProcessor},
+    };
+    use std::cell::Cell;
+    use tempdir::TempDir;
+
+    struct Counter(Cell<SourceId>);
+
+    impl EventSequenceProvider for Counter {
+        fn get_next_id(&self) -> SourceId {
+            let id = self.0.get();
+            self.0.set(id + 1);
+            id
+        }
+    }
+
+    fn keyboard(source_id: SourceId, value: &str) -> Event {
+        Event {
+            source_id,
+            etype: EventType::Keyboard(KeyboardEvent {
+                key: Key::Other(0),
+                value: Some(value.to_string()),
+                status: Status::Pressed,
+                variant: None,
+            }),
+        }
+    }
+
+    fn injections(events: &[Event]) -> Vec<String> {
+        events
+            .iter()
+            .filter_map(|event| match &event.etype {
+                EventType::TriggerCompensation(event) => {
+                    Some(format!("compensate {}", event.trigger))
+                }
+                EventType::TextInject(event) => Some(format!("inject {}", event.text)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recorded_events_are_replayed_with_the_same_effects() {
+        let dir = TempDir::new("espanso-replay").unwrap();
+        let config_dir = dir.path().join("config");
+        let match_dir = dir.path().join("match");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::create_dir_all(&match_dir).unwrap();
+        std::fs::write(config_dir.join("default.yml"), "").unwrap();
+        std::fs::write(
+            match_dir.join("base.yml"),
+            r#"
+matches:
+  - trigger: ":hi"
+    replace: "hello"
+"#,
+        )
+        .unwrap();
+
+        let (config_store, match_store, _) = espanso_config::load(dir.path()).unwrap();
+        let paths = Paths {
+            config: dir.path().to_path_buf(),
+            runtime: dir.path().join("runtime"),
+            packages: dir.path().join("packages"),
+            logs: dir.path().join("runtime"),
+            is_portable_mode: false,
+        };
+        let app_info_provider = FixedAppInfoProvider::new(None, None, None);
+
+        let mut buffer = Vec::new();
+        let live_effects = {
+            let counter = Counter(Cell::new(1000));
+            let recorder = EventRecorder::new(Box::new(&mut buffer), false, &counter);
+            with_processor(
+                &paths,
+                &*config_store,
+                &*match_store,
+                &app_info_provider,
+                &recorder,
+                |processor| {
+                    let mut processor = RecordingProcessor::new(processor, &recorder);
+                    (1..)
+                        .zip([":", "h", "i"])
+                        .flat_map(|(source_id, value)| {
+                            processor.process(keyboard(source_id, value))
+                        })
+                        .collect::<Vec<_>>()
+                },
+            )
+        };
+
+        let recording = read_recording(buffer.as_slice()).unwrap();
+        assert_eq!(recording.len(), 3);
+
+        let replayed_effects = replay_recording(
+            recording,
+            &paths,
+            &*config_store,
+            &*match_store,
+            &app_info_provider,
+        );
+
+        assert_eq!(
+            injections(&replayed_effects),
+            vec!["compensate :hi".to_string(), "inject hello".to_string()]
+        );
+        assert_eq!(injections(&live_effects), injections(&replayed_effects));
+    }
+}
@@ -0,0 +1,482 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use espanso_config::{config::ConfigStore, matches::store::MatchStore};
+use espanso_engine::{
+    event::{
+        input::{Key, KeyboardEvent, MouseButton, MouseEvent, Status},
+        Event, EventType,
+    },
+    process::{
+        MatchFilter, Matcher, MatcherMiddleware, Middleware, ModifierState, ModifierStateProvider,
+        ModifierStatusProvider, ProcessorState, Renderer,
+    },
+};
+use serde::Serialize;
+
+use super::dry_run::{with_renderer, FixedAppInfoProvider};
+use crate::{
+    cli::worker::{
+        builtin::{get_builtin_matches, BuiltInMatch},
+        config::ConfigManager,
+        engine::process::middleware::{
+            matcher::{
+                convert::MatchConverter,
+                regex::{RegexMatcherAdapter, RegexMatcherAdapterOptions},
+                rolling::{RollingMatcherAdapter, RollingMatcherAdapterOptions},
+                MatcherState,
+            },
+            render::MatchProvider,
+        },
+        match_cache::MatchCache,
+    },
+    path::Paths,
+};
+
+pub fn test_main(
+    cli_args: &ArgMatches,
+    paths: &Paths,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+) -> Result<()> {
+    let input = if let Some(input) = cli_args.value_of("input") {
+        input.to_string()
+    } else if let Some(file) = cli_args.value_of("file") {
+        std::fs::read_to_string(file)
+            .with_context(|| format!("unable to read keystrokes file: {file}"))?
+    } else {
+        bail!("You need to specify either the --input or the --file option. Run `espanso match test --help` for more information.");
+    };
+
+    let app_info_provider = FixedAppInfoProvider::new(
+        cli_args.value_of("title"),
+        cli_args.value_of("class"),
+        cli_args.value_of("exec"),
+    );
+    let detections = simulate_input(&input, paths, config_store, match_store, &app_info_provider);
+
+    if cli_args.is_present("json") {
+        println!("{}", serde_json::to_string_pretty(&detections)?);
+    } else {
+        print_detections(&detections);
+    }
+
+    Ok(())
+}
+
+// Type the given input against the matchers built exactly like the worker does
+fn simulate_input(
+    input: &str,
+    paths: &Paths,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+    app_info_provider: &FixedAppInfoProvider,
+) -> Vec<Detection> {
+    let config_manager = ConfigManager::new(config_store, match_store, app_info_provider);
+    let match_cache = MatchCache::load(config_store, match_store);
+
+    let default_config = config_store.default();
+    let builtin_matches = get_builtin_matches(&*default_config);
+    let match_converter = MatchConverter::new(config_store, match_store, &builtin_matches);
+    let rolling_matcher = RollingMatcherAdapter::new(
+        &match_converter.get_rolling_matches(),
+        RollingMatcherAdapterOptions {
+            char_word_separators: default_config.word_separators(),
+        },
+    );
+    let regex_matcher = RegexMatcherAdapter::new(
+        &match_converter.get_regex_matches(),
+        &RegexMatcherAdapterOptions {
+            max_buffer_size: default_config.max_regex_buffer_size(),
+        },
+    );
+    let matchers: Vec<&dyn Matcher<MatcherState>> = vec![&rolling_matcher, &regex_matcher];
+    let processor_state = ProcessorState::new();
+    let matcher_middleware =
+        MatcherMiddleware::new(&matchers, &config_manager, &NoModifiers, &processor_state);
+
+    let keystrokes = parse_keystrokes(input);
+    with_renderer(paths, &config_manager, &match_cache, |renderer| {
+        simulate(
+            &keystrokes,
+            &matcher_middleware,
+            &config_manager,
+            &match_cache,
+            &builtin_matches,
+            renderer,
+        )
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Keystroke {
+    Char(char),
+    Key(Key),
+    Click,
+}
+
+#[derive(Debug, Serialize)]
+struct Detection {
+    offset: usize,
+    trigger: String,
+    label: Option<String>,
+    active: bool,
+    output: Option<String>,
+    error: Option<String>,
+}
+
+const NAMED_KEYS: &[(&str, Keystroke)] = &[
+    ("<backspace>", Keystroke::Key(Key::Backspace)),
+    ("<enter>", Keystroke::Key(Key::Enter)),
+    ("<tab>", Keystroke::Key(Key::Tab)),
+    ("<esc>", Keystroke::Key(Key::Escape)),
+    ("<left>", Keystroke::Key(Key::ArrowLeft)),
+    ("<right>", Keystroke::Key(Key::ArrowRight)),
+    ("<up>", Keystroke::Key(Key::ArrowUp)),
+    ("<down>", Keystroke::Key(Key::ArrowDown)),
+    ("<home>", Keystroke::Key(Key::Home)),
+    ("<end>", Keystroke::Key(Key::End)),
+    ("<click>", Keystroke::Click),
+];
+
+// Convert the input into keystrokes, each paired with the offset (in characters)
+// of the input right after it. Besides plain characters, the input can contain
+// named keys such as <backspace>, while unknown names are typed literally.
+fn parse_keystrokes(input: &str) -> Vec<(usize, Keystroke)> {
+    let mut keystrokes = Vec::new();
+    let mut offset = 0;
+    let mut rest = input;
+
+    while let Some(c) = rest.chars().next() {
+        let named_key = NAMED_KEYS.iter().find(|(name, _)| rest.starts_with(name));
+
+        let (len, keystroke) = match (c, named_key) {
+            (_, Some((name, keystroke))) => (name.len(), keystroke.clone()),
+            // Treat CRLF line endings as a single Enter key
+            ('\r', _) if rest.starts_with("\r\n") => (2, Keystroke::Char('\n')),
+            _ => (c.len_utf8(), Keystroke::Char(c)),
+        };
+
+        offset += rest[..len].chars().count();
+        rest = &rest[len..];
+        keystrokes.push((offset, keystroke));
+    }
+
+    keystrokes
+}
+
+fn convert_to_event_type(keystroke: &Keystroke) -> EventType {
+    let (key, value) = match keystroke {
+        Keystroke::Click => {
+            return EventType::Mouse(MouseEvent {
+                button: MouseButton::Left,
+                status: Status::Pressed,
+            })
+        }
+        Keystroke::Char(' ') => (Key::Space, Some(" ".to_string())),
+        Keystroke::Char('\n') | Keystroke::Key(Key::Enter) => (Key::Enter, Some("\n".to_string())),
+        Keystroke::Char('\t') | Keystroke::Key(Key::Tab) => (Key::Tab, Some("\t".to_string())),
+        Keystroke::Char(c) => (Key::Other(0), Some(c.to_string())),
+        Keystroke::Key(key) => (key.clone(), None),
+    };
+
+    EventType::Keyboard(KeyboardEvent {
+        key,
+        value,
+        status: Status::Pressed,
+        variant: None,
+    })
+}
+
+// Feed the keystrokes to the matcher middleware, rendering the matches
+// that would be expanded in place of injecting them
+fn simulate<'a>(
+    keystrokes: &[(usize, Keystroke)],
+    matcher_middleware: &MatcherMiddleware<MatcherState>,
+    match_filter: &dyn MatchFilter,
+    match_cache: &MatchCache,
+    builtin_matches: &[BuiltInMatch],
+    renderer: &'a dyn Renderer<'a>,
+) -> Vec<Detection> {
+    let mut detections = Vec::new();
+
+    for (source_id, (offset, keystroke)) in (1..).zip(keystrokes) {
+        let event = Event::caused_by(source_id, convert_to_event_type(keystroke));
+        let EventType::MatchesDetected(detected) =
+            matcher_middleware.next(event, &mut |_| {}).etype
+        else {
+            continue;
+        };
+
+        let ids: Vec<i32> = detected.matches.iter().map(|m| m.id).collect();
+        let active_ids = match_filter.filter_active(&ids);
+        let mut has_expanded = false;
+
+        for detected_match in detected.matches {
+            let active = active_ids.contains(&detected_match.id);
+            let builtin = builtin_matches.iter().find(|m| m.id == detected_match.id);
+            let label = match builtin {
+                Some(builtin) => Some(builtin.label.to_string()),
+                None => match_cache
+                    .get(detected_match.id)
+                    .and_then(|m| m.label.clone()),
+            };
+
+            let (output, error) = if !active {
+                (None, None)
+            } else if builtin.is_some() {
+                (None, Some("built-in matches can't be rendered".to_string()))
+            } else {
+                match renderer.render(
+                    detected_match.id,
+                    detected_match.trigger.as_deref(),
+                    detected_match.args,
+                ) {
+                    Ok(output) => (Some(output), None),
+                    Err(err) => (None, Some(format!("{err:#}"))),
+                }
+            };
+            has_expanded |= active;
+
+            detections.push(Detection {
+                offset: *offset,
+                trigger: detected_match.trigger.unwrap_or_default(),
+                label,
+                active,
+                output,
+                error,
+            });
+        }
+
+        // The worker notifies the matchers after an expansion, so that
+        // the injected text acts as a separator
+        if has_expanded {
+            matcher_middleware.next(
+                Event::caused_by(source_id, EventType::MatchInjected),
+                &mut |_| {},
+            );
+        }
+    }
+
+    detections
+}
+
+fn print_detections(detections: &[Detection]) {
+    if detections.is_empty() {
+        println!("no match detected");
+        return;
+    }
+
+    for detection in detections {
+        let description = if !detection.active {
+            "not active for this app".to_string()
+        } else if let Some(error) = &detection.error {
+            format!("error: {error}")
+        } else {
+            format!("{:?}", detection.output.as_deref().unwrap_or_default())
+        };
+        let label = detection
+            .label
+            .as_deref()
+            .map(|label| format!(" ({})", label.replace('\n', " ")))
+            .unwrap_or_default();
+
+        println!(
+            "offset {}: {:?}{label} -> {description}",
+            detection.offset, detection.trigger
+        );
+    }
+}
+
+pub struct NoModifiers;
+
+impl ModifierStateProvider for NoModifiers {
+    fn get_modifier_state(&self) -> ModifierState {
+        ModifierState {
+            is_ctrl_down: false,
+            is_alt_down: false,
+            is_meta_down: false,
+        }
+    }
+}
+
+impl ModifierStatusProvider for NoModifiers {
+    fn is_any_conflicting_modifier_pressed(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn parse_keystrokes_plain_chars() {
+        assert_eq!(
+            parse_keystrokes("a è"),
+            vec![
+                (1, Keystroke::Char('a')),
+                (2, Keystroke::Char(' ')),
+                (3, Keystroke::Char('è')),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_keystrokes_special_keys() {
+        assert_eq!(
+            parse_keystrokes("a<backspace><enter><click>"),
+            vec![
+                (1, Keystroke::Char('a')),
+                (12, Keystroke::Key(Key::Backspace)),
+                (19, Keystroke::Key(Key::Enter)),
+                (26, Keystroke::Click),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_keystrokes_line_endings() {
+        assert_eq!(
+            parse_keystrokes("a\r\nb\tc"),
+            vec![
+                (1, Keystroke::Char('a')),
+                (3, Keystroke::Char('\n')),
+                (4, Keystroke::Char('b')),
+                (5, Keystroke::Char('\t')),
+                (6, Keystroke::Char('c')),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_keystrokes_types_unknown_or_malformed_names_literally() {
+        let chars = |input: &str| -> Vec<Keystroke> {
+            parse_keystrokes(input)
+                .into_iter()
+                .map(|(_, keystroke)| keystroke)
+                .collect()
+        };
+
+        assert_eq!(
+            chars("<foo>"),
+            "<foo>".chars().map(Keystroke::Char).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            chars("<enter"),
+            "<enter".chars().map(Keystroke::Char).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            chars("<ENTER>"),
+            "<ENTER>".chars().map(Keystroke::Char).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            chars("<<tab>"),
+            vec![Keystroke::Char('<'), Keystroke::Key(Key::Tab)]
+        );
+        assert!(parse_keystrokes("").is_empty());
+    }
+
+    fn simulate_with_matches(matches_yaml: &str, input: &str) -> Vec<Detection> {
+        let dir = TempDir::new("espanso-simulate").unwrap();
+        let config_dir = dir.path().join("config");
+        let match_dir = dir.path().join("match");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::create_dir_all(&match_dir).unwrap();
+        std::fs::write(config_dir.join("default.yml"), "").unwrap();
+        std::fs::write(match_dir.join("base.yml"), matches_yaml).unwrap();
+
+        let (config_store, match_store, _) = espanso_config::load(dir.path()).unwrap();
+        let paths = Paths {
+            config: dir.path().to_path_buf(),
+            runtime: dir.path().join("runtime"),
+            packages: dir.path().join("packages"),
+            logs: dir.path().join("runtime"),
+            is_portable_mode: false,
+        };
+
+        simulate_input(
+            input,
+            &paths,
+            &*config_store,
+            &*match_store,
+            &FixedAppInfoProvider::new(None, None, None),
+        )
+    }
+
+    #[test]
+    fn simulate_reports_detections() {
+        let detections = simulate_with_matches(
+            r#"
+matches:
+  - trigger: ":hi"
+    replace: "hello"
+    label: "greeting"
+  - trigger: ":broken"
+    replace: "{{missing}}"
+"#,
+            "x :hi :hx<backspace>i :broken",
+        );
+
+        let detections: Vec<_> = detections
+            .iter()
+            .map(|d| {
+                (
+                    d.offset,
+                    d.trigger.as_str(),
+                    d.label.as_deref(),
+                    d.active,
+                    d.output.as_deref(),
+                    d.error.is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            detections,
+            vec![
+                (5, ":hi", Some("greeting"), true, Some("hello"), false),
+                (21, ":hi", Some("greeting"), true, Some("hello"), false),
+                (29, ":broken", None, true, None, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn simulate_json_output_shape() {
+        let detections = simulate_with_matches(
+            "matches:\n  - trigger: \":hi\"\n    replace: \"hello\"\n",
+            ":hi",
+        );
+
+        assert_eq!(
+            serde_json::to_value(&detections).unwrap(),
+            serde_json::json!([{
+                "offset": 3,
+                "trigger": ":hi",
+                "label": null,
+                "active": true,
+                "output": "hello",
+                "error": null,
+            }])
+        );
+    }
+}
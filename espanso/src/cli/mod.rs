@@ -23,6 +23,7 @@ use crate::path::Paths;
 use clap::ArgMatches;
 use espanso_config::{config::ConfigStore, error::NonFatalErrorSet, matches::store::MatchStore};
 
+pub mod check;
 pub mod cmd;
 pub mod daemon;
 pub mod edit;
@@ -34,6 +35,7 @@ pub mod modulo;
 pub mod package;
 pub mod path;
 pub mod service;
+pub mod state;
 pub mod stats;
 pub mod util;
 pub mod workaround;
@@ -58,6 +58,12 @@ pub fn troubleshoot_main(paths: &Paths, icon_paths: &IconPaths) -> i32 {
                                 }
                             },
                             message: format!("{:?}", error.error),
+                            location: error.location.map(|location| {
+                                espanso_modulo::troubleshooting::ErrorLocation {
+                                    line: location.line,
+                                    column: location.column,
+                                }
+                            }),
                         })
                         .collect(),
                 })
@@ -81,6 +87,12 @@ pub fn troubleshoot_main(paths: &Paths, icon_paths: &IconPaths) -> i32 {
                     errors: vec![espanso_modulo::troubleshooting::ErrorRecord {
                         level: espanso_modulo::troubleshooting::ErrorLevel::Error,
                         message: format!("{err:?}"),
+                        location: espanso_config::error::locate_error(&err).map(|location| {
+                            espanso_modulo::troubleshooting::ErrorLocation {
+                                line: location.line,
+                                column: location.column,
+                            }
+                        }),
                     }],
                 }],
             )
@@ -0,0 +1,93 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::{bail, Result};
+use espanso_render::extension::state::StateProvider;
+use serde::Serialize;
+
+use super::{
+    worker::engine::process::middleware::render::extension::state, CliModule, CliModuleArgs,
+};
+
+pub fn new() -> CliModule {
+    CliModule {
+        requires_paths: true,
+        subcommand: "state".to_string(),
+        entry: state_main,
+        ..Default::default()
+    }
+}
+
+#[derive(Serialize)]
+struct StateEntry {
+    name: String,
+    value: String,
+}
+
+fn state_main(args: CliModuleArgs) -> i32 {
+    let cli_args = args.cli_args.expect("missing cli_args");
+    let paths = args.paths.expect("missing paths argument");
+
+    let result = state::create(&paths.runtime).and_then(|adapter| {
+        if let Some(sub_args) = cli_args.subcommand_matches("get") {
+            let name = sub_args.value_of("name").expect("missing name argument");
+            match adapter.get(name)? {
+                Some(value) => println!("{value}"),
+                None => bail!("state entry '{}' is not set", name),
+            }
+        } else if let Some(sub_args) = cli_args.subcommand_matches("set") {
+            let name = sub_args.value_of("name").expect("missing name argument");
+            let value = sub_args.value_of("value").expect("missing value argument");
+            adapter.set(name, value)?;
+        } else if let Some(sub_args) = cli_args.subcommand_matches("list") {
+            let entries = list_entries(&adapter)?;
+            if sub_args.is_present("json") {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                for entry in entries {
+                    println!("{} = {}", entry.name, entry.value);
+                }
+            }
+        } else {
+            bail!("please specify a subcommand, see `espanso state --help` for more information");
+        }
+
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("{err}");
+            1
+        }
+    }
+}
+
+fn list_entries<KVSType: espanso_kvs::KVS>(
+    adapter: &state::StateAdapter<KVSType>,
+) -> Result<Vec<StateEntry>> {
+    let mut entries = Vec::new();
+    for name in adapter.names()? {
+        if let Some(value) = adapter.get(&name)? {
+            entries.push(StateEntry { name, value });
+        }
+    }
+    Ok(entries)
+}
@@ -28,6 +28,7 @@ use super::context::Context;
 mod debug;
 mod process;
 mod search;
+mod undo;
 
 const MIN_BUILTIN_MATCH_ID: i32 = 1_000_000_000;
 
@@ -69,6 +70,12 @@ pub fn get_builtin_matches(config: &dyn Config) -> Vec<BuiltInMatch> {
         ));
     }
 
+    if config.undo_shortcut().is_some() {
+        matches.push(undo::create_match_undo_last_expansion(
+            config.undo_shortcut(),
+        ));
+    }
+
     matches
 }
 
@@ -23,7 +23,9 @@ use espanso_config::{
     config::{AppProperties, Config, ConfigStore},
     matches::store::{MatchSet, MatchStore},
 };
+use espanso_engine::event::input::Key;
 use espanso_info::{AppInfo, AppInfoProvider};
+use log::warn;
 
 use super::{
     builtin::is_builtin_match,
@@ -56,9 +58,12 @@ impl<'a> ConfigManager<'a> {
     }
 
     pub fn active_context(&'_ self) -> (Arc<dyn Config>, MatchSet<'_>) {
-        let config = self.active();
-        let match_paths = config.match_paths();
-        (config.clone(), self.match_store.query(match_paths))
+        let current_app = self.app_info_provider.get_info();
+        let info = to_app_properties(&current_app);
+        let config = self.config_store.active(&info);
+        let mut match_set = self.match_store.query(config.match_paths());
+        match_set.retain_for_app(&info);
+        (config, match_set)
     }
 
     pub fn default(&self) -> Arc<dyn Config> {
@@ -110,8 +115,14 @@ impl<'a> super::engine::process::middleware::render::ConfigProvider<'a> for Conf
             .collect()
     }
 
-    fn active(&'_ self) -> (Arc<dyn Config>, MatchSet<'_>) {
-        self.active_context()
+    fn active(&self) -> Arc<dyn Config> {
+        ConfigManager::active(self)
+    }
+
+    // Application filters are not applied, as the result is shared
+    // by all the applications using the same config
+    fn match_set(&'_ self, config: &dyn Config) -> MatchSet<'_> {
+        self.match_store.query(config.match_paths())
     }
 }
 
@@ -190,6 +201,10 @@ impl espanso_engine::process::UndoEnabledProvider for ConfigManager<'_> {
 
         self.active().undo_backspace()
     }
+
+    fn undo_shortcut(&self) -> Option<String> {
+        self.active().undo_shortcut()
+    }
 }
 
 impl espanso_engine::process::EnabledStatusProvider for ConfigManager<'_> {
@@ -223,3 +238,35 @@ impl espanso_engine::process::AltCodeSynthEnabledProvider for ConfigManager<'_>
         self.active().emulate_alt_codes()
     }
 }
+
+impl espanso_engine::process::TabStopKeyProvider for ConfigManager<'_> {
+    fn get_tab_stop_key(&self) -> Option<Key> {
+        let name = self.active().tab_stop_key()?;
+        let key = parse_tab_stop_key(&name);
+        if key.is_none() {
+            warn!("unsupported tab_stop_key '{name}', jumping between tab stops is disabled");
+        }
+        key
+    }
+}
+
+fn parse_tab_stop_key(name: &str) -> Option<Key> {
+    let key = match name.to_uppercase().as_str() {
+        "TAB" => Key::Tab,
+        "ENTER" => Key::Enter,
+        "F1" => Key::F1,
+        "F2" => Key::F2,
+        "F3" => Key::F3,
+        "F4" => Key::F4,
+        "F5" => Key::F5,
+        "F6" => Key::F6,
+        "F7" => Key::F7,
+        "F8" => Key::F8,
+        "F9" => Key::F9,
+        "F10" => Key::F10,
+        "F11" => Key::F11,
+        "F12" => Key::F12,
+        _ => return None,
+    };
+    Some(key)
+}
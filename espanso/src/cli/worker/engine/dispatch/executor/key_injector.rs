@@ -17,10 +17,10 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use espanso_inject::{InjectionOptions, Injector};
+use espanso_inject::{keys::parse_key_sequence, InjectionOptions, Injector};
 use std::convert::TryInto;
 
-use espanso_engine::dispatch::KeyInjector;
+use espanso_engine::dispatch::{KeyInjector, MacroKeyInjector};
 
 use super::InjectParamsProvider;
 
@@ -38,11 +38,11 @@ impl<'a> KeyInjectorAdapter<'a> {
     }
 }
 
-impl KeyInjector for KeyInjectorAdapter<'_> {
-    fn inject_sequence(&self, keys: &[espanso_engine::event::input::Key]) -> anyhow::Result<()> {
+impl KeyInjectorAdapter<'_> {
+    fn injection_options(&self) -> InjectionOptions {
         let params = self.params_provider.get();
 
-        let injection_options = InjectionOptions {
+        InjectionOptions {
             delay: params
                 .key_delay
                 .unwrap_or_else(|| InjectionOptions::default().delay.try_into().unwrap())
@@ -60,10 +60,36 @@ impl KeyInjector for KeyInjectorAdapter<'_> {
                 .try_into()
                 .unwrap(),
             x11_use_xdotool_fallback: params.x11_use_xdotool_backend,
-        };
+        }
+    }
+}
 
+impl KeyInjector for KeyInjectorAdapter<'_> {
+    fn inject_sequence(&self, keys: &[espanso_engine::event::input::Key]) -> anyhow::Result<()> {
         let converted_keys: Vec<_> = keys.iter().map(convert_to_inject_key).collect();
-        self.injector.send_keys(&converted_keys, injection_options)
+        self.injector
+            .send_keys(&converted_keys, self.injection_options())
+    }
+}
+
+impl MacroKeyInjector for KeyInjectorAdapter<'_> {
+    fn inject_keys(&self, keys: &str) -> anyhow::Result<()> {
+        let combinations = parse_key_sequence(keys)?;
+        let injection_options = self.injection_options();
+
+        for combination in combinations {
+            for _ in 0..combination.repeat {
+                if combination.keys.len() == 1 {
+                    self.injector
+                        .send_keys(&combination.keys, injection_options)?;
+                } else {
+                    self.injector
+                        .send_key_combination(&combination.keys, injection_options)?;
+                }
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -17,21 +17,32 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::thread::JoinHandle;
+use std::{fs::OpenOptions, io::LineWriter, path::Path, thread::JoinHandle};
 
 use crate::path::Paths;
 use anyhow::Result;
 use crossbeam::channel::Receiver;
-use espanso_clipboard::ClipboardOptions;
-use espanso_config::{config::ConfigStore, matches::store::MatchStore};
-use espanso_detect::SourceCreationOptions;
-use espanso_engine::event::{EventType, ExitMode};
-use espanso_inject::{InjectorCreationOptions, KeyboardStateProvider};
+use espanso_clipboard::{Clipboard, ClipboardOptions};
+use espanso_config::{
+    config::{Config, ConfigStore},
+    matches::store::MatchStore,
+};
+use espanso_detect::{hotkey::HotKey, SourceCreationOptions};
+use espanso_engine::{
+    event::{EventType, ExitMode},
+    funnel::Funnel,
+    process::{EventSequenceProvider, Processor, ProcessorState},
+    record::{EventRecorder, RecordingProcessor},
+};
+use espanso_info::AppInfoProvider;
+use espanso_inject::{Injector, InjectorCreationOptions, KeyboardStateProvider};
+use espanso_render::extension::state::StateProvider;
 use espanso_ui::{event::UIEvent, UIRemote};
 use log::{debug, error, info, warn};
 
 use crate::{
     cli::worker::{
+        builtin::BuiltInMatch,
         context::Context,
         engine::{
             dispatch::executor::{
@@ -53,7 +64,7 @@ use crate::{
                 render::{
                     extension::{
                         choice::ChoiceSelectorAdapter, clipboard::ClipboardAdapter,
-                        form::FormProviderAdapter,
+                        form::FormProviderAdapter, state, ExtensionProviders, RenderExtensions,
                     },
                     RendererAdapter,
                 },
@@ -66,9 +77,11 @@ use crate::{
         WORKER_START_REASON_CONFIG_CHANGED, WORKER_START_REASON_KEYBOARD_LAYOUT_CHANGED,
         WORKER_START_REASON_MANUAL,
     },
+    gui::modulo::manager::ModuloManager,
     preferences::Preferences,
 };
 
+use self::funnel::{modifier::ModifierStateStore, sequencer::Sequencer};
 use super::secure_input::SecureInputEvent;
 
 mod caches;
@@ -104,30 +117,15 @@ pub fn initialize_and_spawn(
                 &*app_info_provider,
                 std::time::Duration::from_millis(400),
             );
-            let config_manager = super::config::ConfigManager::new(
-                &*config_store,
-                &*match_store,
-                &cached_app_info_provider,
-            );
-            let match_cache = MatchCache::load(&*config_store, &*match_store);
-            let default_config = &*config_manager.default();
-
-            let modulo_manager = crate::gui::modulo::manager::ModuloManager::new();
-            let modulo_form_ui =
-                crate::gui::modulo::form::ModuloFormUI::new(&modulo_manager, &config_manager);
-            let modulo_search_ui =
-                crate::gui::modulo::search::ModuloSearchUI::new(&modulo_manager, &config_manager);
-            let modulo_text_ui = crate::gui::modulo::textview::ModuloTextUI::new(&modulo_manager);
-
-            let context: Box<dyn Context> = Box::new(super::context::DefaultContext::new(
-                &config_manager,
-                &cached_app_info_provider,
-            ));
-            let builtin_matches = super::builtin::get_builtin_matches(&*config_manager.default());
-            let combined_match_cache = CombinedMatchCache::load(&match_cache, &builtin_matches);
-
-            let match_converter =
-                MatchConverter::new(&*config_store, &*match_store, &builtin_matches);
+            let default_config = config_store.default();
+
+            let modulo_manager = ModuloManager::new();
+
+            // Built-in matches are created only once, because the hotkeys
+            // registered in the detection module refer to their ids
+            let builtin_matches = super::builtin::get_builtin_matches(&*default_config);
+            let hotkeys =
+                MatchConverter::new(&*config_store, &*match_store, &builtin_matches).get_hotkeys();
 
             let has_granted_capabilities = grant_linux_capabilities(use_evdev_backend);
 
@@ -136,9 +134,9 @@ pub fn initialize_and_spawn(
                 super::engine::funnel::init_and_spawn(SourceCreationOptions {
                     use_evdev: use_evdev_backend,
                     evdev_keyboard_rmlvo: keyboard_layout_util::generate_detect_rmlvo(
-                        &*config_manager.default(),
+                        &*default_config,
                     ),
-                    hotkeys: match_converter.get_hotkeys(),
+                    hotkeys: hotkeys.clone(),
                     win32_exclude_orphan_events: default_config.win32_exclude_orphan_events(),
                     win32_keyboard_layout_cache_interval: default_config
                         .win32_keyboard_layout_cache_interval(),
@@ -159,122 +157,17 @@ pub fn initialize_and_spawn(
             }
             let funnel = espanso_engine::funnel::default(&sources);
 
-            let rolling_matcher = RollingMatcherAdapter::new(
-                &match_converter.get_rolling_matches(),
-                RollingMatcherAdapterOptions {
-                    char_word_separators: config_manager.default().word_separators(),
-                },
-            );
-            let regex_matcher = RegexMatcherAdapter::new(
-                &match_converter.get_regex_matches(),
-                &RegexMatcherAdapterOptions {
-                    max_buffer_size: default_config.max_regex_buffer_size(),
-                },
-            );
-            let matchers: Vec<
-                &dyn espanso_engine::process::Matcher<
-                    super::engine::process::middleware::matcher::MatcherState,
-                >,
-            > = vec![&rolling_matcher, &regex_matcher];
-            let selector = MatchSelectorAdapter::new(&modulo_search_ui, &combined_match_cache);
-            let multiplexer = MultiplexAdapter::new(&combined_match_cache, &*context);
-
             let injector = espanso_inject::get_injector(InjectorCreationOptions {
                 use_evdev: use_evdev_backend,
                 keyboard_state_provider: key_state_store
                     .map(|store| Box::new(store) as Box<dyn KeyboardStateProvider>),
-                evdev_keyboard_rmlvo: keyboard_layout_util::generate_inject_rmlvo(
-                    &*config_manager.default(),
-                ),
+                evdev_keyboard_rmlvo: keyboard_layout_util::generate_inject_rmlvo(&*default_config),
                 ..Default::default()
             })
             .expect("failed to initialize injector module"); // TODO: handle the options
             let clipboard = espanso_clipboard::get_clipboard(ClipboardOptions::default())
                 .expect("failed to initialize clipboard module"); // TODO: handle options
 
-            let clipboard_adapter = ClipboardAdapter::new(&*clipboard, &config_manager);
-            let clipboard_extension =
-                espanso_render::extension::clipboard::ClipboardExtension::new(&clipboard_adapter);
-            let locale_provider = espanso_render::extension::date::DefaultLocaleProvider::new();
-            let date_extension =
-                espanso_render::extension::date::DateExtension::new(&locale_provider);
-            let echo_extension = espanso_render::extension::echo::EchoExtension::new();
-            let random_extension = espanso_render::extension::random::RandomExtension::new();
-            let home_path = dirs::home_dir().expect("unable to obtain home dir path");
-            let script_extension = espanso_render::extension::script::ScriptExtension::new(
-                &paths.config,
-                &home_path,
-                &paths.packages,
-            );
-            let shell_extension =
-                espanso_render::extension::shell::ShellExtension::new(&paths.config);
-            let form_adapter = FormProviderAdapter::new(&modulo_form_ui);
-            let form_extension = espanso_render::extension::form::FormExtension::new(&form_adapter);
-            let choice_adapter = ChoiceSelectorAdapter::new(&modulo_search_ui);
-            let choice_extension =
-                espanso_render::extension::choice::ChoiceExtension::new(&choice_adapter);
-            let renderer = espanso_render::create(vec![
-                &clipboard_extension,
-                &date_extension,
-                &echo_extension,
-                &random_extension,
-                &script_extension,
-                &shell_extension,
-                &form_extension,
-                &choice_extension,
-            ]);
-            let renderer_adapter = RendererAdapter::new(&match_cache, &config_manager, &renderer);
-            let path_provider = PathProviderAdapter::new(&paths);
-            let config_path_provider = ConfigPathProviderAdapter::new(&paths);
-
-            let disable_options =
-                process::middleware::disable::extract_disable_options(&*config_manager.default());
-
-            let notification_manager = NotificationManager::new(&*ui_remote, default_config);
-
-            let mut processor = espanso_engine::process::default(
-                &matchers,
-                &config_manager,
-                &selector,
-                &multiplexer,
-                &renderer_adapter,
-                &match_cache,
-                &modifier_state_store,
-                &sequencer,
-                &path_provider,
-                &config_path_provider,
-                disable_options,
-                &config_manager,
-                &combined_match_cache,
-                &config_manager,
-                &config_manager,
-                &modifier_state_store,
-                &combined_match_cache,
-                &notification_manager,
-                &config_manager,
-            );
-
-            let event_injector = EventInjectorAdapter::new(&*injector, &config_manager);
-            let clipboard_injector =
-                ClipboardInjectorAdapter::new(&*injector, &*clipboard, &config_manager);
-            let key_injector = KeyInjectorAdapter::new(&*injector, &config_manager);
-            let context_menu_adapter = ContextMenuHandlerAdapter::new(&*ui_remote);
-            let icon_adapter = IconHandlerAdapter::new(&*ui_remote);
-            let secure_input_adapter = SecureInputManagerAdapter::new();
-            let text_ui_adapter = TextUIHandlerAdapter::new(&modulo_text_ui, &paths);
-            let dispatcher = espanso_engine::dispatch::default(
-                &event_injector,
-                &clipboard_injector,
-                &config_manager,
-                &key_injector,
-                &clipboard_injector,
-                &clipboard_injector,
-                &context_menu_adapter,
-                &icon_adapter,
-                &secure_input_adapter,
-                &text_ui_adapter,
-            );
-
             // Disable previously granted linux capabilities if not needed anymore
             if has_granted_capabilities {
                 if let Err(err) = crate::capabilities::clear_capabilities() {
@@ -282,24 +175,73 @@ pub fn initialize_and_spawn(
                 }
             }
 
-            match start_reason.as_deref() {
-                Some(flag) if flag == WORKER_START_REASON_CONFIG_CHANGED => {
-                    notification_manager.notify_config_reloaded(false);
-                }
-                Some(flag) if flag == WORKER_START_REASON_MANUAL => {
-                    notification_manager.notify_config_reloaded(true);
-                }
-                Some(flag) if flag == WORKER_START_REASON_KEYBOARD_LAYOUT_CHANGED => {
-                    notification_manager.notify_keyboard_layout_reloaded();
+            let home_path = dirs::home_dir().expect("unable to obtain home dir path");
+            let path_provider = PathProviderAdapter::new(&paths);
+            let config_path_provider = ConfigPathProviderAdapter::new(&paths);
+            let state_adapter =
+                state::create(&paths.runtime).expect("failed to initialize state store");
+
+            // The state of the processor (such as the matchers buffer, the undo history and
+            // the enabled status) is preserved when reloading the configuration
+            let processor_state = ProcessorState::new();
+
+            let components = EngineComponents {
+                paths: &paths,
+                home_path: &home_path,
+                ui_remote: &*ui_remote,
+                app_info_provider: &cached_app_info_provider,
+                modulo_manager: &modulo_manager,
+                builtin_matches: &builtin_matches,
+                funnel: &funnel,
+                sequencer: &sequencer,
+                modifier_state_store: &modifier_state_store,
+                injector: &*injector,
+                clipboard: &*clipboard,
+                path_provider: &path_provider,
+                config_path_provider: &config_path_provider,
+                state_provider: &state_adapter,
+                processor_state: &processor_state,
+            };
+
+            let mut config_store = config_store;
+            let mut match_store = match_store;
+            let mut start_reason = start_reason;
+            let exit_mode = loop {
+                let exit_mode = run_engine(
+                    &components,
+                    &*config_store,
+                    &*match_store,
+                    start_reason.as_deref(),
+                );
+                if !matches!(exit_mode, ExitMode::ReloadConfig) {
+                    break exit_mode;
                 }
-                _ => {
-                    notification_manager.notify_start();
+
+                info!("reloading configuration...");
+                let previous_default_config = config_store.default();
+                match crate::config::reload_config(&paths.config, match_store) {
+                    Ok(result) => {
+                        config_store = result.config_store;
+                        match_store = result.match_store;
+                    }
+                    Err(err) => {
+                        error!("unable to reload configuration, restarting worker: {err:?}");
+                        break ExitMode::RestartWorker;
+                    }
                 }
-            }
 
-            let mut engine = espanso_engine::Engine::new(&funnel, &mut processor, &dispatcher);
-            let exit_mode = engine.run();
+                let updated_hotkeys =
+                    MatchConverter::new(&*config_store, &*match_store, &builtin_matches)
+                        .get_hotkeys();
+                if requires_restart(&*previous_default_config, &*config_store.default())
+                    || !are_hotkeys_equal(&hotkeys, &updated_hotkeys)
+                {
+                    info!("configuration change can't be applied while running, restarting worker");
+                    break ExitMode::RestartWorker;
+                }
 
+                start_reason = Some(WORKER_START_REASON_CONFIG_CHANGED.to_string());
+            };
 
             info!("engine eventloop has terminated, propagating exit event...");
             ui_remote.exit();
@@ -310,6 +252,215 @@ pub fn initialize_and_spawn(
     Ok(handle)
 }
 
+// Components that are initialized once and survive configuration reloads
+struct EngineComponents<'a> {
+    paths: &'a Paths,
+    home_path: &'a Path,
+    ui_remote: &'a dyn UIRemote,
+    app_info_provider: &'a dyn AppInfoProvider,
+    modulo_manager: &'a ModuloManager,
+    builtin_matches: &'a [BuiltInMatch],
+    funnel: &'a dyn Funnel,
+    sequencer: &'a Sequencer,
+    modifier_state_store: &'a ModifierStateStore,
+    injector: &'a dyn Injector,
+    clipboard: &'a dyn Clipboard,
+    path_provider: &'a PathProviderAdapter<'a>,
+    config_path_provider: &'a ConfigPathProviderAdapter<'a>,
+    state_provider: &'a dyn StateProvider,
+    processor_state: &'a ProcessorState,
+}
+
+// Build the components that depend on the configuration and run the engine
+// until an exit (or reload) is requested
+fn run_engine(
+    components: &EngineComponents,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+    start_reason: Option<&str>,
+) -> ExitMode {
+    let config_manager =
+        super::config::ConfigManager::new(config_store, match_store, components.app_info_provider);
+    let match_cache = MatchCache::load(config_store, match_store);
+    let default_config = &*config_manager.default();
+
+    let modulo_form_ui =
+        crate::gui::modulo::form::ModuloFormUI::new(components.modulo_manager, &config_manager);
+    let modulo_search_ui =
+        crate::gui::modulo::search::ModuloSearchUI::new(components.modulo_manager, &config_manager);
+    let modulo_text_ui = crate::gui::modulo::textview::ModuloTextUI::new(components.modulo_manager);
+
+    let context: Box<dyn Context> = Box::new(super::context::DefaultContext::new(
+        &config_manager,
+        components.app_info_provider,
+    ));
+    let combined_match_cache = CombinedMatchCache::load(&match_cache, components.builtin_matches);
+
+    let match_converter =
+        MatchConverter::new(config_store, match_store, components.builtin_matches);
+
+    let rolling_matcher = RollingMatcherAdapter::new(
+        &match_converter.get_rolling_matches(),
+        RollingMatcherAdapterOptions {
+            char_word_separators: config_manager.default().word_separators(),
+        },
+    );
+    let regex_matcher = RegexMatcherAdapter::new(
+        &match_converter.get_regex_matches(),
+        &RegexMatcherAdapterOptions {
+            max_buffer_size: default_config.max_regex_buffer_size(),
+        },
+    );
+    let matchers: Vec<
+        &dyn espanso_engine::process::Matcher<
+            super::engine::process::middleware::matcher::MatcherState,
+        >,
+    > = vec![&rolling_matcher, &regex_matcher];
+    let selector = MatchSelectorAdapter::new(&modulo_search_ui, &combined_match_cache);
+    let multiplexer = MultiplexAdapter::new(&combined_match_cache, &*context);
+
+    let clipboard_adapter = ClipboardAdapter::new(components.clipboard, &config_manager);
+    let form_adapter = FormProviderAdapter::new(&modulo_form_ui);
+    let choice_adapter = ChoiceSelectorAdapter::new(&modulo_search_ui);
+    let extensions = RenderExtensions::new(
+        components.paths,
+        components.home_path,
+        &ExtensionProviders {
+            clipboard: &clipboard_adapter,
+            form: &form_adapter,
+            choice: &choice_adapter,
+            state: components.state_provider,
+        },
+    );
+    let renderer = extensions.create_renderer();
+    let renderer_adapter = RendererAdapter::new(&match_cache, &config_manager, &renderer);
+
+    let cursor_hint_strategy_adapter =
+        process::middleware::cursor_hint::CursorHintStrategyAdapter::new(
+            &match_cache,
+            &config_manager,
+        );
+
+    let notification_manager = NotificationManager::new(components.ui_remote, default_config);
+
+    let event_recorder = create_event_recorder(components.sequencer);
+    let event_sequence_provider: &dyn EventSequenceProvider = match &event_recorder {
+        Some(recorder) => recorder,
+        None => components.sequencer,
+    };
+
+    let mut processor = process::create_processor(
+        &matchers,
+        &config_manager,
+        &match_cache,
+        &combined_match_cache,
+        &cursor_hint_strategy_adapter,
+        &process::ProcessorProviders {
+            match_selector: &selector,
+            multiplexer: &multiplexer,
+            renderer: &renderer_adapter,
+            modifier_status_provider: components.modifier_state_store,
+            modifier_state_provider: components.modifier_state_store,
+            event_sequence_provider,
+            path_provider: components.path_provider,
+            config_path_provider: components.config_path_provider,
+            notification_manager: &notification_manager,
+        },
+        components.processor_state,
+    );
+
+    let event_injector = EventInjectorAdapter::new(components.injector, &config_manager);
+    let clipboard_injector =
+        ClipboardInjectorAdapter::new(components.injector, components.clipboard, &config_manager);
+    let key_injector = KeyInjectorAdapter::new(components.injector, &config_manager);
+    let context_menu_adapter = ContextMenuHandlerAdapter::new(components.ui_remote);
+    let icon_adapter = IconHandlerAdapter::new(components.ui_remote);
+    let secure_input_adapter = SecureInputManagerAdapter::new();
+    let text_ui_adapter = TextUIHandlerAdapter::new(&modulo_text_ui, components.paths);
+    let dispatcher = espanso_engine::dispatch::default(
+        &event_injector,
+        &clipboard_injector,
+        &config_manager,
+        &key_injector,
+        &key_injector,
+        &clipboard_injector,
+        &clipboard_injector,
+        &context_menu_adapter,
+        &icon_adapter,
+        &secure_input_adapter,
+        &text_ui_adapter,
+    );
+
+    match start_reason {
+        Some(flag) if flag == WORKER_START_REASON_CONFIG_CHANGED => {
+            notification_manager.notify_config_reloaded(false);
+        }
+        Some(flag) if flag == WORKER_START_REASON_MANUAL => {
+            notification_manager.notify_config_reloaded(true);
+        }
+        Some(flag) if flag == WORKER_START_REASON_KEYBOARD_LAYOUT_CHANGED => {
+            notification_manager.notify_keyboard_layout_reloaded();
+        }
+        _ => {
+            notification_manager.notify_start();
+        }
+    }
+
+    let mut recording_processor;
+    let processor: &mut dyn Processor = if let Some(recorder) = &event_recorder {
+        recording_processor = RecordingProcessor::new(&mut processor, recorder);
+        &mut recording_processor
+    } else {
+        &mut processor
+    };
+
+    let mut engine = espanso_engine::Engine::new(components.funnel, processor, &dispatcher);
+    engine.run()
+}
+
+// When ESPANSO_RECORD_EVENTS points to a file, the events received by the engine
+// are appended to it, so that they can be replayed with `espanso match replay`.
+// Setting ESPANSO_RECORD_REDACT=true hides the typed characters.
+fn create_event_recorder(sequencer: &Sequencer) -> Option<EventRecorder<'_>> {
+    let path = std::env::var_os("ESPANSO_RECORD_EVENTS")?;
+    let redact =
+        std::env::var("ESPANSO_RECORD_REDACT").unwrap_or_else(|_| "false".to_string()) == "true";
+
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => {
+            warn!("recording engine events to: {path:?} (redacted: {redact})");
+            Some(EventRecorder::new(
+                Box::new(LineWriter::new(file)),
+                redact,
+                sequencer,
+            ))
+        }
+        Err(err) => {
+            error!("unable to open events recording file {path:?}: {err}");
+            None
+        }
+    }
+}
+
+// The options read when initializing the detection and injection modules
+// (as well as the built-in matches) can only be applied with a restart
+fn requires_restart(previous: &dyn Config, current: &dyn Config) -> bool {
+    previous.backend() != current.backend()
+        || previous.keyboard_layout() != current.keyboard_layout()
+        || previous.search_trigger() != current.search_trigger()
+        || previous.search_shortcut() != current.search_shortcut()
+        || previous.undo_shortcut() != current.undo_shortcut()
+        || previous.show_icon() != current.show_icon()
+        || previous.stats_enabled() != current.stats_enabled()
+        || previous.win32_exclude_orphan_events() != current.win32_exclude_orphan_events()
+        || previous.win32_keyboard_layout_cache_interval()
+            != current.win32_keyboard_layout_cache_interval()
+}
+
+fn are_hotkeys_equal(previous: &[HotKey], current: &[HotKey]) -> bool {
+    previous.len() == current.len() && previous.iter().all(|hotkey| current.contains(hotkey))
+}
+
 fn grant_linux_capabilities(use_evdev_backend: bool) -> bool {
     if use_evdev_backend {
         if crate::capabilities::can_use_capabilities() {
@@ -0,0 +1,57 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use espanso_engine::process::{CursorHintStrategy, CursorHintStrategyProvider};
+
+use super::render::MatchProvider;
+use crate::cli::worker::config::ConfigManager;
+
+pub struct CursorHintStrategyAdapter<'a> {
+    match_provider: &'a dyn MatchProvider<'a>,
+    config_manager: &'a ConfigManager<'a>,
+}
+
+impl<'a> CursorHintStrategyAdapter<'a> {
+    pub fn new(
+        match_provider: &'a dyn MatchProvider<'a>,
+        config_manager: &'a ConfigManager<'a>,
+    ) -> Self {
+        Self {
+            match_provider,
+            config_manager,
+        }
+    }
+}
+
+impl CursorHintStrategyProvider for CursorHintStrategyAdapter<'_> {
+    fn get_cursor_hint_strategy(&self, match_id: i32) -> CursorHintStrategy {
+        // The match-specific strategy takes precedence over the app-specific one
+        let strategy = self
+            .match_provider
+            .get(match_id)
+            .and_then(|m| m.effect.as_text())
+            .and_then(|text_effect| text_effect.cursor_hint_strategy)
+            .unwrap_or_else(|| self.config_manager.active().cursor_hint_strategy());
+
+        match strategy {
+            espanso_config::config::CursorHintStrategy::Chars => CursorHintStrategy::Chars,
+            espanso_config::config::CursorHintStrategy::Lines => CursorHintStrategy::Lines,
+        }
+    }
+}
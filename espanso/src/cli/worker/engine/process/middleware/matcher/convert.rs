@@ -103,9 +103,21 @@ impl<'a> MatchConverter<'a> {
     }
 
     pub fn get_hotkeys(&self) -> Vec<HotKey> {
+        let match_set = self.global_match_set();
         let mut hotkeys = Vec::new();
 
-        // TODO: read user-defined matches
+        // First convert configuration (user-defined) matches
+        for m in match_set.matches {
+            if let MatchCause::Hotkey(cause) = &m.cause {
+                let hotkey = &cause.hotkey;
+                match HotKey::new(m.id, hotkey) {
+                    Ok(hotkey) => hotkeys.push(hotkey),
+                    Err(err) => {
+                        error!("unable to register hotkey: {hotkey}, with error: {err}");
+                    }
+                }
+            }
+        }
 
         // Then convert built-in ones
         for m in self.builtin_matches {
@@ -17,6 +17,7 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod cursor_hint;
 pub mod disable;
 pub mod image_resolve;
 pub mod match_select;
@@ -22,8 +22,9 @@ use espanso_config::matches::{Match, MatchEffect};
 use crate::cli::worker::{builtin::BuiltInMatch, context::Context};
 use espanso_engine::{
     event::{
+        effect::MacroStep,
         internal::DetectedMatch,
-        internal::{ImageRequestedEvent, RenderingRequestedEvent, TextFormat},
+        internal::{ImageRequestedEvent, MacroRequestedEvent, RenderingRequestedEvent, TextFormat},
         EventType,
     },
     process::Multiplexer,
@@ -70,6 +71,13 @@ impl Multiplexer for MultiplexAdapter<'_> {
                         trigger: detected_match.trigger,
                     }))
                 }
+                MatchEffect::Macro(effect) => {
+                    Some(EventType::MacroRequested(MacroRequestedEvent {
+                        match_id: detected_match.id,
+                        steps: effect.steps.iter().map(convert_macro_step).collect(),
+                        trigger: detected_match.trigger,
+                    }))
+                }
                 MatchEffect::None => None,
             },
             MatchResult::Builtin(m) => Some((m.action)(self.context)),
@@ -84,3 +92,12 @@ fn convert_format(format: &espanso_config::matches::TextFormat) -> TextFormat {
         espanso_config::matches::TextFormat::Html => TextFormat::Html,
     }
 }
+
+fn convert_macro_step(step: &espanso_config::matches::MacroStep) -> MacroStep {
+    match step {
+        espanso_config::matches::MacroStep::Text(text) => MacroStep::Text(text.clone()),
+        espanso_config::matches::MacroStep::Keys(keys) => MacroStep::Keys(keys.clone()),
+        espanso_config::matches::MacroStep::Wait(millis) => MacroStep::Wait(*millis),
+        espanso_config::matches::MacroStep::Paste(text) => MacroStep::Paste(text.clone()),
+    }
+}
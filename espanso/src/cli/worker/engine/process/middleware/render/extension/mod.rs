@@ -20,3 +20,95 @@
 pub mod choice;
 pub mod clipboard;
 pub mod form;
+pub mod state;
+
+use std::path::Path;
+
+use espanso_render::extension::{
+    calc::CalcExtension,
+    choice::{ChoiceExtension, ChoiceSelector},
+    clipboard::{ClipboardExtension, ClipboardProvider},
+    date::{DateExtension, DefaultLocaleProvider},
+    echo::EchoExtension,
+    env::EnvExtension,
+    file::FileExtension,
+    form::{FormExtension, FormProvider},
+    generator::GeneratorExtension,
+    random::RandomExtension,
+    rhai::RhaiExtension,
+    script::ScriptExtension,
+    shell::ShellExtension,
+    state::{StateExtension, StateProvider},
+};
+
+use crate::path::Paths;
+
+static LOCALE_PROVIDER: DefaultLocaleProvider = DefaultLocaleProvider {};
+
+// The providers backing the extensions that depend on the caller, as the
+// worker interacts with the user while dry runs don't
+pub struct ExtensionProviders<'a> {
+    pub clipboard: &'a dyn ClipboardProvider,
+    pub form: &'a dyn FormProvider,
+    pub choice: &'a dyn ChoiceSelector,
+    pub state: &'a dyn StateProvider,
+}
+
+// All the extensions available to the matches, shared by the worker
+// and the match CLI commands so that they render in the same way
+pub struct RenderExtensions<'a> {
+    calc: CalcExtension,
+    clipboard: ClipboardExtension<'a>,
+    date: DateExtension<'a>,
+    echo: EchoExtension,
+    env: EnvExtension,
+    file: FileExtension,
+    generator: GeneratorExtension,
+    random: RandomExtension,
+    rhai: RhaiExtension,
+    script: ScriptExtension,
+    shell: ShellExtension,
+    form: FormExtension<'a>,
+    choice: ChoiceExtension<'a>,
+    state: StateExtension<'a>,
+}
+
+impl<'a> RenderExtensions<'a> {
+    pub fn new(paths: &Paths, home_path: &Path, providers: &ExtensionProviders<'a>) -> Self {
+        Self {
+            calc: CalcExtension::new(),
+            clipboard: ClipboardExtension::new(providers.clipboard),
+            date: DateExtension::new(&LOCALE_PROVIDER),
+            echo: EchoExtension::new(),
+            env: EnvExtension::new(&paths.config, home_path, &paths.packages),
+            file: FileExtension::new(&paths.config, home_path, &paths.packages),
+            generator: GeneratorExtension::new(),
+            random: RandomExtension::new(),
+            rhai: RhaiExtension::new(&paths.config, home_path, &paths.packages),
+            script: ScriptExtension::new(&paths.config, home_path, &paths.packages),
+            shell: ShellExtension::new(&paths.config, home_path, &paths.packages),
+            form: FormExtension::new(providers.form),
+            choice: ChoiceExtension::new(providers.choice),
+            state: StateExtension::new(providers.state),
+        }
+    }
+
+    pub fn create_renderer(&self) -> impl espanso_render::Renderer + '_ {
+        espanso_render::create(vec![
+            &self.calc,
+            &self.clipboard,
+            &self.date,
+            &self.echo,
+            &self.env,
+            &self.file,
+            &self.generator,
+            &self.random,
+            &self.rhai,
+            &self.script,
+            &self.shell,
+            &self.form,
+            &self.choice,
+            &self.state,
+        ])
+    }
+}
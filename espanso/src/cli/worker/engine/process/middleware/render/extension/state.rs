@@ -0,0 +1,71 @@
+/*
+ * This file is part of espanso.
+ *
+ * Copyright (C) 2019-2021 Federico Terzi
+ *
+ * espanso is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * espanso is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use espanso_kvs::KVS;
+use espanso_render::extension::state::StateProvider;
+
+const STATE_NAMESPACE: &str = "state";
+
+pub struct StateAdapter<KVSType: KVS> {
+    kvs: KVSType,
+}
+
+// Values are stored as strings in a dedicated namespace of the runtime KVS,
+// so that they survive worker restarts and can be inspected from the CLI
+pub fn create(runtime_dir: &Path) -> Result<StateAdapter<impl KVS>> {
+    let kvs = espanso_kvs::get_persistent_namespace(runtime_dir, STATE_NAMESPACE)?;
+    Ok(StateAdapter { kvs })
+}
+
+impl<KVSType: KVS> StateAdapter<KVSType> {
+    pub fn names(&self) -> Result<Vec<String>> {
+        self.kvs.keys()
+    }
+}
+
+impl<KVSType: KVS> StateProvider for StateAdapter<KVSType> {
+    fn get(&self, name: &str) -> Result<Option<String>> {
+        self.kvs.get(name)
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<()> {
+        self.kvs.set(name, value)
+    }
+
+    fn increment(&self, name: &str, step: i64, initial: i64) -> Result<i64> {
+        let updated = self.kvs.update(name, |current: Option<String>| {
+            let current = match current {
+                Some(value) => value
+                    .trim()
+                    .parse::<i64>()
+                    .map_err(|_| anyhow!("state value '{}' is not a number: '{}'", name, value))?,
+                None => initial,
+            };
+            let updated = current
+                .checked_add(step)
+                .ok_or_else(|| anyhow!("state value '{}' overflowed", name))?;
+            Ok(updated.to_string())
+        })?;
+
+        Ok(updated.parse()?)
+    }
+}
@@ -36,7 +36,8 @@ pub trait MatchProvider<'a> {
 
 pub trait ConfigProvider<'a> {
     fn configs(&'_ self) -> Vec<(Arc<dyn Config>, MatchSet<'_>)>;
-    fn active(&'_ self) -> (Arc<dyn Config>, MatchSet<'_>);
+    fn active(&self) -> Arc<dyn Config>;
+    fn match_set(&'_ self, config: &dyn Config) -> MatchSet<'_>;
 }
 
 pub struct RendererAdapter<'a> {
@@ -133,6 +134,7 @@ fn convert_to_template(m: &Match) -> Option<Template> {
             ids,
             body: text_effect.replace.clone(),
             vars: convert_vars(text_effect.vars.clone()),
+            sequential_vars: text_effect.sequential_vars,
         })
     } else {
         None
@@ -191,10 +193,13 @@ impl<'a> Renderer<'a> for RendererAdapter<'a> {
         trigger_vars: HashMap<String, String>,
     ) -> anyhow::Result<String> {
         if let Some(Some(template)) = self.template_map.get(&match_id) {
-            let (config, match_set) = self.config_provider.active();
+            // The context only depends on the active config, so its matches
+            // are only queried the first time the config is used
+            let config = self.config_provider.active();
 
             let mut context_cache = self.context_cache.borrow_mut();
             let context = context_cache.entry(config.id()).or_insert_with(|| {
+                let match_set = self.config_provider.match_set(&*config);
                 generate_context(&match_set, &self.template_map, &self.global_vars_map)
             });
 
@@ -18,3 +18,68 @@
  */
 
 pub mod middleware;
+
+use espanso_engine::process::{
+    ConfigPathProvider, EventSequenceProvider, MatchSelector, Matcher, ModifierStateProvider,
+    ModifierStatusProvider, Multiplexer, NotificationManager, PathProvider, Processor,
+    ProcessorState, Renderer,
+};
+
+use self::middleware::{
+    cursor_hint::CursorHintStrategyAdapter, disable::extract_disable_options, matcher::MatcherState,
+};
+use crate::cli::worker::{
+    config::ConfigManager,
+    match_cache::{CombinedMatchCache, MatchCache},
+};
+
+// The components that differ between the worker and the headless
+// callers (such as the match replay), which don't interact with the user
+pub struct ProcessorProviders<'a> {
+    pub match_selector: &'a dyn MatchSelector,
+    pub multiplexer: &'a dyn Multiplexer,
+    pub renderer: &'a dyn Renderer<'a>,
+    pub modifier_status_provider: &'a dyn ModifierStatusProvider,
+    pub modifier_state_provider: &'a dyn ModifierStateProvider,
+    pub event_sequence_provider: &'a dyn EventSequenceProvider,
+    pub path_provider: &'a dyn PathProvider,
+    pub config_path_provider: &'a dyn ConfigPathProvider,
+    pub notification_manager: &'a dyn NotificationManager,
+}
+
+// The remaining providers are backed by the config manager and the match caches
+pub fn create_processor<'a>(
+    matchers: &'a [&'a dyn Matcher<'a, MatcherState<'a>>],
+    config_manager: &'a ConfigManager<'_>,
+    match_cache: &'a MatchCache<'_>,
+    combined_match_cache: &'a CombinedMatchCache<'_>,
+    cursor_hint_strategy_adapter: &'a CursorHintStrategyAdapter<'_>,
+    providers: &ProcessorProviders<'a>,
+    state: &'a ProcessorState,
+) -> impl Processor + 'a {
+    espanso_engine::process::default(
+        matchers,
+        config_manager,
+        providers.match_selector,
+        providers.multiplexer,
+        providers.renderer,
+        match_cache,
+        providers.modifier_status_provider,
+        providers.event_sequence_provider,
+        providers.path_provider,
+        providers.config_path_provider,
+        extract_disable_options(&*config_manager.default()),
+        config_manager,
+        combined_match_cache,
+        config_manager,
+        config_manager,
+        providers.modifier_state_provider,
+        combined_match_cache,
+        providers.notification_manager,
+        config_manager,
+        cursor_hint_strategy_adapter,
+        match_cache,
+        config_manager,
+        state,
+    )
+}
@@ -57,6 +57,15 @@ pub fn initialize_and_spawn(
 
                         EventHandlerResponse::NoResponse
                     }
+                    IPCEvent::ReloadConfig => {
+                        if let Err(err) = exit_notify.send(ExitMode::ReloadConfig) {
+                            error!(
+                "experienced error while sending reload signal from worker ipc handler: {err}"
+              );
+                        }
+
+                        EventHandlerResponse::NoResponse
+                    }
                     IPCEvent::DisableRequest => {
                         send_event(&event_notify, EventType::DisableRequest)
                     }
@@ -66,6 +75,7 @@ pub fn initialize_and_spawn(
                     IPCEvent::OpenConfigFolder => {
                         send_event(&event_notify, EventType::ShowConfigFolder)
                     }
+                    IPCEvent::UndoRequest => send_event(&event_notify, EventType::UndoRequest),
                     IPCEvent::RequestMatchExpansion(payload) => send_event(
                         &event_notify,
                         EventType::MatchExecRequest(MatchExecRequestEvent {
@@ -83,6 +83,18 @@ impl espanso_engine::process::MatchInfoProvider for MatchCache<'_> {
     }
 }
 
+impl espanso_engine::process::TabStopsEnabledProvider for MatchCache<'_> {
+    fn are_tab_stops_enabled(&self, match_id: i32) -> bool {
+        self.cache.get(&match_id).is_some_and(|m| {
+            if let MatchEffect::Text(text_effect) = &m.effect {
+                text_effect.tab_stops
+            } else {
+                false
+            }
+        })
+    }
+}
+
 pub struct CombinedMatchCache<'a> {
     user_match_cache: &'a MatchCache<'a>,
     builtin_match_cache: HashMap<i32, &'a BuiltInMatch>,
@@ -34,13 +34,13 @@ use self::ui::util::convert_icon_paths_to_tray_vec;
 
 use super::{CliModule, CliModuleArgs};
 
-mod builtin;
-mod config;
-mod context;
+pub mod builtin;
+pub mod config;
+pub mod context;
 mod daemon_monitor;
-mod engine;
+pub mod engine;
 mod ipc;
-mod match_cache;
+pub mod match_cache;
 mod secure_input;
 mod stats_recorder;
 mod ui;
@@ -178,7 +178,9 @@ fn worker_main(args: CliModuleArgs) -> i32 {
                 info!("exiting worker process and daemon...");
                 WORKER_EXIT_ALL_PROCESSES
             }
-            ExitMode::RestartWorker => {
+            // Reload requests are handled by the engine thread itself,
+            // so they should never reach this point
+            ExitMode::RestartWorker | ExitMode::ReloadConfig => {
                 info!("exiting worker (to be restarted)");
                 WORKER_RESTART
             }
@@ -17,11 +17,11 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use espanso_config::{
     config::ConfigStore,
-    error::{ErrorLevel, NonFatalErrorSet},
-    matches::store::MatchStore,
+    error::{ErrorLevel, ErrorRecord, NonFatalErrorSet},
+    matches::{store::MatchStore, MacroStep, MatchEffect},
 };
 use log::{error, info, warn};
 use std::path::Path;
@@ -89,8 +89,34 @@ pub struct ConfigLoadResult {
 }
 
 pub fn load_config(config_path: &Path) -> Result<ConfigLoadResult> {
-    let (config_store, match_store, non_fatal_errors) =
-        espanso_config::load(config_path).context("unable to load config")?;
+    let loaded = espanso_config::load(config_path).context("unable to load config")?;
+    Ok(process_loaded_config(config_path, loaded))
+}
+
+/// Load the config again, only parsing the match files that changed
+/// since the given match store was loaded.
+pub fn reload_config(
+    config_path: &Path,
+    previous_match_store: Box<dyn MatchStore>,
+) -> Result<ConfigLoadResult> {
+    let loaded = espanso_config::reload(config_path, previous_match_store)
+        .context("unable to reload config")?;
+    Ok(process_loaded_config(config_path, loaded))
+}
+
+fn process_loaded_config(
+    config_path: &Path,
+    (config_store, match_store, mut non_fatal_errors): (
+        Box<dyn ConfigStore>,
+        Box<dyn MatchStore>,
+        Vec<NonFatalErrorSet>,
+    ),
+) -> ConfigLoadResult {
+    non_fatal_errors.extend(validate_macro_keys(
+        config_path,
+        &*config_store,
+        &*match_store,
+    ));
 
     // TODO: add an option to avoid dumping the errors in the logs
     if !non_fatal_errors.is_empty() {
@@ -98,20 +124,101 @@ pub fn load_config(config_path: &Path) -> Result<ConfigLoadResult> {
         for non_fatal_error_set in &non_fatal_errors {
             warn!(">>> {}", non_fatal_error_set.file.to_string_lossy());
             for record in &non_fatal_error_set.errors {
+                let location = record
+                    .location
+                    .map(|location| format!("({location}) "))
+                    .unwrap_or_default();
                 if record.level == ErrorLevel::Error {
-                    error!("{:?}", record.error);
+                    error!("{}{:?}", location, record.error);
                 } else {
-                    warn!("{:?}", record.error);
+                    warn!("{}{:?}", location, record.error);
                 }
             }
         }
         warn!("-----------------------------------------------------------");
     }
 
-    Ok(ConfigLoadResult {
+    ConfigLoadResult {
         // Apply the built-in patches
         config_store: crate::patch::patch_store(config_store),
         match_store,
         non_fatal_errors,
-    })
+    }
+}
+
+// The key sequences of macros are parsed by the injection module, which the
+// config module doesn't depend on, so they are validated here.
+fn validate_macro_keys(
+    config_path: &Path,
+    config_store: &dyn ConfigStore,
+    match_store: &dyn MatchStore,
+) -> Option<NonFatalErrorSet> {
+    let paths: Vec<String> = config_store.get_all_match_paths().into_iter().collect();
+    let errors: Vec<ErrorRecord> = match_store
+        .query(&paths)
+        .matches
+        .into_iter()
+        .flat_map(|m| {
+            let steps = match &m.effect {
+                MatchEffect::Macro(effect) => effect.steps.as_slice(),
+                _ => &[],
+            };
+            steps.iter().filter_map(|step| {
+                let MacroStep::Keys(keys) = step else {
+                    return None;
+                };
+                let err = espanso_inject::keys::parse_key_sequence(keys).err()?;
+                Some(ErrorRecord::error(anyhow!(
+                    "invalid keys {:?} in macro triggered by {}: {}",
+                    keys,
+                    m.cause.long_description(),
+                    err
+                )))
+            })
+        })
+        .collect();
+
+    if errors.is_empty() {
+        None
+    } else {
+        Some(NonFatalErrorSet::new(config_path, errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn invalid_macro_keys_are_reported_as_non_fatal_errors() {
+        let dir = TempDir::new("espanso-config").unwrap();
+        std::fs::create_dir_all(dir.path().join("config")).unwrap();
+        std::fs::create_dir_all(dir.path().join("match")).unwrap();
+        std::fs::write(dir.path().join("config/default.yml"), "").unwrap();
+        std::fs::write(
+            dir.path().join("match/base.yml"),
+            r#"
+matches:
+  - trigger: ":valid"
+    macro:
+      - keys: "{Ctrl+V}{Tab}"
+  - trigger: ":invalid"
+    macro:
+      - text: "John"
+      - keys: "{Ctrl+Foo}"
+"#,
+        )
+        .unwrap();
+
+        let (config_store, match_store, _) = espanso_config::load(dir.path()).unwrap();
+        let error_set = validate_macro_keys(dir.path(), &*config_store, &*match_store).unwrap();
+
+        assert_eq!(error_set.file, dir.path());
+        assert_eq!(error_set.errors.len(), 1);
+        assert_eq!(error_set.errors[0].level, ErrorLevel::Error);
+        let message = format!("{:?}", error_set.errors[0].error);
+        assert!(message.contains("{Ctrl+Foo}"));
+        assert!(message.contains(":invalid"));
+    }
 }
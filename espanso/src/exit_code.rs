@@ -49,6 +49,10 @@ pub const WORKAROUND_FAILURE: i32 = 1;
 #[allow(dead_code)]
 pub const WORKAROUND_NOT_AVAILABLE: i32 = 2;
 
+pub const CHECK_SUCCESS: i32 = 0;
+pub const CHECK_ERRORS_FOUND: i32 = 1;
+pub const CHECK_FATAL_ERROR: i32 = 2;
+
 pub const PACKAGE_SUCCESS: i32 = 0;
 pub const PACKAGE_UNEXPECTED_FAILURE: i32 = 1;
 pub const PACKAGE_INSTALL_FAILED: i32 = 2;
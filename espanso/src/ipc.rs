@@ -32,6 +32,8 @@ pub enum IPCEvent {
     ToggleRequest,
     OpenSearchBar,
     OpenConfigFolder,
+    ReloadConfig,
+    UndoRequest,
 
     RequestMatchExpansion(RequestMatchExpansionPayload),
 }
@@ -58,10 +58,10 @@ mod util;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-
 static CLI_HANDLERS: LazyLock<Vec<CliModule>> = LazyLock::new(|| {
     vec![
         cli::path::new(),
+        cli::check::new(),
         cli::edit::new(),
         cli::launcher::new(),
         cli::log::new(),
@@ -75,6 +75,7 @@ static CLI_HANDLERS: LazyLock<Vec<CliModule>> = LazyLock::new(|| {
         cli::package::new(),
         cli::match_cli::new(),
         cli::cmd::new(),
+        cli::state::new(),
     ]
 });
 
@@ -142,6 +143,8 @@ fn main() {
             .about("Enable/Disable expansions."))
         .subcommand(SubCommand::with_name("search")
             .about("Open the Espanso's search bar."))
+        .subcommand(SubCommand::with_name("undo")
+            .about("Revert the last expansion."))
     )
     .subcommand(SubCommand::with_name("edit")
         .about("Shortcut to open the default text editor to edit config files")
@@ -285,6 +288,17 @@ For example, specifying 'email' is equivalent to 'match/email.yml'."#))
         )
         .subcommand(SubCommand::with_name("base").about("Print the default match file path.")),
     )
+    .subcommand(
+      SubCommand::with_name("check")
+        .about("Check the configuration and match files, reporting errors with their location.")
+        .arg(
+          Arg::with_name("strict")
+            .long("strict")
+            .required(false)
+            .takes_value(false)
+            .help("Exit with a failure code also when warnings are found."),
+        ),
+    )
     .subcommand(
       SubCommand::with_name("service")
         .subcommand(SubCommand::with_name("register").about("Register espanso as a system service"))
@@ -318,7 +332,7 @@ For example, specifying 'email' is equivalent to 'match/email.yml'."#))
         .about("A collection of commands to manage the Espanso service (for example, enabling auto-start on system boot)."),
     )
     .subcommand(SubCommand::with_name("match")
-        .about("List and execute matches from the CLI")
+        .about("List, execute, render, test and edit matches from the CLI")
         .subcommand(SubCommand::with_name("list")
             .about("Print matches to standard output")
             .arg(Arg::with_name("json")
@@ -379,6 +393,215 @@ For example, specifying 'email' is equivalent to 'match/email.yml'."#))
                 .number_of_values(1)
             )
         )
+        .subcommand(SubCommand::with_name("render")
+            .about("Render a match and print the result, without injecting it")
+            .arg(Arg::with_name("trigger")
+                .short('t')
+                .long("trigger")
+                .help("The trigger of the match to be rendered")
+                .required(true)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("arg")
+                .long("arg")
+                .help("Specify also an argument for the rendering, following the --arg 'name=value' format. You can specify multiple ones.")
+                .required(false)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+            )
+            .arg(Arg::with_name("json")
+                .short('j')
+                .long("json")
+                .help("Output the result (or the error) in the JSON format")
+                .required(false)
+                .takes_value(false)
+            )
+            .arg(Arg::with_name("class")
+                .long("class")
+                .help("Render the match as if an app with the given class was focused, to apply app-specific configs.")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("title")
+                .long("title")
+                .help("Render the match as if an app with the given title was focused, to apply app-specific configs.")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("exec")
+                .long("exec")
+                .help("Render the match as if an app with the given exec was focused, to apply app-specific configs.")
+                .required(false)
+                .takes_value(true)
+            )
+        )
+        .subcommand(SubCommand::with_name("add")
+            .about("Add a match to a match file, preserving its formatting")
+            .arg(Arg::with_name("file")
+                .short('f')
+                .long("file")
+                .help("The match file to edit, relative to the match directory. Defaults to base.yml")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("trigger")
+                .short('t')
+                .long("trigger")
+                .help("The trigger of the new match")
+                .required(true)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("replace")
+                .short('r')
+                .long("replace")
+                .help("The replacement text of the new match")
+                .required(true)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("label")
+                .long("label")
+                .help("An optional label describing the match")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("word")
+                .long("word")
+                .help("Only expand the match when the trigger is a separate word")
+                .required(false)
+                .takes_value(true)
+                .possible_values(["true", "false"])
+            )
+        )
+        .subcommand(SubCommand::with_name("update")
+            .about("Update the fields of a match, preserving the formatting of the match file")
+            .arg(Arg::with_name("file")
+                .short('f')
+                .long("file")
+                .help("The match file to edit, relative to the match directory. Defaults to base.yml")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("trigger")
+                .short('t')
+                .long("trigger")
+                .help("The trigger of the match to update")
+                .required(true)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("new-trigger")
+                .long("new-trigger")
+                .help("Change the trigger of the match")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("replace")
+                .short('r')
+                .long("replace")
+                .help("The new replacement text of the match")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("label")
+                .long("label")
+                .help("The new label of the match")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("word")
+                .long("word")
+                .help("Only expand the match when the trigger is a separate word")
+                .required(false)
+                .takes_value(true)
+                .possible_values(["true", "false"])
+            )
+        )
+        .subcommand(SubCommand::with_name("remove")
+            .about("Remove a match from a match file, preserving its formatting")
+            .arg(Arg::with_name("file")
+                .short('f')
+                .long("file")
+                .help("The match file to edit, relative to the match directory. Defaults to base.yml")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("trigger")
+                .short('t')
+                .long("trigger")
+                .help("The trigger of the match to remove")
+                .required(true)
+                .takes_value(true)
+            )
+        )
+        .subcommand(SubCommand::with_name("test")
+            .about("Simulate typing to check which matches would be expanded, without injecting anything")
+            .arg(Arg::with_name("input")
+                .short('i')
+                .long("input")
+                .help("The text to type. Special keys can be specified as <backspace>, <enter>, <tab>, <esc>, <left>, <right>, <up>, <down>, <home>, <end> and <click>.")
+                .required(false)
+                .takes_value(true)
+                .conflicts_with("file")
+            )
+            .arg(Arg::with_name("file")
+                .short('f')
+                .long("file")
+                .help("Read the keystrokes to type from the given file, using the same format as --input")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("json")
+                .short('j')
+                .long("json")
+                .help("Output the detected matches in the JSON format")
+                .required(false)
+                .takes_value(false)
+            )
+            .arg(Arg::with_name("class")
+                .long("class")
+                .help("Simulate typing inside an app with the given class, to apply app-specific configs.")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("title")
+                .long("title")
+                .help("Simulate typing inside an app with the given title, to apply app-specific configs.")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("exec")
+                .long("exec")
+                .help("Simulate typing inside an app with the given exec, to apply app-specific configs.")
+                .required(false)
+                .takes_value(true)
+            )
+        )
+        .subcommand(SubCommand::with_name("replay")
+            .about("Replay the events recorded by the worker (see ESPANSO_RECORD_EVENTS) and print the resulting effects, without injecting anything")
+            .arg(Arg::with_name("file")
+                .help("The recording file, containing one event per line")
+                .required(true)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("class")
+                .long("class")
+                .help("Replay the events inside an app with the given class, to apply app-specific configs.")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("title")
+                .long("title")
+                .help("Replay the events inside an app with the given title, to apply app-specific configs.")
+                .required(false)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("exec")
+                .long("exec")
+                .help("Replay the events inside an app with the given exec, to apply app-specific configs.")
+                .required(false)
+                .takes_value(true)
+            )
+        )
     )
     .subcommand(
       SubCommand::with_name("package")
@@ -448,6 +671,33 @@ SubCommand::with_name("install")
           SubCommand::with_name("list").about("List all installed packages"),
         ),
     )
+    .subcommand(
+      SubCommand::with_name("state")
+        .about("Inspect and modify the persistent values used by the 'state' extension.")
+        .subcommand(
+          SubCommand::with_name("get")
+            .about("Print the current value of a state entry.")
+            .arg(Arg::with_name("name").help("The name of the entry").required(true)),
+        )
+        .subcommand(
+          SubCommand::with_name("set")
+            .about("Set the value of a state entry.")
+            .arg(Arg::with_name("name").help("The name of the entry").required(true))
+            .arg(Arg::with_name("value").help("The new value").required(true)),
+        )
+        .subcommand(
+          SubCommand::with_name("list")
+            .about("List all state entries with their values.")
+            .arg(
+              Arg::with_name("json")
+                .short('j')
+                .long("json")
+                .required(false)
+                .takes_value(false)
+                .help("Output the entries in the JSON format"),
+            ),
+        ),
+    )
     .subcommand(
       SubCommand::with_name("workaround")
         .subcommand(
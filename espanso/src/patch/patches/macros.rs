@@ -83,6 +83,10 @@ macro_rules! generate_patchable_config {
           self.base.search_shortcut()
         }
 
+        fn undo_shortcut(&self) -> Option<String> {
+          self.base.undo_shortcut()
+        }
+
         fn show_icon(&self) -> bool {
           self.base.show_icon()
         }
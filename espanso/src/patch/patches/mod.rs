@@ -17,7 +17,7 @@
  * along with espanso.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use espanso_config::config::{Backend, RMLVOConfig, ToggleKey};
+use espanso_config::config::{Backend, CursorHintStrategy, RMLVOConfig, ToggleKey};
 
 #[cfg(target_os = "windows")]
 pub mod win;
@@ -48,6 +48,8 @@ generate_patchable_config!(
   backspace_limit -> usize,
   apply_patch -> bool,
   undo_backspace -> bool,
+  tab_stop_key -> Option<String>,
+  cursor_hint_strategy -> CursorHintStrategy,
   post_form_delay -> usize,
   max_form_width -> usize,
   max_form_height -> usize,